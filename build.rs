@@ -0,0 +1,54 @@
+//! Records the versions of the main dependencies, from Cargo.lock, and the git
+//! commit being built, for the `info` command.
+
+use std::path::Path;
+use std::process::Command;
+use std::{env, fs};
+
+/// The crates whose versions are recorded, with the prefix of their variables.
+const CRATES: &[(&str, &str)] = &[("simplicity-lang", "SIMPLICITY"), ("elements", "ELEMENTS")];
+
+/// The version and source of the given package in the lock file, if present.
+fn locked_package(lock: &str, name: &str) -> Option<(String, String)> {
+	lock.split("[[package]]").find_map(|package| {
+		let field = |key: &str| {
+			package.lines().find_map(|line| {
+				let value = line.strip_prefix(key)?.trim_start().strip_prefix('=')?;
+				Some(value.trim().trim_matches('"').to_owned())
+			})
+		};
+		if field("name")?.as_str() != name {
+			return None;
+		}
+		Some((field("version")?, field("source").unwrap_or_default()))
+	})
+}
+
+fn main() {
+	let dir = env::var("CARGO_MANIFEST_DIR").expect("set by cargo");
+	let lock_path = Path::new(&dir).join("Cargo.lock");
+	println!("cargo:rerun-if-changed={}", lock_path.display());
+	// Cargo.lock is not committed, but cargo writes it before running this script.
+	// Without it, e.g. in a workspace, the versions are empty and `info` omits them.
+	let lock = fs::read_to_string(&lock_path).unwrap_or_default();
+	for (name, prefix) in CRATES {
+		let (version, source) = locked_package(&lock, name).unwrap_or_default();
+		println!("cargo:rustc-env=HAL_{}_VERSION={}", prefix, version);
+		println!("cargo:rustc-env=HAL_{}_SOURCE={}", prefix, source);
+	}
+
+	let git_dir = Path::new(&dir).join(".git");
+	if git_dir.exists() {
+		println!("cargo:rerun-if-changed={}", git_dir.join("HEAD").display());
+		println!("cargo:rerun-if-changed={}", git_dir.join("refs").display());
+	}
+	let commit = Command::new("git")
+		.args(["rev-parse", "HEAD"])
+		.current_dir(&dir)
+		.output()
+		.ok()
+		.filter(|out| out.status.success())
+		.map(|out| String::from_utf8_lossy(&out.stdout).trim().to_owned())
+		.unwrap_or_default();
+	println!("cargo:rustc-env=HAL_GIT_COMMIT={}", commit);
+}
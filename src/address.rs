@@ -1,44 +1,85 @@
 use elements::bitcoin::{secp256k1, PublicKey};
 use elements::{Address, PubkeyHash, Script, ScriptHash, WPubkeyHash, WScriptHash};
+use schemars::JsonSchema;
 use serde::{Deserialize, Serialize};
 
 use crate::Network;
 
-#[derive(Clone, PartialEq, Eq, Debug, Deserialize, Serialize)]
+#[derive(Clone, PartialEq, Eq, Debug, Deserialize, Serialize, JsonSchema)]
 pub struct AddressInfo {
 	pub network: Network,
 	#[serde(rename = "type")]
 	pub type_: Option<String>,
+	#[schemars(with = "crate::tx::OutputScriptInfo")]
 	pub script_pub_key: ::hal::tx::OutputScriptInfo,
 	#[serde(skip_serializing_if = "Option::is_none")]
 	pub witness_program_version: Option<usize>,
 	#[serde(skip_serializing_if = "Option::is_none")]
+	#[schemars(with = "Option<String>")]
 	pub pubkey_hash: Option<PubkeyHash>,
 	#[serde(skip_serializing_if = "Option::is_none")]
+	#[schemars(with = "Option<String>")]
 	pub script_hash: Option<ScriptHash>,
 	#[serde(skip_serializing_if = "Option::is_none")]
+	#[schemars(with = "Option<String>")]
 	pub witness_pubkey_hash: Option<WPubkeyHash>,
 	#[serde(skip_serializing_if = "Option::is_none")]
+	#[schemars(with = "Option<String>")]
 	pub witness_script_hash: Option<WScriptHash>,
 	#[serde(skip_serializing_if = "Option::is_none")]
+	#[schemars(with = "Option<String>")]
 	pub blinding_pubkey: Option<secp256k1::PublicKey>,
 	#[serde(skip_serializing_if = "Option::is_none")]
+	#[schemars(with = "Option<String>")]
 	pub unconfidential: Option<Address>,
+	/// The address with the same script and blinding key on the other networks.
+	pub other_networks: Vec<NetworkAddress>,
 }
 
-#[derive(Clone, PartialEq, Eq, Debug, Default, Deserialize, Serialize)]
+#[derive(Clone, PartialEq, Eq, Debug, Deserialize, Serialize, JsonSchema)]
+pub struct NetworkAddress {
+	pub network: Network,
+	#[schemars(with = "String")]
+	pub address: Address,
+}
+
+impl NetworkAddress {
+	/// The given address re-encoded for each network other than its own.
+	pub fn other_networks(address: &Address) -> Vec<NetworkAddress> {
+		[Network::Liquid, Network::LiquidTestnet, Network::ElementsRegtest]
+			.into_iter()
+			.filter(|network| network.address_params() != address.params)
+			.map(|network| NetworkAddress {
+				network,
+				address: Address {
+					params: network.address_params(),
+					payload: address.payload.clone(),
+					blinding_pubkey: address.blinding_pubkey,
+				},
+			})
+			.collect()
+	}
+}
+
+#[derive(Clone, PartialEq, Eq, Debug, Default, Deserialize, Serialize, JsonSchema)]
 pub struct Addresses {
 	#[serde(skip_serializing_if = "Option::is_none")]
+	#[schemars(with = "Option<String>")]
 	pub p2pkh: Option<Address>,
 	#[serde(skip_serializing_if = "Option::is_none")]
+	#[schemars(with = "Option<String>")]
 	pub p2wpkh: Option<Address>,
 	#[serde(skip_serializing_if = "Option::is_none")]
+	#[schemars(with = "Option<String>")]
 	pub p2shwpkh: Option<Address>,
 	#[serde(skip_serializing_if = "Option::is_none")]
+	#[schemars(with = "Option<String>")]
 	pub p2sh: Option<Address>,
 	#[serde(skip_serializing_if = "Option::is_none")]
+	#[schemars(with = "Option<String>")]
 	pub p2wsh: Option<Address>,
 	#[serde(skip_serializing_if = "Option::is_none")]
+	#[schemars(with = "Option<String>")]
 	pub p2shwsh: Option<Address>,
 }
 
@@ -2,6 +2,7 @@ use clap;
 use elements::bitcoin::{secp256k1, PublicKey};
 use elements::hashes::Hash;
 use elements::{Address, WPubkeyHash, WScriptHash};
+use schemars::schema::RootSchema;
 
 use crate::cmd;
 
@@ -13,6 +14,14 @@ pub fn subcommand<'a>() -> clap::App<'a, 'a> {
 		.subcommand(cmd_inspect())
 }
 
+/// The schemas of the outputs of the subcommands, by subcommand name.
+pub fn schemas() -> Vec<(&'static str, RootSchema)> {
+	vec![
+		("create", schemars::schema_for!(hal_simplicity::address::Addresses)),
+		("inspect", schemars::schema_for!(hal_simplicity::address::AddressInfo)),
+	]
+}
+
 pub fn execute<'a>(matches: &clap::ArgMatches<'a>) {
 	match matches.subcommand() {
 		("create", Some(m)) => exec_create(m),
@@ -22,12 +31,26 @@ pub fn execute<'a>(matches: &clap::ArgMatches<'a>) {
 }
 
 fn cmd_create<'a>() -> clap::App<'a, 'a> {
-	cmd::subcommand("create", "create addresses").args(&cmd::opts_networks()).args(&[
-		cmd::opt_yaml(),
-		cmd::opt("pubkey", "a public key in hex").takes_value(true).required(false),
-		cmd::opt("script", "a script in hex").takes_value(true).required(false),
-		cmd::opt("blinder", "a blinding pubkey in hex").takes_value(true).required(false),
-	])
+	cmd::subcommand("create", "create addresses")
+		.args(&cmd::opts_networks())
+		.args(&cmd::opts_raw_value())
+		.args(&[
+			cmd::opt_yaml(),
+			cmd::opt_output(),
+			cmd::opt_ndjson(),
+			cmd::opt_keep_going(),
+			cmd::opt("pubkey", "a public key in hex; may be given several times")
+				.takes_value(true)
+				.multiple(true)
+				.number_of_values(1)
+				.required(false),
+			cmd::opt("script", "a script in hex; may be given several times")
+				.takes_value(true)
+				.multiple(true)
+				.number_of_values(1)
+				.required(false),
+			cmd::opt("blinder", "a blinding pubkey in hex").takes_value(true).required(false),
+		])
 }
 
 fn exec_create<'a>(matches: &clap::ArgMatches<'a>) {
@@ -38,29 +61,41 @@ fn exec_create<'a>(matches: &clap::ArgMatches<'a>) {
 		secp256k1::PublicKey::from_slice(&bytes).expect("invalid blinder")
 	});
 
-	let created = if let Some(pubkey_hex) = matches.value_of("pubkey") {
-		let pubkey: PublicKey = pubkey_hex.parse().expect("invalid pubkey");
-		hal_simplicity::address::Addresses::from_pubkey(&pubkey, blinder, network)
-	} else if let Some(script_hex) = matches.value_of("script") {
-		let script_bytes = hex::decode(script_hex).expect("invalid script hex");
-		let script = script_bytes.into();
-
-		hal_simplicity::address::Addresses::from_script(&script, blinder, network)
+	if let Some(pubkeys) = cmd::Batch::args(matches, "pubkey") {
+		cmd::print_batch(matches, pubkeys, |pubkey_hex| {
+			let pubkey: PublicKey = pubkey_hex.parse().expect("invalid pubkey");
+			hal_simplicity::address::Addresses::from_pubkey(&pubkey, blinder, network)
+		})
+	} else if let Some(scripts) = cmd::Batch::args(matches, "script") {
+		cmd::print_batch(matches, scripts, |script_hex| {
+			let script_bytes = hex::decode(&*script_hex).expect("invalid script hex");
+			let script = script_bytes.into();
+
+			hal_simplicity::address::Addresses::from_script(&script, blinder, network)
+		})
 	} else {
 		panic!("Can't create addresses without a pubkey");
-	};
-
-	cmd::print_output(matches, &created)
+	}
 }
 
 fn cmd_inspect<'a>() -> clap::App<'a, 'a> {
-	cmd::subcommand("inspect", "inspect addresses")
-		.args(&[cmd::opt_yaml(), cmd::arg("address", "the address").required(true)])
+	cmd::subcommand("inspect", "inspect addresses").args(&cmd::opts_raw_value()).args(&[
+		cmd::opt_yaml(),
+		cmd::opt_output(),
+		cmd::opt_ndjson(),
+		cmd::opt_keep_going(),
+		cmd::arg("address", "the addresses").multiple(true).required(true),
+	])
 }
 
 fn exec_inspect<'a>(matches: &clap::ArgMatches<'a>) {
-	let address_str = matches.value_of("address").expect("no address provided");
-	let address: Address = address_str.parse().expect("invalid address format");
+	let addresses = cmd::Batch::args(matches, "address").expect("no address provided");
+	cmd::print_batch(matches, addresses, |address_str| {
+		inspect(address_str.parse().expect("invalid address format"))
+	})
+}
+
+fn inspect(address: Address) -> hal_simplicity::address::AddressInfo {
 	let script_pk = address.script_pubkey();
 
 	let mut info = hal_simplicity::address::AddressInfo {
@@ -87,6 +122,7 @@ fn exec_inspect<'a>(matches: &clap::ArgMatches<'a>) {
 		} else {
 			None
 		},
+		other_networks: hal_simplicity::address::NetworkAddress::other_networks(&address),
 	};
 
 	use elements::address::Payload;
@@ -124,5 +160,5 @@ fn exec_inspect<'a>(matches: &clap::ArgMatches<'a>) {
 		}
 	}
 
-	cmd::print_output(matches, &info)
+	info
 }
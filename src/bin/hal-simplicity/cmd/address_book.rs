@@ -0,0 +1,125 @@
+//! The address book given with `--address-book`, labelling the inputs and outputs
+//! of decoded transactions.
+//!
+//! The address book is a JSON or YAML object mapping addresses or scriptPubKeys in
+//! hex to labels. Outputs are matched by their scriptPubKey. Inputs don't carry the
+//! scriptPubKey they spend, so it is reconstructed from the input when it can be:
+//! for P2PKH, P2SH, P2WPKH, P2WSH and taproot script path spends.
+
+use std::collections::{BTreeMap, HashMap};
+use std::fs;
+
+use elements::hashes::{hash160, Hash, HashEngine};
+use elements::secp256k1_zkp;
+use elements::taproot::{ControlBlock, TapLeafHash, TapNodeHash};
+use elements::{PubkeyHash, Script, Transaction, TxIn, WPubkeyHash};
+use hal_simplicity::tx::TransactionInfo;
+
+/// The first byte of a taproot annex.
+const ANNEX_TAG: u8 = 0x50;
+
+pub struct AddressBook {
+	labels: HashMap<Script, String>,
+}
+
+impl AddressBook {
+	/// Load the address book given with `--address-book`, if any.
+	pub fn load<'a>(matches: &clap::ArgMatches<'a>) -> Option<AddressBook> {
+		let path = matches.value_of("address-book")?;
+		let contents = fs::read_to_string(path)
+			.unwrap_or_else(|e| panic!("could not read address book {}: {}", path, e));
+		let entries: BTreeMap<String, String> = serde_yaml::from_str(&contents)
+			.unwrap_or_else(|e| panic!("invalid address book {}: {}", path, e));
+		let labels = entries
+			.into_iter()
+			.map(|(key, label)| {
+				let script = match key.parse::<elements::Address>() {
+					Ok(address) => address.script_pubkey(),
+					Err(_) => Script::from(hex::decode(&key).unwrap_or_else(|_| {
+						panic!("invalid address book entry '{}', expected an address or hex", key)
+					})),
+				};
+				(script, label)
+			})
+			.collect();
+		Some(AddressBook {
+			labels,
+		})
+	}
+
+	/// Set the labels of the inputs and outputs of the decoded transaction.
+	pub fn annotate(&self, tx: &Transaction, info: &mut TransactionInfo) {
+		if let Some(ref mut inputs) = info.inputs {
+			for (input, info) in tx.input.iter().zip(inputs) {
+				info.label = spent_script(input).and_then(|s| self.labels.get(&s)).cloned();
+			}
+		}
+		if let Some(ref mut outputs) = info.outputs {
+			for (output, info) in tx.output.iter().zip(outputs) {
+				info.label = self.labels.get(&output.script_pubkey).cloned();
+			}
+		}
+	}
+}
+
+/// The scriptPubKey of a taproot output spent with the given script and control block.
+fn taproot_script_pubkey(script: &[u8], control_block: &ControlBlock) -> Script {
+	let leaf = TapLeafHash::from_script(&Script::from(script.to_vec()), control_block.leaf_version);
+	let mut node = TapNodeHash::from_byte_array(leaf.to_byte_array());
+	for sibling in control_block.merkle_branch.as_inner() {
+		let mut engine = TapNodeHash::engine();
+		if node.as_byte_array() < sibling.as_byte_array() {
+			engine.input(node.as_ref());
+			engine.input(sibling.as_ref());
+		} else {
+			engine.input(sibling.as_ref());
+			engine.input(node.as_ref());
+		}
+		node = TapNodeHash::from_engine(engine);
+	}
+	Script::new_v1_p2tr(secp256k1_zkp::SECP256K1, control_block.internal_key, Some(node))
+}
+
+/// The scriptPubKey spent by the input, if it can be told from the input.
+fn spent_script(input: &TxIn) -> Option<Script> {
+	if input.is_pegin {
+		return None;
+	}
+
+	let pushes = input
+		.script_sig
+		.instructions()
+		.map(|i| match i {
+			Ok(elements::script::Instruction::PushBytes(data)) => Some(data.to_vec()),
+			_ => None,
+		})
+		.collect::<Option<Vec<_>>>()?;
+	match pushes.as_slice() {
+		// P2SH, including wrapped segwit.
+		[redeem_script] => return Some(Script::from(redeem_script.clone()).to_p2sh()),
+		[_, pubkey] if pubkey.len() == 33 || pubkey.len() == 65 => {
+			return Some(Script::new_p2pkh(&PubkeyHash::from_raw_hash(hash160::Hash::hash(
+				pubkey,
+			))));
+		}
+		[] => {}
+		_ => return None,
+	}
+
+	let mut witness = input.witness.script_witness.as_slice();
+	if witness.len() >= 2 && witness.last().and_then(|a| a.first()) == Some(&ANNEX_TAG) {
+		witness = &witness[..witness.len() - 1];
+	}
+	match witness {
+		[] => None,
+		// A compressed key would also parse as a control block, with an unused leaf version.
+		[_, pubkey] if pubkey.len() == 33 && (pubkey[0] == 0x02 || pubkey[0] == 0x03) => {
+			Some(Script::new_v0_wpkh(&WPubkeyHash::from_raw_hash(hash160::Hash::hash(pubkey))))
+		}
+		[.., script, control_block] if ControlBlock::from_slice(control_block).is_ok() => {
+			let control_block = ControlBlock::from_slice(control_block).expect("checked above");
+			Some(taproot_script_pubkey(script, &control_block))
+		}
+		[.., witness_script] => Some(Script::from(witness_script.clone()).to_v0_p2wsh()),
+	}
+}
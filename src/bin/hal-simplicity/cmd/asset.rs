@@ -0,0 +1,233 @@
+use elements::hashes::Hash;
+use elements::secp256k1_zkp::ZERO_TWEAK;
+use elements::{
+	confidential, AssetId, AssetIssuance, ContractHash, OutPoint, Script, Transaction, TxIn, TxOut,
+};
+use log::warn;
+use schemars::schema::RootSchema;
+use schemars::JsonSchema;
+use serde::Serialize;
+
+use crate::cmd;
+
+/// The fields of a contract required by the Liquid asset registry.
+const REGISTRY_FIELDS: &[&str] =
+	&["version", "issuer_pubkey", "name", "ticker", "precision", "entity"];
+
+pub fn subcommand<'a>() -> clap::App<'a, 'a> {
+	cmd::subcommand_group("asset", "issued asset tools").subcommand(cmd_issue())
+}
+
+/// The schemas of the outputs of the subcommands, by subcommand name.
+pub fn schemas() -> Vec<(&'static str, RootSchema)> {
+	vec![("issue", schemars::schema_for!(IssueInfo))]
+}
+
+pub fn execute<'a>(matches: &clap::ArgMatches<'a>) {
+	match matches.subcommand() {
+		("issue", Some(m)) => exec_issue(m),
+		(_, _) => unreachable!("clap prints help"),
+	};
+}
+
+fn cmd_issue<'a>() -> clap::App<'a, 'a> {
+	cmd::subcommand("issue", "build the unsigned transaction issuing a new asset")
+		.args(&cmd::opts_raw_value())
+		.args(&[
+			cmd::opt_yaml(),
+			cmd::opt_output(),
+			cmd::opt(
+				"utxo",
+				"the output funding the issuance and its fee as TXID:VOUT:ASSET:VALUE",
+			)
+			.short("u")
+			.takes_value(true)
+			.required(true),
+			cmd::opt("supply", "the amount of the asset to issue, in satoshis")
+				.short("s")
+				.takes_value(true)
+				.required(true),
+			cmd::opt("tokens", "the amount of reissuance tokens to issue, in satoshis")
+				.takes_value(true)
+				.default_value("0"),
+			cmd::opt("contract", "the asset's contract in JSON, committed to by the asset ID")
+				.short("c")
+				.takes_value(true)
+				.required(false),
+			cmd::opt("address", "the address receiving the issued asset")
+				.short("a")
+				.takes_value(true)
+				.required(true),
+			cmd::opt("token-address", "the address receiving the tokens (defaults to --address)")
+				.takes_value(true)
+				.required(false),
+			cmd::opt("change-address", "the address receiving the change (defaults to --address)")
+				.takes_value(true)
+				.required(false),
+			cmd::opt("fee", "the fee of the transaction in satoshis, paid in the UTXO's asset")
+				.short("f")
+				.takes_value(true)
+				.required(true),
+		])
+}
+
+/// The payload to submit to the Liquid asset registry to register the asset.
+#[derive(Serialize, JsonSchema)]
+struct RegistryPayload {
+	#[schemars(with = "String")]
+	asset_id: AssetId,
+	contract: serde_json::Value,
+}
+
+#[derive(Serialize, JsonSchema)]
+struct IssueInfo {
+	/// The unsigned issuance transaction in hex.
+	tx: String,
+	#[schemars(with = "String")]
+	txid: elements::Txid,
+	#[schemars(with = "String")]
+	asset_id: AssetId,
+	#[schemars(with = "String")]
+	token_id: AssetId,
+	/// The entropy of the issuance, from which the asset and token IDs derive.
+	#[schemars(with = "String")]
+	entropy: elements::hashes::sha256::Midstate,
+	#[schemars(with = "String")]
+	contract_hash: ContractHash,
+	#[serde(skip_serializing_if = "Option::is_none")]
+	registry: Option<RegistryPayload>,
+}
+
+/// Parse a `TXID:VOUT:ASSET:VALUE` UTXO with an explicit asset and value.
+fn parse_utxo(s: &str) -> (OutPoint, AssetId, u64) {
+	let parts = s.split(':').collect::<Vec<_>>();
+	match parts[..] {
+		[txid, vout, asset, value] => (
+			OutPoint::new(
+				txid.parse().expect("invalid UTXO txid"),
+				vout.parse().expect("invalid UTXO vout"),
+			),
+			asset.parse().expect("invalid UTXO asset"),
+			value.parse().expect("invalid UTXO value"),
+		),
+		_ => panic!("invalid UTXO '{}', expected TXID:VOUT:ASSET:VALUE", s),
+	}
+}
+
+fn address_script<'a>(matches: &clap::ArgMatches<'a>, arg: &str) -> Script {
+	let address = matches
+		.value_of(arg)
+		.or_else(|| matches.value_of("address"))
+		.expect("address is mandatory");
+	address
+		.parse::<elements::Address>()
+		.unwrap_or_else(|e| panic!("invalid address '{}': {}", address, e))
+		.script_pubkey()
+}
+
+fn explicit_output(asset: AssetId, value: u64, script_pubkey: Script) -> TxOut {
+	TxOut {
+		asset: confidential::Asset::Explicit(asset),
+		value: confidential::Value::Explicit(value),
+		nonce: confidential::Nonce::Null,
+		script_pubkey,
+		witness: Default::default(),
+	}
+}
+
+fn exec_issue<'a>(matches: &clap::ArgMatches<'a>) {
+	let (prevout, utxo_asset, utxo_value) =
+		parse_utxo(matches.value_of("utxo").expect("utxo is mandatory"));
+	let supply: u64 =
+		matches.value_of("supply").expect("supply is mandatory").parse().expect("invalid supply");
+	let tokens: u64 =
+		matches.value_of("tokens").expect("tokens has a default").parse().expect("invalid tokens");
+	let fee: u64 = matches.value_of("fee").expect("fee is mandatory").parse().expect("invalid fee");
+	if supply == 0 && tokens == 0 {
+		panic!("nothing to issue, --supply and --tokens are both zero");
+	}
+	if fee > utxo_value {
+		panic!("fee of {} sat is more than the UTXO value of {} sat", fee, utxo_value);
+	}
+
+	let contract = matches.value_of("contract").map(|json| {
+		let contract: serde_json::Value =
+			serde_json::from_str(json).expect("invalid contract JSON");
+		if !contract.is_object() {
+			panic!("the contract must be a JSON object");
+		}
+		for field in REGISTRY_FIELDS {
+			if contract.get(field).is_none() {
+				warn!("Contract has no \"{}\" field, required by the asset registry.", field);
+			}
+		}
+		contract
+	});
+	let contract_hash = match contract {
+		Some(ref contract) => {
+			ContractHash::from_json_contract(&contract.to_string()).expect("contract is valid JSON")
+		}
+		None => ContractHash::all_zeros(),
+	};
+
+	let entropy = AssetId::generate_asset_entropy(prevout, contract_hash);
+	let asset_id = AssetId::from_entropy(entropy);
+	let token_id = AssetId::reissuance_token_from_entropy(entropy, false);
+
+	let input = TxIn {
+		previous_output: prevout,
+		sequence: elements::Sequence::MAX,
+		asset_issuance: AssetIssuance {
+			asset_blinding_nonce: ZERO_TWEAK,
+			asset_entropy: contract_hash.to_byte_array(),
+			amount: if supply > 0 {
+				confidential::Value::Explicit(supply)
+			} else {
+				confidential::Value::Null
+			},
+			inflation_keys: if tokens > 0 {
+				confidential::Value::Explicit(tokens)
+			} else {
+				confidential::Value::Null
+			},
+		},
+		..Default::default()
+	};
+
+	let mut outputs = Vec::new();
+	if supply > 0 {
+		outputs.push(explicit_output(asset_id, supply, address_script(matches, "address")));
+	}
+	if tokens > 0 {
+		outputs.push(explicit_output(token_id, tokens, address_script(matches, "token-address")));
+	}
+	if utxo_value > fee {
+		outputs.push(explicit_output(
+			utxo_asset,
+			utxo_value - fee,
+			address_script(matches, "change-address"),
+		));
+	}
+	outputs.push(TxOut::new_fee(fee, utxo_asset));
+
+	let tx = Transaction {
+		version: 2,
+		lock_time: elements::LockTime::ZERO,
+		input: vec![input],
+		output: outputs,
+	};
+
+	let info = IssueInfo {
+		tx: hex::encode(elements::encode::serialize(&tx)),
+		txid: tx.txid(),
+		asset_id,
+		token_id,
+		entropy,
+		contract_hash,
+		registry: contract.map(|contract| RegistryPayload {
+			asset_id,
+			contract,
+		}),
+	};
+	cmd::print_output(matches, &info)
+}
@@ -0,0 +1,193 @@
+//! The audit log of signing operations enabled with `--audit-log`.
+//!
+//! The log is a file of JSON lines, one per operation. Each record holds the
+//! SHA256 digests of the command's inputs, its output and the `hash` of the
+//! previous record, and its own `hash` is the SHA256 of the record serialized
+//! as compact JSON, with sorted keys and without the `hash` field. Changing or
+//! removing a record therefore breaks the chain of all following records, which
+//! `audit verify` checks.
+//!
+//! The log is locked while the last record is read and the new one appended, so
+//! that concurrent commands don't both chain from the same record.
+
+use std::collections::BTreeMap;
+use std::fs::{self, File, OpenOptions};
+use std::io::{self, Read, Write};
+
+use elements::hashes::{sha256, Hash};
+use schemars::schema::RootSchema;
+use schemars::JsonSchema;
+use serde::Serialize;
+use serde_json::Value;
+
+use crate::cmd;
+
+/// The `prev` hash of the first record of a log.
+const GENESIS_HASH: &str = "0000000000000000000000000000000000000000000000000000000000000000";
+
+pub fn subcommand<'a>() -> clap::App<'a, 'a> {
+	cmd::subcommand_group("audit", "inspect audit logs written with --audit-log")
+		.subcommand(cmd_verify())
+}
+
+/// The schemas of the outputs of the subcommands, by subcommand name.
+pub fn schemas() -> Vec<(&'static str, RootSchema)> {
+	vec![("verify", schemars::schema_for!(AuditLogInfo))]
+}
+
+pub fn execute<'a>(matches: &clap::ArgMatches<'a>) {
+	match matches.subcommand() {
+		("verify", Some(m)) => exec_verify(m),
+		(_, _) => unreachable!("clap prints help"),
+	};
+}
+
+/// Open the audit log at the given path for reading and appending, holding an
+/// exclusive lock on it until the file is closed.
+fn open_locked(path: &str) -> File {
+	let file = OpenOptions::new()
+		.create(true)
+		.read(true)
+		.append(true)
+		.open(path)
+		.unwrap_or_else(|e| panic!("could not open audit log {}: {}", path, e));
+	lock_exclusive(&file).unwrap_or_else(|e| panic!("could not lock audit log {}: {}", path, e));
+	file
+}
+
+#[cfg(unix)]
+fn lock_exclusive(file: &File) -> io::Result<()> {
+	use std::os::unix::io::AsRawFd;
+
+	// SAFETY: the descriptor is owned by `file`, which outlives the call. The lock
+	// is released when the file is closed, also when the process exits.
+	if unsafe { libc::flock(file.as_raw_fd(), libc::LOCK_EX) } == 0 {
+		Ok(())
+	} else {
+		Err(io::Error::last_os_error())
+	}
+}
+
+#[cfg(not(unix))]
+fn lock_exclusive(_file: &File) -> io::Result<()> {
+	Ok(())
+}
+
+/// The `seq` and `hash` the next record of the given audit log chains from.
+fn last_record(path: &str, file: &mut File) -> (u64, String) {
+	let mut contents = String::new();
+	file.read_to_string(&mut contents)
+		.unwrap_or_else(|e| panic!("could not read audit log {}: {}", path, e));
+	match contents.lines().rev().find(|l| !l.trim().is_empty()) {
+		Some(line) => {
+			let record: Value = serde_json::from_str(line)
+				.unwrap_or_else(|e| panic!("invalid last record in audit log {}: {}", path, e));
+			match (record["seq"].as_u64(), record["hash"].as_str()) {
+				(Some(seq), Some(hash)) => (seq + 1, hash.to_owned()),
+				_ => panic!("invalid last record in audit log {}", path),
+			}
+		}
+		None => (0, GENESIS_HASH.to_owned()),
+	}
+}
+
+/// Append a record of the command to the audit log given with `--audit-log`, if any.
+///
+/// The values of the given arguments are only recorded as digests, so that secret
+/// keys don't end up in the log.
+pub fn record<'a, T: serde::Serialize>(
+	matches: &clap::ArgMatches<'a>,
+	command: &str,
+	args: &[&str],
+	output: &T,
+) {
+	let path = match matches.value_of("audit-log") {
+		Some(path) => path,
+		None => return,
+	};
+
+	let inputs = args
+		.iter()
+		.filter_map(|arg| {
+			let digests = matches
+				.values_of(arg)?
+				.map(|v| sha256::Hash::hash(v.as_bytes()).to_string())
+				.collect::<Vec<_>>();
+			Some((arg.to_string(), digests))
+		})
+		.collect::<BTreeMap<_, _>>();
+
+	let mut file = open_locked(path);
+	let (seq, prev) = last_record(path, &mut file);
+	let mut record = serde_json::json!({
+		"seq": seq,
+		"timestamp": chrono::Utc::now().format("%Y-%m-%dT%H:%M:%S%.3fZ").to_string(),
+		"command": command,
+		"inputs": inputs,
+		"output": output,
+		"prev": prev,
+	});
+	let hash = sha256::Hash::hash(record.to_string().as_bytes());
+	record["hash"] = Value::String(hash.to_string());
+
+	writeln!(file, "{}", record)
+		.unwrap_or_else(|e| panic!("could not write audit log {}: {}", path, e));
+}
+
+fn cmd_verify<'a>() -> clap::App<'a, 'a> {
+	cmd::subcommand("verify", "check the hash chain of an audit log")
+		.args(&cmd::opts_raw_value())
+		.args(&[
+			cmd::opt_yaml(),
+			cmd::opt_output(),
+			cmd::arg("audit-log", "the path of the audit log").required(true),
+		])
+}
+
+#[derive(Serialize, JsonSchema)]
+struct AuditLogInfo {
+	/// The number of records in the log.
+	records: u64,
+	/// The `hash` of the last record, or zeros for an empty log.
+	last_hash: String,
+}
+
+fn exec_verify<'a>(matches: &clap::ArgMatches<'a>) {
+	let path = matches.value_of("audit-log").expect("audit-log is mandatory");
+	let contents = fs::read_to_string(path)
+		.unwrap_or_else(|e| panic!("could not read audit log {}: {}", path, e));
+
+	let mut info = AuditLogInfo {
+		records: 0,
+		last_hash: GENESIS_HASH.to_owned(),
+	};
+	for (index, line) in contents.lines().enumerate() {
+		if line.trim().is_empty() {
+			continue;
+		}
+		let mut record: Value = serde_json::from_str(line)
+			.unwrap_or_else(|e| panic!("invalid record on line {}: {}", index + 1, e));
+		let hash = match record.as_object_mut().and_then(|r| r.remove("hash")) {
+			Some(Value::String(hash)) => hash,
+			_ => panic!("record on line {} has no hash", index + 1),
+		};
+		if record["seq"].as_u64() != Some(info.records) {
+			panic!(
+				"record on line {} has seq {}, expected {}",
+				index + 1,
+				record["seq"],
+				info.records
+			);
+		}
+		if record["prev"].as_str() != Some(info.last_hash.as_str()) {
+			panic!("record on line {} does not chain from the previous record", index + 1);
+		}
+		if sha256::Hash::hash(record.to_string().as_bytes()).to_string() != hash {
+			panic!("record on line {} does not match its hash", index + 1);
+		}
+		info.records += 1;
+		info.last_hash = hash;
+	}
+
+	cmd::print_output(matches, &info)
+}
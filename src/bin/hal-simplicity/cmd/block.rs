@@ -4,9 +4,14 @@ use elements::encode::{deserialize, serialize};
 use elements::{dynafed, Block, BlockExtData, BlockHeader};
 
 use crate::cmd;
+use crate::cmd::address_book::AddressBook;
 use crate::cmd::tx::create_transaction;
-use hal_simplicity::block::{BlockHeaderInfo, BlockInfo, ParamsInfo, ParamsType};
+use hal_simplicity::block::{
+	BlockHeaderInfo, BlockInfo, Coinbase, CoinbaseInfo, ParamsInfo, ParamsType,
+};
 use log::warn;
+use schemars::schema::RootSchema;
+use schemars::JsonSchema;
 
 pub fn subcommand<'a>() -> clap::App<'a, 'a> {
 	cmd::subcommand_group("block", "manipulate blocks")
@@ -14,6 +19,22 @@ pub fn subcommand<'a>() -> clap::App<'a, 'a> {
 		.subcommand(cmd_decode())
 }
 
+/// The output of `block decode`, which is only the header unless `--txids`,
+/// `--address-book` or `--coinbase` is given.
+#[derive(JsonSchema)]
+#[serde(untagged)]
+#[allow(dead_code)]
+enum BlockDecodeInfo {
+	Header(BlockHeaderInfo),
+	Block(BlockInfo),
+	Coinbase(CoinbaseInfo),
+}
+
+/// The schemas of the outputs of the subcommands, by subcommand name.
+pub fn schemas() -> Vec<(&'static str, RootSchema)> {
+	vec![("decode", schemars::schema_for!(BlockDecodeInfo))]
+}
+
 pub fn execute<'a>(matches: &clap::ArgMatches<'a>) {
 	match matches.subcommand() {
 		("create", Some(m)) => exec_create(m),
@@ -24,7 +45,8 @@ pub fn execute<'a>(matches: &clap::ArgMatches<'a>) {
 
 fn cmd_create<'a>() -> clap::App<'a, 'a> {
 	cmd::subcommand("create", "create a raw block from JSON").args(&[
-		cmd::arg("block-info", "the block info in JSON").required(false),
+		cmd::arg("block-info", "the block info in JSON or YAML").required(false),
+		cmd::opt_input_format(),
 		cmd::opt("raw-stdout", "output the raw bytes of the result to stdout")
 			.short("r")
 			.required(false),
@@ -91,8 +113,7 @@ fn create_block_header(info: BlockHeaderInfo) -> BlockHeader {
 }
 
 fn exec_create<'a>(matches: &clap::ArgMatches<'a>) {
-	let info = serde_json::from_str::<BlockInfo>(&cmd::arg_or_stdin(matches, "block-info"))
-		.expect("invaid json JSON input");
+	let info = cmd::parse_input::<BlockInfo>(matches, "block-info");
 
 	if info.txids.is_some() {
 		warn!("Field \"txids\" is ignored.");
@@ -113,42 +134,98 @@ fn exec_create<'a>(matches: &clap::ArgMatches<'a>) {
 
 	let block_bytes = serialize(&block);
 	if matches.is_present("raw-stdout") {
-		::std::io::stdout().write_all(&block_bytes).unwrap();
+		cmd::Output.write_all(&block_bytes).unwrap();
 	} else {
-		print!("{}", hex::encode(&block_bytes));
+		write!(cmd::Output, "{}", hex::encode(&block_bytes)).unwrap();
 	}
 }
 
 fn cmd_decode<'a>() -> clap::App<'a, 'a> {
-	cmd::subcommand("decode", "decode a raw block to JSON").args(&cmd::opts_networks()).args(&[
-		cmd::opt_yaml(),
-		cmd::arg("raw-block", "the raw block in hex").required(false),
-		cmd::opt("txids", "provide transactions IDs instead of full transactions"),
-	])
+	cmd::subcommand("decode", "decode a raw block to JSON")
+		.args(&cmd::opts_networks())
+		.args(&cmd::opts_raw_value())
+		.args(&[
+			cmd::opt_yaml(),
+			cmd::opt_output(),
+			cmd::opt_abbrev(),
+			cmd::opt_ndjson(),
+			cmd::opt_keep_going(),
+			cmd::arg("raw-block", "the raw blocks or headers in hex, base64 or as file paths")
+				.multiple(true)
+				.required(false),
+			cmd::opt("txids", "provide transactions IDs instead of full transactions"),
+			cmd::opt_address_book().conflicts_with("txids"),
+			cmd::opt(
+				"coinbase",
+				"provide only the coinbase transaction, with its height, fees and commitments",
+			)
+			.conflicts_with("txids"),
+		])
 }
 
 fn exec_decode<'a>(matches: &clap::ArgMatches<'a>) {
-	let hex_tx = cmd::arg_or_stdin(matches, "raw-block");
-	let raw_tx = hex::decode(hex_tx.as_ref()).expect("could not decode raw block hex");
-
-	if matches.is_present("txids") {
-		let block: Block = deserialize(&raw_tx).expect("invalid block format");
-		let info = BlockInfo {
-			header: crate::GetInfo::get_info(&block.header, cmd::network(matches)),
-			txids: Some(block.txdata.iter().map(|t| t.txid()).collect()),
-			transactions: None,
-			raw_transactions: None,
-		};
-		cmd::print_output(matches, &info)
-	} else {
-		let header: BlockHeader = match deserialize(&raw_tx) {
-			Ok(header) => header,
-			Err(_) => {
-				let block: Block = deserialize(&raw_tx).expect("invalid block format");
-				block.header
+	let raw_blocks = cmd::Batch::args_or_stdin(matches, "raw-block");
+
+	if matches.is_present("coinbase") {
+		let address_book = AddressBook::load(matches);
+		cmd::print_batch(matches, raw_blocks, |raw_block| {
+			let raw_block = cmd::decode_binary(&raw_block, "raw-block");
+			let block: Block = deserialize(&raw_block)
+				.expect("invalid block format, --coinbase needs full blocks");
+			let coinbase = block.txdata.first().expect("the block has no transactions");
+			let mut info = Coinbase(coinbase)
+				.try_get_info(cmd::network(matches))
+				.unwrap_or_else(|e| panic!("{}", e));
+			if let Some(ref address_book) = address_book {
+				address_book.annotate(coinbase, &mut info.transaction);
+			}
+			info
+		})
+	} else if matches.is_present("txids") {
+		cmd::print_batch(matches, raw_blocks, |raw_block| {
+			let raw_block = cmd::decode_binary(&raw_block, "raw-block");
+			let block: Block = deserialize(&raw_block).expect("invalid block format");
+			BlockInfo {
+				header: crate::GetInfo::get_info(&block.header, cmd::network(matches)),
+				txids: Some(block.txdata.iter().map(|t| t.txid()).collect()),
+				transactions: None,
+				raw_transactions: None,
 			}
-		};
-		let info = crate::GetInfo::get_info(&header, cmd::network(matches));
-		cmd::print_output(matches, &info)
+		})
+	} else if let Some(address_book) = AddressBook::load(matches) {
+		cmd::print_batch(matches, raw_blocks, |raw_block| {
+			let raw_block = cmd::decode_binary(&raw_block, "raw-block");
+			let block: Block = deserialize(&raw_block)
+				.expect("invalid block format, --address-book needs full blocks");
+			let network = cmd::network(matches);
+			BlockInfo {
+				header: crate::GetInfo::get_info(&block.header, network),
+				transactions: Some(
+					block
+						.txdata
+						.iter()
+						.map(|tx| {
+							let mut info = crate::GetInfo::get_info(tx, network);
+							address_book.annotate(tx, &mut info);
+							info
+						})
+						.collect(),
+				),
+				txids: None,
+				raw_transactions: None,
+			}
+		})
+	} else {
+		cmd::print_batch(matches, raw_blocks, |raw_block| {
+			let raw_block = cmd::decode_binary(&raw_block, "raw-block");
+			let header: BlockHeader = match deserialize(&raw_block) {
+				Ok(header) => header,
+				Err(_) => {
+					let block: Block = deserialize(&raw_block).expect("invalid block format");
+					block.header
+				}
+			};
+			crate::GetInfo::get_info(&header, cmd::network(matches))
+		})
 	}
 }
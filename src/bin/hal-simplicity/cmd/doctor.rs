@@ -0,0 +1,239 @@
+use std::io::Read;
+use std::sync::Arc;
+
+use elements::bitcoin::blockdata::constants::genesis_block;
+use elements::bitcoin::secp256k1::{self, ecdsa, schnorr, Keypair, Message, SecretKey};
+use elements::encode::deserialize;
+use elements::hashes::sha256;
+use elements::taproot::ControlBlock;
+use elements::Transaction;
+use hal_simplicity::hal_simplicity::{control_block, elements_address};
+use hal_simplicity::policy::parse_policy;
+use hal_simplicity::simplicity::jet::elements::ElementsEnv;
+use hal_simplicity::simplicity::Cmr;
+use log::info;
+use schemars::schema::RootSchema;
+use schemars::JsonSchema;
+use serde::Serialize;
+
+use crate::cmd;
+use crate::cmd::rpc::{self, RpcClient};
+use crate::cmd::simplicity::{parse_utxo, LIQUID_GENESIS_HASH};
+
+/// The secret key, message and signature of the first BIP-340 test vector,
+/// signed with all-zero auxiliary randomness.
+const BIP340_SECRET_KEY: &str = "0000000000000000000000000000000000000000000000000000000000000003";
+const BIP340_MESSAGE: [u8; 32] = [0; 32];
+const BIP340_SIGNATURE: &str = "e907831f80848d1069a5371b402410364bdf1c5f8307b0084c55f1ce2dca821525f66a4a85ea8b71e482a74f382d2ce5ebeee8fdb2172f477df4900d310536c0";
+
+/// A policy with its known CMR, address and control block.
+const VECTOR_POLICY: &str = "thresh(1,pk(9bef8d556d80e43ae7e0becb3a7e6838b95defe45896ed6075bb9035d06c9964),pk(f22ce68b963eab1522c3b19e3a53f20f19ea6ed61577c565956111bafe8e7cef))";
+const VECTOR_CMR: &str = "a3d5258f85377454e6929bdbee3cc808c2e659863ee8b1c674dc6203e0492fe7";
+const VECTOR_ADDRESS: &str = "ex1pjukxxz5p4d96ku50xy5r926tguym5mf6tjjvuetpz4negzvhr3uqt9w2cg";
+const VECTOR_CONTROL_BLOCK: &str =
+	"bff5919fa64ce45f8306849072b26c1bfdd2937e6b81774796ff372bd1eb5362d2";
+
+/// A Liquid coinbase transaction, with the known sighash of its input as if it spent
+/// `VECTOR_UTXO`, an output paying to `VECTOR_POLICY`.
+const VECTOR_TX: &str = "0200000001010000000000000000000000000000000000000000000000000000000000000000ffffffff0603a730180101ffffffff03016d521c38ec1ea15734ae22b7c46064412829c0d0579f0a713d1c04ede979026f01000000000000000000266a240a8ce26fdbb51a2d03d4e62fdafd4a06dd7faa0d1c083aa7e27905000000000000000000016d521c38ec1ea15734ae22b7c46064412829c0d0579f0a713d1c04ede979026f010000000000000106001976a914fc26751a5025129a2fd006c6fbfa598ddd67f7e188ac016d521c38ec1ea15734ae22b7c46064412829c0d0579f0a713d1c04ede979026f01000000000000000000266a24aa21a9ede8497768bc893ee587244bf5303ac3cf482bab8e4b3fd22e8b114c2a52525ab30000000000000120000000000000000000000000000000000000000000000000000000000000000000000000000000";
+const VECTOR_UTXO: &str = "512096ec63185c1aebdd3f1c3fe8d87d9f27be14b7fc1eccc38d06059ba0c1c71e3c:6f0279e9ed041c3d710a9f57d0c02928416460c4b722ae3457a11eec381c526d:100000";
+const VECTOR_SIGHASH: &str = "34783b35f568dbe7b2eb818f46a1db268902d4224163412e21b94c726060f46b";
+
+/// The genesis block hashes of the mainchains of the supported networks.
+const BITCOIN_GENESIS_HASH: &str =
+	"000000000019d6689c085ae165831e934ff763ae46a2a6c172b3f1b60a8ce26f";
+const REGTEST_GENESIS_HASH: &str =
+	"0f9188f13cb7b2c71f2a335e3a4fc328bf5beb436012afca590b1a11466e2206";
+
+pub fn subcommand<'a>() -> clap::App<'a, 'a> {
+	cmd::subcommand("doctor", "run self-checks of the cryptography, known vectors and connectivity")
+		.args(&cmd::opts_raw_value())
+		.args(&rpc::opts_auth())
+		.args(&[
+			cmd::opt_yaml(),
+			cmd::opt_output(),
+			cmd::opt("rpc", "also check the JSON-RPC interface of the node at the given URL")
+				.takes_value(true)
+				.required(false),
+			cmd::opt("esplora", "also check the Esplora API at the given URL")
+				.takes_value(true)
+				.required(false),
+		])
+}
+
+pub fn schema() -> RootSchema {
+	schemars::schema_for!(DoctorReport)
+}
+
+#[derive(Serialize, JsonSchema)]
+struct CheckResult {
+	name: String,
+	passed: bool,
+	/// What was found, or why the check failed.
+	detail: String,
+}
+
+#[derive(Serialize, JsonSchema)]
+struct DoctorReport {
+	checks: Vec<CheckResult>,
+	/// Whether all checks passed.
+	passed: bool,
+}
+
+/// Fail the check with the given message unless the values are equal.
+fn check_eq<T: PartialEq + std::fmt::Display>(
+	what: &str,
+	found: T,
+	expected: T,
+) -> Result<(), String> {
+	if found == expected {
+		Ok(())
+	} else {
+		Err(format!("{} is {}, expected {}", what, found, expected))
+	}
+}
+
+/// Sign and verify the BIP-340 vector, and an ECDSA signature.
+fn check_secp() -> Result<String, String> {
+	let sk: SecretKey = BIP340_SECRET_KEY.parse().expect("constant secret key");
+	let keypair = Keypair::from_secret_key(secp256k1::SECP256K1, &sk);
+	let msg = Message::from_digest(BIP340_MESSAGE);
+	let sig = secp256k1::SECP256K1.sign_schnorr_with_aux_rand(&msg, &keypair, &[0; 32]);
+	let expected: schnorr::Signature = BIP340_SIGNATURE.parse().expect("constant signature");
+	check_eq("BIP-340 signature", sig, expected)?;
+	secp256k1::SECP256K1
+		.verify_schnorr(&sig, &msg, &keypair.x_only_public_key().0)
+		.map_err(|e| format!("BIP-340 verification failed: {}", e))?;
+
+	let sig: ecdsa::Signature = secp256k1::SECP256K1.sign_ecdsa(&msg, &sk);
+	secp256k1::SECP256K1
+		.verify_ecdsa(&msg, &sig, &keypair.public_key())
+		.map_err(|e| format!("ECDSA verification failed: {}", e))?;
+	Ok("BIP-340 and ECDSA signatures verified".to_owned())
+}
+
+/// Recompute the CMR, address and control block of a known policy.
+fn check_cmr() -> Result<String, String> {
+	let policy = parse_policy(VECTOR_POLICY).map_err(|e| e.to_string())?;
+	let cmr = policy.commit().ok_or("the vector policy does not compile")?.cmr();
+	check_eq("CMR", cmr, VECTOR_CMR.parse().expect("constant CMR"))?;
+	check_eq(
+		"address",
+		elements_address(cmr, &elements::AddressParams::LIQUID).to_string(),
+		VECTOR_ADDRESS.to_owned(),
+	)?;
+	check_eq(
+		"control block",
+		hex::encode(control_block(cmr).serialize()),
+		VECTOR_CONTROL_BLOCK.to_owned(),
+	)?;
+	Ok(format!("CMR {} recomputed", cmr))
+}
+
+/// Recompute the sighash of a known Simplicity input.
+fn check_sighash() -> Result<String, String> {
+	let tx: Transaction =
+		deserialize(&hex::decode(VECTOR_TX).expect("constant tx")).expect("constant tx");
+	let cmr: Cmr = VECTOR_CMR.parse().expect("constant CMR");
+	let control_block =
+		ControlBlock::from_slice(&hex::decode(VECTOR_CONTROL_BLOCK).expect("constant hex"))
+			.expect("constant control block");
+	let env = ElementsEnv::new(
+		Arc::new(tx),
+		vec![parse_utxo(VECTOR_UTXO)],
+		0,
+		cmr,
+		control_block,
+		None,
+		LIQUID_GENESIS_HASH.parse().expect("constant genesis hash"),
+	);
+	let sighash = env.c_tx_env().sighash_all();
+	check_eq(
+		"sighash",
+		sighash,
+		VECTOR_SIGHASH.parse::<sha256::Hash>().expect("constant sighash"),
+	)?;
+	Ok(format!("sighash {} recomputed", sighash))
+}
+
+/// Recompute the genesis hashes of the mainchains. The Liquid one commits to the
+/// federation's scripts, so only a `liquidv1` node can check it.
+fn check_genesis_hashes() -> Result<String, String> {
+	for (network, expected) in [
+		(elements::bitcoin::Network::Bitcoin, BITCOIN_GENESIS_HASH),
+		(elements::bitcoin::Network::Regtest, REGTEST_GENESIS_HASH),
+	] {
+		check_eq(
+			&format!("the {} genesis hash", network),
+			genesis_block(network).block_hash().to_string(),
+			expected.to_owned(),
+		)?;
+	}
+	Ok("bitcoin and regtest genesis hashes recomputed".to_owned())
+}
+
+/// Query the node's chain and check its genesis hash if it is Liquid.
+fn check_rpc(rpc: &RpcClient) -> Result<String, String> {
+	let info = rpc.call("getblockchaininfo", serde_json::json!([]))?;
+	let chain = info["chain"].as_str().unwrap_or_default().to_owned();
+	let genesis = rpc.call("getblockhash", serde_json::json!([0]))?;
+	let genesis = genesis.as_str().unwrap_or_default();
+	if chain == "liquidv1" {
+		check_eq("the node's genesis hash", genesis, LIQUID_GENESIS_HASH)?;
+	}
+	Ok(format!("{} at height {}, genesis {}", chain, info["blocks"], genesis))
+}
+
+/// Query the tip and the genesis block of Esplora.
+fn check_esplora(url: &str) -> Result<String, String> {
+	let url = url.trim_end_matches('/');
+	let get = |path: &str| -> Result<String, String> {
+		let url = format!("{}{}", url, path);
+		info!("Querying {}", url);
+		let mut body = String::new();
+		ureq::get(&url)
+			.call()
+			.map_err(|e| format!("Esplora request {} failed: {}", url, e))?
+			.into_reader()
+			.read_to_string(&mut body)
+			.map_err(|e| format!("invalid Esplora response from {}: {}", url, e))?;
+		Ok(body.trim().to_owned())
+	};
+	let height = get("/blocks/tip/height")?;
+	let genesis = get("/block-height/0")?;
+	Ok(format!("tip at height {}, genesis {}", height, genesis))
+}
+
+/// Run a check, turning a panic into a failure.
+fn run(name: &str, check: impl FnOnce() -> Result<String, String>) -> CheckResult {
+	let result = cmd::catch_silently(check).and_then(|result| result);
+	CheckResult {
+		name: name.to_owned(),
+		passed: result.is_ok(),
+		detail: result.unwrap_or_else(|e| e),
+	}
+}
+
+pub fn execute<'a>(matches: &clap::ArgMatches<'a>) {
+	let mut checks = vec![
+		run("secp256k1", check_secp),
+		run("cmr", check_cmr),
+		run("sighash", check_sighash),
+		run("genesis-hashes", check_genesis_hashes),
+	];
+	if let Some(url) = matches.value_of("rpc") {
+		checks.push(run("rpc", || check_rpc(&RpcClient::new(url, matches))));
+	}
+	if let Some(url) = matches.value_of("esplora") {
+		checks.push(run("esplora", || check_esplora(url)));
+	}
+
+	let report = DoctorReport {
+		passed: checks.iter().all(|check| check.passed),
+		checks,
+	};
+	cmd::print_output(matches, &report);
+	if !report.passed {
+		cmd::exit(1);
+	}
+}
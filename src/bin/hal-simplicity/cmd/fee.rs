@@ -0,0 +1,221 @@
+use std::io::Read;
+
+use elements::encode::deserialize;
+use elements::hashes::Hash;
+use elements::{Block, BlockHash};
+use log::info;
+use schemars::schema::RootSchema;
+use schemars::JsonSchema;
+use serde::Serialize;
+
+use crate::cmd;
+use crate::cmd::rpc::{self, RpcClient};
+
+/// The minimum relay feerate of Elements, in sat/vB, suggested when no transaction is found.
+const MIN_FEERATE: f64 = 0.1;
+
+pub fn subcommand<'a>() -> clap::App<'a, 'a> {
+	cmd::subcommand_group("fee", "fee estimation").subcommand(cmd_estimate())
+}
+
+/// The schemas of the outputs of the subcommands, by subcommand name.
+pub fn schemas() -> Vec<(&'static str, RootSchema)> {
+	vec![("estimate", schemars::schema_for!(FeeEstimate))]
+}
+
+pub fn execute<'a>(matches: &clap::ArgMatches<'a>) {
+	match matches.subcommand() {
+		("estimate", Some(m)) => exec_estimate(m),
+		(_, _) => unreachable!("clap prints help"),
+	};
+}
+
+fn cmd_estimate<'a>() -> clap::App<'a, 'a> {
+	cmd::subcommand(
+		"estimate",
+		"suggest a feerate from the feerates paid in recent blocks, using discounted vsizes",
+	)
+	.args(&cmd::opts_networks())
+	.args(&cmd::opts_raw_value())
+	.args(&rpc::opts_auth())
+	.args(&[
+		cmd::opt_yaml(),
+		cmd::opt_output(),
+		cmd::arg(
+			"raw-block",
+			"the blocks to examine in hex, base64 or as file paths (read from stdin if omitted)",
+		)
+		.multiple(true)
+		.required(false),
+		cmd::opt("rpc", "fetch the recent blocks from the JSON-RPC interface at the given URL")
+			.takes_value(true)
+			.conflicts_with_all(&["raw-block", "esplora"]),
+		cmd::opt("esplora", "fetch the recent blocks from the Esplora API at the given URL")
+			.takes_value(true)
+			.conflicts_with("raw-block"),
+		cmd::opt("count", "the number of recent blocks to fetch with --rpc or --esplora")
+			.short("n")
+			.takes_value(true)
+			.default_value("6"),
+		cmd::opt("percentile", "the feerate percentile to suggest")
+			.takes_value(true)
+			.default_value("50"),
+	])
+}
+
+/// The feerate percentiles, weighted by discounted vsize, in sat/vB.
+#[derive(Serialize, JsonSchema)]
+struct FeeratePercentiles {
+	p10: f64,
+	p25: f64,
+	p50: f64,
+	p75: f64,
+	p90: f64,
+}
+
+#[derive(Serialize, JsonSchema)]
+struct FeeEstimate {
+	/// The number of blocks examined.
+	blocks: usize,
+	/// The number of transactions examined, which pay a fee in the policy asset.
+	transactions: usize,
+	#[serde(skip_serializing_if = "Option::is_none")]
+	min_feerate: Option<f64>,
+	#[serde(skip_serializing_if = "Option::is_none")]
+	max_feerate: Option<f64>,
+	#[serde(skip_serializing_if = "Option::is_none")]
+	percentiles: Option<FeeratePercentiles>,
+	/// The suggested feerate in sat/vB, at least the minimum relay feerate.
+	feerate: f64,
+}
+
+/// Round a feerate to a thousandth of sat/vB.
+fn round(feerate: f64) -> f64 {
+	(feerate * 1000.0).round() / 1000.0
+}
+
+/// The feerate at the given percentile of feerates weighted by vsize,
+/// given sorted (feerate, vsize) pairs.
+fn percentile(feerates: &[(f64, usize)], percentile: f64) -> f64 {
+	let total = feerates.iter().map(|(_, vsize)| vsize).sum::<usize>() as f64;
+	let threshold = total * percentile / 100.0;
+	let mut cumulative = 0.0;
+	for (feerate, vsize) in feerates {
+		cumulative += *vsize as f64;
+		if cumulative >= threshold {
+			return *feerate;
+		}
+	}
+	feerates.last().map(|(feerate, _)| *feerate).unwrap_or(MIN_FEERATE)
+}
+
+/// Fetch the last `count` blocks from the node, the most recent first.
+fn rpc_blocks(rpc: &RpcClient, count: usize) -> Vec<Block> {
+	let tip =
+		rpc.call("getbestblockhash", serde_json::json!([])).unwrap_or_else(|e| panic!("{}", e));
+	let mut hash: BlockHash = serde_json::from_value(tip).expect("invalid getbestblockhash result");
+	let mut blocks = Vec::with_capacity(count);
+	while blocks.len() < count && hash != BlockHash::all_zeros() {
+		info!("Fetching block {}", hash);
+		let raw =
+			rpc.raw("getblock", serde_json::json!([hash, 0])).unwrap_or_else(|e| panic!("{}", e));
+		let block: Block = deserialize(&raw).expect("invalid block returned by getblock");
+		hash = block.header.prev_blockhash;
+		blocks.push(block);
+	}
+	blocks
+}
+
+/// Fetch the last `count` blocks from Esplora, the most recent first.
+fn esplora_blocks(url: &str, count: usize) -> Vec<Block> {
+	let url = url.trim_end_matches('/');
+	let get = |path: String| -> Vec<u8> {
+		let url = format!("{}{}", url, path);
+		info!("Querying {}", url);
+		let mut body = Vec::new();
+		ureq::get(&url)
+			.call()
+			.unwrap_or_else(|e| panic!("Esplora request {} failed: {}", url, e))
+			.into_reader()
+			.read_to_end(&mut body)
+			.unwrap_or_else(|e| panic!("invalid Esplora response from {}: {}", url, e));
+		body
+	};
+
+	let tip = String::from_utf8(get("/blocks/tip/hash".to_owned())).unwrap_or_default();
+	let mut hash: BlockHash = tip.trim().parse().expect("invalid tip hash returned by Esplora");
+	let mut blocks = Vec::with_capacity(count);
+	while blocks.len() < count && hash != BlockHash::all_zeros() {
+		let block: Block = deserialize(&get(format!("/block/{}/raw", hash)))
+			.expect("invalid block returned by Esplora");
+		hash = block.header.prev_blockhash;
+		blocks.push(block);
+	}
+	blocks
+}
+
+fn exec_estimate<'a>(matches: &clap::ArgMatches<'a>) {
+	let count: usize =
+		matches.value_of("count").expect("count has a default").parse().expect("invalid count");
+	let target: f64 = matches
+		.value_of("percentile")
+		.expect("percentile has a default")
+		.parse()
+		.expect("invalid percentile");
+	if !(0.0..=100.0).contains(&target) {
+		panic!("percentile must be between 0 and 100");
+	}
+
+	let blocks = if let Some(url) = matches.value_of("rpc") {
+		rpc_blocks(&RpcClient::new(url, matches), count)
+	} else if let Some(url) = matches.value_of("esplora") {
+		esplora_blocks(url, count)
+	} else {
+		cmd::args_or_stdin(matches, "raw-block")
+			.iter()
+			.map(|raw_block| {
+				let raw_block = cmd::decode_binary(raw_block, "raw-block");
+				deserialize::<Block>(&raw_block).expect("invalid block format")
+			})
+			.collect()
+	};
+
+	// Transactions without fee outputs in the policy asset, such as coinbases, don't say
+	// anything about the market. Amounts of different assets can't be added up.
+	let asset = cmd::policy_asset(cmd::network(matches));
+	let mut feerates = blocks
+		.iter()
+		.flat_map(|block| block.txdata.iter())
+		.filter(|tx| !tx.is_coinbase())
+		.filter_map(|tx| {
+			let fee = tx.all_fees().get(&asset).copied().filter(|fee| *fee > 0)?;
+			let vsize = tx.discount_vsize();
+			Some((fee as f64 / vsize as f64, vsize))
+		})
+		.collect::<Vec<_>>();
+	feerates.sort_by(|a, b| a.0.total_cmp(&b.0));
+
+	let estimate = FeeEstimate {
+		blocks: blocks.len(),
+		transactions: feerates.len(),
+		min_feerate: feerates.first().map(|(feerate, _)| round(*feerate)),
+		max_feerate: feerates.last().map(|(feerate, _)| round(*feerate)),
+		percentiles: if feerates.is_empty() {
+			None
+		} else {
+			Some(FeeratePercentiles {
+				p10: round(percentile(&feerates, 10.0)),
+				p25: round(percentile(&feerates, 25.0)),
+				p50: round(percentile(&feerates, 50.0)),
+				p75: round(percentile(&feerates, 75.0)),
+				p90: round(percentile(&feerates, 90.0)),
+			})
+		},
+		feerate: if feerates.is_empty() {
+			MIN_FEERATE
+		} else {
+			round(percentile(&feerates, target).max(MIN_FEERATE))
+		},
+	};
+	cmd::print_output(matches, &estimate)
+}
@@ -0,0 +1,107 @@
+use hal_simplicity::simplicity::ffi::ffi::BUDGET_MAX;
+use hal_simplicity::simplicity::jet::Elements;
+use hal_simplicity::Network;
+use schemars::schema::RootSchema;
+use schemars::JsonSchema;
+use serde::Serialize;
+
+use crate::cmd;
+
+pub fn subcommand<'a>() -> clap::App<'a, 'a> {
+	cmd::subcommand("info", "report the version and capabilities of hal-simplicity")
+		.args(&cmd::opts_raw_value())
+		.args(&[cmd::opt_yaml(), cmd::opt_output()])
+}
+
+pub fn schema() -> RootSchema {
+	schemars::schema_for!(InfoReport)
+}
+
+#[derive(Serialize, JsonSchema)]
+struct DependencyInfo {
+	version: String,
+	/// Where the dependency was taken from, as recorded in Cargo.lock.
+	source: String,
+	/// The commit of the dependency if it was taken from a git repository.
+	#[serde(skip_serializing_if = "Option::is_none")]
+	commit: Option<String>,
+}
+
+impl DependencyInfo {
+	fn new(version: &str, source: &str) -> Option<DependencyInfo> {
+		if version.is_empty() {
+			return None;
+		}
+		Some(DependencyInfo {
+			version: version.to_owned(),
+			source: source.to_owned(),
+			commit: source
+				.strip_prefix("git+")
+				.and_then(|s| s.rsplit_once('#'))
+				.map(|(_, commit)| commit.to_owned()),
+		})
+	}
+}
+
+#[derive(Serialize, JsonSchema)]
+struct JetFamilyInfo {
+	name: String,
+	/// The number of jets in the family.
+	jets: usize,
+}
+
+#[derive(Serialize, JsonSchema)]
+struct FeaturesInfo {
+	/// Whether hal-simplicity was built for WebAssembly.
+	wasm: bool,
+}
+
+#[derive(Serialize, JsonSchema)]
+struct ConsensusInfo {
+	/// The taproot leaf version of Simplicity programs.
+	tapleaf_version: u8,
+	/// The maximum cost of a program allowed by consensus, in milliweight units.
+	max_cost: u64,
+}
+
+#[derive(Serialize, JsonSchema)]
+struct InfoReport {
+	version: String,
+	/// The git commit hal-simplicity was built from, if known.
+	#[serde(skip_serializing_if = "Option::is_none")]
+	git_commit: Option<String>,
+	#[serde(skip_serializing_if = "Option::is_none")]
+	simplicity: Option<DependencyInfo>,
+	#[serde(skip_serializing_if = "Option::is_none")]
+	elements: Option<DependencyInfo>,
+	jet_families: Vec<JetFamilyInfo>,
+	networks: Vec<Network>,
+	features: FeaturesInfo,
+	consensus: ConsensusInfo,
+}
+
+pub fn execute<'a>(matches: &clap::ArgMatches<'a>) {
+	let info = InfoReport {
+		version: clap::crate_version!().to_owned(),
+		git_commit: Some(env!("HAL_GIT_COMMIT")).filter(|c| !c.is_empty()).map(str::to_owned),
+		simplicity: DependencyInfo::new(
+			env!("HAL_SIMPLICITY_VERSION"),
+			env!("HAL_SIMPLICITY_SOURCE"),
+		),
+		elements: DependencyInfo::new(env!("HAL_ELEMENTS_VERSION"), env!("HAL_ELEMENTS_SOURCE")),
+		jet_families: vec![JetFamilyInfo {
+			name: "elements".to_owned(),
+			jets: Elements::ALL.len(),
+		}],
+		networks: vec![Network::ElementsRegtest, Network::Liquid, Network::LiquidTestnet],
+		features: FeaturesInfo {
+			wasm: cfg!(target_arch = "wasm32"),
+		},
+		consensus: ConsensusInfo {
+			tapleaf_version: hal_simplicity::simplicity::leaf_version().as_u8(),
+			// The budget is in weight units.
+			max_cost: u64::from(BUDGET_MAX) * 1000,
+		},
+	};
+	cmd::print_output(matches, &info)
+}
@@ -1,41 +1,248 @@
 use clap;
+use elements::bitcoin::secp256k1::ecdsa::{RecoverableSignature, RecoveryId};
 use elements::bitcoin::secp256k1::{self, rand};
+use elements::bitcoin::{self, PrivateKey};
+use elements::hashes::{hash160, Hash};
+use hal_simplicity::Network;
+use log::info;
+use schemars::schema::RootSchema;
+use schemars::JsonSchema;
 
 use crate::cmd;
 
 pub fn subcommand<'a>() -> clap::App<'a, 'a> {
 	cmd::subcommand_group("keypair", "manipulate private and public keys")
 		.subcommand(cmd_generate())
+		.subcommand(cmd_grind())
+		.subcommand(cmd_recover())
+}
+
+/// The schemas of the outputs of the subcommands, by subcommand name.
+pub fn schemas() -> Vec<(&'static str, RootSchema)> {
+	vec![
+		("generate", schemars::schema_for!(KeypairInfo)),
+		("grind", schemars::schema_for!(GrindInfo)),
+		("recover", schemars::schema_for!(Vec<RecoveredKey>)),
+	]
 }
 
 pub fn execute<'a>(matches: &clap::ArgMatches<'a>) {
 	match matches.subcommand() {
 		("generate", Some(m)) => exec_generate(m),
+		("grind", Some(m)) => exec_grind(m),
+		("recover", Some(m)) => exec_recover(m),
 		(_, _) => unreachable!("clap prints help"),
 	};
 }
 
 fn cmd_generate<'a>() -> clap::App<'a, 'a> {
-	cmd::subcommand("generate", "generate a random private/public keypair").args(&[cmd::opt_yaml()])
+	cmd::subcommand("generate", "generate a random private/public keypair")
+		.args(&cmd::opts_networks())
+		.args(&cmd::opts_raw_value())
+		.args(&[
+			cmd::opt_yaml(),
+			cmd::opt_output(),
+			cmd::opt("even-parity", "regenerate the keypair until its public key has even parity")
+				.takes_value(false)
+				.required(false),
+		])
+}
+
+#[derive(serde::Serialize, JsonSchema)]
+struct KeypairInfo {
+	#[schemars(with = "String")]
+	secret: secp256k1::SecretKey,
+	#[schemars(with = "String")]
+	x_only: secp256k1::XOnlyPublicKey,
+	#[schemars(with = "u8")]
+	parity: secp256k1::Parity,
+	/// The x-only public key as a descriptor key expression with its fingerprint as origin,
+	/// e.g. for `eltr(...)`.
+	descriptor_public_key: String,
+	/// The secret key as a descriptor key expression, in WIF with its fingerprint as origin.
+	descriptor_secret_key: String,
+}
+
+impl KeypairInfo {
+	fn new(secret: secp256k1::SecretKey, network: Network) -> Self {
+		let public = secret.public_key(secp256k1::SECP256K1);
+		let (x_only, parity) = public.x_only_public_key();
+		let fingerprint = &hash160::Hash::hash(&public.serialize())[..4];
+		let origin = format!("[{}]", hex::encode(fingerprint));
+		// WIF only tells mainnet from testnet keys, and Liquid uses the mainnet prefix.
+		let wif_network = match network {
+			Network::Liquid => bitcoin::Network::Bitcoin,
+			Network::ElementsRegtest | Network::LiquidTestnet => bitcoin::Network::Testnet,
+		};
+		KeypairInfo {
+			secret,
+			x_only,
+			parity,
+			descriptor_public_key: format!("{}{}", origin, x_only),
+			descriptor_secret_key: format!("{}{}", origin, PrivateKey::new(secret, wif_network)),
+		}
+	}
+
+	fn random(network: Network) -> Self {
+		KeypairInfo::new(secp256k1::SecretKey::new(&mut rand::thread_rng()), network)
+	}
 }
 
 fn exec_generate<'a>(matches: &clap::ArgMatches<'a>) {
-	#[derive(serde::Serialize)]
-	struct Res {
-		secret: secp256k1::SecretKey,
-		x_only: secp256k1::XOnlyPublicKey,
-		parity: secp256k1::Parity,
+	let network = cmd::network(matches);
+	let mut keypair = KeypairInfo::random(network);
+	while matches.is_present("even-parity") && keypair.parity == secp256k1::Parity::Odd {
+		keypair = KeypairInfo::random(network);
 	}
+	cmd::print_output(matches, &keypair);
+}
 
-	let (secret, public) = secp256k1::generate_keypair(&mut rand::thread_rng());
-	let (x_only, parity) = public.x_only_public_key();
+fn cmd_grind<'a>() -> clap::App<'a, 'a> {
+	cmd::subcommand(
+		"grind",
+		"generate random keypairs until one has an even public key, optionally with a given prefix",
+	)
+	.args(&cmd::opts_networks())
+	.args(&cmd::opts_raw_value())
+	.args(&[
+		cmd::opt_yaml(),
+		cmd::opt_output(),
+		cmd::opt("prefix", "the hex digits the x-only public key must start with")
+			.takes_value(true)
+			.required(false),
+	])
+}
 
+#[derive(serde::Serialize, JsonSchema)]
+struct GrindInfo {
+	#[serde(flatten)]
+	keypair: KeypairInfo,
+	/// The number of keypairs generated.
+	attempts: u64,
+}
+
+fn exec_grind<'a>(matches: &clap::ArgMatches<'a>) {
+	let prefix = matches.value_of("prefix").unwrap_or("").to_lowercase();
+	if !prefix.chars().all(|c| c.is_ascii_hexdigit()) {
+		panic!("invalid prefix '{}', expected hex digits", prefix);
+	}
+	// Each hex digit takes 16 times longer to find.
+	if prefix.len() > 4 {
+		panic!("the prefix '{}' is too long to grind, use at most 4 hex digits", prefix);
+	}
+
+	let network = cmd::network(matches);
+	let mut attempts = 0;
+	let keypair = loop {
+		attempts += 1;
+		let keypair = KeypairInfo::random(network);
+		if keypair.x_only.to_string().starts_with(&prefix) {
+			break keypair;
+		}
+	};
+	info!("Found the keypair after {} attempts.", attempts);
+
+	// Negating the secret key flips the parity but keeps the x-only public key.
+	let keypair = match keypair.parity {
+		secp256k1::Parity::Even => keypair,
+		secp256k1::Parity::Odd => KeypairInfo::new(keypair.secret.negate(), network),
+	};
 	cmd::print_output(
 		matches,
-		&Res {
-			secret,
-			x_only,
-			parity,
+		&GrindInfo {
+			keypair,
+			attempts,
 		},
 	);
 }
+
+fn cmd_recover<'a>() -> clap::App<'a, 'a> {
+	cmd::subcommand("recover", "recover the public keys which made a compact ECDSA signature")
+		.args(&cmd::opts_raw_value())
+		.args(&[
+			cmd::opt_yaml(),
+			cmd::opt_output(),
+			cmd::opt(
+				"recovery-id",
+				"the recovery ID (0 to 3) of a 64-byte signature (default: try all of them)",
+			)
+			.takes_value(true)
+			.required(false),
+			cmd::arg(
+				"signature",
+				"the signature in hex or base64, either 64 bytes or 65 bytes starting with a recovery \
+				 header as made by signmessage",
+			)
+			.required(true),
+			cmd::arg("digest", "the signed 32-byte message digest in hex").required(true),
+		])
+}
+
+#[derive(serde::Serialize, JsonSchema)]
+struct RecoveredKey {
+	recovery_id: u8,
+	#[schemars(with = "String")]
+	public_key: secp256k1::PublicKey,
+	/// Whether the signature header says the key is compressed, if it has one.
+	#[serde(skip_serializing_if = "Option::is_none")]
+	compressed: Option<bool>,
+	#[schemars(with = "String")]
+	x_only: secp256k1::XOnlyPublicKey,
+	#[schemars(with = "u8")]
+	parity: secp256k1::Parity,
+}
+
+fn exec_recover<'a>(matches: &clap::ArgMatches<'a>) {
+	let sig = matches.value_of("signature").expect("signature is mandatory");
+	let sig = cmd::decode_binary(sig, "signature");
+	let digest = matches.value_of("digest").expect("digest is mandatory");
+	let digest: [u8; 32] = hex::decode(digest)
+		.ok()
+		.and_then(|bytes| bytes.try_into().ok())
+		.expect("invalid digest, expected 32 bytes in hex");
+	let msg = secp256k1::Message::from_digest(digest);
+
+	// The header of a 65-byte signature is 27 + the recovery ID, plus 4 for
+	// compressed keys.
+	let (sig, ids, compressed) = match sig.len() {
+		64 => {
+			let ids = match matches.value_of("recovery-id") {
+				Some(id) => vec![id.parse().expect("invalid recovery ID")],
+				None => vec![0, 1, 2, 3],
+			};
+			(&sig[..], ids, None)
+		}
+		65 => {
+			if matches.is_present("recovery-id") {
+				panic!("the recovery ID is given by the header of a 65-byte signature");
+			}
+			let header = sig[0].checked_sub(27).filter(|h| *h < 8).unwrap_or_else(|| {
+				panic!("invalid signature header {}, expected 27 to 34", sig[0])
+			});
+			(&sig[1..], vec![i32::from(header % 4)], Some(header >= 4))
+		}
+		n => panic!("invalid signature of {} bytes, expected 64 or 65", n),
+	};
+
+	let keys = ids
+		.into_iter()
+		.filter_map(|id| {
+			let recid = RecoveryId::from_i32(id).expect("invalid recovery ID, expected 0 to 3");
+			let sig =
+				RecoverableSignature::from_compact(sig, recid).expect("invalid ECDSA signature");
+			let public_key = secp256k1::SECP256K1.recover_ecdsa(&msg, &sig).ok()?;
+			let (x_only, parity) = public_key.x_only_public_key();
+			Some(RecoveredKey {
+				recovery_id: id as u8,
+				public_key,
+				compressed,
+				x_only,
+				parity,
+			})
+		})
+		.collect::<Vec<_>>();
+	if keys.is_empty() {
+		panic!("no public key can be recovered from the signature");
+	}
+	cmd::print_output(matches, &keys);
+}
@@ -1,12 +1,36 @@
 pub mod address;
+pub mod address_book;
+pub mod asset;
+pub mod audit;
 pub mod block;
+pub mod doctor;
+pub mod fee;
+pub mod info;
 pub mod keypair;
+pub mod pegin;
+pub mod protobuf;
+pub mod rpc;
+pub mod schema;
+pub mod session;
+pub mod signatures;
 pub mod simplicity;
 pub mod tx;
+pub mod vectors;
+pub mod wallet;
+pub mod watch;
 
+use std::any::Any;
 use std::borrow::Cow;
-use std::io;
-use std::io::Read;
+use std::io::{Read, Write};
+use std::path::Path;
+use std::sync::Mutex;
+use std::{fs, io, iter, panic, process};
+
+use elements::bitcoin::base64::engine::general_purpose::STANDARD as BASE64;
+use elements::bitcoin::base64::Engine;
+use flate2::write::GzEncoder;
+use log::{info, warn};
+use schemars::JsonSchema;
 
 use hal_simplicity::Network;
 
@@ -14,10 +38,21 @@ use hal_simplicity::Network;
 pub fn subcommands<'a>() -> Vec<clap::App<'a, 'a>> {
 	vec![
 		address::subcommand(),
+		asset::subcommand(),
+		audit::subcommand(),
 		block::subcommand(),
+		doctor::subcommand(),
+		fee::subcommand(),
+		info::subcommand(),
 		keypair::subcommand(),
+		pegin::subcommand(),
+		schema::subcommand(),
+		session::subcommand(),
 		simplicity::subcommand(),
 		tx::subcommand(),
+		vectors::subcommand(),
+		wallet::subcommand(),
+		watch::subcommand(),
 	]
 }
 
@@ -61,6 +96,11 @@ pub fn opts_networks<'a>() -> Vec<clap::Arg<'a, 'a>> {
 			.help("run in liquid mode")
 			.takes_value(false)
 			.required(false),
+		clap::Arg::with_name("liquidtestnet")
+			.long("liquidtestnet")
+			.help("run in liquidtestnet mode")
+			.takes_value(false)
+			.required(false),
 	]
 }
 
@@ -69,11 +109,28 @@ pub fn network<'a>(matches: &clap::ArgMatches<'a>) -> Network {
 		Network::ElementsRegtest
 	} else if matches.is_present("liquid") {
 		Network::Liquid
+	} else if matches.is_present("liquidtestnet") {
+		Network::LiquidTestnet
 	} else {
 		Network::ElementsRegtest
 	}
 }
 
+/// The policy asset of the given network, in which fees are paid and which is pegged in.
+pub fn policy_asset(network: Network) -> elements::AssetId {
+	let asset = match network {
+		// With the default chain parameters.
+		Network::ElementsRegtest => {
+			"b2e15d0d7a0c94e4e2ce0fe6e8691b9e451377f6e46e8045a86f7c4b5d4f0f23"
+		}
+		Network::Liquid => "6f0279e9ed041c3d710a9f57d0c02928416460c4b722ae3457a11eec381c526d",
+		Network::LiquidTestnet => {
+			"144c654344aa716d6f3abcc1ca90e5641e4e2a7f633bc09fe3baf64585819a49"
+		}
+	};
+	asset.parse().expect("valid asset id")
+}
+
 pub fn opt_yaml<'a>() -> clap::Arg<'a, 'a> {
 	clap::Arg::with_name("yaml")
 		.long("yaml")
@@ -83,32 +140,626 @@ pub fn opt_yaml<'a>() -> clap::Arg<'a, 'a> {
 		.required(false)
 }
 
+pub fn opt_output<'a>() -> clap::Arg<'a, 'a> {
+	clap::Arg::with_name("output")
+		.long("output")
+		.short("o")
+		.help("the output format (see `schema --proto` for the protobuf messages)")
+		.takes_value(true)
+		.possible_values(&["json", "yaml", "protobuf"])
+		.conflicts_with("yaml")
+		.required(false)
+}
+
+pub fn opt_abbrev<'a>() -> clap::Arg<'a, 'a> {
+	clap::Arg::with_name("abbrev")
+		.long("abbrev")
+		.help("abbreviate long hex fields such as rangeproofs and witnesses")
+		.takes_value(false)
+		.required(false)
+}
+
+pub fn opt_input_format<'a>() -> clap::Arg<'a, 'a> {
+	opt(
+		"input-format",
+		"the format of the input (by default JSON if it starts with '{', else YAML)",
+	)
+	.takes_value(true)
+	.possible_values(&["json", "yaml"])
+	.required(false)
+}
+
+pub fn opt_ndjson<'a>() -> clap::Arg<'a, 'a> {
+	clap::Arg::with_name("ndjson")
+		.long("ndjson")
+		.help("print one compact JSON object per line for each item instead of an array")
+		.takes_value(false)
+		.conflicts_with_all(&["yaml", "output"])
+		.required(false)
+}
+
+pub fn opt_address_book<'a>() -> clap::Arg<'a, 'a> {
+	opt(
+		"address-book",
+		"label the inputs and outputs whose scripts are in the given JSON or YAML file",
+	)
+	.takes_value(true)
+	.required(false)
+}
+
+pub fn opt_audit_log<'a>() -> clap::Arg<'a, 'a> {
+	opt("audit-log", "append a hash-chained record of the operation to the given file")
+		.takes_value(true)
+		.required(false)
+}
+
+pub fn opt_keep_going<'a>() -> clap::Arg<'a, 'a> {
+	clap::Arg::with_name("keep-going")
+		.long("keep-going")
+		.help("with --ndjson, print an error record for items that fail and continue with the rest")
+		.takes_value(false)
+		.requires("ndjson")
+		.required(false)
+}
+
+/// Options to print a single field of the output as a raw, unquoted value.
+pub fn opts_raw_value<'a>() -> Vec<clap::Arg<'a, 'a>> {
+	vec![
+		opt("raw-value", "print only the given output field (e.g. txid) as a raw value")
+			.takes_value(true)
+			.required(false),
+		opt("no-newline", "don't print a trailing newline after a raw value")
+			.takes_value(false)
+			.required(false)
+			.requires("raw-value"),
+	]
+}
+
+/// Read all of stdin, for when the named argument is not provided.
+fn read_stdin(arg: &str) -> String {
+	let mut input = Vec::new();
+	let stdin = io::stdin();
+	let mut stdin_lock = stdin.lock();
+	let _ = stdin_lock.read_to_end(&mut input);
+	while stdin_lock.read_to_end(&mut input).unwrap_or(0) > 0 {}
+	if input.is_empty() {
+		panic!("no '{}' argument given", arg);
+	}
+	String::from_utf8(input)
+		.unwrap_or_else(|e| panic!("invalid utf8 on stdin for '{}': {}", arg, e))
+		.trim()
+		.to_owned()
+}
+
 /// Get the named argument from the CLI arguments or try read from stdin if not provided.
 pub fn arg_or_stdin<'a>(matches: &'a clap::ArgMatches<'a>, arg: &str) -> Cow<'a, str> {
 	if let Some(s) = matches.value_of(arg) {
 		s.into()
 	} else {
-		// Read from stdin.
-		let mut input = Vec::new();
-		let stdin = io::stdin();
-		let mut stdin_lock = stdin.lock();
-		let _ = stdin_lock.read_to_end(&mut input);
-		while stdin_lock.read_to_end(&mut input).unwrap_or(0) > 0 {}
-		if input.is_empty() {
-			panic!("no '{}' argument given", arg);
-		}
-		String::from_utf8(input)
-			.unwrap_or_else(|e| panic!("invalid utf8 on stdin for '{}': {}", arg, e))
-			.trim()
-			.to_owned()
-			.into()
-	}
-}
-
-pub fn print_output<'a, T: serde::Serialize>(matches: &clap::ArgMatches<'a>, out: &T) {
-	if matches.is_present("yaml") {
-		serde_yaml::to_writer(::std::io::stdout(), &out).unwrap();
+		read_stdin(arg).into()
+	}
+}
+
+/// Get all values of the named argument from the CLI arguments or, if not provided,
+/// the whitespace-separated values on stdin.
+pub fn args_or_stdin<'a>(matches: &'a clap::ArgMatches<'a>, arg: &str) -> Vec<Cow<'a, str>> {
+	if let Some(values) = matches.values_of(arg) {
+		values.map(Cow::from).collect()
+	} else {
+		read_stdin(arg).split_whitespace().map(|s| Cow::from(s.to_owned())).collect()
+	}
+}
+
+/// The items processed by a batch command, given as arguments or on stdin.
+pub struct Batch<'a> {
+	/// The items, or the errors reading them from stdin.
+	items: Box<dyn Iterator<Item = Result<Cow<'a, str>, String>> + 'a>,
+	/// Whether the outputs are printed as an array without `--ndjson`, which is
+	/// the case when there is more than one item.
+	array: bool,
+}
+
+impl<'a> Batch<'a> {
+	/// The values of the named argument, if any.
+	pub fn args(matches: &'a clap::ArgMatches<'a>, arg: &str) -> Option<Self> {
+		let values = matches.values_of(arg)?.collect::<Vec<_>>();
+		Some(Batch {
+			array: values.len() > 1,
+			items: Box::new(values.into_iter().map(|v| Ok(Cow::from(v)))),
+		})
+	}
+
+	/// The values of the named argument or, if not provided, the whitespace-separated
+	/// values on stdin, which are read as they are processed.
+	///
+	/// Without `--ndjson` the second value is read before the first is processed,
+	/// to know whether the outputs are printed as an array.
+	pub fn args_or_stdin(matches: &'a clap::ArgMatches<'a>, arg: &str) -> Self {
+		if let Some(batch) = Batch::args(matches, arg) {
+			return batch;
+		}
+		let name = arg.to_owned();
+		let mut items = io::stdin()
+			.lines()
+			.flat_map(move |line| match line {
+				Ok(line) => {
+					line.split_whitespace().map(|s| Ok(Cow::from(s.to_owned()))).collect::<Vec<_>>()
+				}
+				Err(e) if e.kind() == io::ErrorKind::InvalidData => {
+					vec![Err(format!("invalid utf8 on stdin for '{}': {}", name, e))]
+				}
+				Err(e) => vec![Err(format!("could not read stdin for '{}': {}", name, e))],
+			})
+			.peekable();
+		let first = items.next().unwrap_or_else(|| panic!("no '{}' argument given", arg));
+		Batch {
+			array: !matches.is_present("ndjson") && items.peek().is_some(),
+			items: Box::new(iter::once(first).chain(items)),
+		}
+	}
+
+	/// Whether there is more than one item, known only without `--ndjson`.
+	pub fn is_array(&self) -> bool {
+		self.array
+	}
+}
+
+/// A text encoding of binary input.
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub enum TextFormat {
+	Hex,
+	Base64,
+}
+
+impl TextFormat {
+	fn name(self) -> &'static str {
+		match self {
+			TextFormat::Hex => "hex",
+			TextFormat::Base64 => "base64",
+		}
+	}
+
+	fn decode(self, input: &str) -> Option<Vec<u8>> {
+		match self {
+			TextFormat::Hex => hex::decode(input).ok(),
+			TextFormat::Base64 => BASE64.decode(input).ok(),
+		}
+	}
+
+	fn other(self) -> TextFormat {
+		match self {
+			TextFormat::Hex => TextFormat::Base64,
+			TextFormat::Base64 => TextFormat::Hex,
+		}
+	}
+}
+
+/// Decode binary input given as hex, as base64 or as the path of a binary file,
+/// reporting which of them was assumed.
+///
+/// Input which is valid hex is always taken as hex, even if it is valid base64 too.
+pub fn decode_binary(input: &str, arg: &str) -> Vec<u8> {
+	decode_binary_preferring(input, arg, TextFormat::Hex)
+}
+
+/// Decode binary input like [`decode_binary`], trying the `preferred` text format
+/// first, then a file path and then the other text format.
+///
+/// The assumed format is logged with `-v`, and as a warning if the input is valid
+/// in another format too. Hex input is not a warning just for being valid base64,
+/// which hex of a multiple of 4 digits always is.
+pub fn decode_binary_preferring(input: &str, arg: &str, preferred: TextFormat) -> Vec<u8> {
+	let is_file = Path::new(input).is_file();
+	let mut alternatives = vec![];
+	let (format, bytes) = if let Some(bytes) = preferred.decode(input) {
+		if is_file {
+			alternatives.push("the path of a file".to_owned());
+		}
+		if preferred == TextFormat::Base64 && TextFormat::Hex.decode(input).is_some() {
+			alternatives.push("valid hex".to_owned());
+		}
+		(preferred.name(), bytes)
+	} else if is_file {
+		if preferred.other().decode(input).is_some() {
+			alternatives.push(format!("valid {}", preferred.other().name()));
+		}
+		let bytes = fs::read(input).unwrap_or_else(|e| panic!("could not read {}: {}", input, e));
+		("file", bytes)
+	} else if let Some(bytes) = preferred.other().decode(input) {
+		(preferred.other().name(), bytes)
+	} else {
+		panic!("invalid '{}': expected hex, base64 or the path of a file", arg);
+	};
+	if alternatives.is_empty() {
+		info!("Assuming {} input for '{}'.", format, arg);
 	} else {
-		serde_json::to_writer_pretty(::std::io::stdout(), &out).unwrap();
+		warn!(
+			"Assuming {} input for '{}', although it is {} too.",
+			format,
+			arg,
+			alternatives.join(" and ")
+		);
+	}
+	bytes
+}
+
+/// Parse the named argument (or stdin) as JSON or YAML, according to `--input-format`.
+///
+/// Without that option, input starting with `{` (or empty input) is read as JSON and
+/// anything else as YAML.
+pub fn parse_input<'a, T: serde::de::DeserializeOwned>(
+	matches: &'a clap::ArgMatches<'a>,
+	arg: &str,
+) -> T {
+	let input = arg_or_stdin(matches, arg);
+	let yaml = match matches.value_of("input-format") {
+		Some("json") => false,
+		Some("yaml") => true,
+		Some(_) => unreachable!("clap checks possible values"),
+		None => !(input.is_empty() || input.trim_start().starts_with('{')),
+	};
+
+	if yaml {
+		// Some of our types (e.g. HexBytes) can only be deserialized from borrowed
+		// strings, which serde_yaml doesn't provide, so we go through a JSON value.
+		let value = serde_yaml::from_str::<serde_json::Value>(&input)
+			.unwrap_or_else(|e| panic!("invalid YAML provided: {}", e));
+		T::deserialize(&value).unwrap_or_else(|e| panic!("invalid YAML provided: {}", e))
+	} else {
+		serde_json::from_str(&input).unwrap_or_else(|e| panic!("invalid JSON provided: {:?}", e))
+	}
+}
+
+/// Get the single field selected by `--raw-value`, using dots to separate
+/// the keys of nested objects and the indices of arrays.
+fn raw_value<T: serde::Serialize>(field: &str, out: &T) -> String {
+	let value = serde_json::to_value(out).expect("output is always valid JSON");
+	let pointer = format!("/{}", field.replace('.', "/"));
+	match value.pointer(&pointer) {
+		Some(serde_json::Value::String(s)) => s.clone(),
+		Some(serde_json::Value::Null) | None => panic!("no field '{}' in output", field),
+		Some(other) => other.to_string(),
+	}
+}
+
+/// The file given with `--out`.
+struct OutFile {
+	writer: Box<dyn Write + Send>,
+	path: String,
+	/// The number of bytes written, before compression.
+	written: usize,
+}
+
+static OUT_FILE: Mutex<Option<OutFile>> = Mutex::new(None);
+
+/// Open the file given with `--out`, compressed with gzip if its name ends in
+/// `.gz`, so that the output is written to it instead of stdout.
+pub fn open_output<'a>(matches: &clap::ArgMatches<'a>) {
+	let path = match matches.value_of("out") {
+		Some(path) => path,
+		None => return,
+	};
+	let file = fs::File::create(path)
+		.unwrap_or_else(|e| panic!("failed to create output file {}: {}", path, e));
+	let file = io::BufWriter::new(file);
+	let writer: Box<dyn Write + Send> = if path.ends_with(".gz") {
+		Box::new(GzEncoder::new(file, flate2::Compression::default()))
+	} else {
+		Box::new(file)
+	};
+	*OUT_FILE.lock().unwrap() = Some(OutFile {
+		writer,
+		path: path.to_owned(),
+		written: 0,
+	});
+}
+
+/// Flush and close the file given with `--out`, if any.
+///
+/// Must be called before exiting since destructors don't run then, which would
+/// leave the gzip stream unfinished.
+pub fn close_output() {
+	// Don't block if the panic hook runs in the middle of a write.
+	let out = match OUT_FILE.try_lock() {
+		Ok(mut out) => out.take(),
+		Err(_) => None,
+	};
+	if let Some(mut out) = out {
+		out.writer
+			.flush()
+			.unwrap_or_else(|e| panic!("failed to write output file {}: {}", out.path, e));
+		drop(out.writer);
+		info!("Wrote {} bytes of output to {}.", out.written, out.path);
+	}
+}
+
+/// Exit the process after closing the output.
+pub fn exit(code: i32) -> ! {
+	close_output();
+	process::exit(code)
+}
+
+/// The destination of the output of commands: the file given with `--out`, or stdout.
+pub struct Output;
+
+impl Write for Output {
+	fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+		match *OUT_FILE.lock().unwrap() {
+			Some(ref mut out) => {
+				let n = out.writer.write(buf)?;
+				out.written += n;
+				Ok(n)
+			}
+			None => io::stdout().write(buf),
+		}
+	}
+
+	fn flush(&mut self) -> io::Result<()> {
+		match *OUT_FILE.lock().unwrap() {
+			Some(ref mut out) => out.writer.flush(),
+			None => io::stdout().flush(),
+		}
+	}
+}
+
+/// Print the single field selected by `--raw-value`.
+fn print_raw_value<'a, T: serde::Serialize>(matches: &clap::ArgMatches<'a>, field: &str, out: &T) {
+	let raw = raw_value(field, out);
+	if matches.is_present("no-newline") {
+		write!(Output, "{}", raw).unwrap();
+	} else {
+		writeln!(Output, "{}", raw).unwrap();
+	}
+}
+
+/// The fields shortened by `--abbrev`: rangeproofs, surjection proofs, witnesses
+/// and dynafed witness items.
+const ABBREV_FIELDS: &[&str] = &[
+	"rangeproof",
+	"surjection_proof",
+	"amount_rangeproof",
+	"inflation_keys_rangeproof",
+	"script_witness",
+	"pegin_witness",
+	"dynafed_witness",
+	"witness_hex",
+];
+
+/// Hex strings up to this many characters (i.e. 32-byte hashes) are never abbreviated.
+const ABBREV_MIN_HEX_LEN: usize = 64;
+
+/// Replace the long hex strings of the fields in [`ABBREV_FIELDS`], or in arrays in
+/// them, by `<first8>…<last8> (N bytes)`.
+///
+/// This works on a YAML value because, unlike JSON values, it keeps the order of the
+/// fields.
+fn abbreviate_fields(value: &mut serde_yaml::Value, abbreviate: bool) {
+	match value {
+		serde_yaml::Value::String(s) if abbreviate => {
+			let len = s.len();
+			if len > ABBREV_MIN_HEX_LEN && len % 2 == 0 && s.chars().all(|c| c.is_ascii_hexdigit())
+			{
+				*s = format!("{}…{} ({} bytes)", &s[..8], &s[len - 8..], len / 2);
+			}
+		}
+		serde_yaml::Value::Sequence(items) => {
+			for item in items {
+				abbreviate_fields(item, abbreviate);
+			}
+		}
+		serde_yaml::Value::Mapping(fields) => {
+			for (key, field) in fields.iter_mut() {
+				let abbreviate = key.as_str().is_some_and(|k| ABBREV_FIELDS.contains(&k));
+				abbreviate_fields(field, abbreviate);
+			}
+		}
+		_ => {}
+	}
+}
+
+/// The output format selected by `--output` or `--yaml`.
+fn output_format<'a>(matches: &'a clap::ArgMatches<'a>) -> &'a str {
+	match matches.value_of("output") {
+		Some(format) => format,
+		None if matches.is_present("yaml") => "yaml",
+		None => "json",
+	}
+}
+
+/// Render an output as pretty JSON or as YAML, abbreviated with `--abbrev`.
+fn render<'a, T: serde::Serialize>(matches: &clap::ArgMatches<'a>, out: &T) -> String {
+	let yaml = output_format(matches) == "yaml";
+	if matches.is_present("abbrev") {
+		let mut value = serde_yaml::to_value(out).expect("output is always valid YAML");
+		abbreviate_fields(&mut value, false);
+		if yaml {
+			serde_yaml::to_string(&value).unwrap()
+		} else {
+			serde_json::to_string_pretty(&value).unwrap()
+		}
+	} else if yaml {
+		serde_yaml::to_string(out).unwrap()
+	} else {
+		serde_json::to_string_pretty(out).unwrap()
+	}
+}
+
+pub fn print_output<'a, T: serde::Serialize + JsonSchema>(matches: &clap::ArgMatches<'a>, out: &T) {
+	if let Some(field) = matches.value_of("raw-value") {
+		print_raw_value(matches, field, out);
+	} else if output_format(matches) == "protobuf" {
+		let value = serde_json::to_value(out).expect("output is always valid JSON");
+		let bytes = protobuf::encode(&schemars::schema_for!(T), &value);
+		Output.write_all(&bytes).unwrap();
+	} else {
+		write!(Output, "{}", render(matches, out)).unwrap();
+	}
+}
+
+/// Get the message of a panic payload, as passed to the panic hook or returned
+/// by [`panic::catch_unwind`].
+pub fn panic_message(payload: &(dyn Any + Send)) -> &str {
+	if let Some(m) = payload.downcast_ref::<String>() {
+		m
+	} else if let Some(m) = payload.downcast_ref::<&str>() {
+		m
+	} else {
+		"No error message provided"
+	}
+}
+
+/// Runs `f`, returning the message of its panic as an error instead of printing it.
+pub(crate) fn catch_silently<R>(f: impl FnOnce() -> R) -> Result<R, String> {
+	// The panic hook would otherwise print the error and abort the process.
+	let hook = panic::take_hook();
+	panic::set_hook(Box::new(|_| {}));
+	let result = panic::catch_unwind(panic::AssertUnwindSafe(f));
+	panic::set_hook(hook);
+	result.map_err(|payload| panic_message(&*payload).to_owned())
+}
+
+/// Prints the outputs of a batch as an array, each as soon as it is ready, in
+/// the same format as [`print_output`] prints a `Vec` of them.
+///
+/// With `--raw-value` the value of each output is printed on its own line.
+struct ArrayPrinter<'m, 'a> {
+	matches: &'m clap::ArgMatches<'a>,
+	count: usize,
+}
+
+impl<'m, 'a> ArrayPrinter<'m, 'a> {
+	fn print<T: serde::Serialize + JsonSchema>(&mut self, out: &T) {
+		let matches = self.matches;
+		if let Some(field) = matches.value_of("raw-value") {
+			print_raw_value(matches, field, out);
+		} else if output_format(matches) == "protobuf" {
+			let value = serde_json::to_value(out).expect("output is always valid JSON");
+			let bytes = protobuf::encode_list_item(&schemars::schema_for!(T), &value);
+			Output.write_all(&bytes).unwrap();
+		} else if output_format(matches) == "yaml" {
+			let item = render(matches, out);
+			let item = item.strip_prefix("---\n").unwrap_or(&item);
+			if self.count == 0 {
+				writeln!(Output, "---").unwrap();
+			} else {
+				writeln!(Output).unwrap();
+			}
+			for (i, line) in item.lines().enumerate() {
+				let prefix = if i == 0 {
+					"- "
+				} else {
+					"\n  "
+				};
+				write!(Output, "{}{}", prefix, line).unwrap();
+			}
+		} else {
+			let separator = if self.count == 0 {
+				"["
+			} else {
+				","
+			};
+			write!(Output, "{}", separator).unwrap();
+			for line in render(matches, out).lines() {
+				write!(Output, "\n  {}", line).unwrap();
+			}
+		}
+		Output.flush().unwrap();
+		self.count += 1;
+	}
+
+	fn finish(self) {
+		if self.matches.is_present("raw-value") || output_format(self.matches) == "protobuf" {
+			return;
+		}
+		let end = match (output_format(self.matches) == "yaml", self.count) {
+			(true, 0) => "---\n[]",
+			(true, _) => "",
+			(false, 0) => "[]",
+			(false, _) => "\n]",
+		};
+		write!(Output, "{}", end).unwrap();
+	}
+}
+
+/// The line printed for an output with `--ndjson`.
+pub fn ndjson_line<'a, T: serde::Serialize>(matches: &clap::ArgMatches<'a>, out: &T) -> String {
+	match matches.value_of("raw-value") {
+		Some(field) => raw_value(field, out),
+		None => serde_json::to_string(out).unwrap(),
+	}
+}
+
+/// Process the items of a batch and print their outputs, each as soon as it is
+/// ready.
+///
+/// Without `--ndjson` a single item is printed as by [`print_output`], and
+/// several items as an array. With `--ndjson` each output is printed as one
+/// compact JSON object (or raw value) per line. With `--keep-going` too, an item
+/// which fails, or which cannot be read from stdin, is printed as an
+/// `{"index", "error"}` record instead of aborting, and a `{"summary"}` record
+/// ends the output. The process then exits with
+/// status 1 if any item failed.
+pub fn print_batch<'a, 'b, T, F>(matches: &clap::ArgMatches<'a>, batch: Batch<'b>, f: F)
+where
+	T: serde::Serialize + JsonSchema,
+	F: Fn(Cow<'b, str>) -> T,
+{
+	let Batch {
+		items,
+		array,
+	} = batch;
+	// Without --keep-going the errors reading stdin abort like those of the items.
+	let f = |item: Result<Cow<'b, str>, String>| f(item.unwrap_or_else(|e| panic!("{}", e)));
+	if !matches.is_present("ndjson") {
+		if !array {
+			for item in items {
+				print_output(matches, &f(item));
+			}
+			return;
+		}
+		let mut printer = ArrayPrinter {
+			matches,
+			count: 0,
+		};
+		for item in items {
+			printer.print(&f(item));
+		}
+		return printer.finish();
+	}
+	if !matches.is_present("keep-going") {
+		for item in items {
+			writeln!(Output, "{}", ndjson_line(matches, &f(item))).unwrap();
+		}
+		return;
+	}
+
+	let mut total = 0;
+	let mut failed = 0;
+	for (index, item) in items.enumerate() {
+		total += 1;
+		let line = item.and_then(|item| catch_silently(|| ndjson_line(matches, &f(Ok(item)))));
+		match line {
+			Ok(line) => writeln!(Output, "{}", line).unwrap(),
+			Err(error) => {
+				failed += 1;
+				let error = serde_json::json!({
+					"index": index,
+					"error": error,
+				});
+				writeln!(Output, "{}", error).unwrap();
+			}
+		}
+	}
+
+	let summary = serde_json::json!({
+		"summary": {
+			"total": total,
+			"succeeded": total - failed,
+			"failed": failed,
+		},
+	});
+	writeln!(Output, "{}", summary).unwrap();
+	if failed > 0 {
+		exit(1);
 	}
 }
@@ -0,0 +1,343 @@
+use elements::bitcoin::blockdata::constants::genesis_block;
+use elements::bitcoin::secp256k1::{self, PublicKey, Scalar};
+use elements::bitcoin::{self, MerkleBlock};
+use elements::hashes::{sha256, Hash, HashEngine, Hmac, HmacEngine};
+use elements::{confidential, AssetId, OutPoint, PeginData, Script, Transaction, TxIn, TxOut};
+use hal_simplicity::Network;
+use schemars::schema::RootSchema;
+use schemars::JsonSchema;
+use serde::Serialize;
+
+use crate::cmd;
+
+/// The default fedpeg script of elementsregtest, OP_TRUE.
+const ELEMENTSREGTEST_FEDPEG_SCRIPT: &str = "51";
+
+pub fn subcommand<'a>() -> clap::App<'a, 'a> {
+	cmd::subcommand_group("pegin", "peg Bitcoin into an Elements chain")
+		.subcommand(cmd_address())
+		.subcommand(cmd_claim())
+}
+
+/// The schemas of the outputs of the subcommands, by subcommand name.
+pub fn schemas() -> Vec<(&'static str, RootSchema)> {
+	vec![
+		("address", schemars::schema_for!(PeginAddressInfo)),
+		("claim", schemars::schema_for!(PeginClaimInfo)),
+	]
+}
+
+pub fn execute<'a>(matches: &clap::ArgMatches<'a>) {
+	match matches.subcommand() {
+		("address", Some(m)) => exec_address(m),
+		("claim", Some(m)) => exec_claim(m),
+		(_, _) => unreachable!("clap prints help"),
+	};
+}
+
+/// The options describing the peg-in address.
+fn opts_pegin<'a>() -> Vec<clap::Arg<'a, 'a>> {
+	vec![
+		cmd::opt(
+			"claim-script",
+			"the script claiming the peg-in on the Elements chain, in hex or as an address",
+		)
+		.short("c")
+		.takes_value(true)
+		.required(true),
+		cmd::opt(
+			"fedpeg-script",
+			"the federation's fedpegscript in hex (defaults to OP_TRUE on elementsregtest)",
+		)
+		.takes_value(true)
+		.required(false),
+		cmd::opt("p2wsh", "use a native P2WSH address instead of P2SH-wrapped P2WSH")
+			.takes_value(false)
+			.required(false),
+	]
+}
+
+fn cmd_address<'a>() -> clap::App<'a, 'a> {
+	cmd::subcommand("address", "derive the mainchain address to send Bitcoin to peg in")
+		.args(&cmd::opts_networks())
+		.args(&cmd::opts_raw_value())
+		.args(&opts_pegin())
+		.args(&[cmd::opt_yaml(), cmd::opt_output()])
+}
+
+fn cmd_claim<'a>() -> clap::App<'a, 'a> {
+	cmd::subcommand(
+		"claim",
+		"build the unsigned transaction claiming a peg-in from its mainchain transaction",
+	)
+	.args(&cmd::opts_networks())
+	.args(&cmd::opts_raw_value())
+	.args(&opts_pegin())
+	.args(&[
+		cmd::opt_yaml(),
+		cmd::opt_output(),
+		cmd::arg("mainchain-tx", "the mainchain transaction in hex, base64 or as a file path")
+			.required(true),
+		cmd::arg(
+			"txoutproof",
+			"the proof of the mainchain transaction's inclusion in a block, as returned by gettxoutproof",
+		)
+		.required(true),
+		cmd::opt("fee", "the fee of the claim transaction in satoshis")
+			.short("f")
+			.takes_value(true)
+			.required(true),
+		cmd::opt(
+			"address",
+			"the Elements address receiving the peg-in (defaults to the claim script)",
+		)
+		.short("a")
+		.takes_value(true)
+		.required(false),
+		cmd::opt("asset", "the pegged asset (defaults to the network's)")
+			.takes_value(true)
+			.required(false),
+		cmd::opt(
+			"mainchain-genesis-hash",
+			"the genesis block hash of the mainchain (defaults to the network's)",
+		)
+		.takes_value(true)
+		.required(false),
+	])
+}
+
+#[derive(Serialize, JsonSchema)]
+struct PeginAddressInfo {
+	/// The mainchain address to send the Bitcoin to peg in to.
+	mainchain_address: String,
+	/// The fedpegscript tweaked with the claim script, the witness script of the address.
+	#[schemars(with = "String")]
+	witness_script: bitcoin::ScriptBuf,
+	#[schemars(with = "String")]
+	claim_script: Script,
+}
+
+#[derive(Serialize, JsonSchema)]
+struct PeginClaimInfo {
+	/// The unsigned claim transaction in hex.
+	tx: String,
+	#[schemars(with = "String")]
+	txid: elements::Txid,
+	#[schemars(with = "String")]
+	mainchain_txid: bitcoin::Txid,
+	mainchain_vout: u32,
+	/// The value pegged in, in satoshis.
+	value: u64,
+	fee: u64,
+	#[schemars(with = "String")]
+	claim_script: Script,
+}
+
+/// The Bitcoin network the given Elements network pegs into.
+fn mainchain_network(network: Network) -> bitcoin::Network {
+	match network {
+		Network::ElementsRegtest => bitcoin::Network::Regtest,
+		Network::Liquid => bitcoin::Network::Bitcoin,
+		Network::LiquidTestnet => bitcoin::Network::Testnet,
+	}
+}
+
+/// Parse a claim script given in hex or as an Elements address.
+fn parse_claim_script(s: &str) -> Script {
+	match s.parse::<elements::Address>() {
+		Ok(address) => address.script_pubkey(),
+		Err(_) => {
+			Script::from(hex::decode(s).expect("invalid claim script, expected hex or an address"))
+		}
+	}
+}
+
+fn fedpeg_script<'a>(matches: &clap::ArgMatches<'a>, network: Network) -> bitcoin::ScriptBuf {
+	let hex = match (matches.value_of("fedpeg-script"), network) {
+		(Some(hex), _) => hex,
+		(None, Network::ElementsRegtest) => ELEMENTSREGTEST_FEDPEG_SCRIPT,
+		(None, Network::Liquid | Network::LiquidTestnet) => {
+			panic!("--fedpeg-script is required on Liquid")
+		}
+	};
+	bitcoin::ScriptBuf::from(hex::decode(hex).expect("invalid fedpeg script hex"))
+}
+
+/// Tweak the keys of a fedpegscript with the claim script, as Elements'
+/// `calculate_contract` does.
+///
+/// Each 33-byte key pushed before any OP_ELSE is tweaked by the HMAC-SHA256 of the
+/// claim script keyed with the key. The keys after an OP_ELSE are the emergency keys
+/// of Liquid's fedpegscript and are left untouched.
+fn tweak_fedpeg_script(
+	fedpeg_script: &bitcoin::Script,
+	claim_script: &Script,
+) -> bitcoin::ScriptBuf {
+	let mut builder = bitcoin::blockdata::script::Builder::new();
+	let mut emergency = false;
+	for instruction in fedpeg_script.instructions() {
+		match instruction.expect("invalid fedpeg script") {
+			bitcoin::blockdata::script::Instruction::Op(op) => {
+				if op == bitcoin::opcodes::all::OP_ELSE {
+					emergency = true;
+				}
+				builder = builder.push_opcode(op);
+			}
+			bitcoin::blockdata::script::Instruction::PushBytes(data) => {
+				let key = match PublicKey::from_slice(data.as_bytes()) {
+					Ok(key) if !emergency && data.len() == 33 => key,
+					_ => {
+						builder = builder.push_slice(data);
+						continue;
+					}
+				};
+				let mut engine = HmacEngine::<sha256::Hash>::new(&key.serialize());
+				engine.input(claim_script.as_bytes());
+				let tweak = Hmac::<sha256::Hash>::from_engine(engine);
+				let tweak = Scalar::from_be_bytes(tweak.to_byte_array()).expect("tweak is valid");
+				let tweaked =
+					key.add_exp_tweak(secp256k1::SECP256K1, &tweak).expect("tweak is valid");
+				builder = builder.push_slice(tweaked.serialize());
+			}
+		}
+	}
+	builder.into_script()
+}
+
+/// The mainchain address paying to the given tweaked fedpegscript.
+fn mainchain_address(
+	witness_script: &bitcoin::Script,
+	p2wsh: bool,
+	network: bitcoin::Network,
+) -> bitcoin::Address {
+	if p2wsh {
+		bitcoin::Address::p2wsh(witness_script, network)
+	} else {
+		bitcoin::Address::p2shwsh(witness_script, network)
+	}
+}
+
+fn exec_address<'a>(matches: &clap::ArgMatches<'a>) {
+	let network = cmd::network(matches);
+	let claim_script =
+		parse_claim_script(matches.value_of("claim-script").expect("claim script is mandatory"));
+	let witness_script = tweak_fedpeg_script(&fedpeg_script(matches, network), &claim_script);
+	let address =
+		mainchain_address(&witness_script, matches.is_present("p2wsh"), mainchain_network(network));
+
+	let info = PeginAddressInfo {
+		mainchain_address: address.to_string(),
+		witness_script,
+		claim_script,
+	};
+	cmd::print_output(matches, &info)
+}
+
+fn exec_claim<'a>(matches: &clap::ArgMatches<'a>) {
+	let network = cmd::network(matches);
+	let claim_script =
+		parse_claim_script(matches.value_of("claim-script").expect("claim script is mandatory"));
+	let witness_script = tweak_fedpeg_script(&fedpeg_script(matches, network), &claim_script);
+	let address =
+		mainchain_address(&witness_script, matches.is_present("p2wsh"), mainchain_network(network));
+
+	let raw_tx = cmd::decode_binary(
+		matches.value_of("mainchain-tx").expect("mainchain tx is mandatory"),
+		"mainchain-tx",
+	);
+	let mainchain_tx: bitcoin::Transaction =
+		bitcoin::consensus::deserialize(&raw_tx).expect("invalid mainchain tx format");
+	let mainchain_txid = mainchain_tx.compute_txid();
+	let vout = mainchain_tx
+		.output
+		.iter()
+		.position(|o| o.script_pubkey == address.script_pubkey())
+		.unwrap_or_else(|| panic!("mainchain tx has no output paying to {}", address))
+		as u32;
+	let value = mainchain_tx.output[vout as usize].value.to_sat();
+
+	let raw_proof = cmd::decode_binary(
+		matches.value_of("txoutproof").expect("txoutproof is mandatory"),
+		"txoutproof",
+	);
+	let proof: MerkleBlock =
+		bitcoin::consensus::deserialize(&raw_proof).expect("invalid txoutproof format");
+	let mut matched = Vec::new();
+	proof.extract_matches(&mut matched, &mut Vec::new()).expect("invalid txoutproof");
+	if !matched.contains(&mainchain_txid) {
+		panic!("txoutproof doesn't prove the inclusion of mainchain tx {}", mainchain_txid);
+	}
+
+	let fee: u64 = matches.value_of("fee").expect("fee is mandatory").parse().expect("invalid fee");
+	if fee >= value {
+		panic!("fee of {} sat is not less than the peg-in value of {} sat", fee, value);
+	}
+	let asset: AssetId = match matches.value_of("asset") {
+		Some(asset) => asset.parse().expect("invalid asset"),
+		None => cmd::policy_asset(network),
+	};
+	let genesis_hash = match matches.value_of("mainchain-genesis-hash") {
+		Some(hash) => hash.parse().expect("invalid mainchain genesis hash"),
+		None => genesis_block(mainchain_network(network)).block_hash(),
+	};
+	let destination = match matches.value_of("address") {
+		Some(address) => {
+			address.parse::<elements::Address>().expect("invalid address").script_pubkey()
+		}
+		None => claim_script.clone(),
+	};
+
+	// Elements only commits to the fields of the mainchain tx covered by its txid.
+	let mut stripped_tx = mainchain_tx.clone();
+	for input in &mut stripped_tx.input {
+		input.witness.clear();
+	}
+	let stripped_tx = bitcoin::consensus::serialize(&stripped_tx);
+	let pegin_data = PeginData {
+		outpoint: bitcoin::OutPoint::new(mainchain_txid, vout),
+		value,
+		asset,
+		genesis_hash,
+		claim_script: claim_script.as_bytes(),
+		tx: &stripped_tx,
+		merkle_proof: &raw_proof,
+		referenced_block: proof.header.block_hash(),
+	};
+
+	let mut input = TxIn {
+		previous_output: OutPoint::new(
+			elements::Txid::from_raw_hash(mainchain_txid.to_raw_hash()),
+			vout,
+		),
+		is_pegin: true,
+		sequence: elements::Sequence::MAX,
+		..Default::default()
+	};
+	input.witness.pegin_witness = pegin_data.to_pegin_witness();
+	let tx = Transaction {
+		version: 2,
+		lock_time: elements::LockTime::ZERO,
+		input: vec![input],
+		output: vec![
+			TxOut {
+				asset: confidential::Asset::Explicit(asset),
+				value: confidential::Value::Explicit(value - fee),
+				nonce: confidential::Nonce::Null,
+				script_pubkey: destination,
+				witness: Default::default(),
+			},
+			TxOut::new_fee(fee, asset),
+		],
+	};
+
+	let info = PeginClaimInfo {
+		tx: hex::encode(elements::encode::serialize(&tx)),
+		txid: tx.txid(),
+		mainchain_txid,
+		mainchain_vout: vout,
+		value,
+		fee,
+		claim_script,
+	};
+	cmd::print_output(matches, &info)
+}
@@ -0,0 +1,683 @@
+//! Protocol buffer encoding of command outputs.
+//!
+//! The messages are derived from the JSON schemas of the outputs, so that they
+//! always match the JSON output:
+//!
+//! - the fields of an object are those of the JSON object, and fields which are
+//!   absent or `null` are left out;
+//! - a value which can take several shapes (e.g. an enum) is a message with a
+//!   single `oneof`, with one field per shape; a shape which is an object with a
+//!   single property (e.g. `{"Blocks": 10}`) is represented by the value of that
+//!   property, and a shape which is a list by a message like those of the outputs
+//!   of a command given several inputs;
+//! - maps are `map<string, _>` and arbitrary JSON values are strings holding JSON;
+//! - the outputs of a command given several inputs are the repeated `items` of a
+//!   message named after the message of one output, e.g. `TransactionInfoList`.
+//!
+//! The field numbers are those of the published `proto/hal_simplicity.proto`, so
+//! that they never change once released. Fields which are not in that file yet
+//! get the next unused numbers, in alphabetical order for objects and in the
+//! order of the schema for `oneof`s, and the numbers of fields which were removed
+//! are reserved. The file must be regenerated with `schema --proto` whenever an
+//! output changes.
+
+use std::collections::{BTreeMap, BTreeSet};
+use std::sync::OnceLock;
+
+use schemars::schema::{InstanceType, RootSchema, Schema, SingleOrVec};
+use schemars::Map;
+use serde_json::Value;
+
+/// The shape of a value described by a schema, after resolving references and
+/// leaving out `null`.
+enum Shape<'a> {
+	/// Arbitrary JSON.
+	Any,
+	Bool,
+	Integer {
+		signed: bool,
+		/// Whether the value may not fit in 32 bits.
+		wide: bool,
+	},
+	Double,
+	String,
+	Array(&'a Schema),
+	Map(&'a Schema),
+	/// An object with the given properties, named after its definition if any.
+	Object(Option<&'a str>, &'a Map<String, Schema>, &'a BTreeSet<String>),
+	/// One of several variants, named after its definition if any.
+	OneOf(Option<&'a str>, Vec<&'a Schema>),
+}
+
+fn is_null(schema: &Schema) -> bool {
+	match schema {
+		Schema::Object(obj) => {
+			obj.instance_type == Some(SingleOrVec::Single(Box::new(InstanceType::Null)))
+		}
+		Schema::Bool(_) => false,
+	}
+}
+
+fn shape<'a>(defs: &'a Map<String, Schema>, schema: &'a Schema) -> Shape<'a> {
+	let obj = match schema {
+		Schema::Bool(_) => return Shape::Any,
+		Schema::Object(obj) => obj,
+	};
+
+	if let Some(ref reference) = obj.reference {
+		let name = reference.trim_start_matches("#/definitions/");
+		let def = defs.get(name).unwrap_or_else(|| panic!("unknown schema reference {}", name));
+		return match shape(defs, def) {
+			Shape::Object(None, props, required) => Shape::Object(Some(name), props, required),
+			Shape::OneOf(None, variants) => Shape::OneOf(Some(name), variants),
+			other => other,
+		};
+	}
+
+	if let Some(ref sub) = obj.subschemas {
+		let variants = [&sub.all_of, &sub.any_of, &sub.one_of]
+			.into_iter()
+			.flatten()
+			.flatten()
+			.filter(|s| !is_null(s))
+			.collect::<Vec<_>>();
+		return match variants.len() {
+			0 => Shape::Any,
+			1 => shape(defs, variants[0]),
+			_ => Shape::OneOf(None, variants),
+		};
+	}
+
+	let instance_type = match obj.instance_type {
+		Some(SingleOrVec::Single(ref t)) => Some(**t),
+		Some(SingleOrVec::Vec(ref ts)) => ts.iter().copied().find(|t| *t != InstanceType::Null),
+		None => None,
+	};
+	match instance_type {
+		Some(InstanceType::Boolean) => Shape::Bool,
+		Some(InstanceType::Integer) => {
+			let format = obj.format.as_deref().unwrap_or("int64");
+			Shape::Integer {
+				signed: !format.starts_with('u'),
+				wide: !["int8", "int16", "int32", "uint8", "uint16", "uint32"].contains(&format),
+			}
+		}
+		Some(InstanceType::Number) => Shape::Double,
+		Some(InstanceType::String) => Shape::String,
+		Some(InstanceType::Array) => match obj.array.as_ref().and_then(|a| a.items.as_ref()) {
+			Some(SingleOrVec::Single(items)) => Shape::Array(items),
+			_ => Shape::Any,
+		},
+		Some(InstanceType::Object) => {
+			let object = obj.object.as_ref().expect("object schemas have properties");
+			match object.additional_properties {
+				Some(ref values) if object.properties.is_empty() => Shape::Map(values),
+				_ => Shape::Object(None, &object.properties, &object.required),
+			}
+		}
+		Some(InstanceType::Null) | None => Shape::Any,
+	}
+}
+
+/// If the variant is an object with a single property, that property.
+fn single_property<'a>(
+	defs: &'a Map<String, Schema>,
+	variant: &'a Schema,
+) -> Option<(&'a String, &'a Schema)> {
+	match shape(defs, variant) {
+		Shape::Object(None, props, _) if props.len() == 1 => props.iter().next(),
+		_ => None,
+	}
+}
+
+/// Whether the value has the shape described by the schema.
+fn matches(defs: &Map<String, Schema>, schema: &Schema, value: &Value) -> bool {
+	match shape(defs, schema) {
+		Shape::Any => true,
+		Shape::Bool => value.is_boolean(),
+		Shape::Integer {
+			..
+		} => value.is_i64() || value.is_u64(),
+		Shape::Double => value.is_number(),
+		Shape::String => value.is_string(),
+		Shape::Array(_) => value.is_array(),
+		Shape::Map(_) => value.is_object(),
+		Shape::Object(_, props, required) => match value.as_object() {
+			Some(map) => {
+				map.keys().all(|k| props.contains_key(k))
+					&& required.iter().all(|k| map.contains_key(k))
+			}
+			None => false,
+		},
+		Shape::OneOf(_, variants) => variants.iter().any(|v| matches(defs, v, value)),
+	}
+}
+
+fn write_varint(out: &mut Vec<u8>, mut n: u64) {
+	while n >= 0x80 {
+		out.push(n as u8 | 0x80);
+		n >>= 7;
+	}
+	out.push(n as u8);
+}
+
+fn write_key(out: &mut Vec<u8>, number: usize, wire_type: u8) {
+	write_varint(out, (number as u64) << 3 | wire_type as u64);
+}
+
+fn write_bytes(out: &mut Vec<u8>, number: usize, bytes: &[u8]) {
+	write_key(out, number, 2);
+	write_varint(out, bytes.len() as u64);
+	out.extend_from_slice(bytes);
+}
+
+/// The published `.proto` file, which fixes the field numbers.
+const PUBLISHED: &str = include_str!("../../../../proto/hal_simplicity.proto");
+
+/// The field numbers of a message of the published `.proto` file.
+#[derive(Default)]
+struct Published {
+	fields: BTreeMap<String, usize>,
+	reserved: BTreeSet<usize>,
+}
+
+/// Read the field numbers of the messages of a `.proto` file as generated by
+/// [`ProtoFile`].
+fn parse_published(proto: &str) -> BTreeMap<String, Published> {
+	let mut messages = BTreeMap::new();
+	let mut current: Option<(String, Published)> = None;
+	for line in proto.lines() {
+		if let Some(name) = line.strip_prefix("message ") {
+			let name = name.trim_end_matches(" {").to_owned();
+			current = Some((name, Published::default()));
+		} else if line.starts_with('}') {
+			if let Some((name, message)) = current.take() {
+				messages.insert(name, message);
+			}
+		} else if let Some((_, ref mut message)) = current {
+			let line = line.trim().trim_end_matches(';');
+			if let Some(numbers) = line.strip_prefix("reserved ") {
+				let numbers =
+					numbers.split(", ").map(|n| n.parse::<usize>().expect("valid .proto"));
+				message.reserved.extend(numbers);
+			} else if let Some((decl, number)) = line.split_once(" = ") {
+				let name = decl.rsplit(' ').next().expect("valid .proto");
+				message.fields.insert(name.to_owned(), number.parse().expect("valid .proto"));
+			}
+		}
+	}
+	messages
+}
+
+fn published() -> &'static BTreeMap<String, Published> {
+	static PARSED: OnceLock<BTreeMap<String, Published>> = OnceLock::new();
+	PARSED.get_or_init(|| parse_published(PUBLISHED))
+}
+
+/// A field of a message.
+struct Field<'a> {
+	name: String,
+	number: usize,
+	schema: &'a Schema,
+	/// The property holding the value of the field, if any.
+	property: Option<&'a String>,
+	/// For the fields of a `oneof`, the schema of the variant.
+	variant: Option<&'a Schema>,
+}
+
+impl Field<'_> {
+	/// The name the messages of the field are named after.
+	fn key(&self) -> &str {
+		self.property.map_or(&self.name, |prop| prop)
+	}
+}
+
+/// The fields of the message with the given name and shape, numbered as
+/// described in the module documentation, and the numbers which are reserved.
+fn fields<'a>(
+	defs: &'a Map<String, Schema>,
+	message: &str,
+	message_shape: Shape<'a>,
+) -> (Vec<Field<'a>>, BTreeSet<usize>) {
+	let mut fields = match message_shape {
+		Shape::Object(_, props, _) => props
+			.iter()
+			.map(|(prop, schema)| Field {
+				name: field_name(prop),
+				number: 0,
+				schema,
+				property: Some(prop),
+				variant: None,
+			})
+			.collect::<Vec<_>>(),
+		Shape::OneOf(_, variants) => variants
+			.into_iter()
+			.enumerate()
+			.map(|(i, variant)| match single_property(defs, variant) {
+				Some((prop, schema)) => Field {
+					name: field_name(prop),
+					number: 0,
+					schema,
+					property: Some(prop),
+					variant: Some(variant),
+				},
+				None => Field {
+					name: match shape(defs, variant) {
+						Shape::Object(Some(def), ..) | Shape::OneOf(Some(def), _) => {
+							Some(snake_case(def))
+						}
+						Shape::Array(items) => list_name(items).map(|name| snake_case(&name)),
+						_ => None,
+					}
+					.unwrap_or_else(|| format!("variant_{}", i + 1)),
+					number: 0,
+					schema: variant,
+					property: None,
+					variant: Some(variant),
+				},
+			})
+			.collect(),
+		Shape::Array(items) => vec![Field {
+			name: "items".to_owned(),
+			number: 0,
+			schema: items,
+			property: None,
+			variant: None,
+		}],
+		_ => unreachable!("only called for messages"),
+	};
+
+	let empty = Published::default();
+	let published = published().get(message).unwrap_or(&empty);
+	let mut next = published.fields.values().chain(&published.reserved).max().map_or(1, |n| n + 1);
+	for field in &mut fields {
+		field.number = match published.fields.get(&field.name) {
+			Some(&number) => number,
+			None => {
+				next += 1;
+				next - 1
+			}
+		};
+	}
+	let mut reserved = published.reserved.clone();
+	reserved.extend(
+		published
+			.fields
+			.iter()
+			.filter(|(name, _)| fields.iter().all(|f| f.name != **name))
+			.map(|(_, &number)| number),
+	);
+	(fields, reserved)
+}
+
+/// The name of the message of a field, which is the name of its definition if
+/// any, or else made of the names of the parent message and of the field.
+fn nested_name(shape: &Shape, parent: &str, field: &str) -> String {
+	match *shape {
+		Shape::Object(Some(name), ..) | Shape::OneOf(Some(name), _) => message_name(name),
+		_ => format!("{}{}", parent, message_name(field)),
+	}
+}
+
+/// Encode a field with the given number, where `key` is the name its messages
+/// are named after.
+fn encode_field(
+	defs: &Map<String, Schema>,
+	parent: &str,
+	key: &str,
+	number: usize,
+	schema: &Schema,
+	value: &Value,
+	out: &mut Vec<u8>,
+) {
+	match shape(defs, schema) {
+		Shape::Any => write_bytes(out, number, value.to_string().as_bytes()),
+		Shape::Bool => {
+			write_key(out, number, 0);
+			write_varint(out, value.as_bool().expect("boolean value") as u64);
+		}
+		Shape::Integer {
+			..
+		} => {
+			write_key(out, number, 0);
+			let n = value.as_u64().or(value.as_i64().map(|n| n as u64));
+			write_varint(out, n.expect("integer value"));
+		}
+		Shape::Double => {
+			write_key(out, number, 1);
+			out.extend_from_slice(&value.as_f64().expect("number value").to_le_bytes());
+		}
+		Shape::String => match value.as_str() {
+			Some(s) => write_bytes(out, number, s.as_bytes()),
+			None => write_bytes(out, number, value.to_string().as_bytes()),
+		},
+		Shape::Array(items) => {
+			for item in value.as_array().expect("array value") {
+				encode_field(defs, parent, key, number, items, item, out);
+			}
+		}
+		Shape::Map(values) => {
+			for (k, v) in value.as_object().expect("object value") {
+				let mut entry = Vec::new();
+				write_bytes(&mut entry, 1, k.as_bytes());
+				encode_field(defs, parent, key, 2, values, v, &mut entry);
+				write_bytes(out, number, &entry);
+			}
+		}
+		shape @ (Shape::Object(..) | Shape::OneOf(..)) => {
+			let name = nested_name(&shape, parent, key);
+			let mut message = Vec::new();
+			encode_message(defs, &name, shape, value, &mut message);
+			write_bytes(out, number, &message);
+		}
+	}
+}
+
+fn encode_message(
+	defs: &Map<String, Schema>,
+	name: &str,
+	shape: Shape,
+	value: &Value,
+	out: &mut Vec<u8>,
+) {
+	let is_oneof = matches!(shape, Shape::OneOf(..));
+	let (fields, _) = fields(defs, name, shape);
+	if is_oneof {
+		let field = fields
+			.iter()
+			.find(|f| f.variant.is_some_and(|v| matches(defs, v, value)))
+			.expect("output matches its schema");
+		match field.property {
+			Some(prop) => {
+				encode_field(defs, name, field.key(), field.number, field.schema, &value[prop], out)
+			}
+			None => match list_message(defs, field.schema) {
+				Some(list) => {
+					let mut message = Vec::new();
+					encode_message(defs, &list, shape(defs, field.schema), value, &mut message);
+					write_bytes(out, field.number, &message);
+				}
+				None => {
+					encode_field(defs, name, field.key(), field.number, field.schema, value, out)
+				}
+			},
+		}
+	} else {
+		for field in &fields {
+			match field.property {
+				Some(prop) => match value.get(prop) {
+					Some(Value::Null) | None => {}
+					Some(v) => {
+						encode_field(defs, name, field.key(), field.number, field.schema, v, out)
+					}
+				},
+				// The items of a list.
+				None => {
+					for item in value.as_array().expect("array value") {
+						encode_field(
+							defs,
+							name,
+							field.key(),
+							field.number,
+							field.schema,
+							item,
+							out,
+						);
+					}
+				}
+			}
+		}
+	}
+}
+
+/// The name and shape of the message of an output, given the schema of the
+/// output as a [`Schema`].
+fn root_message<'a>(root: &'a RootSchema, schema: &'a Schema) -> (String, Shape<'a>) {
+	let shape = shape(&root.definitions, schema);
+	let name = match shape {
+		Shape::Array(items) if list_name(items).is_some() => {
+			list_name(items).expect("checked above")
+		}
+		Shape::Object(..) | Shape::OneOf(..) => root
+			.schema
+			.metadata
+			.as_ref()
+			.and_then(|m| m.title.clone())
+			.expect("output schemas have a title"),
+		_ => panic!(
+			"protobuf output is only supported for outputs which are objects or lists of them"
+		),
+	};
+	(name, shape)
+}
+
+/// The name of the message of a list of items of a definition, e.g.
+/// `TransactionInfoList`.
+fn list_name(items: &Schema) -> Option<String> {
+	match items {
+		Schema::Object(items) => items.reference.as_deref().map(|reference| {
+			format!("{}List", message_name(reference.trim_start_matches("#/definitions/")))
+		}),
+		Schema::Bool(_) => None,
+	}
+}
+
+/// The name of the message of a list, if the schema is that of a list of items of
+/// a definition.
+fn list_message(defs: &Map<String, Schema>, schema: &Schema) -> Option<String> {
+	match shape(defs, schema) {
+		Shape::Array(items) => list_name(items),
+		_ => None,
+	}
+}
+
+/// Encode the JSON value of an output as a protobuf message following its schema.
+pub fn encode(root: &RootSchema, value: &Value) -> Vec<u8> {
+	let schema = Schema::Object(root.schema.clone());
+	let (name, shape) = root_message(root, &schema);
+	let mut out = Vec::new();
+	encode_message(&root.definitions, &name, shape, value, &mut out);
+	out
+}
+
+/// Encode the JSON value of an output as an item of the list message of the
+/// outputs of a command given several inputs, so that the concatenated items
+/// make the whole list.
+pub fn encode_list_item(root: &RootSchema, value: &Value) -> Vec<u8> {
+	let mut out = Vec::new();
+	write_bytes(&mut out, 1, &encode(root, value));
+	out
+}
+
+/// The schema of the outputs of a command given several inputs, as printed
+/// without `--ndjson`.
+pub fn list_schema(root: &RootSchema) -> RootSchema {
+	let name = root
+		.schema
+		.metadata
+		.as_ref()
+		.and_then(|m| m.title.clone())
+		.expect("output schemas have a title");
+	let mut list = schemars::schema_for!(Vec<()>);
+	list.definitions = root.definitions.clone();
+	list.definitions.insert(name.clone(), Schema::Object(root.schema.clone()));
+	let items =
+		Schema::Object(schemars::schema::SchemaObject::new_ref(format!("#/definitions/{}", name)));
+	list.schema.array().items = Some(SingleOrVec::Single(Box::new(items)));
+	list
+}
+
+/// Turn a property name into a valid protobuf field name.
+fn field_name(name: &str) -> String {
+	name.chars()
+		.map(|c| {
+			if c.is_ascii_alphanumeric() {
+				c.to_ascii_lowercase()
+			} else {
+				'_'
+			}
+		})
+		.collect()
+}
+
+/// Turn a definition name into a field name, e.g. `BlockInfo` into `block_info`.
+fn snake_case(name: &str) -> String {
+	let mut ret = String::new();
+	for c in name.chars() {
+		if c.is_ascii_uppercase() && !ret.is_empty() {
+			ret.push('_');
+		}
+		ret.push(c);
+	}
+	field_name(&ret)
+}
+
+/// Turn a property name into a message name, e.g. `script_pub_key` into `ScriptPubKey`.
+fn message_name(name: &str) -> String {
+	name.split(|c: char| !c.is_ascii_alphanumeric())
+		.map(|part| {
+			let mut chars = part.chars();
+			match chars.next() {
+				Some(c) => c.to_ascii_uppercase().to_string() + chars.as_str(),
+				None => String::new(),
+			}
+		})
+		.collect()
+}
+
+/// Generator of the protobuf definitions of the messages of several outputs.
+#[derive(Default)]
+pub struct ProtoFile {
+	messages: String,
+	done: BTreeSet<String>,
+}
+
+impl ProtoFile {
+	/// Add the message of an output, and all the messages it depends on.
+	pub fn add(&mut self, command: &str, root: &RootSchema) {
+		self.add_message(&format!("The output of `{}`.", command), root);
+	}
+
+	/// Add the message of the outputs of a command given several inputs, which
+	/// wraps the message of a single output.
+	pub fn add_list(&mut self, command: &str, root: &RootSchema) {
+		let comment = format!("The outputs of `{}` given several inputs.", command);
+		self.add_message(&comment, &list_schema(root));
+	}
+
+	fn add_message(&mut self, comment: &str, root: &RootSchema) {
+		let schema = Schema::Object(root.schema.clone());
+		let (name, _) = root_message(root, &schema);
+		if self.done.contains(&name) {
+			return;
+		}
+		self.messages.push_str(&format!("// {}\n", comment));
+		let mut pending = vec![(name, &schema)];
+		while let Some((name, schema)) = pending.pop() {
+			if self.done.insert(name.clone()) {
+				let message = message(&root.definitions, &name, schema, &mut pending);
+				self.messages.push_str(&message);
+			}
+		}
+	}
+
+	/// The contents of the `.proto` file.
+	pub fn finish(self) -> String {
+		format!("syntax = \"proto3\";\n\npackage hal_simplicity;\n\n{}", self.messages)
+	}
+}
+
+/// The protobuf type of a field, queueing the messages it needs in `pending`.
+fn field_type<'a>(
+	defs: &'a Map<String, Schema>,
+	parent: &str,
+	field: &str,
+	schema: &'a Schema,
+	pending: &mut Vec<(String, &'a Schema)>,
+) -> String {
+	let nested = |t: String| {
+		if t.starts_with("repeated ") || t.starts_with("map<") {
+			panic!("field {} of {} can't be represented in protobuf", field, parent);
+		}
+		t
+	};
+	match shape(defs, schema) {
+		Shape::Any | Shape::String => "string".to_owned(),
+		Shape::Bool => "bool".to_owned(),
+		Shape::Integer {
+			signed,
+			wide,
+		} => format!(
+			"{}int{}",
+			if signed {
+				""
+			} else {
+				"u"
+			},
+			if wide {
+				64
+			} else {
+				32
+			}
+		),
+		Shape::Double => "double".to_owned(),
+		Shape::Array(items) => {
+			format!("repeated {}", nested(field_type(defs, parent, field, items, pending)))
+		}
+		Shape::Map(values) => {
+			format!("map<string, {}>", nested(field_type(defs, parent, field, values, pending)))
+		}
+		shape @ (Shape::Object(..) | Shape::OneOf(..)) => {
+			let name = nested_name(&shape, parent, field);
+			pending.push((name.clone(), schema));
+			name
+		}
+	}
+}
+
+/// The definition of a message.
+fn message<'a>(
+	defs: &'a Map<String, Schema>,
+	name: &str,
+	schema: &'a Schema,
+	pending: &mut Vec<(String, &'a Schema)>,
+) -> String {
+	let shape = shape(defs, schema);
+	let is_oneof = matches!(shape, Shape::OneOf(..));
+	let (fields, reserved) = fields(defs, name, shape);
+
+	let mut ret = format!("message {} {{\n", name);
+	let indent = if is_oneof {
+		ret.push_str("  oneof value {\n");
+		"    "
+	} else {
+		"  "
+	};
+	for field in &fields {
+		let ty = match (field.property, list_message(defs, field.schema)) {
+			// The items of a list.
+			(None, _) if !is_oneof => {
+				format!("repeated {}", field_type(defs, name, &field.name, field.schema, pending))
+			}
+			// A variant which is a list.
+			(None, Some(list)) => {
+				pending.push((list.clone(), field.schema));
+				list
+			}
+			_ => field_type(defs, name, field.key(), field.schema, pending),
+		};
+		if is_oneof && (ty.starts_with("repeated ") || ty.starts_with("map<")) {
+			panic!("variant {} of {} can't be represented in protobuf", field.name, name);
+		}
+		ret.push_str(&format!("{}{} {} = {};\n", indent, ty, field.name, field.number));
+	}
+	if is_oneof {
+		ret.push_str("  }\n");
+	}
+	if !reserved.is_empty() {
+		let reserved = reserved.iter().map(|n| n.to_string()).collect::<Vec<_>>();
+		ret.push_str(&format!("  reserved {};\n", reserved.join(", ")));
+	}
+	ret.push_str("}\n\n");
+	ret
+}
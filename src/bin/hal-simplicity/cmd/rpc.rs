@@ -0,0 +1,82 @@
+//! A minimal JSON-RPC client for the commands talking to an Elements node.
+
+use std::fs;
+
+use elements::bitcoin::base64::engine::general_purpose::STANDARD as BASE64;
+use elements::bitcoin::base64::Engine;
+
+use crate::cmd;
+
+/// The options to authenticate to the node's JSON-RPC interface.
+pub fn opts_auth<'a>() -> Vec<clap::Arg<'a, 'a>> {
+	vec![
+		cmd::opt("rpc-user", "the JSON-RPC user name").takes_value(true).requires("rpc-pass"),
+		cmd::opt("rpc-pass", "the JSON-RPC password").takes_value(true).requires("rpc-user"),
+		cmd::opt("rpc-cookie", "the path of the JSON-RPC cookie file")
+			.takes_value(true)
+			.conflicts_with("rpc-user"),
+	]
+}
+
+/// A minimal JSON-RPC client for an Elements node.
+pub struct RpcClient {
+	pub url: String,
+	auth: Option<String>,
+}
+
+impl RpcClient {
+	/// A client for the given URL using the credentials given with `opts_auth`.
+	pub fn new<'a>(url: &str, matches: &clap::ArgMatches<'a>) -> RpcClient {
+		let credentials = if let Some(path) = matches.value_of("rpc-cookie") {
+			let cookie = fs::read_to_string(path)
+				.unwrap_or_else(|e| panic!("could not read cookie file {}: {}", path, e));
+			Some(cookie.trim().to_owned())
+		} else {
+			matches.value_of("rpc-user").map(|user| {
+				format!("{}:{}", user, matches.value_of("rpc-pass").expect("required by clap"))
+			})
+		};
+		RpcClient {
+			url: url.to_owned(),
+			auth: credentials.map(|c| format!("Basic {}", BASE64.encode(c))),
+		}
+	}
+
+	/// Call the given method, returning its result or the error message.
+	pub fn call(
+		&self,
+		method: &str,
+		params: serde_json::Value,
+	) -> Result<serde_json::Value, String> {
+		let mut request = ureq::post(&self.url);
+		if let Some(ref auth) = self.auth {
+			request = request.set("Authorization", auth);
+		}
+		let body = serde_json::json!({
+			"jsonrpc": "1.0",
+			"id": "hal-simplicity",
+			"method": method,
+			"params": params,
+		});
+		// Errors are returned with an HTTP error status but a JSON body.
+		let response = match request.send_json(body) {
+			Ok(response) => response,
+			Err(ureq::Error::Status(_, response)) => response,
+			Err(e) => return Err(format!("RPC call {} failed: {}", method, e)),
+		};
+		let mut response: serde_json::Value = response
+			.into_json()
+			.map_err(|e| format!("invalid response to RPC call {}: {}", method, e))?;
+		if !response["error"].is_null() {
+			return Err(format!("RPC call {} failed: {}", method, response["error"]));
+		}
+		Ok(response["result"].take())
+	}
+
+	/// Call a method returning hex data, such as `getrawtransaction`, and decode it.
+	pub fn raw(&self, method: &str, params: serde_json::Value) -> Result<Vec<u8>, String> {
+		let hex = self.call(method, params)?;
+		hex::decode(hex.as_str().unwrap_or_default())
+			.map_err(|_| format!("invalid hex returned by RPC call {}", method))
+	}
+}
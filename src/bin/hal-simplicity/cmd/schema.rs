@@ -0,0 +1,76 @@
+use std::collections::BTreeMap;
+use std::io::Write;
+
+use schemars::schema::RootSchema;
+
+use crate::cmd;
+use crate::cmd::protobuf::ProtoFile;
+
+pub fn subcommand<'a>() -> clap::App<'a, 'a> {
+	cmd::subcommand("schema", "print the JSON schemas of the outputs of the commands").args(&[
+		cmd::opt_yaml(),
+		cmd::opt("proto", "print the protobuf messages used by `--output protobuf` instead")
+			.takes_value(false)
+			.required(false),
+		cmd::arg("command", "the command to print the schema of, e.g. 'tx decode' (default: all)")
+			.multiple(true)
+			.required(false),
+	])
+}
+
+/// The commands which take several inputs, and print a list of outputs for them.
+const BATCH_COMMANDS: &[&str] = &["address create", "address inspect", "block decode", "tx decode"];
+
+/// The schemas of the outputs of all commands printing JSON, by command name.
+fn schemas() -> BTreeMap<String, RootSchema> {
+	let groups = vec![
+		("address", cmd::address::schemas()),
+		("asset", cmd::asset::schemas()),
+		("audit", cmd::audit::schemas()),
+		("block", cmd::block::schemas()),
+		("fee", cmd::fee::schemas()),
+		("keypair", cmd::keypair::schemas()),
+		("pegin", cmd::pegin::schemas()),
+		("session", cmd::session::schemas()),
+		("simplicity", cmd::simplicity::schemas()),
+		("tx", cmd::tx::schemas()),
+		("vectors", cmd::vectors::schemas()),
+		("wallet", cmd::wallet::schemas()),
+	];
+	let mut schemas = groups
+		.into_iter()
+		.flat_map(|(group, schemas)| {
+			schemas.into_iter().map(move |(name, schema)| (format!("{} {}", group, name), schema))
+		})
+		.collect::<BTreeMap<_, _>>();
+	schemas.insert("doctor".to_owned(), cmd::doctor::schema());
+	schemas.insert("info".to_owned(), cmd::info::schema());
+	schemas.insert("watch".to_owned(), cmd::watch::schema());
+	schemas
+}
+
+pub fn execute<'a>(matches: &clap::ArgMatches<'a>) {
+	let mut schemas = schemas();
+	if let Some(command) = matches.values_of("command") {
+		let command = command.collect::<Vec<_>>().join(" ");
+		let schema = schemas
+			.remove(&command)
+			.unwrap_or_else(|| panic!("no schema for command '{}'", command));
+		schemas = BTreeMap::from([(command, schema)]);
+	}
+
+	if matches.is_present("proto") {
+		let mut proto = ProtoFile::default();
+		for (command, schema) in &schemas {
+			proto.add(command, schema);
+			if BATCH_COMMANDS.contains(&command.as_str()) {
+				proto.add_list(command, schema);
+			}
+		}
+		write!(cmd::Output, "{}", proto.finish()).unwrap();
+	} else if schemas.len() == 1 {
+		cmd::print_output(matches, &schemas.into_values().next().expect("one schema"))
+	} else {
+		cmd::print_output(matches, &schemas)
+	}
+}
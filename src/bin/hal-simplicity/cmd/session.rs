@@ -0,0 +1,593 @@
+//! Signing sessions, for spends of Simplicity programs by several operators.
+//!
+//! A session file is a JSON object holding the unsigned transaction, the outputs
+//! it spends and, for each input spending a Simplicity policy, the policy, its
+//! control block, the sighash to sign and the signatures collected so far. The
+//! file is created once, passed around for each operator to add their signatures
+//! or preimages, and finalized into the signed transaction by anyone holding it.
+//!
+//! As the file passes through several hands, its CMRs and control blocks are
+//! checked against the policies whenever it is loaded, and its sighashes against
+//! the transaction before anything is signed.
+
+use std::collections::{BTreeMap, HashMap};
+use std::fs;
+use std::path::Path;
+use std::sync::Arc;
+
+use elements::bitcoin::secp256k1::{self, schnorr, Keypair, Message, SecretKey, XOnlyPublicKey};
+use elements::encode::{deserialize, serialize};
+use elements::hashes::{sha256, Hash};
+use elements::taproot::{ControlBlock, TapLeafHash};
+use elements::{BlockHash, SchnorrSig, SchnorrSighashType, Script, Transaction};
+use hal_simplicity::hal_simplicity::{control_block, elements_address};
+use hal_simplicity::policy::parse_policy;
+use hal_simplicity::simplicity::jet::elements::{ElementsEnv, ElementsUtxo};
+use hal_simplicity::simplicity::jet::Elements;
+use hal_simplicity::simplicity::policy::{Preimage32, Satisfier};
+use hal_simplicity::simplicity::{Cmr, Policy, RedeemNode};
+use hal_simplicity::HexBytes;
+use log::warn;
+use schemars::schema::RootSchema;
+use schemars::JsonSchema;
+use serde::{Deserialize, Serialize};
+
+use crate::cmd;
+use crate::cmd::simplicity::{genesis_hash, opts_env, parse_utxo};
+
+/// The version of the session file format written by this version of hal-simplicity.
+const SESSION_VERSION: u32 = 1;
+
+pub fn subcommand<'a>() -> clap::App<'a, 'a> {
+	cmd::subcommand_group("session", "collaborative signing of Simplicity spends")
+		.subcommand(cmd_create())
+		.subcommand(cmd_add_sig())
+		.subcommand(cmd_status())
+		.subcommand(cmd_finalize())
+}
+
+/// The schemas of the outputs of the subcommands, by subcommand name.
+pub fn schemas() -> Vec<(&'static str, RootSchema)> {
+	vec![
+		("add-sig", schemars::schema_for!(SessionStatus)),
+		("create", schemars::schema_for!(SessionStatus)),
+		("finalize", schemars::schema_for!(FinalizeInfo)),
+		("status", schemars::schema_for!(SessionStatus)),
+	]
+}
+
+pub fn execute<'a>(matches: &clap::ArgMatches<'a>) {
+	match matches.subcommand() {
+		("create", Some(m)) => exec_create(m),
+		("add-sig", Some(m)) => exec_add_sig(m),
+		("status", Some(m)) => exec_status(m),
+		("finalize", Some(m)) => exec_finalize(m),
+		(_, _) => unreachable!("clap prints help"),
+	};
+}
+
+fn arg_session<'a>() -> clap::Arg<'a, 'a> {
+	cmd::arg("session", "the path of the session file").required(true)
+}
+
+fn cmd_create<'a>() -> clap::App<'a, 'a> {
+	cmd::subcommand("create", "start a signing session for a transaction in a new session file")
+		.args(&opts_env())
+		.args(&cmd::opts_raw_value())
+		.args(&[
+			cmd::opt_yaml(),
+			cmd::opt_output(),
+			arg_session(),
+			cmd::arg("tx", "the unsigned transaction in hex, base64 or as a file path")
+				.required(true),
+			cmd::opt(
+				"policy",
+				"the policy of the Simplicity program spent by an input as INDEX:POLICY; \
+				 may be given several times",
+			)
+			.short("p")
+			.takes_value(true)
+			.multiple(true)
+			.number_of_values(1)
+			.required(true),
+		])
+}
+
+fn cmd_add_sig<'a>() -> clap::App<'a, 'a> {
+	cmd::subcommand("add-sig", "add signatures or preimages to a session file")
+		.args(&cmd::opts_raw_value())
+		.args(&[
+			cmd::opt_yaml(),
+			cmd::opt_output(),
+			cmd::opt_audit_log(),
+			arg_session(),
+			cmd::opt(
+				"secret-key",
+				"a secret key to sign all inputs whose policy has its public key; \
+				 may be given several times",
+			)
+			.short("s")
+			.takes_value(true)
+			.multiple(true)
+			.number_of_values(1)
+			.required(false),
+			cmd::opt(
+				"signature",
+				"a signature made elsewhere as INDEX:PUBKEY:SIGNATURE; may be given several times",
+			)
+			.takes_value(true)
+			.multiple(true)
+			.number_of_values(1)
+			.required(false),
+			cmd::opt(
+				"preimage",
+				"a 32-byte preimage of a sha256 fragment in hex; may be given several times",
+			)
+			.takes_value(true)
+			.multiple(true)
+			.number_of_values(1)
+			.required(false),
+		])
+}
+
+fn cmd_status<'a>() -> clap::App<'a, 'a> {
+	cmd::subcommand("status", "show the signatures collected and missing in a session file")
+		.args(&cmd::opts_raw_value())
+		.args(&[cmd::opt_yaml(), cmd::opt_output(), arg_session()])
+}
+
+fn cmd_finalize<'a>() -> clap::App<'a, 'a> {
+	cmd::subcommand("finalize", "build the signed transaction from a complete session file")
+		.args(&cmd::opts_raw_value())
+		.args(&[cmd::opt_yaml(), cmd::opt_output(), arg_session()])
+}
+
+/// An input spending a Simplicity policy.
+#[derive(Serialize, Deserialize, JsonSchema)]
+struct PolicyInput {
+	policy: String,
+	#[schemars(with = "String")]
+	cmr: Cmr,
+	#[schemars(with = "String")]
+	control_block: HexBytes,
+	/// The SIGHASH_ALL of the input, signed by each key of the policy.
+	#[schemars(with = "String")]
+	sighash: sha256::Hash,
+	/// The signatures collected so far, keyed by x-only public key.
+	#[serde(default)]
+	signatures: BTreeMap<String, String>,
+}
+
+#[derive(Serialize, Deserialize, JsonSchema)]
+struct SessionInput {
+	/// The output spent by the input as SCRIPTPUBKEY:ASSET:VALUE.
+	utxo: String,
+	/// The policy spent by the input; inputs without one are signed outside the session.
+	#[serde(default, skip_serializing_if = "Option::is_none")]
+	simplicity: Option<PolicyInput>,
+}
+
+/// The contents of a session file.
+#[derive(Serialize, Deserialize, JsonSchema)]
+struct Session {
+	version: u32,
+	/// The unsigned transaction in hex.
+	#[schemars(with = "String")]
+	tx: HexBytes,
+	#[schemars(with = "String")]
+	genesis_hash: BlockHash,
+	inputs: Vec<SessionInput>,
+	/// The preimages of sha256 fragments collected so far, keyed by hash.
+	#[serde(default)]
+	preimages: BTreeMap<String, String>,
+}
+
+#[derive(Serialize, JsonSchema)]
+struct InputStatus {
+	index: usize,
+	#[serde(skip_serializing_if = "Option::is_none")]
+	#[schemars(with = "Option<String>")]
+	cmr: Option<Cmr>,
+	/// The keys of the policy which have signed.
+	#[serde(skip_serializing_if = "Vec::is_empty")]
+	signed: Vec<String>,
+	/// The keys of the policy which haven't signed yet.
+	#[serde(skip_serializing_if = "Vec::is_empty")]
+	unsigned: Vec<String>,
+	/// Whether the input can be finalized. Inputs without a policy are ready if the
+	/// transaction already holds their witness or scriptSig.
+	ready: bool,
+}
+
+#[derive(Serialize, JsonSchema)]
+struct SessionStatus {
+	#[schemars(with = "String")]
+	txid: elements::Txid,
+	inputs: Vec<InputStatus>,
+	/// Whether all inputs are ready and the session can be finalized.
+	complete: bool,
+}
+
+#[derive(Serialize, JsonSchema)]
+struct FinalizeInfo {
+	/// The signed transaction in hex.
+	tx: String,
+	#[schemars(with = "String")]
+	txid: elements::Txid,
+}
+
+/// Looks up the signatures and preimages of a session for [`Policy::satisfy`].
+struct SessionSatisfier<'a> {
+	signatures: HashMap<XOnlyPublicKey, SchnorrSig>,
+	preimages: HashMap<sha256::Hash, Preimage32>,
+	tx: &'a Transaction,
+	index: usize,
+}
+
+impl Satisfier<XOnlyPublicKey> for SessionSatisfier<'_> {
+	fn lookup_tap_leaf_script_sig(
+		&self,
+		key: &XOnlyPublicKey,
+		_: &TapLeafHash,
+	) -> Option<SchnorrSig> {
+		self.signatures.get(key).copied()
+	}
+
+	fn lookup_sha256(&self, hash: &sha256::Hash) -> Option<Preimage32> {
+		self.preimages.get(hash).copied()
+	}
+
+	fn check_older(&self, n: elements::Sequence) -> bool {
+		<elements::Sequence as Satisfier<XOnlyPublicKey>>::check_older(
+			&self.tx.input[self.index].sequence,
+			n,
+		)
+	}
+
+	fn check_after(&self, n: elements::LockTime) -> bool {
+		<elements::LockTime as Satisfier<XOnlyPublicKey>>::check_after(&self.tx.lock_time, n)
+	}
+}
+
+impl Session {
+	fn load(path: &str) -> Session {
+		let contents = fs::read_to_string(path)
+			.unwrap_or_else(|e| panic!("could not read session file {}: {}", path, e));
+		let session: Session = serde_json::from_str(&contents)
+			.unwrap_or_else(|e| panic!("invalid session file {}: {}", path, e));
+		if session.version != SESSION_VERSION {
+			panic!("unsupported session file version {}", session.version);
+		}
+		let n_inputs = session.tx().input.len();
+		if session.inputs.len() != n_inputs {
+			panic!(
+				"session file has {} inputs for {} transaction inputs",
+				session.inputs.len(),
+				n_inputs
+			);
+		}
+		for (index, input) in session.inputs.iter().enumerate() {
+			let simplicity = match input.simplicity {
+				Some(ref simplicity) => simplicity,
+				None => continue,
+			};
+			let cmr = policy(&simplicity.policy)
+				.commit()
+				.expect("policies without asm fragments always compile")
+				.cmr();
+			if cmr != simplicity.cmr {
+				panic!("the CMR of input {} doesn't match its policy", index);
+			}
+			if control_block(cmr).serialize() != simplicity.control_block.bytes() {
+				panic!("the control block of input {} doesn't match its policy", index);
+			}
+		}
+		session
+	}
+
+	fn save(&self, path: &str) {
+		let contents = serde_json::to_string_pretty(self).expect("session is always valid JSON");
+		fs::write(path, contents + "\n")
+			.unwrap_or_else(|e| panic!("could not write session file {}: {}", path, e));
+	}
+
+	fn tx(&self) -> Transaction {
+		deserialize(self.tx.bytes()).expect("invalid transaction in session file")
+	}
+
+	fn utxos(&self) -> Vec<ElementsUtxo> {
+		self.inputs.iter().map(|input| parse_utxo(&input.utxo)).collect()
+	}
+
+	fn preimages(&self) -> HashMap<sha256::Hash, Preimage32> {
+		self.preimages
+			.iter()
+			.map(|(hash, preimage)| {
+				let preimage = hex::decode(preimage).expect("invalid preimage in session file");
+				(
+					hash.parse().expect("invalid preimage hash in session file"),
+					preimage.try_into().expect("invalid preimage in session file"),
+				)
+			})
+			.collect()
+	}
+
+	/// The environment of the given input, for computing its sighash and pruning its program.
+	fn env(
+		&self,
+		tx: &Transaction,
+		index: usize,
+		input: &PolicyInput,
+	) -> ElementsEnv<Arc<Transaction>> {
+		ElementsEnv::new(
+			Arc::new(tx.clone()),
+			self.utxos(),
+			index as u32,
+			input.cmr,
+			ControlBlock::from_slice(input.control_block.bytes())
+				.expect("invalid control block in session file"),
+			None,
+			self.genesis_hash,
+		)
+	}
+
+	/// Satisfy the policy of the given input with the signatures and preimages collected so far.
+	fn satisfy(
+		&self,
+		tx: &Transaction,
+		index: usize,
+		input: &PolicyInput,
+	) -> Option<Arc<RedeemNode<Elements>>> {
+		let satisfier = SessionSatisfier {
+			signatures: input
+				.signatures
+				.iter()
+				.map(|(key, sig)| {
+					let sig = SchnorrSig {
+						sig: sig.parse().expect("invalid signature in session file"),
+						hash_ty: SchnorrSighashType::Default,
+					};
+					(key.parse().expect("invalid public key in session file"), sig)
+				})
+				.collect(),
+			preimages: self.preimages(),
+			tx,
+			index,
+		};
+		policy(&input.policy).satisfy(&satisfier, &self.env(tx, index, input)).ok()
+	}
+
+	fn status(&self) -> SessionStatus {
+		let tx = self.tx();
+		let inputs = self
+			.inputs
+			.iter()
+			.enumerate()
+			.map(|(index, input)| match input.simplicity {
+				Some(ref simplicity) => {
+					let (signed, unsigned) = policy(&simplicity.policy)
+						.iter_pk()
+						.map(|key| key.to_string())
+						.partition::<Vec<_>, _>(|key| simplicity.signatures.contains_key(key));
+					InputStatus {
+						index,
+						cmr: Some(simplicity.cmr),
+						signed,
+						unsigned,
+						ready: self.satisfy(&tx, index, simplicity).is_some(),
+					}
+				}
+				None => InputStatus {
+					index,
+					cmr: None,
+					signed: vec![],
+					unsigned: vec![],
+					ready: !tx.input[index].witness.script_witness.is_empty()
+						|| !tx.input[index].script_sig.is_empty(),
+				},
+			})
+			.collect::<Vec<_>>();
+		SessionStatus {
+			txid: tx.txid(),
+			complete: inputs.iter().all(|input| input.ready),
+			inputs,
+		}
+	}
+}
+
+fn policy(s: &str) -> Policy<XOnlyPublicKey> {
+	parse_policy(s).unwrap_or_else(|e| panic!("invalid policy '{}': {}", s, e))
+}
+
+/// Parse an `INDEX:VALUE` pair, checking the index against the number of inputs.
+fn parse_indexed<'a>(s: &'a str, what: &str, inputs: usize) -> (usize, &'a str) {
+	let (index, value) =
+		s.split_once(':').unwrap_or_else(|| panic!("invalid {} '{}', expected INDEX:...", what, s));
+	let index: usize =
+		index.parse().unwrap_or_else(|_| panic!("invalid {} index '{}'", what, index));
+	if index >= inputs {
+		panic!("{} index {} out of range for {} inputs", what, index, inputs);
+	}
+	(index, value)
+}
+
+fn exec_create<'a>(matches: &clap::ArgMatches<'a>) {
+	let path = matches.value_of("session").expect("session is mandatory");
+	if Path::new(path).exists() {
+		panic!("session file {} already exists", path);
+	}
+	let tx_bytes = cmd::decode_binary(matches.value_of("tx").expect("tx is mandatory"), "tx");
+	let tx: Transaction = deserialize(&tx_bytes).expect("invalid tx format");
+	let utxos =
+		matches.values_of("input-utxo").expect("input UTXOs are mandatory").collect::<Vec<_>>();
+	if utxos.len() != tx.input.len() {
+		panic!("{} input UTXOs given for {} inputs", utxos.len(), tx.input.len());
+	}
+
+	let mut session = Session {
+		version: SESSION_VERSION,
+		tx: tx_bytes.into(),
+		genesis_hash: genesis_hash(matches),
+		inputs: utxos
+			.iter()
+			.map(|utxo| {
+				parse_utxo(utxo);
+				SessionInput {
+					utxo: utxo.to_string(),
+					simplicity: None,
+				}
+			})
+			.collect(),
+		preimages: BTreeMap::new(),
+	};
+
+	for arg in matches.values_of("policy").expect("policy is mandatory") {
+		let (index, s) = parse_indexed(arg, "policy", tx.input.len());
+		let policy = policy(s);
+		let cmr = policy.commit().expect("policies without asm fragments always compile").cmr();
+		let script_pubkey = elements_address(cmr, &elements::AddressParams::LIQUID).script_pubkey();
+		if parse_utxo(&session.inputs[index].utxo).script_pubkey != script_pubkey {
+			panic!("the UTXO of input {} is not an output of the policy {}", index, policy);
+		}
+		if session.inputs[index].simplicity.is_some() {
+			panic!("several policies given for input {}", index);
+		}
+		let mut input = PolicyInput {
+			policy: policy.to_string(),
+			cmr,
+			control_block: control_block(cmr).serialize().into(),
+			sighash: sha256::Hash::all_zeros(),
+			signatures: BTreeMap::new(),
+		};
+		input.sighash = session.env(&tx, index, &input).c_tx_env().sighash_all();
+		session.inputs[index].simplicity = Some(input);
+	}
+
+	session.save(path);
+	cmd::print_output(matches, &session.status())
+}
+
+fn exec_add_sig<'a>(matches: &clap::ArgMatches<'a>) {
+	let path = matches.value_of("session").expect("session is mandatory");
+	let mut session = Session::load(path);
+	let n_inputs = session.inputs.len();
+
+	// Never sign a sighash which doesn't commit to the transaction of the session.
+	let tx = session.tx();
+	for (index, input) in session.inputs.iter().enumerate() {
+		if let Some(ref simplicity) = input.simplicity {
+			if session.env(&tx, index, simplicity).c_tx_env().sighash_all() != simplicity.sighash {
+				panic!(
+					"the sighash of input {} doesn't match the transaction of the session",
+					index
+				);
+			}
+		}
+	}
+
+	for sk in matches.values_of("secret-key").into_iter().flatten() {
+		let sk: SecretKey = sk.parse().expect("invalid secret key");
+		let keypair = Keypair::from_secret_key(secp256k1::SECP256K1, &sk);
+		let key = keypair.x_only_public_key().0;
+		let mut signed = false;
+		for input in session.inputs.iter_mut().filter_map(|input| input.simplicity.as_mut()) {
+			if policy(&input.policy).iter_pk().any(|k| k == key) {
+				let msg = Message::from_digest(input.sighash.to_byte_array());
+				let sig = secp256k1::SECP256K1.sign_schnorr(&msg, &keypair);
+				input.signatures.insert(key.to_string(), sig.to_string());
+				signed = true;
+			}
+		}
+		if !signed {
+			panic!("key {} is not in the policy of any input", key);
+		}
+	}
+
+	for arg in matches.values_of("signature").into_iter().flatten() {
+		let (index, rest) = parse_indexed(arg, "signature", n_inputs);
+		let (key, sig) = rest.split_once(':').unwrap_or_else(|| {
+			panic!("invalid signature '{}', expected INDEX:PUBKEY:SIGNATURE", arg)
+		});
+		let key: XOnlyPublicKey = key.parse().expect("invalid x-only public key");
+		let sig: schnorr::Signature = sig.parse().expect("invalid signature");
+		let input = session.inputs[index]
+			.simplicity
+			.as_mut()
+			.unwrap_or_else(|| panic!("input {} has no policy", index));
+		if !policy(&input.policy).iter_pk().any(|k| k == key) {
+			panic!("key {} is not in the policy of input {}", key, index);
+		}
+		let msg = Message::from_digest(input.sighash.to_byte_array());
+		secp256k1::SECP256K1
+			.verify_schnorr(&sig, &msg, &key)
+			.unwrap_or_else(|_| panic!("invalid signature by {} for input {}", key, index));
+		input.signatures.insert(key.to_string(), sig.to_string());
+	}
+
+	for preimage in matches.values_of("preimage").into_iter().flatten() {
+		let bytes = hex::decode(preimage).expect("preimage not hex");
+		if bytes.len() != 32 {
+			panic!("preimage must be 32 bytes, got {}", bytes.len());
+		}
+		let hash = sha256::Hash::hash(&bytes);
+		let used =
+			session.inputs.iter().filter_map(|input| input.simplicity.as_ref()).any(|input| {
+				policy(&input.policy).iter().any(|p| matches!(p, Policy::Sha256(h) if *h == hash))
+			});
+		if !used {
+			warn!("No policy has a sha256({}) fragment.", hash);
+		}
+		session.preimages.insert(hash.to_string(), preimage.to_owned());
+	}
+
+	session.save(path);
+	let status = session.status();
+	cmd::audit::record(
+		matches,
+		"session add-sig",
+		&["session", "secret-key", "signature", "preimage"],
+		&status,
+	);
+	cmd::print_output(matches, &status)
+}
+
+fn exec_status<'a>(matches: &clap::ArgMatches<'a>) {
+	let session = Session::load(matches.value_of("session").expect("session is mandatory"));
+	cmd::print_output(matches, &session.status())
+}
+
+fn exec_finalize<'a>(matches: &clap::ArgMatches<'a>) {
+	let session = Session::load(matches.value_of("session").expect("session is mandatory"));
+	let mut tx = session.tx();
+	let unsigned = tx.clone();
+
+	for (index, input) in session.inputs.iter().enumerate() {
+		let simplicity = match input.simplicity {
+			Some(ref simplicity) => simplicity,
+			None => {
+				if tx.input[index].witness.script_witness.is_empty()
+					&& tx.input[index].script_sig.is_empty()
+				{
+					warn!("Input {} has no policy and is left unsigned.", index);
+				}
+				continue;
+			}
+		};
+		let redeem = session.satisfy(&unsigned, index, simplicity).unwrap_or_else(|| {
+			panic!("input {} can't be satisfied with the signatures and preimages collected", index)
+		});
+		let (program, witness) = redeem.to_vec_with_witness();
+		tx.input[index].witness.script_witness = vec![
+			witness,
+			program,
+			Script::from(simplicity.cmr.as_ref().to_vec()).into_bytes(),
+			simplicity.control_block.bytes().to_vec(),
+		];
+	}
+
+	let info = FinalizeInfo {
+		tx: hex::encode(serialize(&tx)),
+		txid: tx.txid(),
+	};
+	cmd::print_output(matches, &info)
+}
@@ -0,0 +1,224 @@
+//! The signature checks of `tx decode --verify-sigs`.
+//!
+//! The signatures of P2PKH, P2WPKH, P2SH-wrapped P2WPKH and taproot key path
+//! inputs are checked against the outputs they spend. Other inputs, notably
+//! Simplicity and other taproot script path spends, are reported without being
+//! checked.
+
+use elements::bitcoin::secp256k1::{self, ecdsa, schnorr, Message};
+use elements::hashes::{hash160, Hash};
+use elements::script::Instruction;
+use elements::sighash::{Prevouts, SighashCache};
+use elements::{
+	confidential, BlockHash, EcdsaSighashType, PubkeyHash, SchnorrSighashType, Script, Transaction,
+	TxOut,
+};
+use hal_simplicity::simplicity::jet::elements::ElementsUtxo;
+use hal_simplicity::tx::{SignatureCheckInfo, TransactionInfo};
+
+/// The first byte of a taproot annex.
+const ANNEX_TAG: u8 = 0x50;
+
+/// Set the result of checking the signature of each input of the decoded transaction.
+pub fn annotate(
+	tx: &Transaction,
+	utxos: &[ElementsUtxo],
+	genesis_hash: BlockHash,
+	info: &mut TransactionInfo,
+) {
+	if utxos.len() != tx.input.len() {
+		panic!(
+			"the transaction has {} inputs but {} input UTXOs were given",
+			tx.input.len(),
+			utxos.len()
+		);
+	}
+	let prevouts = utxos
+		.iter()
+		.map(|utxo| TxOut {
+			asset: utxo.asset,
+			value: utxo.value,
+			nonce: confidential::Nonce::Null,
+			script_pubkey: utxo.script_pubkey.clone(),
+			witness: Default::default(),
+		})
+		.collect::<Vec<_>>();
+	if let Some(ref mut inputs) = info.inputs {
+		for (index, info) in inputs.iter_mut().enumerate() {
+			info.signature_check = Some(check_input(tx, index, &prevouts, genesis_hash));
+		}
+	}
+}
+
+/// Check the signature of the input spending the given output.
+fn check_input(
+	tx: &Transaction,
+	index: usize,
+	prevouts: &[TxOut],
+	genesis_hash: BlockHash,
+) -> SignatureCheckInfo {
+	let input = &tx.input[index];
+	let spk = &prevouts[index].script_pubkey;
+	let (type_, result) = if input.is_pegin {
+		("pegin", None)
+	} else if spk.is_p2pkh() {
+		let result = match pushes(&input.script_sig).as_deref() {
+			Some([sig, pubkey]) => check_p2pkh(tx, index, spk, sig, pubkey),
+			_ => Err("expected a signature and a public key in the scriptSig".to_owned()),
+		};
+		("p2pkh", Some(result))
+	} else if spk.is_v0_p2wpkh() {
+		let result = check_p2wpkh(tx, index, spk, prevouts[index].value);
+		("p2wpkh", Some(result))
+	} else if spk.is_p2sh() && is_wrapped_p2wpkh(&input.script_sig) {
+		let redeem_script = Script::from(input.script_sig.as_bytes()[1..].to_vec());
+		let result = if redeem_script.to_p2sh() == *spk {
+			check_p2wpkh(tx, index, &redeem_script, prevouts[index].value)
+		} else {
+			Err("the redeem script does not match the spent script".to_owned())
+		};
+		("p2sh-p2wpkh", Some(result))
+	} else if spk.is_v1_p2tr() {
+		let mut witness = input.witness.script_witness.as_slice();
+		if witness.len() >= 2 && witness.last().and_then(|a| a.first()) == Some(&ANNEX_TAG) {
+			witness = &witness[..witness.len() - 1];
+		}
+		match witness {
+			[sig] => ("p2tr", Some(check_p2tr(tx, index, prevouts, sig, genesis_hash))),
+			_ => ("p2tr-script", None),
+		}
+	} else if spk.is_p2sh() {
+		("p2sh", None)
+	} else if spk.is_v0_p2wsh() {
+		("p2wsh", None)
+	} else {
+		("unknown", None)
+	};
+
+	SignatureCheckInfo {
+		type_: type_.to_owned(),
+		valid: result.as_ref().map(Result::is_ok),
+		error: match result {
+			Some(Ok(())) => None,
+			Some(Err(e)) => Some(e),
+			None => Some("inputs of this type are not checked".to_owned()),
+		},
+	}
+}
+
+/// The data pushed by the script, if it only pushes data.
+fn pushes(script: &Script) -> Option<Vec<Vec<u8>>> {
+	script
+		.instructions()
+		.map(|i| match i {
+			Ok(Instruction::PushBytes(data)) => Some(data.to_vec()),
+			_ => None,
+		})
+		.collect()
+}
+
+/// Whether the scriptSig only pushes a P2WPKH redeem script.
+fn is_wrapped_p2wpkh(script_sig: &Script) -> bool {
+	match pushes(script_sig).as_deref() {
+		Some([redeem_script]) => Script::from(redeem_script.clone()).is_v0_p2wpkh(),
+		_ => false,
+	}
+}
+
+/// Check that the public key hashes to the given P2PKH or P2WPKH script.
+fn parse_pubkey(pubkey: &[u8], hash: &[u8]) -> Result<secp256k1::PublicKey, String> {
+	if hash160::Hash::hash(pubkey).as_byte_array()[..] != *hash {
+		return Err("the public key does not match the spent script".to_owned());
+	}
+	secp256k1::PublicKey::from_slice(pubkey).map_err(|e| format!("invalid public key: {}", e))
+}
+
+/// Split an ECDSA signature into the DER signature and its sighash type.
+fn parse_ecdsa(sig: &[u8]) -> Result<(ecdsa::Signature, EcdsaSighashType), String> {
+	let (sighash_type, der) = sig.split_last().ok_or("empty signature")?;
+	let mut sig =
+		ecdsa::Signature::from_der(der).map_err(|e| format!("invalid DER signature: {}", e))?;
+	// Consensus accepts high-S signatures, which libsecp256k1 doesn't verify.
+	sig.normalize_s();
+	Ok((sig, EcdsaSighashType::from_u32(u32::from(*sighash_type))))
+}
+
+fn verify_ecdsa(
+	sighash: elements::Sighash,
+	sig: &ecdsa::Signature,
+	pubkey: &secp256k1::PublicKey,
+) -> Result<(), String> {
+	let msg = Message::from_digest(sighash.to_byte_array());
+	secp256k1::SECP256K1
+		.verify_ecdsa(&msg, sig, pubkey)
+		.map_err(|_| format!("invalid signature of sighash {}", sighash))
+}
+
+fn check_p2pkh(
+	tx: &Transaction,
+	index: usize,
+	spk: &Script,
+	sig: &[u8],
+	pubkey: &[u8],
+) -> Result<(), String> {
+	let pubkey = parse_pubkey(pubkey, &spk.as_bytes()[3..23])?;
+	let (sig, sighash_type) = parse_ecdsa(sig)?;
+	let sighash = SighashCache::new(tx).legacy_sighash(index, spk, sighash_type);
+	verify_ecdsa(sighash, &sig, &pubkey)
+}
+
+/// Check the input spending the given P2WPKH script, possibly wrapped in P2SH.
+fn check_p2wpkh(
+	tx: &Transaction,
+	index: usize,
+	wpkh: &Script,
+	value: confidential::Value,
+) -> Result<(), String> {
+	let (sig, pubkey) = match tx.input[index].witness.script_witness.as_slice() {
+		[sig, pubkey] => (sig, pubkey),
+		_ => return Err("expected a signature and a public key in the witness".to_owned()),
+	};
+	let pubkey = parse_pubkey(pubkey, &wpkh.as_bytes()[2..])?;
+	let (sig, sighash_type) = parse_ecdsa(sig)?;
+	let script_code = Script::new_p2pkh(&PubkeyHash::from_slice(&wpkh.as_bytes()[2..]).unwrap());
+	let sighash = SighashCache::new(tx).segwitv0_sighash(index, &script_code, value, sighash_type);
+	verify_ecdsa(sighash, &sig, &pubkey)
+}
+
+fn check_p2tr(
+	tx: &Transaction,
+	index: usize,
+	prevouts: &[TxOut],
+	sig: &[u8],
+	genesis_hash: BlockHash,
+) -> Result<(), String> {
+	let (sig, sighash_type) = match sig.len() {
+		64 => (sig, SchnorrSighashType::Default),
+		// BIP-341: SIGHASH_DEFAULT is only implied by 64-byte signatures.
+		65 if sig[64] == 0 => {
+			return Err("invalid sighash type 0 in a 65-byte signature".to_owned())
+		}
+		65 => (
+			&sig[..64],
+			SchnorrSighashType::from_u8(sig[64])
+				.ok_or_else(|| format!("invalid sighash type {}", sig[64]))?,
+		),
+		n => return Err(format!("invalid signature of {} bytes", n)),
+	};
+	let sig =
+		schnorr::Signature::from_slice(sig).map_err(|e| format!("invalid signature: {}", e))?;
+	let output_key = secp256k1::XOnlyPublicKey::from_slice(&prevouts[index].script_pubkey[2..])
+		.map_err(|e| format!("invalid output key: {}", e))?;
+	let sighash = SighashCache::new(tx)
+		.taproot_key_spend_signature_hash(
+			index,
+			&Prevouts::All(prevouts),
+			sighash_type,
+			genesis_hash,
+		)
+		.map_err(|e| format!("could not compute the sighash: {}", e))?;
+	let msg = Message::from_digest(sighash.to_byte_array());
+	secp256k1::SECP256K1
+		.verify_schnorr(&sig, &msg, &output_key)
+		.map_err(|_| format!("invalid signature of sighash {}", sighash))
+}
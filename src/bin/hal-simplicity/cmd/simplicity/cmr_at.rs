@@ -0,0 +1,91 @@
+// SPDX-License-Identifier: CC0-1.0
+
+use crate::cmd;
+
+use hal_simplicity::hal_simplicity::{encoded_nodes, Program};
+use hal_simplicity::simplicity::dag::DagLike;
+use hal_simplicity::simplicity::jet;
+use hal_simplicity::simplicity::Cmr;
+use schemars::schema::RootSchema;
+use schemars::JsonSchema;
+use serde::Serialize;
+
+#[derive(Serialize, JsonSchema)]
+struct CmrAtInfo {
+	/// The position of the node in the encoding of the program, as listed by
+	/// `info --format nodes`.
+	index: usize,
+	node: String,
+	#[schemars(with = "String")]
+	cmr: Cmr,
+	type_arrow: String,
+	decode: String,
+}
+
+pub fn cmd<'a>() -> clap::App<'a, 'a> {
+	cmd::subcommand("cmr-at", "Show the CMR and type of a sub-expression of a Simplicity program")
+		.args(&cmd::opts_raw_value())
+		.args(&[
+			cmd::opt_yaml(),
+			cmd::opt_output(),
+			cmd::arg("program", "a Simplicity program in base64").takes_value(true).required(true),
+			cmd::arg(
+				"node",
+				"the index of the node (as listed by info --format nodes), or a path from the root \
+				 made of 'l' and 'r' for the left and right children",
+			)
+			.takes_value(true)
+			.required(true),
+		])
+}
+
+pub fn schema() -> RootSchema {
+	schemars::schema_for!(CmrAtInfo)
+}
+
+pub fn exec<'a>(matches: &clap::ArgMatches<'a>) {
+	let program = matches.value_of("program").expect("program is mandatory");
+	let program = Program::<jet::Elements>::from_str(program, None).expect("invalid program");
+	let node = matches.value_of("node").expect("node is mandatory");
+
+	let root = program.commit_prog();
+	let nodes = encoded_nodes(root);
+	let (index, target) = match node.parse::<usize>() {
+		Ok(index) => nodes
+			.iter()
+			.find(|(i, _)| *i == index)
+			.copied()
+			.unwrap_or_else(|| panic!("no node at index {}", index)),
+		Err(_) => {
+			let mut target = root;
+			for (depth, step) in node.chars().enumerate() {
+				let child = match step {
+					'l' => target.left_child(),
+					'r' => target.right_child(),
+					_ => panic!("invalid path '{}', expected only 'l' and 'r'", node),
+				};
+				target = child.unwrap_or_else(|| {
+					panic!("no child '{}' at depth {} of path '{}'", step, depth, node)
+				});
+			}
+			// The node may be shared with an identical node listed earlier.
+			let id = target.sharing_id();
+			nodes
+				.iter()
+				.find(|(_, node)| {
+					std::ptr::eq(*node, target) || (id.is_some() && node.sharing_id() == id)
+				})
+				.copied()
+				.expect("every node of the program is listed")
+		}
+	};
+
+	let info = CmrAtInfo {
+		index,
+		node: target.inner().to_string(),
+		cmr: target.cmr(),
+		type_arrow: target.arrow().to_string(),
+		decode: target.display_expr().to_string(),
+	};
+	cmd::print_output(matches, &info)
+}
@@ -0,0 +1,121 @@
+// SPDX-License-Identifier: CC0-1.0
+
+use std::sync::Arc;
+
+use super::{
+	described_witness_slots, preimage_description, signature_description, CompiledInfo, SpendPath,
+	WitnessSlot,
+};
+use crate::cmd;
+
+use elements::bitcoin::secp256k1::XOnlyPublicKey;
+use elements::hashes::sha256;
+use hal_simplicity::hal_simplicity::control_block;
+use hal_simplicity::policy::is_lock_height;
+use hal_simplicity::simplicity::Policy;
+use hal_simplicity::HexBytes;
+use schemars::schema::RootSchema;
+use schemars::JsonSchema;
+use serde::Serialize;
+
+#[derive(Serialize, JsonSchema)]
+struct HtlcInfo {
+	policy: String,
+	#[serde(flatten)]
+	compiled: CompiledInfo,
+	#[schemars(with = "String")]
+	control_block: HexBytes,
+	witness: Vec<WitnessSlot>,
+	claim: SpendPath,
+	refund: SpendPath,
+}
+
+pub fn cmd<'a>() -> clap::App<'a, 'a> {
+	cmd::subcommand("htlc", "Generate a hash-timeout contract as a Simplicity program")
+		.args(&cmd::opts_raw_value())
+		.args(&[
+			cmd::opt_yaml(),
+			cmd::opt_output(),
+			cmd::opt("hash", "the SHA256 hash whose preimage lets the recipient claim")
+				.takes_value(true)
+				.required(true),
+			cmd::opt("recipient", "the x-only public key of the recipient")
+				.takes_value(true)
+				.required(true),
+			cmd::opt(
+				"refund",
+				"the x-only public key which can reclaim the coins after the timeout",
+			)
+			.takes_value(true)
+			.required(true),
+			cmd::opt("timeout", "the block height after which the refund path is enabled")
+				.takes_value(true)
+				.required(true),
+		])
+}
+
+pub fn schema() -> RootSchema {
+	schemars::schema_for!(HtlcInfo)
+}
+
+pub fn exec<'a>(matches: &clap::ArgMatches<'a>) {
+	let hash: sha256::Hash =
+		matches.value_of("hash").expect("hash is mandatory").parse().expect("invalid hash");
+	let recipient: XOnlyPublicKey = matches
+		.value_of("recipient")
+		.expect("recipient is mandatory")
+		.parse()
+		.expect("invalid recipient key");
+	let refund: XOnlyPublicKey = matches
+		.value_of("refund")
+		.expect("refund is mandatory")
+		.parse()
+		.expect("invalid refund key");
+	let timeout: u32 = matches
+		.value_of("timeout")
+		.expect("timeout is mandatory")
+		.parse()
+		.expect("invalid timeout");
+	if !is_lock_height(timeout) {
+		panic!("invalid timeout {}, expected a block height from 1 to 499999999", timeout);
+	}
+
+	let policy = Policy::Or {
+		left: Arc::new(Policy::And {
+			left: Arc::new(Policy::Key(recipient)),
+			right: Arc::new(Policy::Sha256(hash)),
+		}),
+		right: Arc::new(Policy::And {
+			left: Arc::new(Policy::Key(refund)),
+			right: Arc::new(Policy::After(timeout)),
+		}),
+	};
+	let program = policy.commit().expect("policies without asm fragments always compile");
+
+	// The selector bit picks the claim path when 0 and the refund path when 1; the
+	// witness values of the path which is not taken are pruned from the redeem program.
+	let claim = SpendPath {
+		selector: 0,
+		condition: format!("the recipient knows the preimage of {}", hash),
+		witness: vec![signature_description(&recipient), preimage_description(&hash)],
+	};
+	let refund = SpendPath {
+		selector: 1,
+		condition: format!("the spending transaction has a locktime of at least {}", timeout),
+		witness: vec![signature_description(&refund)],
+	};
+	let witness_desc = std::iter::once("0 to claim, 1 to refund".to_owned())
+		.chain(claim.witness.iter().map(|w| format!("{} (claim path only)", w)))
+		.chain(refund.witness.iter().map(|w| format!("{} (refund path only)", w)))
+		.collect::<Vec<_>>();
+
+	let info = HtlcInfo {
+		policy: policy.to_string(),
+		control_block: control_block(program.cmr()).serialize().into(),
+		compiled: CompiledInfo::new(&program),
+		witness: described_witness_slots(&program, &witness_desc),
+		claim,
+		refund,
+	};
+	cmd::print_output(matches, &info)
+}
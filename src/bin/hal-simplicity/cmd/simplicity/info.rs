@@ -0,0 +1,150 @@
+// Copyright 2025 Andrew Poelstra
+// SPDX-License-Identifier: CC0-1.0
+
+use crate::cmd;
+
+use hal_simplicity::hal_simplicity::{elements_address, Program};
+use hal_simplicity::simplicity::{jet, Amr, Cmr, Ihr};
+use schemars::schema::RootSchema;
+use schemars::JsonSchema;
+use serde::Serialize;
+
+#[derive(Serialize, JsonSchema)]
+struct RedeemInfo {
+	redeem_base64: String,
+	witness_hex: String,
+	#[schemars(with = "String")]
+	amr: Amr,
+	#[schemars(with = "String")]
+	ihr: Ihr,
+}
+
+/// A node of the program as listed by `--format nodes`.
+#[derive(Serialize, JsonSchema)]
+struct NodeInfo {
+	index: usize,
+	combinator: String,
+	/// The indexes of the child nodes.
+	children: Vec<usize>,
+	#[schemars(with = "String")]
+	cmr: Cmr,
+	/// Absent for hidden nodes, which have no type.
+	#[serde(skip_serializing_if = "Option::is_none")]
+	type_arrow: Option<String>,
+}
+
+#[derive(Serialize, JsonSchema)]
+struct ProgramInfo {
+	jets: &'static str,
+	commit_base64: String,
+	commit_decode: String,
+	type_arrow: String,
+	#[schemars(with = "String")]
+	cmr: Cmr,
+	liquid_address_unconf: String,
+	liquid_testnet_address_unconf: String,
+	is_redeem: bool,
+	#[serde(flatten)]
+	#[serde(skip_serializing_if = "Option::is_none")]
+	redeem_info: Option<RedeemInfo>,
+}
+
+/// The output of `simplicity info`, which is the list of the nodes of the program
+/// with `--format nodes`.
+#[derive(Serialize, JsonSchema)]
+#[serde(untagged)]
+enum SimplicityInfo {
+	Program(Box<ProgramInfo>),
+	Nodes(Vec<NodeInfo>),
+}
+
+pub fn cmd<'a>() -> clap::App<'a, 'a> {
+	cmd::subcommand("info", "Parse a base64-encoded Simplicity program and decode it")
+		.args(&cmd::opts_networks())
+		.args(&cmd::opts_raw_value())
+		.args(&[
+			cmd::opt_yaml(),
+			cmd::opt_output(),
+			cmd::opt_abbrev(),
+			cmd::opt(
+				"format",
+				"print the program as an expression, or as a table of its nodes with the sharing of its encoding",
+			)
+			.takes_value(true)
+			.possible_values(&["expr", "nodes"]),
+			cmd::arg("program", "a Simplicity program in base64, hex or as a file path")
+				.takes_value(true)
+				.required(true),
+			cmd::arg(
+				"witness",
+				"all the witness data for the program in hex, base64 or as a file path",
+			)
+			.takes_value(true)
+			.required(false),
+		])
+}
+
+pub fn schema() -> RootSchema {
+	schemars::schema_for!(SimplicityInfo)
+}
+
+pub fn exec<'a>(matches: &clap::ArgMatches<'a>) {
+	let program = matches.value_of("program").expect("program is mandatory");
+	let witness = matches.value_of("witness");
+
+	// In the future we should attempt to parse as a Bitcoin program if parsing as
+	// Elements fails. May be tricky/annoying in Rust since Program<Elements> is a
+	// different type from Program<Bitcoin>.
+	// Programs used to be taken in base64 and witnesses in hex only, which still
+	// have precedence.
+	let program = cmd::decode_binary_preferring(program, "program", cmd::TextFormat::Base64);
+	let witness = witness.map(|w| cmd::decode_binary(w, "witness"));
+	let program = Program::<jet::Elements>::from_bytes(&program, witness.as_deref())
+		.expect("invalid program");
+
+	// The format defaults to expr.
+	if matches.value_of("format") == Some("nodes") {
+		let nodes = program
+			.nodes()
+			.into_iter()
+			.map(|node| NodeInfo {
+				index: node.index,
+				combinator: node.combinator,
+				children: node.children,
+				cmr: node.cmr,
+				type_arrow: node.arrow,
+			})
+			.collect::<Vec<_>>();
+		return cmd::print_output(matches, &SimplicityInfo::Nodes(nodes));
+	}
+
+	let redeem_info = program.redeem_node().map(|node| {
+		let disp = node.display();
+		let x = RedeemInfo {
+			redeem_base64: disp.program().to_string(),
+			witness_hex: disp.witness().to_string(),
+			amr: node.amr(),
+			ihr: node.ihr(),
+		};
+		x // binding needed for truly stupid borrowck reasons
+	});
+
+	let info = ProgramInfo {
+		jets: "core",
+		commit_base64: program.commit_prog().to_string(),
+		// FIXME this is, in general, exponential in size. Need to limit it somehow; probably need upstream support
+		commit_decode: program.commit_prog().display_expr().to_string(),
+		type_arrow: program.commit_prog().arrow().to_string(),
+		cmr: program.cmr(),
+		liquid_address_unconf: elements_address(program.cmr(), &elements::AddressParams::LIQUID)
+			.to_string(),
+		liquid_testnet_address_unconf: elements_address(
+			program.cmr(),
+			&elements::AddressParams::LIQUID_TESTNET,
+		)
+		.to_string(),
+		is_redeem: redeem_info.is_some(),
+		redeem_info,
+	};
+	cmd::print_output(matches, &SimplicityInfo::Program(Box::new(info)))
+}
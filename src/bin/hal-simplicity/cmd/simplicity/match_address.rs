@@ -0,0 +1,90 @@
+// SPDX-License-Identifier: CC0-1.0
+
+use crate::cmd;
+
+use elements::bitcoin::secp256k1::XOnlyPublicKey;
+use hal_simplicity::hal_simplicity::{
+	elements_address_with_key, unspendable_internal_key, Program,
+};
+use hal_simplicity::simplicity::{jet, Cmr};
+use hal_simplicity::HexBytes;
+use schemars::schema::RootSchema;
+use schemars::JsonSchema;
+use serde::Serialize;
+
+#[derive(Serialize, JsonSchema)]
+struct MatchInfo {
+	#[schemars(with = "String")]
+	cmr: Cmr,
+	internal_key: String,
+	#[serde(skip_serializing_if = "Option::is_none")]
+	expected_address: Option<String>,
+	#[schemars(with = "String")]
+	expected_script_pubkey: HexBytes,
+	#[schemars(with = "String")]
+	script_pubkey: HexBytes,
+	matches: bool,
+}
+
+pub fn cmd<'a>() -> clap::App<'a, 'a> {
+	cmd::subcommand("match", "Check whether an address commits to a Simplicity program")
+		.args(&cmd::opts_raw_value())
+		.args(&[
+			cmd::opt_yaml(),
+			cmd::opt_output(),
+			cmd::arg("program", "a Simplicity program in base64, or its CMR in hex")
+				.takes_value(true)
+				.required(true),
+			cmd::arg("address", "a Liquid or regtest address, or a scriptPubKey in hex")
+				.takes_value(true)
+				.required(true),
+			cmd::opt(
+				"internal-key",
+				"the taproot internal key (defaults to the unspendable key used by hal-simplicity)",
+			)
+			.takes_value(true)
+			.required(false),
+		])
+}
+
+pub fn schema() -> RootSchema {
+	schemars::schema_for!(MatchInfo)
+}
+
+pub fn exec<'a>(matches: &clap::ArgMatches<'a>) {
+	let program = matches.value_of("program").expect("program is mandatory");
+	let cmr = match program.parse::<Cmr>() {
+		Ok(cmr) => cmr,
+		Err(_) => Program::<jet::Elements>::from_str(program, None).expect("invalid program").cmr(),
+	};
+	let internal_key = matches
+		.value_of("internal-key")
+		.map(|k| k.parse::<XOnlyPublicKey>().expect("invalid internal key"))
+		.unwrap_or_else(unspendable_internal_key);
+
+	let address = matches.value_of("address").expect("address is mandatory");
+	let (params, script_pubkey) = match address.parse::<elements::Address>() {
+		Ok(address) => (Some(address.params), address.script_pubkey()),
+		Err(_) => {
+			let bytes = hex::decode(address).expect("invalid address or scriptPubKey");
+			(None, elements::Script::from(bytes))
+		}
+	};
+
+	// The scriptPubKey doesn't depend on the network, so we can always derive it
+	// with the Liquid parameters.
+	let expected = elements_address_with_key(
+		cmr,
+		internal_key,
+		params.unwrap_or(&elements::AddressParams::LIQUID),
+	);
+	let info = MatchInfo {
+		cmr,
+		internal_key: internal_key.to_string(),
+		expected_address: params.map(|_| expected.to_string()),
+		expected_script_pubkey: expected.script_pubkey().to_bytes().into(),
+		matches: expected.script_pubkey() == script_pubkey,
+		script_pubkey: script_pubkey.to_bytes().into(),
+	};
+	cmd::print_output(matches, &info)
+}
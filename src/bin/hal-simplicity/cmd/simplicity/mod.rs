@@ -0,0 +1,235 @@
+// Copyright 2025 Andrew Poelstra
+// SPDX-License-Identifier: CC0-1.0
+
+mod cmr_at;
+mod htlc;
+mod info;
+mod match_address;
+mod multisig;
+mod policy;
+mod prune;
+mod roots;
+mod sighash;
+mod template;
+mod vault;
+mod witness_types;
+
+use crate::cmd;
+
+use elements::bitcoin::secp256k1::XOnlyPublicKey;
+use elements::hashes::sha256;
+use elements::{confidential, AssetId, BlockHash, Script};
+use hal_simplicity::hal_simplicity::{elements_address, encoded_nodes};
+use hal_simplicity::simplicity::jet::elements::ElementsUtxo;
+use hal_simplicity::simplicity::jet::Elements;
+use hal_simplicity::simplicity::node::Inner;
+use hal_simplicity::simplicity::{Cmr, CommitNode};
+use schemars::schema::RootSchema;
+use schemars::JsonSchema;
+use serde::Serialize;
+
+pub fn subcommand<'a>() -> clap::App<'a, 'a> {
+	cmd::subcommand_group("simplicity", "manipulate Simplicity programs")
+		.subcommand(self::cmr_at::cmd())
+		.subcommand(self::htlc::cmd())
+		.subcommand(self::info::cmd())
+		.subcommand(self::match_address::cmd())
+		.subcommand(self::multisig::cmd())
+		.subcommand(self::policy::cmd())
+		.subcommand(self::prune::cmd())
+		.subcommand(self::roots::cmd())
+		.subcommand(self::sighash::cmd())
+		.subcommand(self::template::cmd())
+		.subcommand(self::vault::cmd())
+		.subcommand(self::witness_types::cmd())
+}
+
+pub fn execute<'a>(matches: &clap::ArgMatches<'a>) {
+	match matches.subcommand() {
+		("cmr-at", Some(m)) => self::cmr_at::exec(m),
+		("htlc", Some(m)) => self::htlc::exec(m),
+		("info", Some(m)) => self::info::exec(m),
+		("match", Some(m)) => self::match_address::exec(m),
+		("multisig", Some(m)) => self::multisig::exec(m),
+		("policy", Some(m)) => self::policy::exec(m),
+		("prune", Some(m)) => self::prune::exec(m),
+		("roots", Some(m)) => self::roots::exec(m),
+		("sighash", Some(m)) => self::sighash::exec(m),
+		("template", Some(m)) => self::template::exec(m),
+		("vault", Some(m)) => self::vault::exec(m),
+		("witness-types", Some(m)) => self::witness_types::exec(m),
+		(_, _) => unreachable!("clap prints help"),
+	};
+}
+
+/// The schemas of the outputs of the subcommands, by subcommand name.
+pub fn schemas() -> Vec<(&'static str, RootSchema)> {
+	vec![
+		("cmr-at", self::cmr_at::schema()),
+		("htlc", self::htlc::schema()),
+		("info", self::info::schema()),
+		("match", self::match_address::schema()),
+		("multisig", self::multisig::schema()),
+		("policy", self::policy::schema()),
+		("prune", self::prune::schema()),
+		("roots", self::roots::schema()),
+		("sighash", self::sighash::schema()),
+		("template", self::template::schema()),
+		("vault", self::vault::schema()),
+		("witness-types", self::witness_types::schema()),
+	]
+}
+
+/// The genesis block hash of the Liquid network.
+pub const LIQUID_GENESIS_HASH: &str =
+	"1466275836220db2944ca059a3a10ef6fd2ea684b0688d2c379296888a206003";
+
+/// Options describing the transaction environment beyond the transaction itself.
+pub fn opts_env<'a>() -> Vec<clap::Arg<'a, 'a>> {
+	vec![
+		cmd::opt(
+			"input-utxo",
+			"an output spent by the transaction as SCRIPTPUBKEY:ASSET:VALUE, given once per input",
+		)
+		.short("i")
+		.takes_value(true)
+		.multiple(true)
+		.number_of_values(1)
+		.required(true),
+		cmd::opt("genesis-hash", "the genesis block hash of the network (defaults to Liquid)")
+			.short("g")
+			.takes_value(true)
+			.required(false),
+	]
+}
+
+/// Parses a `SCRIPTPUBKEY:ASSET:VALUE` triple, where the asset and value are either
+/// explicit (an asset ID and an amount in satoshis) or hex-encoded commitments.
+pub fn parse_utxo(s: &str) -> ElementsUtxo {
+	let mut parts = s.split(':');
+	let (script, asset, value) = match (parts.next(), parts.next(), parts.next(), parts.next()) {
+		(Some(script), Some(asset), Some(value), None) => (script, asset, value),
+		_ => panic!("invalid input UTXO '{}', expected SCRIPTPUBKEY:ASSET:VALUE", s),
+	};
+
+	let script_pubkey = Script::from(hex::decode(script).expect("invalid UTXO scriptPubKey hex"));
+	let asset = match asset.parse::<AssetId>() {
+		Ok(id) => confidential::Asset::Explicit(id),
+		Err(_) => {
+			confidential::Asset::from_commitment(&hex::decode(asset).expect("invalid UTXO asset"))
+				.expect("invalid UTXO asset commitment")
+		}
+	};
+	let value = match value.parse::<u64>() {
+		Ok(sats) => confidential::Value::Explicit(sats),
+		Err(_) => {
+			confidential::Value::from_commitment(&hex::decode(value).expect("invalid UTXO value"))
+				.expect("invalid UTXO value commitment")
+		}
+	};
+	ElementsUtxo {
+		script_pubkey,
+		asset,
+		value,
+	}
+}
+
+fn input_utxos(matches: &clap::ArgMatches) -> Vec<ElementsUtxo> {
+	matches.values_of("input-utxo").expect("input UTXOs are mandatory").map(parse_utxo).collect()
+}
+
+pub fn genesis_hash(matches: &clap::ArgMatches) -> BlockHash {
+	matches
+		.value_of("genesis-hash")
+		.unwrap_or(LIQUID_GENESIS_HASH)
+		.parse()
+		.expect("invalid genesis hash")
+}
+
+/// Information about a commitment-time program generated by hal-simplicity.
+#[derive(Serialize, JsonSchema)]
+struct CompiledInfo {
+	commit_base64: String,
+	commit_decode: String,
+	type_arrow: String,
+	#[schemars(with = "String")]
+	cmr: Cmr,
+	liquid_address_unconf: String,
+	liquid_testnet_address_unconf: String,
+}
+
+impl CompiledInfo {
+	fn new(program: &CommitNode<Elements>) -> Self {
+		CompiledInfo {
+			commit_base64: program.to_string(),
+			commit_decode: program.display_expr().to_string(),
+			type_arrow: program.arrow().to_string(),
+			cmr: program.cmr(),
+			liquid_address_unconf: elements_address(
+				program.cmr(),
+				&elements::AddressParams::LIQUID,
+			)
+			.to_string(),
+			liquid_testnet_address_unconf: elements_address(
+				program.cmr(),
+				&elements::AddressParams::LIQUID_TESTNET,
+			)
+			.to_string(),
+		}
+	}
+}
+
+/// A witness value which must be provided to redeem a program.
+#[derive(Serialize, JsonSchema)]
+struct WitnessSlot {
+	#[serde(rename = "type")]
+	type_: String,
+	bit_width: usize,
+	#[serde(skip_serializing_if = "Option::is_none")]
+	description: Option<String>,
+}
+
+/// One of the ways of spending a program with a selector bit.
+#[derive(Serialize, JsonSchema)]
+struct SpendPath {
+	/// The value of the selector bit which picks this path.
+	selector: u8,
+	condition: String,
+	/// The witness values to provide after the selector bit, in order.
+	witness: Vec<String>,
+}
+
+/// Describes the witness of a `pk` fragment.
+fn signature_description(key: &XOnlyPublicKey) -> String {
+	format!("BIP-340 signature by {} of the SIGHASH_ALL of the spending transaction", key)
+}
+
+/// Describes the witness of a `sha256` fragment.
+fn preimage_description(hash: &sha256::Hash) -> String {
+	format!("32-byte preimage of the SHA256 hash {}", hash)
+}
+
+/// The witness values of the program, in the order in which they are encoded.
+fn witness_slots(program: &CommitNode<Elements>) -> Vec<WitnessSlot> {
+	encoded_nodes(program)
+		.into_iter()
+		.filter(|(_, node)| matches!(node.inner(), Inner::Witness(..)))
+		.map(|(_, node)| WitnessSlot {
+			type_: node.arrow().target.to_string(),
+			bit_width: node.arrow().target.bit_width(),
+			description: None,
+		})
+		.collect()
+}
+
+/// Like [`witness_slots`] but attaches the given description to each slot.
+///
+/// Panics if the number of descriptions does not match the number of witness nodes.
+fn described_witness_slots(program: &CommitNode<Elements>, desc: &[String]) -> Vec<WitnessSlot> {
+	let mut slots = witness_slots(program);
+	assert_eq!(slots.len(), desc.len(), "witness descriptions do not match the program");
+	for (slot, desc) in slots.iter_mut().zip(desc) {
+		slot.description = Some(desc.clone());
+	}
+	slots
+}
@@ -0,0 +1,93 @@
+// SPDX-License-Identifier: CC0-1.0
+
+use super::{described_witness_slots, signature_description, CompiledInfo, WitnessSlot};
+use crate::cmd;
+
+use elements::bitcoin::secp256k1::XOnlyPublicKey;
+use hal_simplicity::hal_simplicity::control_block;
+use hal_simplicity::simplicity::Policy;
+use hal_simplicity::HexBytes;
+use schemars::schema::RootSchema;
+use schemars::JsonSchema;
+use serde::Serialize;
+
+#[derive(Serialize, JsonSchema)]
+struct MultisigInfo {
+	threshold: usize,
+	keys: Vec<String>,
+	policy: String,
+	#[serde(flatten)]
+	compiled: CompiledInfo,
+	/// The control block to pass to `sighash` and to put in the spending witness.
+	#[schemars(with = "String")]
+	control_block: HexBytes,
+	witness: Vec<WitnessSlot>,
+}
+
+pub fn cmd<'a>() -> clap::App<'a, 'a> {
+	cmd::subcommand("multisig", "Generate a k-of-n multisignature Simplicity program")
+		.args(&cmd::opts_raw_value())
+		.args(&[
+			cmd::opt_yaml(),
+			cmd::opt_output(),
+			cmd::opt("threshold", "the number of signatures required")
+				.short("k")
+				.takes_value(true)
+				.required(true),
+			cmd::opt("keys", "the x-only public key of a signer; given once per signer")
+				.takes_value(true)
+				.multiple(true)
+				.number_of_values(1)
+				.required(true),
+		])
+}
+
+pub fn schema() -> RootSchema {
+	schemars::schema_for!(MultisigInfo)
+}
+
+pub fn exec<'a>(matches: &clap::ArgMatches<'a>) {
+	let threshold: usize = matches
+		.value_of("threshold")
+		.expect("threshold is mandatory")
+		.parse()
+		.expect("invalid threshold");
+	let mut keys = Vec::<XOnlyPublicKey>::new();
+	for key in matches.values_of("keys").expect("keys are mandatory") {
+		let key = key.parse().expect("invalid x-only public key");
+		// A repeated key would count several times towards the threshold.
+		if keys.contains(&key) {
+			panic!("public key {} given more than once", key);
+		}
+		keys.push(key);
+	}
+	if threshold == 0 || threshold > keys.len() {
+		panic!("invalid threshold {} of {} keys", threshold, keys.len());
+	}
+
+	let policy = Policy::Threshold(threshold, keys.iter().map(|key| Policy::Key(*key)).collect());
+	let program = policy.commit().expect("policies without asm fragments always compile");
+
+	// Each key is guarded by a selector bit which says whether its signature is provided;
+	// when it is not, the signature witness is pruned from the redeem program.
+	let witness_desc = keys
+		.iter()
+		.enumerate()
+		.flat_map(|(i, key)| {
+			[
+				format!("1 if signer {} provides a signature, 0 otherwise", i),
+				format!("{} (omitted if the selector bit is 0)", signature_description(key)),
+			]
+		})
+		.collect::<Vec<_>>();
+
+	let info = MultisigInfo {
+		threshold,
+		keys: keys.iter().map(ToString::to_string).collect(),
+		policy: policy.to_string(),
+		control_block: control_block(program.cmr()).serialize().into(),
+		compiled: CompiledInfo::new(&program),
+		witness: described_witness_slots(&program, &witness_desc),
+	};
+	cmd::print_output(matches, &info)
+}
@@ -0,0 +1,47 @@
+// SPDX-License-Identifier: CC0-1.0
+
+use super::CompiledInfo;
+use crate::cmd;
+
+use hal_simplicity::policy::parse_policy;
+use schemars::schema::RootSchema;
+use schemars::JsonSchema;
+use serde::Serialize;
+
+#[derive(Serialize, JsonSchema)]
+struct PolicyInfo {
+	policy: String,
+	#[serde(flatten)]
+	compiled: CompiledInfo,
+}
+
+pub fn cmd<'a>() -> clap::App<'a, 'a> {
+	cmd::subcommand("policy", "Compile a policy into a Simplicity program")
+		.args(&cmd::opts_raw_value())
+		.args(&[
+			cmd::opt_yaml(),
+			cmd::opt_output(),
+			cmd::arg(
+				"policy",
+				"a policy such as or(pk(KEY),and(older(N),sha256(HASH))); also supports after(N) and thresh(K,...)",
+			)
+			.takes_value(true)
+			.required(true),
+		])
+}
+
+pub fn schema() -> RootSchema {
+	schemars::schema_for!(PolicyInfo)
+}
+
+pub fn exec<'a>(matches: &clap::ArgMatches<'a>) {
+	let policy = matches.value_of("policy").expect("policy is mandatory");
+	let policy = parse_policy(policy).expect("invalid policy");
+	let program = policy.commit().expect("policies without asm fragments always compile");
+
+	let info = PolicyInfo {
+		policy: policy.to_string(),
+		compiled: CompiledInfo::new(&program),
+	};
+	cmd::print_output(matches, &info)
+}
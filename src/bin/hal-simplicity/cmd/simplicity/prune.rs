@@ -0,0 +1,115 @@
+// SPDX-License-Identifier: CC0-1.0
+
+use std::sync::Arc;
+
+use crate::cmd;
+
+use elements::encode::deserialize;
+use elements::taproot::ControlBlock;
+use elements::Transaction;
+use hal_simplicity::hal_simplicity::{control_block, Program};
+use hal_simplicity::simplicity::jet::elements::ElementsEnv;
+use hal_simplicity::simplicity::{jet, Amr, Cmr, Ihr};
+use schemars::schema::RootSchema;
+use schemars::JsonSchema;
+use serde::Serialize;
+
+#[derive(Serialize, JsonSchema)]
+struct PruneInfo {
+	#[schemars(with = "String")]
+	cmr: Cmr,
+	redeem_base64: String,
+	witness_hex: String,
+	#[schemars(with = "String")]
+	amr: Amr,
+	#[schemars(with = "String")]
+	ihr: Ihr,
+	/// The size of the encoded program and witness before pruning, in bytes.
+	unpruned_size: usize,
+	/// The size of the encoded program and witness after pruning, in bytes.
+	pruned_size: usize,
+}
+
+pub fn cmd<'a>() -> clap::App<'a, 'a> {
+	cmd::subcommand(
+		"prune",
+		"Prune the branches of a program not executed by a spending transaction",
+	)
+	.args(&super::opts_env())
+	.args(&cmd::opts_raw_value())
+	.args(&[
+		cmd::opt_yaml(),
+		cmd::opt_output(),
+		cmd::arg("program", "a Simplicity program in base64").takes_value(true).required(true),
+		cmd::arg("witness", "a hex encoding of all the witness data for the program")
+			.takes_value(true)
+			.required(true),
+		cmd::opt("against", "the spending transaction in hex").takes_value(true).required(true),
+		cmd::opt("input-index", "the index of the input spending the program")
+			.takes_value(true)
+			.default_value("0"),
+		cmd::opt(
+			"control-block",
+			"the taproot control block in hex (defaults to the one of hal-simplicity's address)",
+		)
+		.takes_value(true)
+		.required(false),
+	])
+}
+
+pub fn schema() -> RootSchema {
+	schemars::schema_for!(PruneInfo)
+}
+
+pub fn exec<'a>(matches: &clap::ArgMatches<'a>) {
+	let program = matches.value_of("program").expect("program is mandatory");
+	let witness = matches.value_of("witness").expect("witness is mandatory");
+	let program =
+		Program::<jet::Elements>::from_str(program, Some(witness)).expect("invalid program");
+	let redeem = program.redeem_node().expect("witness is mandatory");
+
+	let tx_hex = matches.value_of("against").expect("spending tx is mandatory");
+	let tx: Transaction =
+		deserialize(&hex::decode(tx_hex).expect("tx not hex")).expect("invalid tx format");
+	let input_index: u32 = matches
+		.value_of("input-index")
+		.expect("input index has a default")
+		.parse()
+		.expect("invalid input index");
+	let control_block = match matches.value_of("control-block") {
+		Some(cb) => ControlBlock::from_slice(&hex::decode(cb).expect("control block not hex"))
+			.expect("invalid control block"),
+		None => control_block(program.cmr()),
+	};
+	let utxos = super::input_utxos(matches);
+	if input_index as usize >= tx.input.len() {
+		panic!("input index {} out of range for {} inputs", input_index, tx.input.len());
+	}
+	if utxos.len() != tx.input.len() {
+		panic!("{} input UTXOs given for {} inputs", utxos.len(), tx.input.len());
+	}
+
+	let env = ElementsEnv::new(
+		Arc::new(tx),
+		utxos,
+		input_index,
+		program.cmr(),
+		control_block,
+		None,
+		super::genesis_hash(matches),
+	);
+	let pruned = redeem.prune(&env).expect("program failed to execute");
+
+	let size = |(prog, wit): (Vec<u8>, Vec<u8>)| prog.len() + wit.len();
+	let disp = pruned.display();
+	let info = PruneInfo {
+		cmr: pruned.cmr(),
+		redeem_base64: disp.program().to_string(),
+		witness_hex: disp.witness().to_string(),
+		amr: pruned.amr(),
+		ihr: pruned.ihr(),
+		unpruned_size: size(redeem.to_vec_with_witness()),
+		pruned_size: size(pruned.to_vec_with_witness()),
+	};
+	cmd::print_output(matches, &info)
+}
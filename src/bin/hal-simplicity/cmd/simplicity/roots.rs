@@ -0,0 +1,122 @@
+// SPDX-License-Identifier: CC0-1.0
+
+use crate::cmd;
+
+use hal_simplicity::hal_simplicity::Program;
+use hal_simplicity::simplicity::{jet, Amr, Cmr, Ihr, Tmr};
+use log::info;
+use schemars::schema::RootSchema;
+use schemars::JsonSchema;
+use serde::Serialize;
+
+/// The Merkle roots of a node, as listed by `--nodes`.
+#[derive(Serialize, JsonSchema)]
+struct NodeRoots {
+	/// The position of the node in the encoding, as listed by `info --format nodes`.
+	index: usize,
+	combinator: String,
+	#[schemars(with = "String")]
+	cmr: Cmr,
+	#[schemars(with = "Option<String>")]
+	#[serde(skip_serializing_if = "Option::is_none")]
+	amr: Option<Amr>,
+	#[schemars(with = "Option<String>")]
+	#[serde(skip_serializing_if = "Option::is_none")]
+	ihr: Option<Ihr>,
+	#[schemars(with = "Option<String>")]
+	#[serde(skip_serializing_if = "Option::is_none")]
+	source_tmr: Option<Tmr>,
+	#[schemars(with = "Option<String>")]
+	#[serde(skip_serializing_if = "Option::is_none")]
+	target_tmr: Option<Tmr>,
+}
+
+#[derive(Serialize, JsonSchema)]
+struct RootsInfo {
+	#[schemars(with = "String")]
+	cmr: Cmr,
+	/// Absent if the program has witness nodes and no witness was given.
+	#[schemars(with = "Option<String>")]
+	#[serde(skip_serializing_if = "Option::is_none")]
+	amr: Option<Amr>,
+	/// Absent if the program has witness nodes and no witness was given.
+	#[schemars(with = "Option<String>")]
+	#[serde(skip_serializing_if = "Option::is_none")]
+	ihr: Option<Ihr>,
+	#[schemars(with = "String")]
+	source_tmr: Tmr,
+	#[schemars(with = "String")]
+	target_tmr: Tmr,
+	#[serde(skip_serializing_if = "Option::is_none")]
+	nodes: Option<Vec<NodeRoots>>,
+}
+
+pub fn cmd<'a>() -> clap::App<'a, 'a> {
+	cmd::subcommand("roots", "List the CMR, AMR, IHR and type Merkle roots of a Simplicity program")
+		.args(&cmd::opts_raw_value())
+		.args(&[
+			cmd::opt_yaml(),
+			cmd::opt_output(),
+			cmd::opt("nodes", "also list the roots of each node of the program")
+				.takes_value(false)
+				.required(false),
+			cmd::arg("program", "a Simplicity program in base64, hex or as a file path")
+				.takes_value(true)
+				.required(true),
+			cmd::arg(
+				"witness",
+				"all the witness data for the program in hex, base64 or as a file path",
+			)
+			.takes_value(true)
+			.required(false),
+		])
+}
+
+pub fn schema() -> RootSchema {
+	schemars::schema_for!(RootsInfo)
+}
+
+pub fn exec<'a>(matches: &clap::ArgMatches<'a>) {
+	let program = matches.value_of("program").expect("program is mandatory");
+	let witness = matches.value_of("witness");
+
+	// Like `simplicity info`, so base64 programs which are valid hex too are read alike.
+	let program = cmd::decode_binary_preferring(program, "program", cmd::TextFormat::Base64);
+	let witness = witness.map(|w| cmd::decode_binary(w, "witness"));
+	let program = Program::<jet::Elements>::from_bytes(&program, witness.as_deref())
+		.expect("invalid program");
+
+	let commit = program.commit_prog();
+	let amr = program.amr().or_else(|| commit.amr());
+	let ihr = program.ihr().or_else(|| commit.ihr());
+	if amr.is_none() {
+		info!("The program has witness nodes, pass the witness to compute its AMR and IHR.");
+	}
+
+	let nodes = matches.is_present("nodes").then(|| {
+		program
+			.redeem_nodes()
+			.unwrap_or_else(|| program.nodes())
+			.into_iter()
+			.map(|node| NodeRoots {
+				index: node.index,
+				combinator: node.combinator,
+				cmr: node.cmr,
+				amr: node.amr,
+				ihr: node.ihr,
+				source_tmr: node.tmrs.map(|(source, _)| source),
+				target_tmr: node.tmrs.map(|(_, target)| target),
+			})
+			.collect()
+	});
+
+	let info = RootsInfo {
+		cmr: program.cmr(),
+		amr,
+		ihr,
+		source_tmr: commit.arrow().source.tmr(),
+		target_tmr: commit.arrow().target.tmr(),
+		nodes,
+	};
+	cmd::print_output(matches, &info)
+}
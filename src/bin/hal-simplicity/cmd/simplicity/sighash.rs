@@ -0,0 +1,115 @@
+// SPDX-License-Identifier: CC0-1.0
+
+use std::collections::BTreeMap;
+use std::sync::Arc;
+
+use crate::cmd;
+
+use elements::bitcoin::secp256k1::{self, Keypair, Message, SecretKey};
+use elements::encode::deserialize;
+use elements::hashes::{sha256, Hash};
+use elements::taproot::ControlBlock;
+use elements::Transaction;
+use hal_simplicity::simplicity::jet::elements::ElementsEnv;
+use hal_simplicity::simplicity::Cmr;
+use schemars::schema::RootSchema;
+use schemars::JsonSchema;
+use serde::Serialize;
+
+#[derive(Serialize, JsonSchema)]
+struct SighashInfo {
+	#[schemars(with = "String")]
+	sighash: sha256::Hash,
+	/// Signatures by each given secret key, keyed by x-only public key.
+	#[serde(skip_serializing_if = "BTreeMap::is_empty")]
+	signatures: BTreeMap<String, String>,
+}
+
+pub fn cmd<'a>() -> clap::App<'a, 'a> {
+	cmd::subcommand("sighash", "Compute the SIGHASH_ALL of a Simplicity transaction input")
+		.args(&super::opts_env())
+		.args(&cmd::opts_raw_value())
+		.args(&[
+			cmd::opt_yaml(),
+			cmd::opt_output(),
+			cmd::opt_audit_log(),
+			cmd::arg("tx", "the spending transaction in hex").takes_value(true).required(true),
+			cmd::arg("input-index", "the index of the input being signed")
+				.takes_value(true)
+				.required(true),
+			cmd::arg("cmr", "the CMR of the Simplicity program being spent")
+				.takes_value(true)
+				.required(true),
+			cmd::arg("control-block", "the taproot control block of the program in hex")
+				.takes_value(true)
+				.required(true),
+			cmd::opt(
+				"secret-key",
+				"a secret key to sign the sighash with; may be given several times",
+			)
+			.short("s")
+			.takes_value(true)
+			.multiple(true)
+			.number_of_values(1)
+			.required(false),
+		])
+}
+
+pub fn schema() -> RootSchema {
+	schemars::schema_for!(SighashInfo)
+}
+
+pub fn exec<'a>(matches: &clap::ArgMatches<'a>) {
+	let tx_hex = matches.value_of("tx").expect("tx is mandatory");
+	let tx: Transaction =
+		deserialize(&hex::decode(tx_hex).expect("tx not hex")).expect("invalid tx format");
+	let input_index: u32 = matches
+		.value_of("input-index")
+		.expect("input index is mandatory")
+		.parse()
+		.expect("invalid input index");
+	let cmr: Cmr = matches.value_of("cmr").expect("cmr is mandatory").parse().expect("invalid cmr");
+	let control_block = ControlBlock::from_slice(
+		&hex::decode(matches.value_of("control-block").expect("control block is mandatory"))
+			.expect("control block not hex"),
+	)
+	.expect("invalid control block");
+	let utxos = super::input_utxos(matches);
+	let genesis_hash = super::genesis_hash(matches);
+
+	if input_index as usize >= tx.input.len() {
+		panic!("input index {} out of range for {} inputs", input_index, tx.input.len());
+	}
+	if utxos.len() != tx.input.len() {
+		panic!("{} input UTXOs given for {} inputs", utxos.len(), tx.input.len());
+	}
+
+	let env =
+		ElementsEnv::new(Arc::new(tx), utxos, input_index, cmr, control_block, None, genesis_hash);
+	let sighash = env.c_tx_env().sighash_all();
+
+	let msg = Message::from_digest(sighash.to_byte_array());
+	let mut signatures = BTreeMap::new();
+	for sk in matches.values_of("secret-key").into_iter().flatten() {
+		let sk: SecretKey = sk.parse().expect("invalid secret key");
+		let keypair = Keypair::from_secret_key(secp256k1::SECP256K1, &sk);
+		let x_only = keypair.x_only_public_key().0.to_string();
+		if signatures.contains_key(&x_only) {
+			panic!("secret key for public key {} given more than once", x_only);
+		}
+		let sig = secp256k1::SECP256K1.sign_schnorr(&msg, &keypair);
+		signatures.insert(x_only, sig.to_string());
+	}
+
+	let info = SighashInfo {
+		sighash,
+		signatures,
+	};
+	cmd::audit::record(
+		matches,
+		"simplicity sighash",
+		&["tx", "input-index", "cmr", "control-block", "input-utxo", "genesis-hash", "secret-key"],
+		&info,
+	);
+	cmd::print_output(matches, &info)
+}
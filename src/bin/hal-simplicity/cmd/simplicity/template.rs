@@ -0,0 +1,150 @@
+// SPDX-License-Identifier: CC0-1.0
+
+use std::collections::HashMap;
+use std::fmt;
+use std::str::FromStr;
+use std::sync::Arc;
+
+use super::{
+	described_witness_slots, preimage_description, signature_description, CompiledInfo, WitnessSlot,
+};
+use crate::cmd;
+
+use elements::bitcoin::secp256k1::XOnlyPublicKey;
+use hal_simplicity::policy::is_lock_height;
+use hal_simplicity::simplicity::Policy;
+use schemars::schema::RootSchema;
+use schemars::JsonSchema;
+use serde::Serialize;
+
+/// The available templates along with the parameters they take.
+const TEMPLATES: &[(&str, &[&str])] = &[
+	("pk", &["key"]),
+	("hashlock", &["hash"]),
+	("csv", &["blocks"]),
+	("cltv", &["height"]),
+	("pk-csv", &["key", "blocks"]),
+	("pk-cltv", &["key", "height"]),
+];
+
+const TEMPLATE_HELP: &str = "\
+TEMPLATES:
+    pk         a single BIP-340 signature check (key=<x-only pubkey>)
+    hashlock   a SHA256 preimage check (hash=<sha256 hash>)
+    csv        a relative timelock (blocks=<n>)
+    cltv       an absolute timelock (height=<n>, from 1 to 499999999)
+    pk-csv     a signature check after a relative timelock (key=<x-only pubkey>, blocks=<n>)
+    pk-cltv    a signature check after an absolute timelock (key=<x-only pubkey>, height=<n>)";
+
+#[derive(Serialize, JsonSchema)]
+struct TemplateInfo {
+	template: String,
+	policy: String,
+	#[serde(flatten)]
+	compiled: CompiledInfo,
+	witness: Vec<WitnessSlot>,
+}
+
+pub fn cmd<'a>() -> clap::App<'a, 'a> {
+	cmd::subcommand("template", "Generate a common Simplicity program from parameters")
+		.after_help(TEMPLATE_HELP)
+		.args(&cmd::opts_raw_value())
+		.args(&[
+			cmd::opt_yaml(),
+			cmd::opt_output(),
+			cmd::arg("template", "the name of the template")
+				.possible_values(&TEMPLATES.iter().map(|(name, _)| *name).collect::<Vec<_>>())
+				.required(true),
+			cmd::opt("param", "a template parameter as NAME=VALUE")
+				.short("p")
+				.takes_value(true)
+				.multiple(true)
+				.number_of_values(1)
+				.required(false),
+		])
+}
+
+/// Parses the named template parameter.
+fn param<T: FromStr>(params: &HashMap<&str, &str>, name: &str) -> T
+where
+	T::Err: fmt::Debug,
+{
+	let value = params.get(name).unwrap_or_else(|| panic!("missing parameter '{}'", name));
+	value.parse().unwrap_or_else(|e| panic!("invalid parameter '{}': {:?}", name, e))
+}
+
+/// Parses the named template parameter as the block height of an absolute timelock.
+fn height_param(params: &HashMap<&str, &str>, name: &str) -> u32 {
+	let height = param(params, name);
+	if !is_lock_height(height) {
+		panic!("invalid parameter '{}': expected a block height from 1 to 499999999", name);
+	}
+	height
+}
+
+/// Builds the policy of the given template, along with a description of each witness value.
+fn template_policy(
+	name: &str,
+	params: &HashMap<&str, &str>,
+) -> (Policy<XOnlyPublicKey>, Vec<String>) {
+	match name {
+		"pk" => {
+			let key = param(params, "key");
+			(Policy::Key(key), vec![signature_description(&key)])
+		}
+		"hashlock" => {
+			let hash = param(params, "hash");
+			(Policy::Sha256(hash), vec![preimage_description(&hash)])
+		}
+		"csv" => (Policy::Older(param(params, "blocks")), vec![]),
+		"cltv" => (Policy::After(height_param(params, "height")), vec![]),
+		"pk-csv" | "pk-cltv" => {
+			let key = param(params, "key");
+			let timelock = if name == "pk-csv" {
+				Policy::Older(param(params, "blocks"))
+			} else {
+				Policy::After(height_param(params, "height"))
+			};
+			let policy = Policy::And {
+				left: Arc::new(Policy::Key(key)),
+				right: Arc::new(timelock),
+			};
+			(policy, vec![signature_description(&key)])
+		}
+		_ => unreachable!("clap checks the template name"),
+	}
+}
+
+pub fn schema() -> RootSchema {
+	schemars::schema_for!(TemplateInfo)
+}
+
+pub fn exec<'a>(matches: &clap::ArgMatches<'a>) {
+	let name = matches.value_of("template").expect("template is mandatory");
+	let mut params = HashMap::new();
+	for p in matches.values_of("param").into_iter().flatten() {
+		let (param, value) = p
+			.split_once('=')
+			.unwrap_or_else(|| panic!("invalid parameter '{}', expected NAME=VALUE", p));
+		if params.insert(param, value).is_some() {
+			panic!("parameter '{}' given more than once", param);
+		}
+	}
+
+	let (_, allowed) =
+		TEMPLATES.iter().find(|(t, _)| *t == name).expect("clap checks the template name");
+	if let Some(unknown) = params.keys().find(|p| !allowed.contains(p)) {
+		panic!("unknown parameter '{}' for template '{}'", unknown, name);
+	}
+
+	let (policy, witness_desc) = template_policy(name, &params);
+	let program = policy.commit().expect("templates have no asm fragments");
+
+	let info = TemplateInfo {
+		template: name.to_owned(),
+		policy: policy.to_string(),
+		compiled: CompiledInfo::new(&program),
+		witness: described_witness_slots(&program, &witness_desc),
+	};
+	cmd::print_output(matches, &info)
+}
@@ -0,0 +1,121 @@
+// SPDX-License-Identifier: CC0-1.0
+
+use super::{described_witness_slots, signature_description, CompiledInfo, SpendPath, WitnessSlot};
+use crate::cmd;
+
+use elements::bitcoin::secp256k1::XOnlyPublicKey;
+use hal_simplicity::hal_simplicity::control_block;
+use hal_simplicity::vault::vault_program;
+use hal_simplicity::HexBytes;
+use schemars::schema::RootSchema;
+use schemars::JsonSchema;
+use serde::Serialize;
+
+#[derive(Serialize, JsonSchema)]
+struct VaultInfo {
+	destination: String,
+	#[schemars(with = "String")]
+	destination_script: HexBytes,
+	max_fee: u64,
+	recovery_key: String,
+	delay: u16,
+	#[serde(flatten)]
+	compiled: CompiledInfo,
+	#[schemars(with = "String")]
+	control_block: HexBytes,
+	witness: Vec<WitnessSlot>,
+	covenant: SpendPath,
+	recovery: SpendPath,
+}
+
+pub fn cmd<'a>() -> clap::App<'a, 'a> {
+	cmd::subcommand(
+		"vault",
+		"Generate a covenant paying to a fixed destination with a recovery path",
+	)
+	.args(&cmd::opts_raw_value())
+	.args(&[
+		cmd::opt_yaml(),
+		cmd::opt_output(),
+		cmd::opt("destination", "the address which the covenant path must pay to")
+			.takes_value(true)
+			.required(true),
+		cmd::opt(
+			"max-fee",
+			"the maximum amount in satoshis by which the payment to the destination may fall \
+			 short of the coins",
+		)
+		.takes_value(true)
+		.required(true),
+		cmd::opt("recovery-key", "the x-only public key which can recover the coins")
+			.takes_value(true)
+			.required(true),
+		cmd::opt("delay", "the number of blocks before the recovery path is enabled")
+			.takes_value(true)
+			.required(true),
+	])
+}
+
+pub fn schema() -> RootSchema {
+	schemars::schema_for!(VaultInfo)
+}
+
+pub fn exec<'a>(matches: &clap::ArgMatches<'a>) {
+	let destination: elements::Address = matches
+		.value_of("destination")
+		.expect("destination is mandatory")
+		.parse()
+		.expect("invalid destination address");
+	let recovery_key: XOnlyPublicKey = matches
+		.value_of("recovery-key")
+		.expect("recovery key is mandatory")
+		.parse()
+		.expect("invalid recovery key");
+	let delay: u16 =
+		matches.value_of("delay").expect("delay is mandatory").parse().expect("invalid delay");
+	let max_fee: u64 = matches
+		.value_of("max-fee")
+		.expect("max fee is mandatory")
+		.parse()
+		.expect("invalid max fee");
+
+	let destination_script = destination.script_pubkey();
+	let program = vault_program(&destination_script, max_fee, &recovery_key, delay);
+
+	let covenant = SpendPath {
+		selector: 0,
+		condition: format!(
+			"the spending transaction has exactly one input and exactly two outputs: output 0 pays \
+			 the asset of the coins to {}, at most {} less than their amount, and output 1 is the \
+			 fee; the asset and amount of the coins and of output 0 must be explicit",
+			destination, max_fee
+		),
+		witness: vec![],
+	};
+	let recovery = SpendPath {
+		selector: 1,
+		condition: format!(
+			"the spending input has a relative timelock of at least {} blocks",
+			delay
+		),
+		witness: vec![signature_description(&recovery_key)],
+	};
+	let witness_desc = vec![
+		"0 for the covenant path, 1 for the recovery path".to_owned(),
+		format!("{} (recovery path only)", recovery.witness[0]),
+	];
+
+	let info = VaultInfo {
+		destination: destination.to_string(),
+		destination_script: destination_script.to_bytes().into(),
+		max_fee,
+		recovery_key: recovery_key.to_string(),
+		delay,
+		control_block: control_block(program.cmr()).serialize().into(),
+		compiled: CompiledInfo::new(&program),
+		witness: described_witness_slots(&program, &witness_desc),
+		covenant,
+		recovery,
+	};
+	cmd::print_output(matches, &info)
+}
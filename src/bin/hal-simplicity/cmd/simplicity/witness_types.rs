@@ -0,0 +1,92 @@
+// SPDX-License-Identifier: CC0-1.0
+
+use crate::cmd;
+
+use hal_simplicity::hal_simplicity::{encoded_nodes, Program};
+use hal_simplicity::simplicity::jet;
+use hal_simplicity::simplicity::node::Inner;
+use schemars::schema::RootSchema;
+use schemars::JsonSchema;
+use serde::Serialize;
+
+/// A node whose value must be supplied at redemption time.
+#[derive(Serialize, JsonSchema)]
+struct WitnessNode {
+	/// The position of the node in the encoding of the program, as listed by
+	/// `info --format nodes`.
+	index: usize,
+	kind: &'static str,
+	/// For witness nodes the type of the value, for disconnect nodes the type of
+	/// the expression to be supplied.
+	#[serde(rename = "type")]
+	type_: String,
+	/// The number of bits taken by a witness value in the encoded witness data.
+	#[serde(skip_serializing_if = "Option::is_none")]
+	bit_width: Option<usize>,
+}
+
+#[derive(Serialize, JsonSchema)]
+struct WitnessTypesInfo {
+	witness_count: usize,
+	disconnect_count: usize,
+	/// The size of the witness data if no branches are pruned.
+	max_witness_bits: usize,
+	nodes: Vec<WitnessNode>,
+}
+
+pub fn cmd<'a>() -> clap::App<'a, 'a> {
+	cmd::subcommand(
+		"witness-types",
+		"List the witness and disconnect nodes of a Simplicity program with their types",
+	)
+	.args(&cmd::opts_raw_value())
+	.args(&[
+		cmd::opt_yaml(),
+		cmd::opt_output(),
+		cmd::arg("program", "a Simplicity program in base64").takes_value(true).required(true),
+	])
+}
+
+pub fn schema() -> RootSchema {
+	schemars::schema_for!(WitnessTypesInfo)
+}
+
+pub fn exec<'a>(matches: &clap::ArgMatches<'a>) {
+	let program = matches.value_of("program").expect("program is mandatory");
+	let program = Program::<jet::Elements>::from_str(program, None).expect("invalid program");
+
+	let nodes = encoded_nodes(program.commit_prog())
+		.into_iter()
+		.filter_map(|(index, node)| match node.inner() {
+			Inner::Witness(..) => Some(WitnessNode {
+				index,
+				kind: "witness",
+				type_: node.arrow().target.to_string(),
+				bit_width: Some(node.arrow().target.bit_width()),
+			}),
+			// disconnect s t : A → B × D where s : 2^256 × A → B × C and the
+			// supplied expression is t : C → D.
+			Inner::Disconnect(left, _) => {
+				let (_, source) =
+					left.arrow().target.as_product().expect("disconnect target is a product");
+				let (_, target) =
+					node.arrow().target.as_product().expect("disconnect target is a product");
+				Some(WitnessNode {
+					index,
+					kind: "disconnect",
+					type_: format!("{} → {}", source, target),
+					bit_width: None,
+				})
+			}
+			_ => None,
+		})
+		.collect::<Vec<_>>();
+
+	let info = WitnessTypesInfo {
+		witness_count: nodes.iter().filter(|n| n.kind == "witness").count(),
+		disconnect_count: nodes.iter().filter(|n| n.kind == "disconnect").count(),
+		max_witness_bits: nodes.iter().filter_map(|n| n.bit_width).sum(),
+		nodes,
+	};
+	cmd::print_output(matches, &info)
+}
@@ -1,3 +1,4 @@
+use std::collections::{BTreeMap, BTreeSet, HashMap};
 use std::convert::TryInto;
 use std::io::Write;
 
@@ -9,12 +10,15 @@ use elements::secp256k1_zkp::{
 	Generator, PedersenCommitment, PublicKey, RangeProof, SurjectionProof, Tweak,
 };
 use elements::{
-	confidential, AssetIssuance, OutPoint, Script, Transaction, TxIn, TxInWitness, TxOut,
+	confidential, AssetId, AssetIssuance, OutPoint, Script, Transaction, TxIn, TxInWitness, TxOut,
 	TxOutWitness,
 };
-use log::warn;
+use hal_simplicity::simplicity::jet::elements::ElementsUtxo;
+use log::{info, warn};
+use schemars::schema::RootSchema;
 
 use crate::cmd;
+use crate::cmd::address_book::AddressBook;
 use hal_simplicity::confidential::{
 	ConfidentialAssetInfo, ConfidentialNonceInfo, ConfidentialType, ConfidentialValueInfo,
 };
@@ -30,6 +34,11 @@ pub fn subcommand<'a>() -> clap::App<'a, 'a> {
 		.subcommand(cmd_decode())
 }
 
+/// The schemas of the outputs of the subcommands, by subcommand name.
+pub fn schemas() -> Vec<(&'static str, RootSchema)> {
+	vec![("decode", schemars::schema_for!(TransactionInfo))]
+}
+
 pub fn execute<'a>(matches: &clap::ArgMatches<'a>) {
 	match matches.subcommand() {
 		("create", Some(m)) => exec_create(m),
@@ -40,10 +49,28 @@ pub fn execute<'a>(matches: &clap::ArgMatches<'a>) {
 
 fn cmd_create<'a>() -> clap::App<'a, 'a> {
 	cmd::subcommand("create", "create a raw transaction from JSON").args(&[
-		cmd::arg("tx-info", "the transaction info in JSON").required(false),
+		cmd::arg("tx-info", "the transaction info in JSON or YAML").required(false),
+		cmd::opt_input_format(),
 		cmd::opt("raw-stdout", "output the raw bytes of the result to stdout")
 			.short("r")
 			.required(false),
+		cmd::opt(
+			"check",
+			"check the transaction for duplicate inputs, unbalanced values, non-standard scripts \
+			 and a missing fee output",
+		)
+		.required(false),
+		cmd::opt(
+			"input-utxo",
+			"an output spent by the transaction as SCRIPTPUBKEY:ASSET:VALUE, given once per input \
+			 to check the value balance",
+		)
+		.short("i")
+		.takes_value(true)
+		.multiple(true)
+		.number_of_values(1)
+		.requires("check")
+		.required(false),
 	])
 }
 
@@ -358,14 +385,54 @@ fn create_script_pubkey_from_pegout_data(pd: PegoutDataInfo) -> Script {
 	builder.into_script()
 }
 
+/// The maximum size of the data of an OP_RETURN output relayed by Elements nodes.
+const MAX_OP_RETURN_DATA: usize = 80;
+
+/// The maximum size of a script which can be executed.
+const MAX_SCRIPT_SIZE: usize = 10_000;
+
+/// The maximum size of a scriptSig relayed by Elements nodes.
+const MAX_STANDARD_SCRIPTSIG_SIZE: usize = 1650;
+
+/// Parse the "data" field of an output, given in hex or as `utf8:<text>`.
+fn parse_output_data(data: &str) -> Vec<u8> {
+	let bytes = match data.strip_prefix("utf8:") {
+		Some(text) => text.as_bytes().to_vec(),
+		None => hex::decode(data)
+			.expect("invalid hex in \"data\" field of output (use \"utf8:\" for text)"),
+	};
+	if bytes.len() > MAX_OP_RETURN_DATA {
+		panic!(
+			"Field \"data\" of output is {} bytes, more than the {} bytes relayed by nodes.",
+			bytes.len(),
+			MAX_OP_RETURN_DATA,
+		);
+	}
+	bytes
+}
+
+/// Create the OP_RETURN script embedding the given data.
+fn create_script_pubkey_from_data(data: &[u8]) -> Script {
+	elements::script::Builder::new()
+		.push_opcode(elements::opcodes::all::OP_RETURN)
+		.push_slice(data)
+		.into_script()
+}
+
 fn create_output(output: OutputInfo) -> TxOut {
 	// Keep track of which network has been used in addresses and error if two different networks
 	// are used.
 	let mut used_network = None;
-	let value = output
-		.value
-		.map(create_confidential_value)
-		.expect("Field \"value\" is required for outputs.");
+	let data = output.data.as_deref().map(parse_output_data);
+	if data.is_some() && (output.script_pub_key.is_some() || output.pegout_data.is_some()) {
+		panic!("Field \"data\" of output conflicts with \"script_pub_key\" and \"pegout_data\".");
+	}
+	// Data outputs are unspendable, so they don't need a value.
+	let value = match output.value {
+		Some(value) => create_confidential_value(value),
+		None if data.is_some() => confidential::Value::Explicit(0),
+		None => panic!("Field \"value\" is required for outputs."),
+	};
 	let asset = output
 		.asset
 		.map(create_confidential_asset)
@@ -375,7 +442,9 @@ fn create_output(output: OutputInfo) -> TxOut {
 		asset,
 		value,
 		nonce: output.nonce.map(create_confidential_nonce).unwrap_or(confidential::Nonce::Null),
-		script_pubkey: if let Some(spk) = output.script_pub_key {
+		script_pubkey: if let Some(ref data) = data {
+			create_script_pubkey_from_data(data)
+		} else if let Some(spk) = output.script_pub_key {
 			if output.pegout_data.is_some() {
 				warn!("Field \"pegout_data\" of output is ignored.");
 			}
@@ -436,30 +505,217 @@ pub fn create_transaction(info: TransactionInfo) -> Transaction {
 	}
 }
 
+/// The explicit value of each asset.
+type AssetTotals = BTreeMap<AssetId, u64>;
+
+/// Add the explicit amount to the per-asset total, returning false if it is confidential
+/// and an error if the total overflows.
+fn add_explicit(
+	totals: &mut AssetTotals,
+	asset: confidential::Asset,
+	value: confidential::Value,
+) -> Result<bool, String> {
+	match (asset.explicit(), value.explicit()) {
+		(Some(asset), Some(value)) => {
+			let total = totals.entry(asset).or_default();
+			*total = total.checked_add(value).ok_or_else(|| {
+				format!("the amounts of asset {} add up to more than {} sat", asset, u64::MAX)
+			})?;
+			Ok(true)
+		}
+		_ => Ok(false),
+	}
+}
+
+/// The explicit values of each asset spent and created by the transaction, or `None`
+/// if some of them are confidential.
+fn explicit_totals(
+	tx: &Transaction,
+	utxos: &[ElementsUtxo],
+) -> Result<Option<(AssetTotals, AssetTotals)>, String> {
+	let mut spent = BTreeMap::new();
+	let mut created = BTreeMap::new();
+	let mut explicit = true;
+	for (input, utxo) in tx.input.iter().zip(utxos) {
+		explicit &= add_explicit(&mut spent, utxo.asset, utxo.value)?;
+		if input.has_issuance() {
+			let (asset, token) = input.issuance_ids();
+			let issuance = &input.asset_issuance;
+			for (id, amount) in [(asset, issuance.amount), (token, issuance.inflation_keys)] {
+				if !amount.is_null() {
+					explicit &=
+						add_explicit(&mut spent, confidential::Asset::Explicit(id), amount)?;
+				}
+			}
+		}
+	}
+	for output in &tx.output {
+		explicit &= add_explicit(&mut created, output.asset, output.value)?;
+	}
+	Ok(explicit.then_some((spent, created)))
+}
+
+/// Check the value of each asset spent by the transaction against the value of its
+/// outputs, if all of them are explicit.
+fn check_balance(tx: &Transaction, utxos: &[ElementsUtxo], problems: &mut Vec<String>) {
+	let (spent, created) = match explicit_totals(tx, utxos) {
+		Ok(Some(totals)) => totals,
+		Ok(None) => {
+			info!("Skipping the value balance check, some amounts are confidential.");
+			return;
+		}
+		Err(e) => {
+			problems.push(e);
+			return;
+		}
+	};
+
+	for asset in spent.keys().chain(created.keys()).collect::<BTreeSet<_>>() {
+		let (spent, created) =
+			(spent.get(asset).copied().unwrap_or(0), created.get(asset).copied().unwrap_or(0));
+		if spent != created {
+			problems.push(format!(
+				"asset {} is unbalanced: {} sat spent and {} sat in outputs",
+				asset, spent, created
+			));
+		}
+	}
+}
+
+/// Sanity checks of the transaction which a node would fail it for.
+fn check_transaction(tx: &Transaction, utxos: Option<&[ElementsUtxo]>) -> Vec<String> {
+	let mut problems = Vec::new();
+
+	let mut prevouts = HashMap::new();
+	for (index, input) in tx.input.iter().enumerate() {
+		if let Some(first) = prevouts.insert(input.previous_output, index) {
+			problems.push(format!(
+				"input {} spends {}, already spent by input {}",
+				index, input.previous_output, first
+			));
+		}
+		if input.script_sig.len() > MAX_STANDARD_SCRIPTSIG_SIZE {
+			problems.push(format!(
+				"input {} has a {} byte scriptSig, more than the standard {} bytes",
+				index,
+				input.script_sig.len(),
+				MAX_STANDARD_SCRIPTSIG_SIZE
+			));
+		}
+	}
+
+	for (index, output) in tx.output.iter().enumerate() {
+		let spk = &output.script_pubkey;
+		if spk.is_op_return() && !output.is_pegout() && spk.len() > MAX_OP_RETURN_DATA + 3 {
+			problems.push(format!(
+				"output {} has a {} byte OP_RETURN script, more than the standard {} bytes",
+				index,
+				spk.len(),
+				MAX_OP_RETURN_DATA + 3
+			));
+		} else if spk.len() > MAX_SCRIPT_SIZE {
+			problems.push(format!(
+				"output {} has a {} byte scriptPubKey, more than the {} bytes which can be spent",
+				index,
+				spk.len(),
+				MAX_SCRIPT_SIZE
+			));
+		}
+	}
+	if !tx.output.iter().any(TxOut::is_fee) {
+		problems.push("the transaction has no fee output".to_owned());
+	}
+
+	match utxos {
+		Some(utxos) if utxos.len() != tx.input.len() => problems.push(format!(
+			"{} input UTXOs given for {} inputs",
+			utxos.len(),
+			tx.input.len()
+		)),
+		Some(utxos) => check_balance(tx, utxos, &mut problems),
+		None => {
+			info!("Skipping the value balance check, pass the spent outputs with --input-utxo.")
+		}
+	}
+	problems
+}
+
 fn exec_create<'a>(matches: &clap::ArgMatches<'a>) {
-	let info = serde_json::from_str::<TransactionInfo>(&cmd::arg_or_stdin(matches, "tx-info"))
-		.expect("invalid JSON provided");
+	let info = cmd::parse_input::<TransactionInfo>(matches, "tx-info");
 	let tx = create_transaction(info);
 
+	if matches.is_present("check") {
+		let utxos = matches
+			.values_of("input-utxo")
+			.map(|utxos| utxos.map(cmd::simplicity::parse_utxo).collect::<Vec<_>>());
+		let problems = check_transaction(&tx, utxos.as_deref());
+		for problem in &problems {
+			warn!("Check failed: {}.", problem);
+		}
+		if !problems.is_empty() {
+			panic!("the transaction failed {} of the checks", problems.len());
+		}
+	}
+
 	let tx_bytes = serialize(&tx);
 	if matches.is_present("raw-stdout") {
-		::std::io::stdout().write_all(&tx_bytes).unwrap();
+		cmd::Output.write_all(&tx_bytes).unwrap();
 	} else {
-		print!("{}", hex::encode(&tx_bytes));
+		write!(cmd::Output, "{}", hex::encode(&tx_bytes)).unwrap();
 	}
 }
 
 fn cmd_decode<'a>() -> clap::App<'a, 'a> {
 	cmd::subcommand("decode", "decode a raw transaction to JSON")
 		.args(&cmd::opts_networks())
-		.args(&[cmd::opt_yaml(), cmd::arg("raw-tx", "the raw transaction in hex").required(false)])
+		.args(&cmd::opts_raw_value())
+		.args(&[
+			cmd::opt_yaml(),
+			cmd::opt_output(),
+			cmd::opt_abbrev(),
+			cmd::opt_ndjson(),
+			cmd::opt_keep_going(),
+			cmd::opt_address_book(),
+			cmd::opt(
+				"verify-sigs",
+				"check the signatures of P2PKH, P2WPKH and taproot key path inputs",
+			)
+			.requires("input-utxo")
+			.conflicts_with("ndjson"),
+			cmd::arg("raw-tx", "the raw transactions in hex, base64 or as file paths")
+				.multiple(true)
+				.required(false),
+		])
+		.args(
+			&cmd::simplicity::opts_env()
+				.into_iter()
+				.map(|arg| arg.required(false).requires("verify-sigs"))
+				.collect::<Vec<_>>(),
+		)
 }
 
 fn exec_decode<'a>(matches: &clap::ArgMatches<'a>) {
-	let hex_tx = cmd::arg_or_stdin(matches, "raw-tx");
-	let raw_tx = hex::decode(hex_tx.as_ref()).expect("could not decode raw tx");
-	let tx: Transaction = deserialize(&raw_tx).expect("invalid tx format");
-
-	let info = crate::GetInfo::get_info(&tx, cmd::network(matches));
-	cmd::print_output(matches, &info)
+	let address_book = AddressBook::load(matches);
+	let utxos = matches.is_present("verify-sigs").then(|| {
+		let utxos = matches.values_of("input-utxo").unwrap();
+		utxos.map(cmd::simplicity::parse_utxo).collect::<Vec<_>>()
+	});
+	let raw_txs = cmd::Batch::args_or_stdin(matches, "raw-tx");
+	// The input UTXOs are those of a single transaction.
+	if utxos.is_some() && raw_txs.is_array() {
+		panic!("--verify-sigs checks a single transaction, but several were given");
+	}
+	cmd::print_batch(matches, raw_txs, |raw_tx| {
+		let raw_tx = cmd::decode_binary(&raw_tx, "raw-tx");
+		let tx: Transaction = deserialize(&raw_tx).expect("invalid tx format");
+		let mut info = crate::GetInfo::get_info(&tx, cmd::network(matches));
+		if let Some(ref address_book) = address_book {
+			address_book.annotate(&tx, &mut info);
+		}
+		if let Some(ref utxos) = utxos {
+			let genesis_hash = cmd::simplicity::genesis_hash(matches);
+			cmd::signatures::annotate(&tx, utxos, genesis_hash, &mut info);
+		}
+		info
+	})
 }
@@ -0,0 +1,317 @@
+use std::sync::Arc;
+
+use elements::bitcoin::secp256k1::{self, Keypair, Message, SecretKey, XOnlyPublicKey};
+use elements::confidential::{self, AssetBlindingFactor, ValueBlindingFactor};
+use elements::encode::serialize;
+use elements::hashes::{sha256, Hash, HashEngine};
+use elements::{
+	AssetId, AssetIssuance, LockTime, OutPoint, Script, Sequence, Transaction, TxIn, TxInWitness,
+	TxOut, Txid,
+};
+use hal_simplicity::hal_simplicity::{control_block, elements_address};
+use hal_simplicity::simplicity::jet::elements::{ElementsEnv, ElementsUtxo};
+use hal_simplicity::simplicity::jet::Elements;
+use hal_simplicity::simplicity::{Cmr, CommitNode, Policy};
+use schemars::schema::RootSchema;
+use schemars::JsonSchema;
+use serde::Serialize;
+
+use crate::cmd;
+
+/// The asset used by the transactions of the sighash vectors (Liquid's L-BTC).
+const VECTORS_ASSET: &str = "6d521c38ec1ea15734ae22b7c46064412829c0d0579f0a713d1c04ede979026f";
+
+pub fn subcommand<'a>() -> clap::App<'a, 'a> {
+	cmd::subcommand_group(
+		"vectors",
+		"generate deterministic test vectors for cross-checking other implementations",
+	)
+	.subcommand(cmd_vectors("address", "addresses of Simplicity programs"))
+	.subcommand(cmd_vectors("cmr", "CMRs of Simplicity programs compiled from policies"))
+	.subcommand(cmd_vectors("commitment", "Pedersen commitments to assets and values"))
+	.subcommand(cmd_vectors("sighash", "sighashes and signatures of Simplicity inputs"))
+}
+
+/// The schemas of the outputs of the subcommands, by subcommand name.
+pub fn schemas() -> Vec<(&'static str, RootSchema)> {
+	vec![
+		("address", schemars::schema_for!(Vectors<AddressVector>)),
+		("cmr", schemars::schema_for!(Vectors<CmrVector>)),
+		("commitment", schemars::schema_for!(Vectors<CommitmentVector>)),
+		("sighash", schemars::schema_for!(Vectors<SighashVector>)),
+	]
+}
+
+pub fn execute<'a>(matches: &clap::ArgMatches<'a>) {
+	match matches.subcommand() {
+		("address", Some(m)) => exec_vectors(m, "address", address_vector),
+		("cmr", Some(m)) => exec_vectors(m, "cmr", cmr_vector),
+		("commitment", Some(m)) => exec_vectors(m, "commitment", commitment_vector),
+		("sighash", Some(m)) => exec_vectors(m, "sighash", sighash_vector),
+		(_, _) => unreachable!("clap prints help"),
+	};
+}
+
+fn cmd_vectors<'a>(area: &'static str, about: &'static str) -> clap::App<'a, 'a> {
+	cmd::subcommand(area, about).args(&cmd::opts_raw_value()).args(&[
+		cmd::opt_yaml(),
+		cmd::opt_output(),
+		cmd::opt("count", "the number of vectors to generate")
+			.short("n")
+			.takes_value(true)
+			.default_value("4"),
+	])
+}
+
+#[derive(Serialize, JsonSchema)]
+#[schemars(rename = "{T}s")]
+struct Vectors<T> {
+	area: String,
+	vectors: Vec<T>,
+}
+
+/// The seed of the vector with the given index: the SHA256 hash of
+/// `hal-simplicity vectors <area> <index>`.
+fn seed(area: &str, index: usize) -> sha256::Hash {
+	sha256::Hash::hash(format!("hal-simplicity vectors {} {}", area, index).as_bytes())
+}
+
+/// The 32 bytes derived from a seed for the given purpose: the SHA256 hash of
+/// the seed followed by the name of the purpose.
+fn derive(seed: &sha256::Hash, purpose: &str) -> [u8; 32] {
+	let mut engine = sha256::Hash::engine();
+	engine.input(seed.as_ref());
+	engine.input(purpose.as_bytes());
+	sha256::Hash::from_engine(engine).to_byte_array()
+}
+
+fn derive_keypair(seed: &sha256::Hash) -> Keypair {
+	let sk = SecretKey::from_slice(&derive(seed, "key")).expect("hash is a valid secret key");
+	Keypair::from_secret_key(secp256k1::SECP256K1, &sk)
+}
+
+/// The program checking a single signature by the given key.
+fn pk_program(key: XOnlyPublicKey) -> Arc<CommitNode<Elements>> {
+	Policy::Key(key).commit().expect("no asm fragments")
+}
+
+fn exec_vectors<'a, T: Serialize + JsonSchema>(
+	matches: &clap::ArgMatches<'a>,
+	area: &str,
+	vector: fn(sha256::Hash, usize) -> T,
+) {
+	let count: usize =
+		matches.value_of("count").expect("count has a default").parse().expect("invalid count");
+	let info = Vectors {
+		area: area.to_owned(),
+		vectors: (0..count).map(|index| vector(seed(area, index), index)).collect(),
+	};
+	cmd::print_output(matches, &info)
+}
+
+#[derive(Serialize, JsonSchema)]
+struct AddressVector {
+	#[schemars(with = "String")]
+	seed: sha256::Hash,
+	/// The key checked by the `pk` program whose addresses are given.
+	#[schemars(with = "String")]
+	x_only: XOnlyPublicKey,
+	#[schemars(with = "String")]
+	cmr: Cmr,
+	liquid_address: String,
+	liquid_testnet_address: String,
+	elementsregtest_address: String,
+}
+
+fn address_vector(seed: sha256::Hash, _index: usize) -> AddressVector {
+	let x_only = derive_keypair(&seed).x_only_public_key().0;
+	let cmr = pk_program(x_only).cmr();
+	let address = |params| elements_address(cmr, params).to_string();
+	AddressVector {
+		seed,
+		x_only,
+		cmr,
+		liquid_address: address(&elements::AddressParams::LIQUID),
+		liquid_testnet_address: address(&elements::AddressParams::LIQUID_TESTNET),
+		elementsregtest_address: address(&elements::AddressParams::ELEMENTS),
+	}
+}
+
+#[derive(Serialize, JsonSchema)]
+struct CmrVector {
+	#[schemars(with = "String")]
+	seed: sha256::Hash,
+	policy: String,
+	commit_base64: String,
+	#[schemars(with = "String")]
+	cmr: Cmr,
+}
+
+fn cmr_vector(seed: sha256::Hash, index: usize) -> CmrVector {
+	let key = derive_keypair(&seed).x_only_public_key().0;
+	let hash = sha256::Hash::from_byte_array(derive(&seed, "hash"));
+	let blocks = u16::from(derive(&seed, "blocks")[0]) + 1;
+	// A block height from 1 to 499999999, as `after` takes no other locktimes.
+	let height =
+		u32::from_be_bytes(derive(&seed, "height")[..4].try_into().unwrap()) % 499_999_999 + 1;
+
+	// Cycle through the policy fragments so that each of them is covered.
+	let policy = match index % 4 {
+		0 => Policy::Key(key),
+		1 => Policy::Sha256(hash),
+		2 => Policy::And {
+			left: Arc::new(Policy::Key(key)),
+			right: Arc::new(Policy::Older(blocks)),
+		},
+		_ => Policy::Or {
+			left: Arc::new(Policy::Sha256(hash)),
+			right: Arc::new(Policy::After(height)),
+		},
+	};
+	let program = policy.commit().expect("no asm fragments");
+	CmrVector {
+		seed,
+		policy: policy.to_string(),
+		commit_base64: program.to_string(),
+		cmr: program.cmr(),
+	}
+}
+
+#[derive(Serialize, JsonSchema)]
+struct CommitmentVector {
+	#[schemars(with = "String")]
+	seed: sha256::Hash,
+	#[schemars(with = "String")]
+	asset_id: AssetId,
+	#[schemars(with = "String")]
+	asset_blinding_factor: AssetBlindingFactor,
+	value: u64,
+	#[schemars(with = "String")]
+	value_blinding_factor: ValueBlindingFactor,
+	asset_commitment: String,
+	value_commitment: String,
+}
+
+fn commitment_vector(seed: sha256::Hash, _index: usize) -> CommitmentVector {
+	let asset_id = AssetId::from_slice(&derive(&seed, "asset")).expect("32 bytes");
+	let asset_blinding_factor =
+		AssetBlindingFactor::from_slice(&derive(&seed, "abf")).expect("hash is a valid tweak");
+	let value =
+		u64::from_be_bytes(derive(&seed, "value")[..8].try_into().unwrap()) % 2_100_000_000_000_000;
+	let value_blinding_factor =
+		ValueBlindingFactor::from_slice(&derive(&seed, "vbf")).expect("hash is a valid tweak");
+
+	let asset = confidential::Asset::new_confidential(
+		secp256k1::SECP256K1,
+		asset_id,
+		asset_blinding_factor,
+	);
+	let generator = asset.commitment().expect("asset is confidential");
+	let value_commitment = confidential::Value::new_confidential(
+		secp256k1::SECP256K1,
+		value,
+		generator,
+		value_blinding_factor,
+	);
+	CommitmentVector {
+		seed,
+		asset_id,
+		asset_blinding_factor,
+		value,
+		value_blinding_factor,
+		asset_commitment: hex::encode(serialize(&asset)),
+		value_commitment: hex::encode(serialize(&value_commitment)),
+	}
+}
+
+#[derive(Serialize, JsonSchema)]
+struct SighashVector {
+	#[schemars(with = "String")]
+	seed: sha256::Hash,
+	#[schemars(with = "String")]
+	secret_key: SecretKey,
+	#[schemars(with = "String")]
+	x_only: XOnlyPublicKey,
+	/// The CMR of the `pk` program being spent.
+	#[schemars(with = "String")]
+	cmr: Cmr,
+	control_block: String,
+	/// The spending transaction in hex.
+	tx: String,
+	input_index: u32,
+	/// The output spent by the transaction, as SCRIPTPUBKEY:ASSET:VALUE.
+	input_utxo: String,
+	genesis_hash: String,
+	#[schemars(with = "String")]
+	sighash: sha256::Hash,
+	/// The BIP-340 signature of the sighash, without auxiliary randomness.
+	signature: String,
+}
+
+fn sighash_vector(seed: sha256::Hash, index: usize) -> SighashVector {
+	let keypair = derive_keypair(&seed);
+	let x_only = keypair.x_only_public_key().0;
+	let cmr = pk_program(x_only).cmr();
+	let control_block = control_block(cmr);
+	let asset: AssetId = VECTORS_ASSET.parse().expect("valid asset id");
+	let genesis_hash = cmd::simplicity::LIQUID_GENESIS_HASH.parse().expect("valid genesis hash");
+
+	let value = 100_000 * (index as u64 + 1);
+	let fee = 500;
+	let utxo = ElementsUtxo {
+		script_pubkey: elements_address(cmr, &elements::AddressParams::LIQUID).script_pubkey(),
+		asset: confidential::Asset::Explicit(asset),
+		value: confidential::Value::Explicit(value),
+	};
+	let tx = Transaction {
+		version: 2,
+		lock_time: LockTime::ZERO,
+		input: vec![TxIn {
+			previous_output: OutPoint::new(Txid::from_byte_array(derive(&seed, "prevout")), 0),
+			is_pegin: false,
+			script_sig: Script::new(),
+			sequence: Sequence::MAX,
+			asset_issuance: AssetIssuance::default(),
+			witness: TxInWitness::default(),
+		}],
+		output: vec![
+			TxOut {
+				asset: confidential::Asset::Explicit(asset),
+				value: confidential::Value::Explicit(value - fee),
+				nonce: confidential::Nonce::Null,
+				script_pubkey: utxo.script_pubkey.clone(),
+				witness: Default::default(),
+			},
+			TxOut::new_fee(fee, asset),
+		],
+	};
+	let tx_hex = hex::encode(serialize(&tx));
+	let input_utxo = format!("{}:{}:{}", hex::encode(utxo.script_pubkey.as_bytes()), asset, value);
+
+	let env = ElementsEnv::new(
+		Arc::new(tx),
+		vec![utxo],
+		0,
+		cmr,
+		control_block.clone(),
+		None,
+		genesis_hash,
+	);
+	let sighash = env.c_tx_env().sighash_all();
+	let msg = Message::from_digest(sighash.to_byte_array());
+	let signature = secp256k1::SECP256K1.sign_schnorr_no_aux_rand(&msg, &keypair);
+
+	SighashVector {
+		seed,
+		secret_key: keypair.secret_key(),
+		x_only,
+		cmr,
+		control_block: hex::encode(control_block.serialize()),
+		tx: tx_hex,
+		input_index: 0,
+		input_utxo,
+		genesis_hash: genesis_hash.to_string(),
+		sighash,
+		signature: signature.to_string(),
+	}
+}
@@ -0,0 +1,254 @@
+use std::collections::{BTreeMap, HashMap, HashSet};
+use std::str::FromStr;
+
+use elements::encode::deserialize;
+use elements::{confidential, OutPoint, Transaction, Txid};
+use elements_miniscript::{Descriptor, DescriptorPublicKey};
+use log::info;
+use schemars::schema::RootSchema;
+use schemars::JsonSchema;
+use serde::{Deserialize, Serialize};
+
+use crate::cmd;
+
+pub fn subcommand<'a>() -> clap::App<'a, 'a> {
+	cmd::subcommand_group("wallet", "watch-only wallet tools").subcommand(cmd_scan())
+}
+
+/// The schemas of the outputs of the subcommands, by subcommand name.
+pub fn schemas() -> Vec<(&'static str, RootSchema)> {
+	vec![("scan", schemars::schema_for!(ScanInfo))]
+}
+
+pub fn execute<'a>(matches: &clap::ArgMatches<'a>) {
+	match matches.subcommand() {
+		("scan", Some(m)) => exec_scan(m),
+		(_, _) => unreachable!("clap prints help"),
+	};
+}
+
+fn cmd_scan<'a>() -> clap::App<'a, 'a> {
+	cmd::subcommand(
+		"scan",
+		"derive the first addresses of an xpub or descriptor and report their unspent outputs",
+	)
+	.args(&cmd::opts_networks())
+	.args(&cmd::opts_raw_value())
+	.args(&[
+		cmd::opt_yaml(),
+		cmd::opt_output(),
+		cmd::arg(
+			"descriptor",
+			"an output descriptor (e.g. elwpkh(xpub.../0/*)), or an xpub for elwpkh(<xpub>/0/*)",
+		)
+		.required(true),
+		cmd::arg(
+			"raw-tx",
+			"the transactions to scan in hex, base64 or as file paths (read from stdin if omitted)",
+		)
+		.multiple(true)
+		.required(false),
+		cmd::opt("count", "the number of addresses to derive")
+			.short("n")
+			.takes_value(true)
+			.default_value("20"),
+		cmd::opt("esplora", "query the unspent outputs from the Esplora API at the given URL")
+			.takes_value(true)
+			.conflicts_with("raw-tx")
+			.required(false),
+		cmd::opt("all", "list the addresses without unspent outputs too")
+			.takes_value(false)
+			.required(false),
+	])
+}
+
+/// An unspent output paying to one of the scanned addresses.
+#[derive(Serialize, JsonSchema)]
+struct UtxoInfo {
+	#[schemars(with = "String")]
+	txid: Txid,
+	vout: u32,
+	/// The asset ID, if the asset is explicit.
+	#[serde(skip_serializing_if = "Option::is_none")]
+	#[schemars(with = "Option<String>")]
+	asset: Option<elements::AssetId>,
+	/// The amount in satoshis, if the value is explicit.
+	#[serde(skip_serializing_if = "Option::is_none")]
+	value: Option<u64>,
+}
+
+#[derive(Serialize, JsonSchema)]
+struct AddressBalance {
+	/// The derivation index of the address.
+	index: u32,
+	#[schemars(with = "String")]
+	address: elements::Address,
+	utxos: Vec<UtxoInfo>,
+	/// The sum of the explicit values of the outputs, by asset ID.
+	balances: BTreeMap<String, u64>,
+	/// The number of outputs whose asset or value is blinded and not counted in the balances.
+	confidential_utxos: usize,
+}
+
+#[derive(Serialize, JsonSchema)]
+struct ScanInfo {
+	descriptor: String,
+	addresses: Vec<AddressBalance>,
+	/// The total balances of all addresses, by asset ID.
+	balances: BTreeMap<String, u64>,
+	confidential_utxos: usize,
+}
+
+/// An unspent output as returned by Esplora's `/address/:address/utxo` endpoint.
+#[derive(Deserialize)]
+struct EsploraUtxo {
+	txid: Txid,
+	vout: u32,
+	#[serde(default)]
+	asset: Option<elements::AssetId>,
+	#[serde(default)]
+	value: Option<u64>,
+}
+
+/// Parse the descriptor argument, wrapping a bare extended key as `elwpkh(<xpub>/0/*)`.
+fn parse_descriptor(s: &str) -> Descriptor<DescriptorPublicKey> {
+	let s = if s.contains('(') {
+		s.to_owned()
+	} else {
+		format!("elwpkh({}/0/*)", s)
+	};
+	Descriptor::from_str(&s).unwrap_or_else(|e| panic!("invalid descriptor '{}': {}", s, e))
+}
+
+/// Find the outputs of the given transactions paying to the given addresses which are
+/// not spent by any of the transactions, by address.
+fn scan_txs(txs: &[Transaction], addresses: &[elements::Address]) -> Vec<Vec<UtxoInfo>> {
+	let scripts = addresses
+		.iter()
+		.enumerate()
+		.map(|(index, address)| (address.script_pubkey(), index))
+		.collect::<HashMap<_, _>>();
+	let spent = txs
+		.iter()
+		.flat_map(|tx| tx.input.iter().map(|i| i.previous_output))
+		.collect::<HashSet<OutPoint>>();
+	let mut utxos = addresses.iter().map(|_| Vec::new()).collect::<Vec<_>>();
+	for tx in txs {
+		let txid = tx.txid();
+		for (vout, output) in tx.output.iter().enumerate() {
+			let index = match scripts.get(&output.script_pubkey) {
+				Some(index) => *index,
+				None => continue,
+			};
+			let vout = vout as u32;
+			if spent.contains(&OutPoint::new(txid, vout)) {
+				continue;
+			}
+			utxos[index].push(UtxoInfo {
+				txid,
+				vout,
+				asset: match output.asset {
+					confidential::Asset::Explicit(id) => Some(id),
+					_ => None,
+				},
+				value: output.value.explicit(),
+			});
+		}
+	}
+	utxos
+}
+
+/// Query the unspent outputs of the given address from Esplora.
+fn esplora_utxos(url: &str, address: &elements::Address) -> Vec<UtxoInfo> {
+	let url = format!("{}/address/{}/utxo", url.trim_end_matches('/'), address);
+	info!("Querying {}", url);
+	let utxos: Vec<EsploraUtxo> = ureq::get(&url)
+		.call()
+		.unwrap_or_else(|e| panic!("Esplora request {} failed: {}", url, e))
+		.into_json()
+		.unwrap_or_else(|e| panic!("invalid Esplora response from {}: {}", url, e));
+	utxos
+		.into_iter()
+		.map(|u| UtxoInfo {
+			txid: u.txid,
+			vout: u.vout,
+			asset: u.asset,
+			value: u.value,
+		})
+		.collect()
+}
+
+/// Add a value to the balance of an asset, failing if it overflows.
+fn add_balance(balances: &mut BTreeMap<String, u64>, asset: elements::AssetId, value: u64) {
+	let balance = balances.entry(asset.to_string()).or_insert(0);
+	*balance = balance.checked_add(value).unwrap_or_else(|| {
+		panic!("the balances of asset {} add up to more than {} sat", asset, u64::MAX)
+	});
+}
+
+fn exec_scan<'a>(matches: &clap::ArgMatches<'a>) {
+	let descriptor =
+		parse_descriptor(matches.value_of("descriptor").expect("descriptor is mandatory"));
+	let count: u32 =
+		matches.value_of("count").expect("count has a default").parse().expect("invalid count");
+	let count = if descriptor.has_wildcard() {
+		count
+	} else {
+		1
+	};
+	let params = cmd::network(matches).address_params();
+
+	let addresses = (0..count)
+		.map(|index| {
+			let derived = descriptor
+				.at_derivation_index(index)
+				.unwrap_or_else(|e| panic!("cannot derive index {}: {}", index, e));
+			derived.address(params).unwrap_or_else(|e| panic!("descriptor has no address: {}", e))
+		})
+		.collect::<Vec<_>>();
+
+	let utxos = if let Some(url) = matches.value_of("esplora") {
+		addresses.iter().map(|address| esplora_utxos(url, address)).collect()
+	} else {
+		let txs = cmd::args_or_stdin(matches, "raw-tx")
+			.iter()
+			.map(|raw_tx| {
+				let raw_tx = cmd::decode_binary(raw_tx, "raw-tx");
+				deserialize::<Transaction>(&raw_tx).expect("invalid tx format")
+			})
+			.collect::<Vec<_>>();
+		scan_txs(&txs, &addresses)
+	};
+
+	let mut info = ScanInfo {
+		descriptor: descriptor.to_string(),
+		addresses: Vec::new(),
+		balances: BTreeMap::new(),
+		confidential_utxos: 0,
+	};
+	for (index, (address, utxos)) in addresses.into_iter().zip(utxos).enumerate() {
+		let mut balances = BTreeMap::new();
+		let mut confidential_utxos = 0;
+		for utxo in &utxos {
+			match (utxo.asset, utxo.value) {
+				(Some(asset), Some(value)) => {
+					add_balance(&mut balances, asset, value);
+					add_balance(&mut info.balances, asset, value);
+				}
+				_ => confidential_utxos += 1,
+			}
+		}
+		info.confidential_utxos += confidential_utxos;
+		if utxos.is_empty() && !matches.is_present("all") {
+			continue;
+		}
+		info.addresses.push(AddressBalance {
+			index: index as u32,
+			address,
+			utxos,
+			balances,
+			confidential_utxos,
+		});
+	}
+	cmd::print_output(matches, &info)
+}
@@ -0,0 +1,339 @@
+use std::collections::HashSet;
+use std::io::{self, Read, Write};
+use std::net::TcpStream;
+use std::sync::mpsc;
+use std::thread;
+use std::time::Duration;
+
+use elements::encode::deserialize;
+use elements::{Block, BlockHash, Transaction, Txid};
+use hal_simplicity::block::BlockInfo;
+use hal_simplicity::tx::TransactionInfo;
+use hal_simplicity::{GetInfo, Network};
+use log::{info, warn};
+use schemars::schema::RootSchema;
+use schemars::JsonSchema;
+use serde::Serialize;
+
+use crate::cmd;
+use crate::cmd::rpc::{self, RpcClient};
+
+pub fn subcommand<'a>() -> clap::App<'a, 'a> {
+	cmd::subcommand(
+		"watch",
+		"print the transactions and blocks of an Elements node as NDJSON as they arrive",
+	)
+	.args(&cmd::opts_networks())
+	.args(&cmd::opts_raw_value())
+	.args(&rpc::opts_auth())
+	.args(&[
+		cmd::opt(
+			"zmq",
+			"a ZMQ endpoint publishing rawtx and/or rawblock (e.g. tcp://127.0.0.1:28332)",
+		)
+		.takes_value(true)
+		.multiple(true)
+		.number_of_values(1)
+		.required_unless("rpc"),
+		cmd::opt(
+			"rpc",
+			"poll the JSON-RPC interface at the given URL (e.g. http://127.0.0.1:7041)",
+		)
+		.takes_value(true)
+		.conflicts_with("zmq"),
+		cmd::opt("interval", "the number of seconds between two RPC polls")
+			.takes_value(true)
+			.default_value("5"),
+		cmd::opt(
+			"txids",
+			"print the IDs of the transactions of blocks instead of full transactions",
+		)
+		.takes_value(false)
+		.required(false),
+		cmd::opt("count", "stop after printing the given number of items")
+			.short("n")
+			.takes_value(true)
+			.required(false),
+	])
+}
+
+pub fn schema() -> RootSchema {
+	schemars::schema_for!(WatchItem)
+}
+
+/// A line of the output of `watch`.
+#[derive(Serialize, JsonSchema)]
+#[serde(rename_all = "lowercase")]
+enum WatchItem {
+	Tx(Box<TransactionInfo>),
+	Block(Box<BlockInfo>),
+}
+
+/// A raw item received from the node.
+enum RawItem {
+	Tx(Vec<u8>),
+	Block(Vec<u8>),
+}
+
+pub fn execute<'a>(matches: &clap::ArgMatches<'a>) {
+	let network = cmd::network(matches);
+	let count = matches.value_of("count").map(|c| c.parse::<usize>().expect("invalid count"));
+
+	if count == Some(0) {
+		return;
+	}
+
+	let (sender, receiver) = mpsc::channel();
+	if let Some(url) = matches.value_of("rpc") {
+		let rpc = RpcClient::new(url, matches);
+		let interval = matches
+			.value_of("interval")
+			.expect("interval has a default")
+			.parse()
+			.map(Duration::from_secs)
+			.expect("invalid interval");
+		thread::spawn(move || poll_rpc(&rpc, interval, &sender));
+	} else {
+		for endpoint in matches.values_of("zmq").expect("zmq or rpc is required") {
+			let endpoint = endpoint.to_owned();
+			let sender = sender.clone();
+			thread::spawn(move || subscribe_zmq(&endpoint, &sender));
+		}
+		drop(sender);
+	}
+
+	let mut printed = 0;
+	for raw in receiver {
+		match decode(raw, network, matches.is_present("txids")) {
+			Ok(item) => {
+				writeln!(cmd::Output, "{}", cmd::ndjson_line(matches, &item)).unwrap();
+				printed += 1;
+			}
+			Err(e) => warn!("{}", e),
+		}
+		if count.is_some_and(|count| printed >= count) {
+			break;
+		}
+	}
+}
+
+fn decode(raw: RawItem, network: Network, txids: bool) -> Result<WatchItem, String> {
+	match raw {
+		RawItem::Tx(bytes) => {
+			let tx: Transaction =
+				deserialize(&bytes).map_err(|e| format!("invalid tx received: {}", e))?;
+			Ok(WatchItem::Tx(Box::new(tx.get_info(network))))
+		}
+		RawItem::Block(bytes) => {
+			let block: Block =
+				deserialize(&bytes).map_err(|e| format!("invalid block received: {}", e))?;
+			let mut info = block.get_info(network);
+			if txids {
+				info.transactions = None;
+				info.txids = Some(block.txdata.iter().map(|tx| tx.txid()).collect());
+			}
+			Ok(WatchItem::Block(Box::new(info)))
+		}
+	}
+}
+
+/// The largest ZMTP frame accepted, well above the largest block a node publishes.
+const MAX_FRAME_LEN: u64 = 32 * 1024 * 1024;
+
+/// Read one ZMTP frame, returning its flags and body.
+fn read_frame(stream: &mut TcpStream) -> io::Result<(u8, Vec<u8>)> {
+	let mut flags = [0; 1];
+	stream.read_exact(&mut flags)?;
+	let len = if flags[0] & 0x02 != 0 {
+		let mut len = [0; 8];
+		stream.read_exact(&mut len)?;
+		u64::from_be_bytes(len)
+	} else {
+		let mut len = [0; 1];
+		stream.read_exact(&mut len)?;
+		u64::from(len[0])
+	};
+	if len > MAX_FRAME_LEN {
+		return Err(io::Error::new(
+			io::ErrorKind::InvalidData,
+			format!("ZMTP frame of {} bytes exceeds the limit of {} bytes", len, MAX_FRAME_LEN),
+		));
+	}
+	let mut body = vec![0; len as usize];
+	stream.read_exact(&mut body)?;
+	Ok((flags[0], body))
+}
+
+/// Write one short ZMTP frame with the given flags.
+fn write_frame(stream: &mut TcpStream, flags: u8, body: &[u8]) -> io::Result<()> {
+	assert!(body.len() < 256, "only short frames are sent");
+	stream.write_all(&[flags, body.len() as u8])?;
+	stream.write_all(body)
+}
+
+/// Connect to a ZMQ PUB socket as a SUB socket subscribed to the given topics,
+/// using the ZMTP 3.0 protocol with the NULL security mechanism.
+fn zmq_connect(endpoint: &str, topics: &[&str]) -> io::Result<TcpStream> {
+	let addr = endpoint.strip_prefix("tcp://").unwrap_or_else(|| {
+		panic!("unsupported ZMQ endpoint '{}', expected tcp://HOST:PORT", endpoint)
+	});
+	let mut stream = TcpStream::connect(addr)?;
+
+	let mut greeting = [0; 64];
+	greeting[0] = 0xff;
+	greeting[9] = 0x7f;
+	greeting[10] = 3;
+	greeting[12..16].copy_from_slice(b"NULL");
+	stream.write_all(&greeting)?;
+	let mut peer_greeting = [0; 64];
+	stream.read_exact(&mut peer_greeting)?;
+	if peer_greeting[0] != 0xff || peer_greeting[9] != 0x7f || peer_greeting[10] < 3 {
+		return Err(io::Error::new(io::ErrorKind::InvalidData, "not a ZMTP 3 peer"));
+	}
+
+	let mut ready = b"\x05READY\x0bSocket-Type".to_vec();
+	ready.extend_from_slice(&3u32.to_be_bytes());
+	ready.extend_from_slice(b"SUB");
+	write_frame(&mut stream, 0x04, &ready)?;
+	let (flags, _) = read_frame(&mut stream)?;
+	if flags & 0x04 == 0 {
+		return Err(io::Error::new(io::ErrorKind::InvalidData, "expected a READY command"));
+	}
+
+	for topic in topics {
+		let mut subscribe = vec![1];
+		subscribe.extend_from_slice(topic.as_bytes());
+		write_frame(&mut stream, 0, &subscribe)?;
+	}
+	Ok(stream)
+}
+
+/// Receive the rawtx and rawblock messages of a ZMQ endpoint until it disconnects.
+fn subscribe_zmq(endpoint: &str, sender: &mpsc::Sender<RawItem>) {
+	let mut stream = zmq_connect(endpoint, &["rawtx", "rawblock"])
+		.unwrap_or_else(|e| panic!("could not subscribe to {}: {}", endpoint, e));
+	info!("Subscribed to {}", endpoint);
+	loop {
+		// Each message is made of the topic, the body and a sequence number.
+		let mut parts = Vec::new();
+		loop {
+			let (flags, body) = match read_frame(&mut stream) {
+				Ok(frame) => frame,
+				Err(e) => {
+					warn!("Connection to {} lost: {}", endpoint, e);
+					return;
+				}
+			};
+			if flags & 0x04 != 0 {
+				continue;
+			}
+			parts.push(body);
+			if flags & 0x01 == 0 {
+				break;
+			}
+		}
+		let item = match (parts.first().map(Vec::as_slice), parts.get(1)) {
+			(Some(b"rawtx"), Some(body)) => RawItem::Tx(body.clone()),
+			(Some(b"rawblock"), Some(body)) => RawItem::Block(body.clone()),
+			_ => continue,
+		};
+		if sender.send(item).is_err() {
+			return;
+		}
+	}
+}
+
+/// The hashes of the blocks connected to the chain since `last_tip`, up to `tip`, in
+/// chain order.
+///
+/// If `last_tip` is no longer in the active chain, the blocks after the last block
+/// both chains share are returned.
+fn connected_blocks(rpc: &RpcClient, last_tip: BlockHash, tip: BlockHash) -> Vec<BlockHash> {
+	let header = |hash: &BlockHash| -> serde_json::Value {
+		rpc.call("getblockheader", serde_json::json!([hash, true]))
+			.unwrap_or_else(|e| panic!("{}", e))
+	};
+	let height = |header: &serde_json::Value| -> u64 {
+		header["height"].as_u64().expect("invalid getblockheader result")
+	};
+	let previous = |header: &serde_json::Value| -> BlockHash {
+		serde_json::from_value(header["previousblockhash"].clone())
+			.expect("invalid getblockheader result")
+	};
+
+	// Blocks which are not in the active chain have -1 confirmations.
+	let mut fork = last_tip;
+	let mut fork_header = header(&fork);
+	while fork_header["confirmations"].as_i64().unwrap_or(-1) < 0 {
+		fork = previous(&fork_header);
+		fork_header = header(&fork);
+	}
+	if fork != last_tip {
+		warn!("Block {} was reorganized out, continuing from block {}", last_tip, fork);
+	}
+	let fork_height = height(&fork_header);
+
+	let mut blocks = Vec::new();
+	let mut hash = tip;
+	let mut tip_header = header(&hash);
+	while height(&tip_header) > fork_height {
+		blocks.push(hash);
+		hash = previous(&tip_header);
+		tip_header = header(&hash);
+	}
+	blocks.reverse();
+	blocks
+}
+
+/// Poll the mempool and the chain tip of the node, sending the transactions entering
+/// the mempool and every block connected to the chain, in order.
+///
+/// What is in the mempool and the tip when polling starts is considered seen.
+fn poll_rpc(rpc: &RpcClient, interval: Duration, sender: &mpsc::Sender<RawItem>) {
+	let mempool = |rpc: &RpcClient| -> HashSet<Txid> {
+		let result =
+			rpc.call("getrawmempool", serde_json::json!([])).unwrap_or_else(|e| panic!("{}", e));
+		serde_json::from_value(result).expect("invalid getrawmempool result")
+	};
+	let tip = |rpc: &RpcClient| -> BlockHash {
+		let result =
+			rpc.call("getbestblockhash", serde_json::json!([])).unwrap_or_else(|e| panic!("{}", e));
+		serde_json::from_value(result).expect("invalid getbestblockhash result")
+	};
+
+	let mut seen = mempool(rpc);
+	let mut last_tip = tip(rpc);
+	info!("Polling {} every {:?}", rpc.url, interval);
+	loop {
+		thread::sleep(interval);
+
+		let current_tip = tip(rpc);
+		if current_tip != last_tip {
+			for hash in connected_blocks(rpc, last_tip, current_tip) {
+				let block = rpc
+					.raw("getblock", serde_json::json!([hash, 0]))
+					.unwrap_or_else(|e| panic!("{}", e));
+				if sender.send(RawItem::Block(block)).is_err() {
+					return;
+				}
+			}
+			last_tip = current_tip;
+		}
+
+		let current = mempool(rpc);
+		for txid in current.difference(&seen) {
+			// The transaction may have left the mempool since it was listed.
+			let tx = match rpc.raw("getrawtransaction", serde_json::json!([txid])) {
+				Ok(tx) => tx,
+				Err(e) => {
+					warn!("{}", e);
+					continue;
+				}
+			};
+			if sender.send(RawItem::Tx(tx)).is_err() {
+				return;
+			}
+		}
+		seen = current;
+	}
+}
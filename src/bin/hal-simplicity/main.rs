@@ -1,5 +1,4 @@
 use std::panic;
-use std::process;
 
 pub use elements::bitcoin;
 
@@ -7,14 +6,23 @@ pub use hal_simplicity::{GetInfo, Network};
 
 pub mod cmd;
 
-/// Setup logging with the given log level.
-fn setup_logger(lvl: log::LevelFilter) {
-	fern::Dispatch::new()
-		.format(|out, message, _record| out.finish(format_args!("{}", message)))
-		.level(lvl)
-		.chain(std::io::stderr())
-		.apply()
-		.expect("error setting up logger");
+/// Setup logging with the given log level, as plain messages or as JSON lines.
+fn setup_logger(lvl: log::LevelFilter, json: bool) {
+	let dispatch = fern::Dispatch::new();
+	let dispatch = if json {
+		dispatch.format(|out, message, record| {
+			let line = serde_json::json!({
+				"timestamp": chrono::Utc::now().format("%Y-%m-%dT%H:%M:%S%.3fZ").to_string(),
+				"level": record.level().to_string(),
+				"module": record.module_path().unwrap_or_else(|| record.target()),
+				"message": message.to_string(),
+			});
+			out.finish(format_args!("{}", line))
+		})
+	} else {
+		dispatch.format(|out, message, _record| out.finish(format_args!("{}", message)))
+	};
+	dispatch.level(lvl).chain(std::io::stderr()).apply().expect("error setting up logger");
 }
 
 /// Create the main app object.
@@ -39,16 +47,41 @@ fn init_app<'a, 'b>() -> clap::App<'a, 'b> {
 				.takes_value(false)
 				.global(true),
 		)
+		.arg(
+			cmd::opt("log-format", "the format of the logging output")
+				.takes_value(true)
+				.possible_values(&["text", "json"])
+				.global(true),
+		)
+		.arg(
+			cmd::opt(
+				"out",
+				"write the output to the given file instead of stdout, gzipped if it ends in .gz",
+			)
+			.takes_value(true)
+			.global(true),
+		)
 }
 
 /// Try execute built-in command. Return false if no command found.
 fn execute_builtin<'a>(matches: &clap::ArgMatches<'a>) -> bool {
 	match matches.subcommand() {
 		("address", Some(m)) => cmd::address::execute(m),
+		("asset", Some(m)) => cmd::asset::execute(m),
+		("audit", Some(m)) => cmd::audit::execute(m),
 		("block", Some(m)) => cmd::block::execute(m),
+		("doctor", Some(m)) => cmd::doctor::execute(m),
+		("fee", Some(m)) => cmd::fee::execute(m),
+		("info", Some(m)) => cmd::info::execute(m),
 		("keypair", Some(m)) => cmd::keypair::execute(m),
+		("pegin", Some(m)) => cmd::pegin::execute(m),
+		("schema", Some(m)) => cmd::schema::execute(m),
+		("session", Some(m)) => cmd::session::execute(m),
 		("simplicity", Some(m)) => cmd::simplicity::execute(m),
 		("tx", Some(m)) => cmd::tx::execute(m),
+		("vectors", Some(m)) => cmd::vectors::execute(m),
+		("wallet", Some(m)) => cmd::wallet::execute(m),
+		("watch", Some(m)) => cmd::watch::execute(m),
 		_ => return false,
 	};
 	true
@@ -58,31 +91,28 @@ fn main() {
 	// Apply a custom panic hook to print a more user-friendly message
 	// in case the execution fails.
 	panic::set_hook(Box::new(|info| {
-		let message = if let Some(m) = info.payload().downcast_ref::<String>() {
-			m
-		} else if let Some(m) = info.payload().downcast_ref::<&str>() {
-			m
-		} else {
-			"No error message provided"
-		};
+		let message = cmd::panic_message(info.payload());
 		println!("Execution failed: {}", message);
-		process::exit(1);
+		cmd::exit(1);
 	}));
 
 	let app = init_app();
 	let matches = app.get_matches();
 
-	// Enable logging in verbose mode.
+	// Enable logging in verbose mode. The log format defaults to text.
+	let json_logs = matches.value_of("log-format") == Some("json");
 	match matches.is_present("verbose") {
-		true => setup_logger(log::LevelFilter::Trace),
-		false => setup_logger(log::LevelFilter::Warn),
+		true => setup_logger(log::LevelFilter::Trace, json_logs),
+		false => setup_logger(log::LevelFilter::Warn, json_logs),
 	}
 
+	cmd::open_output(&matches);
+
 	match matches.subcommand() {
 		("simplicity", Some(m)) => {
 			if execute_builtin(m) {
 				// success
-				process::exit(0);
+				cmd::exit(0);
 			} else {
 				panic!("Subcommand not found: {}", m.subcommand().0);
 			}
@@ -1,12 +1,17 @@
 use elements::hashes::sha256;
-use elements::{dynafed, Block, BlockExtData, BlockHash, BlockHeader, TxMerkleNode, Txid};
+use elements::script::Instruction;
+use elements::{
+	confidential, dynafed, opcodes, AssetId, Block, BlockExtData, BlockHash, BlockHeader,
+	Transaction, TxMerkleNode, Txid,
+};
+use schemars::JsonSchema;
 use serde::{Deserialize, Serialize};
 
 use crate::{GetInfo, HexBytes, Network};
 
 use crate::tx::TransactionInfo;
 
-#[derive(Clone, Default, PartialEq, Eq, Debug, Deserialize, Serialize)]
+#[derive(Clone, Default, PartialEq, Eq, Debug, Deserialize, Serialize, JsonSchema)]
 #[serde(rename_all = "lowercase")]
 pub enum ParamsType {
 	#[default]
@@ -15,21 +20,26 @@ pub enum ParamsType {
 	Full,
 }
 
-#[derive(Clone, PartialEq, Eq, Debug, Default, Deserialize, Serialize)]
+#[derive(Clone, PartialEq, Eq, Debug, Default, Deserialize, Serialize, JsonSchema)]
 pub struct ParamsInfo {
 	pub params_type: ParamsType,
 	// both
+	#[schemars(with = "Option<String>")]
 	pub signblockscript: Option<HexBytes>,
 	pub signblock_witness_limit: Option<u32>,
 	// compact only
 	#[serde(skip_serializing_if = "Option::is_none")]
+	#[schemars(with = "Option<String>")]
 	pub elided_root: Option<sha256::Midstate>,
 	// full only
 	#[serde(skip_serializing_if = "Option::is_none")]
+	#[schemars(with = "Option<String>")]
 	pub fedpeg_program: Option<HexBytes>,
 	#[serde(skip_serializing_if = "Option::is_none")]
+	#[schemars(with = "Option<String>")]
 	pub fedpeg_script: Option<HexBytes>,
 	#[serde(skip_serializing_if = "Option::is_none")]
+	#[schemars(with = "Option<Vec<String>>")]
 	pub extension_space: Option<Vec<HexBytes>>,
 }
 
@@ -57,25 +67,31 @@ impl GetInfo<ParamsInfo> for dynafed::Params {
 	}
 }
 
-#[derive(Clone, PartialEq, Eq, Debug, Deserialize, Serialize)]
+#[derive(Clone, PartialEq, Eq, Debug, Deserialize, Serialize, JsonSchema)]
 pub struct BlockHeaderInfo {
 	#[serde(skip_serializing_if = "Option::is_none")]
+	#[schemars(with = "Option<String>")]
 	pub block_hash: Option<BlockHash>,
 	pub version: u32,
+	#[schemars(with = "String")]
 	pub previous_block_hash: BlockHash,
+	#[schemars(with = "String")]
 	pub merkle_root: TxMerkleNode,
 	pub time: u32,
 	pub height: u32,
 	pub dynafed: bool,
 	#[serde(skip_serializing_if = "Option::is_none")]
+	#[schemars(with = "Option<String>")]
 	pub legacy_challenge: Option<HexBytes>,
 	#[serde(skip_serializing_if = "Option::is_none")]
+	#[schemars(with = "Option<String>")]
 	pub legacy_solution: Option<HexBytes>,
 	#[serde(skip_serializing_if = "Option::is_none")]
 	pub dynafed_current: Option<ParamsInfo>,
 	#[serde(skip_serializing_if = "Option::is_none")]
 	pub dynafed_proposed: Option<ParamsInfo>,
 	#[serde(skip_serializing_if = "Option::is_none")]
+	#[schemars(with = "Option<Vec<String>>")]
 	pub dynafed_witness: Option<Vec<HexBytes>>,
 }
 
@@ -120,15 +136,17 @@ impl GetInfo<BlockHeaderInfo> for BlockHeader {
 	}
 }
 
-#[derive(Clone, PartialEq, Eq, Debug, Deserialize, Serialize)]
+#[derive(Clone, PartialEq, Eq, Debug, Deserialize, Serialize, JsonSchema)]
 pub struct BlockInfo {
 	pub header: BlockHeaderInfo,
 	#[serde(skip_serializing_if = "Option::is_none")]
 	pub transactions: Option<Vec<TransactionInfo>>,
 
 	#[serde(skip_serializing_if = "Option::is_none")]
+	#[schemars(with = "Option<Vec<String>>")]
 	pub txids: Option<Vec<Txid>>,
 	#[serde(skip_serializing_if = "Option::is_none")]
+	#[schemars(with = "Option<Vec<String>>")]
 	pub raw_transactions: Option<Vec<HexBytes>>,
 }
 
@@ -142,3 +160,116 @@ impl GetInfo<BlockInfo> for Block {
 		}
 	}
 }
+
+/// The header of the witness commitment in a coinbase output, as in Bitcoin.
+const WITNESS_COMMITMENT_HEADER: [u8; 4] = [0xaa, 0x21, 0xa9, 0xed];
+
+/// The total explicit value of an asset paid out by a coinbase transaction.
+#[derive(Clone, PartialEq, Eq, Debug, Deserialize, Serialize, JsonSchema)]
+pub struct ClaimedFeeInfo {
+	#[schemars(with = "String")]
+	pub asset: AssetId,
+	pub value: u64,
+}
+
+/// An OP_RETURN output of a coinbase transaction.
+#[derive(Clone, PartialEq, Eq, Debug, Deserialize, Serialize, JsonSchema)]
+pub struct CommitmentInfo {
+	pub vout: u32,
+	/// `witness` for the commitment to the witness merkle root, `unknown` otherwise.
+	#[serde(rename = "type")]
+	pub type_: String,
+	/// The committed hash of a witness commitment, otherwise the data pushed by the output.
+	#[schemars(with = "String")]
+	pub data: HexBytes,
+}
+
+#[derive(Clone, PartialEq, Eq, Debug, Deserialize, Serialize, JsonSchema)]
+pub struct CoinbaseInfo {
+	#[serde(flatten)]
+	pub transaction: TransactionInfo,
+	/// The block height from the scriptSig (BIP34), if it starts with one.
+	pub height: Option<u32>,
+	pub fees_claimed: Vec<ClaimedFeeInfo>,
+	pub commitments: Vec<CommitmentInfo>,
+}
+
+pub struct Coinbase<'a>(pub &'a Transaction);
+
+impl Coinbase<'_> {
+	/// Describe the coinbase transaction, failing if the explicit amounts of an asset add
+	/// up to more than a `u64`, which no valid block claims.
+	pub fn try_get_info(&self, network: Network) -> Result<CoinbaseInfo, String> {
+		let mut fees_claimed = Vec::<ClaimedFeeInfo>::new();
+		let mut commitments = vec![];
+		for (vout, output) in self.0.output.iter().enumerate() {
+			if let (confidential::Asset::Explicit(asset), confidential::Value::Explicit(value)) =
+				(output.asset, output.value)
+			{
+				match fees_claimed.iter_mut().find(|f| f.asset == asset) {
+					Some(fee) => {
+						fee.value = fee.value.checked_add(value).ok_or_else(|| {
+							format!(
+								"the coinbase claims more than {} sat of asset {}",
+								u64::MAX,
+								asset
+							)
+						})?
+					}
+					None => fees_claimed.push(ClaimedFeeInfo {
+						asset,
+						value,
+					}),
+				}
+			}
+			if output.script_pubkey.is_op_return() {
+				let data = output
+					.script_pubkey
+					.instructions()
+					.filter_map(|i| match i {
+						Ok(Instruction::PushBytes(data)) => Some(data),
+						_ => None,
+					})
+					.flatten()
+					.copied()
+					.collect::<Vec<_>>();
+				let (type_, data) = match data.strip_prefix(&WITNESS_COMMITMENT_HEADER[..]) {
+					Some(hash) if hash.len() == 32 => ("witness", hash.to_vec()),
+					_ => ("unknown", data),
+				};
+				commitments.push(CommitmentInfo {
+					vout: vout as u32,
+					type_: type_.to_owned(),
+					data: data.into(),
+				});
+			}
+		}
+
+		Ok(CoinbaseInfo {
+			transaction: self.0.get_info(network),
+			height: self.0.input.first().and_then(|i| bip34_height(&i.script_sig)),
+			fees_claimed,
+			commitments,
+		})
+	}
+}
+
+/// The height pushed at the start of a coinbase scriptSig.
+fn bip34_height(script_sig: &elements::Script) -> Option<u32> {
+	match script_sig.instructions().next()? {
+		Ok(Instruction::PushBytes(data)) if data.len() <= 4 => {
+			Some(data.iter().rev().fold(0, |height, byte| height << 8 | u32::from(*byte)))
+		}
+		Ok(Instruction::Op(op)) => {
+			let op = op.into_u8();
+			if (opcodes::all::OP_PUSHNUM_1.into_u8()..=opcodes::all::OP_PUSHNUM_16.into_u8())
+				.contains(&op)
+			{
+				Some(u32::from(op - opcodes::all::OP_PUSHNUM_1.into_u8() + 1))
+			} else {
+				None
+			}
+		}
+		_ => None,
+	}
+}
@@ -1,10 +1,11 @@
 use elements::confidential::{Asset, Nonce, Value};
 use elements::AssetId;
+use schemars::JsonSchema;
 use serde::{Deserialize, Serialize};
 
 use crate::{GetInfo, HexBytes, Network};
 
-#[derive(Clone, PartialEq, Eq, Debug, Deserialize, Serialize)]
+#[derive(Clone, PartialEq, Eq, Debug, Deserialize, Serialize, JsonSchema)]
 #[serde(rename_all = "lowercase")]
 pub enum ConfidentialType {
 	Null,
@@ -12,13 +13,14 @@ pub enum ConfidentialType {
 	Confidential,
 }
 
-#[derive(Clone, PartialEq, Eq, Debug, Deserialize, Serialize)]
+#[derive(Clone, PartialEq, Eq, Debug, Deserialize, Serialize, JsonSchema)]
 pub struct ConfidentialValueInfo {
 	#[serde(rename = "type")]
 	pub type_: ConfidentialType,
 	#[serde(skip_serializing_if = "Option::is_none")]
 	pub value: Option<u64>,
 	#[serde(skip_serializing_if = "Option::is_none")]
+	#[schemars(with = "Option<String>")]
 	pub commitment: Option<HexBytes>,
 }
 
@@ -42,7 +44,7 @@ impl GetInfo<ConfidentialValueInfo> for Value {
 	}
 }
 
-#[derive(Clone, PartialEq, Eq, Debug, Deserialize, Serialize)]
+#[derive(Clone, PartialEq, Eq, Debug, Deserialize, Serialize, JsonSchema)]
 #[serde(rename_all = "snake_case")]
 pub enum ConfidentialAssetLabel {
 	LiquidBitcoin,
@@ -59,13 +61,15 @@ impl ConfidentialAssetLabel {
 	}
 }
 
-#[derive(Clone, PartialEq, Eq, Debug, Deserialize, Serialize)]
+#[derive(Clone, PartialEq, Eq, Debug, Deserialize, Serialize, JsonSchema)]
 pub struct ConfidentialAssetInfo {
 	#[serde(rename = "type")]
 	pub type_: ConfidentialType,
 	#[serde(skip_serializing_if = "Option::is_none")]
+	#[schemars(with = "Option<String>")]
 	pub asset: Option<AssetId>,
 	#[serde(skip_serializing_if = "Option::is_none")]
+	#[schemars(with = "Option<String>")]
 	pub commitment: Option<HexBytes>,
 	#[serde(skip_serializing_if = "Option::is_none")]
 	pub label: Option<ConfidentialAssetLabel>,
@@ -106,13 +110,15 @@ impl GetInfo<ConfidentialAssetInfo> for AssetId {
 	}
 }
 
-#[derive(Clone, PartialEq, Eq, Debug, Deserialize, Serialize)]
+#[derive(Clone, PartialEq, Eq, Debug, Deserialize, Serialize, JsonSchema)]
 pub struct ConfidentialNonceInfo {
 	#[serde(rename = "type")]
 	pub type_: ConfidentialType,
 	#[serde(skip_serializing_if = "Option::is_none")]
+	#[schemars(with = "Option<String>")]
 	pub nonce: Option<HexBytes>,
 	#[serde(skip_serializing_if = "Option::is_none")]
+	#[schemars(with = "Option<String>")]
 	pub commitment: Option<HexBytes>,
 }
 
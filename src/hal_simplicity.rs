@@ -1,11 +1,15 @@
 // Copyright 2025 Andrew Poelstra
 // SPDX-License-Identifier: CC0-1.0
 
+use std::collections::HashMap;
 use std::sync::Arc;
 
 use simplicity::bitcoin::secp256k1;
+use simplicity::dag::{Dag, DagLike, PostOrderIterItem, SharingTracker};
 use simplicity::jet::Jet;
-use simplicity::{BitIter, CommitNode, DecodeError, ParseError, RedeemNode};
+use simplicity::node::{Commit, Disconnectable, Inner, Marker, Node, Redeem};
+use simplicity::types::arrow::FinalArrow;
+use simplicity::{Amr, BitIter, Cmr, CommitNode, DecodeError, Ihr, ParseError, RedeemNode, Tmr};
 
 /// A representation of a hex or base64-encoded Simplicity program, as seen by
 /// hal-simplicity.
@@ -71,10 +75,218 @@ impl<J: Jet> Program<J> {
 	pub fn redeem_node(&self) -> Option<&RedeemNode<J>> {
 		self.redeem_prog.as_ref().map(Arc::as_ref)
 	}
+
+	/// The nodes of the commitment-time program, in the order and with the
+	/// sharing of its encoding.
+	pub fn nodes(&self) -> Vec<ProgramNode> {
+		ProgramNode::list(&self.commit_prog)
+	}
+
+	/// The nodes of the redemption-time program, if it exists, in the order and
+	/// with the sharing of its encoding.
+	pub fn redeem_nodes(&self) -> Option<Vec<ProgramNode>> {
+		self.redeem_prog.as_deref().map(ProgramNode::list)
+	}
+}
+
+/// A node of an encoded program, referring to its children by index.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct ProgramNode {
+	/// The position of the node in the encoding.
+	pub index: usize,
+	/// The combinator, including the name of jets and the value of words.
+	pub combinator: String,
+	/// The indexes of the children, which always come before the node.
+	pub children: Vec<usize>,
+	pub cmr: Cmr,
+	/// The AMR, or `None` for hidden nodes and commitment-time nodes depending
+	/// on witness data.
+	pub amr: Option<Amr>,
+	/// The IHR, or `None` for hidden nodes and commitment-time nodes depending
+	/// on witness data.
+	pub ihr: Option<Ihr>,
+	/// The source and target types, or `None` for hidden nodes.
+	pub arrow: Option<String>,
+	/// The TMRs of the source and target types, or `None` for hidden nodes.
+	pub tmrs: Option<(Tmr, Tmr)>,
+}
+
+impl ProgramNode {
+	fn list<N: Roots>(program: &Node<N>) -> Vec<ProgramNode> {
+		EncodeNode::Node(program)
+			.post_order_iter::<EncodeSharing<N>>()
+			.map(|item| ProgramNode::from_item(&item))
+			.collect()
+	}
+
+	fn from_item<N: Roots>(item: &PostOrderIterItem<EncodeNode<N>>) -> Self {
+		let children = item.left_index.into_iter().chain(item.right_index).collect();
+		match item.node {
+			EncodeNode::Node(node) => ProgramNode {
+				index: item.index,
+				combinator: node.inner().to_string(),
+				children,
+				cmr: node.cmr(),
+				amr: N::amr(node),
+				ihr: N::ihr(node),
+				arrow: Some(N::arrow(node).to_string()),
+				tmrs: Some((N::arrow(node).source.tmr(), N::arrow(node).target.tmr())),
+			},
+			EncodeNode::Hidden(cmr) => ProgramNode {
+				index: item.index,
+				combinator: "hidden".to_owned(),
+				children,
+				cmr,
+				amr: None,
+				ihr: None,
+				arrow: None,
+				tmrs: None,
+			},
+		}
+	}
+}
+
+/// The nodes of a program with their position in its encoding, in the order
+/// and with the indexes of [`Program::nodes`], leaving out hidden nodes.
+pub fn encoded_nodes<N: Marker>(program: &Node<N>) -> Vec<(usize, &Node<N>)> {
+	EncodeNode::Node(program)
+		.post_order_iter::<EncodeSharing<N>>()
+		.filter_map(|item| match item.node {
+			EncodeNode::Node(node) => Some((item.index, node)),
+			EncodeNode::Hidden(..) => None,
+		})
+		.collect()
+}
+
+/// The Merkle roots and types of the nodes of a kind of program.
+trait Roots: Marker {
+	fn amr(node: &Node<Self>) -> Option<Amr>;
+	fn ihr(node: &Node<Self>) -> Option<Ihr>;
+	fn arrow(node: &Node<Self>) -> &FinalArrow;
+}
+
+impl<J: Jet> Roots for Commit<J> {
+	fn amr(node: &CommitNode<J>) -> Option<Amr> {
+		node.amr()
+	}
+	fn ihr(node: &CommitNode<J>) -> Option<Ihr> {
+		node.ihr()
+	}
+	fn arrow(node: &CommitNode<J>) -> &FinalArrow {
+		node.arrow()
+	}
+}
+
+impl<J: Jet> Roots for Redeem<J> {
+	fn amr(node: &RedeemNode<J>) -> Option<Amr> {
+		Some(node.amr())
+	}
+	fn ihr(node: &RedeemNode<J>) -> Option<Ihr> {
+		Some(node.ihr())
+	}
+	fn arrow(node: &RedeemNode<J>) -> &FinalArrow {
+		node.arrow()
+	}
+}
+
+/// A node as it is encoded, where the pruned branches of assertions are
+/// separate hidden nodes.
+///
+/// This mirrors the private type used by the encoder of rust-simplicity.
+enum EncodeNode<'n, N: Marker> {
+	Node(&'n Node<N>),
+	Hidden(Cmr),
+}
+
+// Derives would require `N: Clone`.
+impl<N: Marker> Clone for EncodeNode<'_, N> {
+	fn clone(&self) -> Self {
+		match *self {
+			EncodeNode::Node(node) => EncodeNode::Node(node),
+			EncodeNode::Hidden(cmr) => EncodeNode::Hidden(cmr),
+		}
+	}
+}
+
+impl<'n, N: Marker> DagLike for EncodeNode<'n, N> {
+	type Node = Self;
+	fn data(&self) -> &Self {
+		self
+	}
+
+	fn as_dag_node(&self) -> Dag<Self> {
+		let node = match *self {
+			EncodeNode::Node(node) => node,
+			EncodeNode::Hidden(..) => return Dag::Nullary,
+		};
+		match node.inner() {
+			Inner::Unit | Inner::Iden | Inner::Fail(..) | Inner::Jet(..) | Inner::Word(..) => {
+				Dag::Nullary
+			}
+			Inner::Witness(..) => Dag::Nullary,
+			Inner::InjL(sub) | Inner::InjR(sub) | Inner::Take(sub) | Inner::Drop(sub) => {
+				Dag::Unary(EncodeNode::Node(sub))
+			}
+			Inner::Comp(left, right) | Inner::Case(left, right) | Inner::Pair(left, right) => {
+				Dag::Binary(EncodeNode::Node(left), EncodeNode::Node(right))
+			}
+			// The right child of a disconnect is only known at redemption.
+			Inner::Disconnect(left, right) => {
+				right.disconnect_dag_ref(left).map(|node| EncodeNode::Node(node))
+			}
+			Inner::AssertL(left, rcmr) => {
+				Dag::Binary(EncodeNode::Node(left), EncodeNode::Hidden(*rcmr))
+			}
+			Inner::AssertR(lcmr, right) => {
+				Dag::Binary(EncodeNode::Hidden(*lcmr), EncodeNode::Node(right))
+			}
+		}
+	}
+}
+
+/// Shares nodes by their sharing ID, and hidden nodes by their CMR, like the
+/// encoder does.
+struct EncodeSharing<N: Marker> {
+	map: HashMap<Result<N::SharingId, Cmr>, usize>,
+}
+
+impl<N: Marker> Default for EncodeSharing<N> {
+	fn default() -> Self {
+		EncodeSharing {
+			map: HashMap::new(),
+		}
+	}
+}
+
+impl<N: Marker> EncodeSharing<N> {
+	fn id(node: &EncodeNode<N>) -> Option<Result<N::SharingId, Cmr>> {
+		match node {
+			EncodeNode::Node(node) => node.sharing_id().map(Ok),
+			EncodeNode::Hidden(cmr) => Some(Err(*cmr)),
+		}
+	}
+}
+
+impl<N: Marker> SharingTracker<EncodeNode<'_, N>> for EncodeSharing<N> {
+	fn record(&mut self, node: &EncodeNode<N>, index: usize) -> Option<usize> {
+		let id = Self::id(node)?;
+		match self.map.get(&id) {
+			Some(&index) => Some(index),
+			None => {
+				self.map.insert(id, index);
+				None
+			}
+		}
+	}
+
+	fn seen_before(&self, node: &EncodeNode<N>) -> Option<usize> {
+		self.map.get(&Self::id(node)?).copied()
+	}
 }
 
 // Stolen from simplicity-webide
-fn unspendable_internal_key() -> secp256k1::XOnlyPublicKey {
+/// The NUMS internal key used for addresses which can only be spent by a Simplicity program.
+pub fn unspendable_internal_key() -> secp256k1::XOnlyPublicKey {
 	secp256k1::XOnlyPublicKey::from_slice(&[
 		0xf5, 0x91, 0x9f, 0xa6, 0x4c, 0xe4, 0x5f, 0x83, 0x06, 0x84, 0x90, 0x72, 0xb2, 0x6c, 0x1b,
 		0xfd, 0xd2, 0x93, 0x7e, 0x6b, 0x81, 0x77, 0x47, 0x96, 0xff, 0x37, 0x2b, 0xd1, 0xeb, 0x53,
@@ -88,20 +300,24 @@ fn script_ver(cmr: simplicity::Cmr) -> (elements::Script, elements::taproot::Lea
 	(script, simplicity::leaf_version())
 }
 
-fn taproot_spend_info(cmr: simplicity::Cmr) -> elements::taproot::TaprootSpendInfo {
+fn taproot_spend_info(
+	cmr: simplicity::Cmr,
+	internal_key: secp256k1::XOnlyPublicKey,
+) -> elements::taproot::TaprootSpendInfo {
 	let builder = elements::taproot::TaprootBuilder::new();
 	let (script, version) = script_ver(cmr);
 	let builder = builder.add_leaf_with_ver(0, script, version).expect("tap tree should be valid");
-	builder
-		.finalize(secp256k1::SECP256K1, unspendable_internal_key())
-		.expect("tap tree should be valid")
+	builder.finalize(secp256k1::SECP256K1, internal_key).expect("tap tree should be valid")
 }
 
-pub fn elements_address(
+/// The taproot address whose only leaf is the program with the given CMR, under
+/// the given internal key.
+pub fn elements_address_with_key(
 	cmr: simplicity::Cmr,
+	internal_key: secp256k1::XOnlyPublicKey,
 	params: &'static elements::AddressParams,
 ) -> elements::Address {
-	let info = taproot_spend_info(cmr);
+	let info = taproot_spend_info(cmr, internal_key);
 	let blinder = None;
 	elements::Address::p2tr(
 		secp256k1::SECP256K1,
@@ -112,6 +328,20 @@ pub fn elements_address(
 	)
 }
 
+pub fn elements_address(
+	cmr: simplicity::Cmr,
+	params: &'static elements::AddressParams,
+) -> elements::Address {
+	elements_address_with_key(cmr, unspendable_internal_key(), params)
+}
+
+/// The taproot control block needed to spend the output of [`elements_address`].
+pub fn control_block(cmr: simplicity::Cmr) -> elements::taproot::ControlBlock {
+	taproot_spend_info(cmr, unspendable_internal_key())
+		.control_block(&script_ver(cmr))
+		.expect("leaf is in the tap tree")
+}
+
 #[cfg(test)]
 mod tests {
 	use super::*;
@@ -153,4 +383,33 @@ mod tests {
 		assert_eq!(prog.amr(), None);
 		assert_eq!(prog.ihr(), None);
 	}
+
+	#[test]
+	fn nodes_follow_encoding() {
+		// A pruned program, whose unused branches are hidden.
+		let b64 = "4ndIosgAAAAL4YijNuIIwCLTArFs1v32PDFCsiTr0+4z/bNPc5Z4vhld8+86AN4cip9kAAAAAfDUCBNrOiGc5Czz/7fGmfzX05uacOSENCf3bkYmhTCJad+bCYugwNAxC1M26AQBvBvWQAAAAD8NgIE4WgwPAAMAHCQahzF419uZZ4aiKDyXDtjsEPdCAa+I0JuklYXa1UCokZLPgGAcSA==";
+		let prog = Program::<simplicity::jet::Elements>::from_str(b64, None).unwrap();
+		let nodes = prog.nodes();
+
+		let bytes = simplicity::base64::Engine::decode(
+			&simplicity::base64::engine::general_purpose::STANDARD,
+			b64,
+		)
+		.unwrap();
+		let len: usize = BitIter::from(&bytes[..]).read_natural(None).unwrap();
+		assert_eq!(nodes.len(), len);
+		assert!(nodes.iter().any(|node| node.combinator == "hidden" && node.arrow.is_none()));
+		for (i, node) in nodes.iter().enumerate() {
+			assert_eq!(node.index, i);
+			assert!(node.children.iter().all(|&child| child < i));
+		}
+		assert_eq!(nodes.last().unwrap().cmr, prog.cmr());
+
+		// With the witness, every node which isn't hidden has an AMR.
+		let prog = Program::<simplicity::jet::Elements>::from_str(b64, Some("00")).unwrap();
+		let nodes = prog.redeem_nodes().unwrap();
+		assert_eq!(nodes.len(), len);
+		assert!(nodes.iter().all(|node| node.amr.is_some() == (node.combinator != "hidden")));
+		assert_eq!(nodes.last().unwrap().amr, prog.amr());
+	}
 }
@@ -3,7 +3,9 @@ pub extern crate simplicity;
 pub mod address;
 pub mod block;
 pub mod hal_simplicity;
+pub mod policy;
 pub mod tx;
+pub mod vault;
 
 pub mod confidential;
 
@@ -11,14 +13,16 @@ pub use elements::bitcoin;
 pub use hal::HexBytes;
 
 use elements::AddressParams;
+use schemars::JsonSchema;
 use serde::{Deserialize, Serialize};
 
 /// Known Elements networks.
-#[derive(Clone, Copy, PartialEq, Eq, Debug, Deserialize, Serialize)]
+#[derive(Clone, Copy, PartialEq, Eq, Debug, Deserialize, Serialize, JsonSchema)]
 #[serde(rename_all = "lowercase")]
 pub enum Network {
 	ElementsRegtest,
 	Liquid,
+	LiquidTestnet,
 }
 
 impl Network {
@@ -27,6 +31,8 @@ impl Network {
 			Some(Network::ElementsRegtest)
 		} else if *params == AddressParams::LIQUID {
 			Some(Network::Liquid)
+		} else if *params == AddressParams::LIQUID_TESTNET {
+			Some(Network::LiquidTestnet)
 		} else {
 			None
 		}
@@ -36,6 +42,7 @@ impl Network {
 		match self {
 			Network::ElementsRegtest => &AddressParams::ELEMENTS,
 			Network::Liquid => &AddressParams::LIQUID,
+			Network::LiquidTestnet => &AddressParams::LIQUID_TESTNET,
 		}
 	}
 }
@@ -0,0 +1,279 @@
+// SPDX-License-Identifier: CC0-1.0
+
+//! Parsing of the Simplicity policy language.
+//!
+//! The language is a small subset of the Miniscript policy language:
+//!
+//! * `pk(KEY)` -- a BIP-340 signature by the given x-only public key
+//! * `older(N)` -- a relative timelock of `N` blocks
+//! * `after(N)` -- an absolute timelock at height `N`, from 1 to 499999999
+//! * `sha256(HASH)` -- the preimage of the given SHA256 hash
+//! * `and(X,Y)`, `or(X,Y)` -- both, respectively one, of two sub-policies
+//! * `thresh(K,X,Y,...)` -- `K` out of a list of sub-policies
+
+use std::fmt;
+use std::str::FromStr;
+use std::sync::Arc;
+
+use elements::bitcoin::secp256k1::XOnlyPublicKey;
+use elements::hashes::sha256;
+use simplicity::Policy;
+
+/// Error parsing a policy expression.
+#[derive(Clone, PartialEq, Eq, Debug)]
+pub enum PolicyParseError {
+	/// The expression ended in the middle of a fragment.
+	UnexpectedEnd,
+	/// An unexpected character was found at the given byte offset.
+	UnexpectedChar(usize, char),
+	/// The fragment name is not part of the policy language.
+	UnknownFragment(String),
+	/// A fragment was given the wrong number of arguments.
+	WrongArgCount {
+		fragment: String,
+		expected: usize,
+		got: usize,
+	},
+	/// A fragment argument could not be parsed.
+	InvalidArgument {
+		fragment: String,
+		arg: String,
+		error: String,
+	},
+	/// A threshold of `k` out of `n` sub-policies with `k` not in `1..=n`.
+	InvalidThreshold(usize, usize),
+}
+
+impl fmt::Display for PolicyParseError {
+	fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+		match self {
+			Self::UnexpectedEnd => f.write_str("unexpected end of policy"),
+			Self::UnexpectedChar(pos, ch) => {
+				write!(f, "unexpected character '{}' at position {}", ch, pos)
+			}
+			Self::UnknownFragment(name) => write!(f, "unknown policy fragment '{}'", name),
+			Self::WrongArgCount {
+				fragment,
+				expected,
+				got,
+			} => write!(f, "'{}' takes {} argument(s) but {} were given", fragment, expected, got),
+			Self::InvalidArgument {
+				fragment,
+				arg,
+				error,
+			} => write!(f, "invalid argument '{}' to '{}': {}", arg, fragment, error),
+			Self::InvalidThreshold(k, n) => {
+				write!(f, "invalid threshold {} of {} sub-policies", k, n)
+			}
+		}
+	}
+}
+
+impl std::error::Error for PolicyParseError {}
+
+/// Whether an absolute locktime is a block height which `CheckLockHeight` can be
+/// satisfied with.
+///
+/// A locktime of 0 is always satisfied, and from 500000000 on it is a timestamp,
+/// so coins locked to either could never be spent.
+pub fn is_lock_height(n: u32) -> bool {
+	n != 0 && elements::locktime::Height::from_consensus(n).is_ok()
+}
+
+/// A policy expression before any interpretation of its fragments.
+struct Tree<'s> {
+	name: &'s str,
+	args: Vec<Tree<'s>>,
+}
+
+impl<'s> Tree<'s> {
+	/// Parses the tree starting at byte offset `pos`, returning it along with the
+	/// offset of the first byte after it.
+	fn parse(s: &'s str, pos: usize) -> Result<(Tree<'s>, usize), PolicyParseError> {
+		let end = s[pos..].find(['(', ')', ',']).map(|i| pos + i).unwrap_or(s.len());
+		let name = s[pos..end].trim();
+		if !s[end..].starts_with('(') {
+			return Ok((
+				Tree {
+					name,
+					args: vec![],
+				},
+				end,
+			));
+		}
+
+		let mut args = vec![];
+		let mut pos = end + 1;
+		loop {
+			let (arg, next) = Tree::parse(s, pos)?;
+			let next = next + s[next..].len() - s[next..].trim_start().len();
+			args.push(arg);
+			match s[next..].chars().next() {
+				Some(',') => pos = next + 1,
+				Some(')') => {
+					return Ok((
+						Tree {
+							name,
+							args,
+						},
+						next + 1,
+					))
+				}
+				Some(ch) => return Err(PolicyParseError::UnexpectedChar(next, ch)),
+				None => return Err(PolicyParseError::UnexpectedEnd),
+			}
+		}
+	}
+
+	fn expect_args(&self, n: usize) -> Result<(), PolicyParseError> {
+		if self.args.len() != n {
+			return Err(PolicyParseError::WrongArgCount {
+				fragment: self.name.to_owned(),
+				expected: n,
+				got: self.args.len(),
+			});
+		}
+		Ok(())
+	}
+
+	/// Parses the single terminal argument of this fragment.
+	fn terminal<T: FromStr>(&self) -> Result<T, PolicyParseError>
+	where
+		T::Err: fmt::Display,
+	{
+		self.expect_args(1)?;
+		let arg = &self.args[0];
+		arg.expect_args(0)?;
+		arg.name.parse().map_err(|e: T::Err| PolicyParseError::InvalidArgument {
+			fragment: self.name.to_owned(),
+			arg: arg.name.to_owned(),
+			error: e.to_string(),
+		})
+	}
+
+	fn to_policy(&self) -> Result<Policy<XOnlyPublicKey>, PolicyParseError> {
+		match self.name {
+			"pk" => Ok(Policy::Key(self.terminal()?)),
+			"older" => Ok(Policy::Older(self.terminal()?)),
+			"after" => {
+				let n = self.terminal()?;
+				if !is_lock_height(n) {
+					return Err(PolicyParseError::InvalidArgument {
+						fragment: self.name.to_owned(),
+						arg: self.args[0].name.to_owned(),
+						error: "expected a block height from 1 to 499999999".to_owned(),
+					});
+				}
+				Ok(Policy::After(n))
+			}
+			"sha256" => Ok(Policy::Sha256(self.terminal::<sha256::Hash>()?)),
+			"and" => {
+				self.expect_args(2)?;
+				Ok(Policy::And {
+					left: Arc::new(self.args[0].to_policy()?),
+					right: Arc::new(self.args[1].to_policy()?),
+				})
+			}
+			"or" => {
+				self.expect_args(2)?;
+				Ok(Policy::Or {
+					left: Arc::new(self.args[0].to_policy()?),
+					right: Arc::new(self.args[1].to_policy()?),
+				})
+			}
+			"thresh" => {
+				let (k, subs) = self.args.split_first().ok_or(PolicyParseError::WrongArgCount {
+					fragment: self.name.to_owned(),
+					expected: 2,
+					got: 0,
+				})?;
+				k.expect_args(0)?;
+				let k: usize = k.name.parse().map_err(|e: std::num::ParseIntError| {
+					PolicyParseError::InvalidArgument {
+						fragment: self.name.to_owned(),
+						arg: k.name.to_owned(),
+						error: e.to_string(),
+					}
+				})?;
+				if k == 0 || k > subs.len() {
+					return Err(PolicyParseError::InvalidThreshold(k, subs.len()));
+				}
+				let subs = subs.iter().map(Tree::to_policy).collect::<Result<_, _>>()?;
+				Ok(Policy::Threshold(k, subs))
+			}
+			name => Err(PolicyParseError::UnknownFragment(name.to_owned())),
+		}
+	}
+}
+
+/// Parses a policy expression such as `or(pk(KEY),and(older(144),sha256(HASH)))`.
+pub fn parse_policy(s: &str) -> Result<Policy<XOnlyPublicKey>, PolicyParseError> {
+	let (tree, end) = Tree::parse(s, 0)?;
+	if let Some(ch) = s[end..].chars().find(|ch| !ch.is_whitespace()) {
+		return Err(PolicyParseError::UnexpectedChar(end, ch));
+	}
+	tree.to_policy()
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+
+	const KEY: &str = "9bef8d556d80e43ae7e0becb3a7e6838b95defe45896ed6075bb9035d06c9964";
+
+	#[test]
+	fn parse_roundtrip() {
+		for s in [
+			format!("pk({})", KEY),
+			"older(144)".to_owned(),
+			"after(1000000)".to_owned(),
+			format!("sha256({})", KEY),
+			format!("and(pk({}),older(10))", KEY),
+			format!("or(pk({}),after(10))", KEY),
+			format!("thresh(2,pk({k}),pk({k}),older(5))", k = KEY),
+		] {
+			assert_eq!(parse_policy(&s).unwrap().to_string(), s);
+		}
+
+		// Whitespace around fragments and arguments is ignored.
+		let spaced = format!(" and( pk({}) , older( 10 ) ) ", KEY);
+		assert_eq!(
+			parse_policy(&spaced).unwrap().to_string(),
+			format!("and(pk({}),older(10))", KEY)
+		);
+	}
+
+	#[test]
+	fn parse_errors() {
+		assert_eq!(parse_policy("foo(1)"), Err(PolicyParseError::UnknownFragment("foo".into())));
+		assert_eq!(parse_policy("older(1"), Err(PolicyParseError::UnexpectedEnd));
+		assert_eq!(parse_policy("older(1))"), Err(PolicyParseError::UnexpectedChar(8, ')')));
+		assert_eq!(
+			parse_policy("thresh(3,older(1),older(2))"),
+			Err(PolicyParseError::InvalidThreshold(3, 2))
+		);
+		assert_eq!(
+			parse_policy("and(older(1))"),
+			Err(PolicyParseError::WrongArgCount {
+				fragment: "and".into(),
+				expected: 2,
+				got: 1,
+			})
+		);
+		assert!(matches!(
+			parse_policy("older(70000)"),
+			Err(PolicyParseError::InvalidArgument { .. })
+		));
+		assert!(matches!(parse_policy("pk(02)"), Err(PolicyParseError::InvalidArgument { .. })));
+		for n in ["0", "500000000"] {
+			assert_eq!(
+				parse_policy(&format!("after({})", n)),
+				Err(PolicyParseError::InvalidArgument {
+					fragment: "after".into(),
+					arg: n.into(),
+					error: "expected a block height from 1 to 499999999".into(),
+				})
+			);
+		}
+	}
+}
@@ -5,6 +5,7 @@ use elements::{
 	TxIn, TxInWitness, TxOut, TxOutWitness, Txid, Wtxid,
 };
 
+use schemars::JsonSchema;
 use serde::{Deserialize, Serialize};
 
 use crate::{GetInfo, HexBytes, Network};
@@ -13,9 +14,11 @@ use crate::confidential::{ConfidentialAssetInfo, ConfidentialNonceInfo, Confiden
 
 const BTCNET: elements::bitcoin::Network = elements::bitcoin::Network::Bitcoin;
 
-#[derive(Clone, PartialEq, Eq, Debug, Deserialize, Serialize)]
+#[derive(Clone, PartialEq, Eq, Debug, Deserialize, Serialize, JsonSchema)]
 pub struct AssetIssuanceInfo {
+	#[schemars(with = "Option<String>")]
 	pub asset_blinding_nonce: Option<HexBytes>,
+	#[schemars(with = "Option<String>")]
 	pub asset_entropy: Option<HexBytes>,
 	pub amount: Option<ConfidentialValueInfo>,
 	pub inflation_keys: Option<ConfidentialValueInfo>,
@@ -32,16 +35,22 @@ impl GetInfo<AssetIssuanceInfo> for AssetIssuance {
 	}
 }
 
-#[derive(Clone, PartialEq, Eq, Debug, Deserialize, Serialize)]
+#[derive(Clone, PartialEq, Eq, Debug, Deserialize, Serialize, JsonSchema)]
 pub struct PeginDataInfo {
 	pub outpoint: String,
 	pub value: u64,
 	pub asset: ConfidentialAssetInfo,
+	#[schemars(with = "String")]
 	pub genesis_hash: bitcoin::BlockHash,
+	#[schemars(with = "String")]
 	pub claim_script: HexBytes,
+	#[schemars(with = "String")]
 	pub mainchain_tx_hex: HexBytes,
+	#[schemars(with = "Option<serde_json::Value>")]
 	pub mainchain_tx: Option<hal::tx::TransactionInfo>,
+	#[schemars(with = "String")]
 	pub merkle_proof: HexBytes,
+	#[schemars(with = "String")]
 	pub referenced_block: bitcoin::BlockHash,
 }
 
@@ -66,13 +75,17 @@ impl<'tx> GetInfo<PeginDataInfo> for PeginData<'tx> {
 	}
 }
 
-#[derive(Clone, PartialEq, Eq, Debug, Deserialize, Serialize)]
+#[derive(Clone, PartialEq, Eq, Debug, Deserialize, Serialize, JsonSchema)]
 pub struct InputWitnessInfo {
+	#[schemars(with = "Option<String>")]
 	pub amount_rangeproof: Option<HexBytes>,
+	#[schemars(with = "Option<String>")]
 	pub inflation_keys_rangeproof: Option<HexBytes>,
 	#[serde(skip_serializing_if = "Option::is_none")]
+	#[schemars(with = "Option<Vec<String>>")]
 	pub script_witness: Option<Vec<HexBytes>>,
 	#[serde(skip_serializing_if = "Option::is_none")]
+	#[schemars(with = "Option<Vec<String>>")]
 	pub pegin_witness: Option<Vec<HexBytes>>,
 }
 
@@ -101,8 +114,9 @@ impl GetInfo<InputWitnessInfo> for TxInWitness {
 	}
 }
 
-#[derive(Clone, PartialEq, Eq, Debug, Deserialize, Serialize)]
+#[derive(Clone, PartialEq, Eq, Debug, Deserialize, Serialize, JsonSchema)]
 pub struct InputScriptInfo {
+	#[schemars(with = "Option<String>")]
 	pub hex: Option<HexBytes>,
 	pub asm: Option<String>,
 }
@@ -118,9 +132,10 @@ impl<'a> GetInfo<InputScriptInfo> for InputScript<'a> {
 	}
 }
 
-#[derive(Clone, PartialEq, Eq, Debug, Deserialize, Serialize)]
+#[derive(Clone, PartialEq, Eq, Debug, Deserialize, Serialize, JsonSchema)]
 pub struct InputInfo {
 	pub prevout: Option<String>,
+	#[schemars(with = "Option<String>")]
 	pub txid: Option<Txid>,
 	pub vout: Option<u32>,
 	pub script_sig: Option<InputScriptInfo>,
@@ -135,6 +150,24 @@ pub struct InputInfo {
 
 	#[serde(skip_serializing_if = "Option::is_none")]
 	pub pegin_data: Option<PeginDataInfo>,
+	/// The label of the spent scriptPubKey in an address book, if known.
+	#[serde(skip_serializing_if = "Option::is_none")]
+	pub label: Option<String>,
+	/// The result of checking the signature of the input, if requested.
+	#[serde(skip_serializing_if = "Option::is_none")]
+	pub signature_check: Option<SignatureCheckInfo>,
+}
+
+#[derive(Clone, PartialEq, Eq, Debug, Deserialize, Serialize, JsonSchema)]
+pub struct SignatureCheckInfo {
+	/// The type of the spent output, e.g. p2wpkh.
+	#[serde(rename = "type")]
+	pub type_: String,
+	/// Whether the signature is valid, or `None` if inputs of this type aren't checked.
+	pub valid: Option<bool>,
+	/// Why the signature is invalid or wasn't checked.
+	#[serde(skip_serializing_if = "Option::is_none")]
+	pub error: Option<String>,
 }
 
 impl GetInfo<InputInfo> for TxIn {
@@ -160,16 +193,21 @@ impl GetInfo<InputInfo> for TxIn {
 				None
 			},
 			pegin_data: self.pegin_data().map(|p| p.get_info(network)),
+			label: None,
+			signature_check: None,
 		}
 	}
 }
 
-#[derive(Clone, PartialEq, Eq, Debug, Deserialize, Serialize)]
+#[derive(Clone, PartialEq, Eq, Debug, Deserialize, Serialize, JsonSchema)]
 pub struct PegoutDataInfo {
 	pub value: u64,
 	pub asset: ConfidentialAssetInfo,
+	#[schemars(with = "String")]
 	pub genesis_hash: bitcoin::BlockHash,
+	#[schemars(with = "OutputScriptInfo")]
 	pub script_pub_key: hal::tx::OutputScriptInfo,
+	#[schemars(with = "Vec<String>")]
 	pub extra_data: Vec<HexBytes>,
 }
 
@@ -188,9 +226,11 @@ impl<'tx> GetInfo<PegoutDataInfo> for PegoutData<'tx> {
 	}
 }
 
-#[derive(Clone, PartialEq, Eq, Debug, Deserialize, Serialize)]
+#[derive(Clone, PartialEq, Eq, Debug, Deserialize, Serialize, JsonSchema)]
 pub struct OutputWitnessInfo {
+	#[schemars(with = "Option<String>")]
 	pub surjection_proof: Option<HexBytes>,
+	#[schemars(with = "Option<String>")]
 	pub rangeproof: Option<HexBytes>,
 }
 
@@ -206,13 +246,15 @@ impl GetInfo<OutputWitnessInfo> for TxOutWitness {
 	}
 }
 
-#[derive(Clone, PartialEq, Eq, Debug, Deserialize, Serialize)]
+#[derive(Clone, PartialEq, Eq, Debug, Deserialize, Serialize, JsonSchema)]
 pub struct OutputScriptInfo {
+	#[schemars(with = "Option<String>")]
 	pub hex: Option<HexBytes>,
 	pub asm: Option<String>,
 	#[serde(skip_serializing_if = "Option::is_none", rename = "type")]
 	pub type_: Option<String>,
 	#[serde(skip_serializing_if = "Option::is_none")]
+	#[schemars(with = "Option<String>")]
 	pub address: Option<Address>,
 }
 
@@ -246,7 +288,7 @@ impl<'a> GetInfo<OutputScriptInfo> for OutputScript<'a> {
 	}
 }
 
-#[derive(Clone, PartialEq, Eq, Debug, Deserialize, Serialize)]
+#[derive(Clone, PartialEq, Eq, Debug, Deserialize, Serialize, JsonSchema)]
 pub struct OutputInfo {
 	pub script_pub_key: Option<OutputScriptInfo>,
 
@@ -258,6 +300,13 @@ pub struct OutputInfo {
 
 	#[serde(skip_serializing_if = "Option::is_none")]
 	pub pegout_data: Option<PegoutDataInfo>,
+	/// Data to embed in an OP_RETURN output, in hex or as `utf8:<text>`.
+	/// Only used when creating transactions.
+	#[serde(skip_serializing_if = "Option::is_none")]
+	pub data: Option<String>,
+	/// The label of the scriptPubKey in an address book, if known.
+	#[serde(skip_serializing_if = "Option::is_none")]
+	pub label: Option<String>,
 }
 
 impl GetInfo<OutputInfo> for TxOut {
@@ -279,19 +328,34 @@ impl GetInfo<OutputInfo> for TxOut {
 			witness: Some(self.witness.get_info(network)),
 			is_fee: Some(is_fee),
 			pegout_data: self.pegout_data().map(|p| p.get_info(network)),
+			data: None,
+			label: None,
 		}
 	}
 }
 
-#[derive(Clone, PartialEq, Eq, Debug, Deserialize, Serialize)]
+/// The serialized form of [`elements::LockTime`], which doesn't implement [`JsonSchema`].
+#[derive(JsonSchema)]
+#[schemars(rename = "LockTime")]
+#[allow(dead_code)]
+enum LockTimeDef {
+	Blocks(u32),
+	Seconds(u32),
+}
+
+#[derive(Clone, PartialEq, Eq, Debug, Deserialize, Serialize, JsonSchema)]
 pub struct TransactionInfo {
+	#[schemars(with = "Option<String>")]
 	pub txid: Option<Txid>,
+	#[schemars(with = "Option<String>")]
 	pub wtxid: Option<Wtxid>,
+	#[schemars(with = "Option<String>")]
 	pub hash: Option<Wtxid>,
 	pub size: Option<usize>,
 	pub weight: Option<usize>,
 	pub vsize: Option<usize>,
 	pub version: Option<u32>,
+	#[schemars(with = "Option<LockTimeDef>")]
 	pub locktime: Option<elements::LockTime>,
 	pub inputs: Option<Vec<InputInfo>>,
 	pub outputs: Option<Vec<OutputInfo>>,
@@ -0,0 +1,314 @@
+// SPDX-License-Identifier: CC0-1.0
+
+//! A covenant which sends coins to a fixed destination, with a recovery path.
+//!
+//! The program has two spend paths, picked by a witness selector bit:
+//!
+//! * `0` -- the covenant path: anyone may spend the coins, as long as the spending
+//!   transaction has exactly one input and exactly two outputs, the first of which pays
+//!   the coins' asset to the fixed destination scriptPubKey and the second of which is
+//!   a fee output. At most the maximum fee may be missing from the amount paid to the
+//!   destination. Both the coins and the first output must have an explicit asset and
+//!   amount.
+//! * `1` -- the recovery path: the recovery key may spend the coins anywhere once
+//!   they are older than the recovery delay.
+
+use std::sync::Arc;
+
+use elements::bitcoin::secp256k1::XOnlyPublicKey;
+use elements::hashes::{sha256, Hash};
+use simplicity::jet::Elements;
+use simplicity::node::{CoreConstructible, JetConstructible, WitnessConstructible};
+use simplicity::{types, CommitNode, ConstructNode, FailEntropy, Word};
+
+type Node = Arc<ConstructNode<Elements>>;
+
+/// `verify(comp(input, bexp))`.
+fn verify_bexp(input: &Node, bexp: Elements) -> Node {
+	let ctx = input.inference_context();
+	let computed = Node::comp(input, &Node::jet(ctx, bexp)).expect("consistent types");
+	Node::comp(&computed, &Node::jet(ctx, Elements::Verify)).expect("consistent types")
+}
+
+/// Unwraps the right side of the sum returned by `node`, failing on the left side.
+///
+/// This unwraps optional values as well as explicit assets and amounts.
+fn unwrap_right(node: &Node) -> Node {
+	let ctx = node.inference_context();
+	let pair = Node::pair(node, &Node::unit(ctx)).expect("consistent types");
+	let unwrap = Node::case(&Node::fail(ctx, FailEntropy::ZERO), &Node::take(&Node::iden(ctx)))
+		.expect("consistent types");
+	Node::comp(&pair, &unwrap).expect("consistent types")
+}
+
+/// Applies `jet` to the given constant and unwraps the optional result, failing on `None`.
+fn unwrap_jet(ctx: &types::Context, jet: Elements, arg: Word) -> Node {
+	let option =
+		Node::comp(&Node::const_word(ctx, arg), &Node::jet(ctx, jet)).expect("consistent types");
+	unwrap_right(&option)
+}
+
+/// The second element of the pair returned by `node`.
+fn second(node: &Node) -> Node {
+	let ctx = node.inference_context();
+	Node::comp(node, &Node::drop_(&Node::iden(ctx))).expect("consistent types")
+}
+
+/// Checks that output `index` pays the asset of the current input, and at most `max_fee`
+/// less than its amount.
+fn output_amount_is_current(ctx: &types::Context, index: u32, max_fee: u64) -> Node {
+	let current_asset = unwrap_right(&Node::jet(ctx, Elements::CurrentAsset));
+	let output_asset = unwrap_right(&unwrap_jet(ctx, Elements::OutputAsset, Word::u32(index)));
+	let same_asset = verify_bexp(
+		&Node::pair(&current_asset, &output_asset).expect("consistent types"),
+		Elements::Eq256,
+	);
+
+	let current_amount = unwrap_right(&second(&Node::jet(ctx, Elements::CurrentAmount)));
+	let output_amount =
+		unwrap_right(&second(&unwrap_jet(ctx, Elements::OutputAmount, Word::u32(index))));
+	let not_more = verify_bexp(
+		&Node::pair(&output_amount, &current_amount).expect("consistent types"),
+		Elements::Le64,
+	);
+	let missing = second(
+		&Node::comp(
+			&Node::pair(&current_amount, &output_amount).expect("consistent types"),
+			&Node::jet(ctx, Elements::Subtract64),
+		)
+		.expect("consistent types"),
+	);
+	let bounded_fee = verify_bexp(
+		&Node::pair(&missing, &Node::const_word(ctx, Word::u64(max_fee)))
+			.expect("consistent types"),
+		Elements::Le64,
+	);
+
+	let checks = Node::comp(&same_asset, &not_more).expect("consistent types");
+	Node::comp(&checks, &bounded_fee).expect("consistent types")
+}
+
+/// Checks that output `index` has the scriptPubKey with the given SHA256 hash.
+fn output_script_is(ctx: &types::Context, index: u32, script_hash: sha256::Hash) -> Node {
+	let expected = Node::const_word(ctx, Word::u256(script_hash.to_byte_array()));
+	let actual = unwrap_jet(ctx, Elements::OutputScriptHash, Word::u32(index));
+	verify_bexp(&Node::pair(&expected, &actual).expect("consistent types"), Elements::Eq256)
+}
+
+/// The covenant path, which constrains the inputs and outputs of the spending transaction.
+///
+/// The coins must be the only input, since the checks of several coins under the same
+/// covenant would all be satisfied by the same destination output.
+fn covenant(ctx: &types::Context, destination: &elements::Script, max_fee: u64) -> Node {
+	let num_inputs =
+		Node::pair(&Node::const_word(ctx, Word::u32(1)), &Node::jet(ctx, Elements::NumInputs))
+			.expect("consistent types");
+	let one_input = verify_bexp(&num_inputs, Elements::Eq32);
+	let num_outputs =
+		Node::pair(&Node::const_word(ctx, Word::u32(2)), &Node::jet(ctx, Elements::NumOutputs))
+			.expect("consistent types");
+	let two_outputs = verify_bexp(&num_outputs, Elements::Eq32);
+	let pays_destination = output_script_is(ctx, 0, sha256::Hash::hash(destination.as_bytes()));
+	let pays_amount = output_amount_is_current(ctx, 0, max_fee);
+	let is_fee = unwrap_jet(ctx, Elements::OutputIsFee, Word::u32(1));
+	let pays_fee =
+		Node::comp(&is_fee, &Node::jet(ctx, Elements::Verify)).expect("consistent types");
+
+	let left = Node::comp(&one_input, &two_outputs).expect("consistent types");
+	let left = Node::comp(&left, &pays_destination).expect("consistent types");
+	let left = Node::comp(&left, &pays_amount).expect("consistent types");
+	Node::comp(&left, &pays_fee).expect("consistent types")
+}
+
+/// The recovery path, a signature check after a relative timelock.
+fn recovery(ctx: &types::Context, key: &XOnlyPublicKey, delay: u16) -> Node {
+	let older = Node::comp(
+		&Node::const_word(ctx, Word::u16(delay)),
+		&Node::jet(ctx, Elements::CheckLockDistance),
+	)
+	.expect("consistent types");
+
+	let const_key = Node::const_word(ctx, Word::u256(key.serialize()));
+	let key_msg =
+		Node::pair(&const_key, &Node::jet(ctx, Elements::SigAllHash)).expect("consistent types");
+	let key_msg_sig = Node::pair(&key_msg, &Node::witness(ctx, None)).expect("consistent types");
+	let signature = Node::comp(&key_msg_sig, &Node::jet(ctx, Elements::Bip0340Verify))
+		.expect("consistent types");
+
+	Node::comp(&older, &signature).expect("consistent types")
+}
+
+/// Builds the vault program paying to `destination`, less a fee of at most `max_fee`,
+/// recoverable by `recovery_key` once the coins are `delay` blocks old.
+///
+/// The witness consists of the selector bit followed by, on the recovery path, the
+/// BIP-340 signature of the recovery key.
+pub fn vault_program(
+	destination: &elements::Script,
+	max_fee: u64,
+	recovery_key: &XOnlyPublicKey,
+	delay: u16,
+) -> Arc<CommitNode<Elements>> {
+	let ctx = types::Context::new();
+	let selector =
+		Node::pair(&Node::witness(&ctx, None), &Node::unit(&ctx)).expect("consistent types");
+	let paths = Node::case(
+		&Node::drop_(&covenant(&ctx, destination, max_fee)),
+		&Node::drop_(&recovery(&ctx, recovery_key, delay)),
+	)
+	.expect("consistent types");
+	let program = Node::comp(&selector, &paths).expect("consistent types");
+	program.finalize_types().expect("vault program has sound types")
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+
+	use elements::{confidential, AssetId, BlockHash, OutPoint, Script, Transaction, TxIn, TxOut};
+	use simplicity::jet::elements::{ElementsEnv, ElementsUtxo};
+	use simplicity::node::SimpleFinalizer;
+	use simplicity::{BitMachine, Value};
+
+	const KEY: &str = "9bef8d556d80e43ae7e0becb3a7e6838b95defe45896ed6075bb9035d06c9964";
+
+	fn output(script_pubkey: Script, value: u64) -> TxOut {
+		output_of(AssetId::default(), script_pubkey, value)
+	}
+
+	fn output_of(asset: AssetId, script_pubkey: Script, value: u64) -> TxOut {
+		TxOut {
+			asset: confidential::Asset::Explicit(asset),
+			value: confidential::Value::Explicit(value),
+			nonce: confidential::Nonce::Null,
+			script_pubkey,
+			witness: Default::default(),
+		}
+	}
+
+	/// Runs the covenant path of the vault, with a maximum fee of 100, against a
+	/// transaction with the given outputs spending 1000 of the default asset.
+	fn spend_covenant(destination: &Script, outputs: Vec<TxOut>) -> bool {
+		spend_covenant_inputs(destination, 1, outputs)
+	}
+
+	/// Like [`spend_covenant`], with `inputs` vault coins of 1000 of the default asset
+	/// spent by the transaction, the first of which runs the program.
+	fn spend_covenant_inputs(destination: &Script, inputs: u32, outputs: Vec<TxOut>) -> bool {
+		let program = vault_program(destination, 100, &KEY.parse().unwrap(), 144);
+		let cmr = program.cmr();
+		let tx = Transaction {
+			version: 2,
+			lock_time: elements::LockTime::ZERO,
+			input: (0..inputs)
+				.map(|vout| TxIn {
+					previous_output: OutPoint::new(elements::Txid::all_zeros(), vout),
+					..Default::default()
+				})
+				.collect(),
+			output: outputs,
+		};
+		let utxos = (0..inputs)
+			.map(|_| ElementsUtxo {
+				script_pubkey: crate::hal_simplicity::elements_address(
+					cmr,
+					&elements::AddressParams::LIQUID,
+				)
+				.script_pubkey(),
+				asset: confidential::Asset::Explicit(AssetId::default()),
+				value: confidential::Value::Explicit(1000),
+			})
+			.collect();
+		let env = ElementsEnv::new(
+			Arc::new(tx),
+			utxos,
+			0,
+			cmr,
+			crate::hal_simplicity::control_block(cmr),
+			None,
+			BlockHash::all_zeros(),
+		);
+
+		let redeem =
+			program.finalize(&mut SimpleFinalizer::new(std::iter::once(Value::u1(0)))).unwrap();
+		let redeem = match redeem.prune(&env) {
+			Ok(redeem) => redeem,
+			Err(_) => return false,
+		};
+		let mut mac = BitMachine::for_program(&redeem).unwrap();
+		mac.exec(&redeem, &env).is_ok()
+	}
+
+	#[test]
+	fn covenant_path() {
+		let destination = Script::from(vec![0x51]);
+		let other = Script::from(vec![0x52]);
+
+		assert!(spend_covenant(
+			&destination,
+			vec![output(destination.clone(), 900), output(Script::new(), 100)]
+		));
+		// Wrong destination
+		assert!(!spend_covenant(
+			&destination,
+			vec![output(other.clone(), 900), output(Script::new(), 100)]
+		));
+		// Second output is not a fee
+		assert!(!spend_covenant(
+			&destination,
+			vec![output(destination.clone(), 900), output(other.clone(), 100)]
+		));
+		// Extra output
+		assert!(!spend_covenant(
+			&destination,
+			vec![output(destination.clone(), 800), output(other, 100), output(Script::new(), 100)]
+		));
+		// A second vault coin, whose value would go to the fee
+		assert!(!spend_covenant_inputs(
+			&destination,
+			2,
+			vec![output(destination.clone(), 900), output(Script::new(), 1100)]
+		));
+	}
+
+	#[test]
+	fn covenant_amount() {
+		let destination = Script::from(vec![0x51]);
+
+		// The whole amount
+		assert!(spend_covenant(
+			&destination,
+			vec![output(destination.clone(), 1000), output(Script::new(), 100)]
+		));
+		// More than the maximum fee missing
+		assert!(!spend_covenant(
+			&destination,
+			vec![output(destination.clone(), 899), output(Script::new(), 101)]
+		));
+		// A dust amount, with the rest as fee
+		assert!(!spend_covenant(
+			&destination,
+			vec![output(destination.clone(), 1), output(Script::new(), 999)]
+		));
+		// More than the input amount
+		assert!(!spend_covenant(
+			&destination,
+			vec![output(destination.clone(), 1001), output(Script::new(), 100)]
+		));
+		// Another asset
+		let other_asset = AssetId::from_slice(&[1; 32]).unwrap();
+		assert!(!spend_covenant(
+			&destination,
+			vec![output_of(other_asset, destination.clone(), 900), output(Script::new(), 100)]
+		));
+		// A confidential amount
+		let mut confidential = output(destination.clone(), 900);
+		confidential.value = confidential::Value::from_commitment(&[
+			0x08, 0x3d, 0x1e, 0x4f, 0x7b, 0x3a, 0x0a, 0x4f, 0x6d, 0x0b, 0x1e, 0x8e, 0x29, 0x13,
+			0x12, 0x5b, 0x20, 0xa2, 0x37, 0x93, 0x4c, 0x63, 0x42, 0x08, 0x79, 0x10, 0x40, 0x3f,
+			0x63, 0x14, 0x0f, 0xc0, 0x5f,
+		])
+		.unwrap();
+		assert!(!spend_covenant(&destination, vec![confidential, output(Script::new(), 100)]));
+	}
+}
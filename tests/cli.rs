@@ -88,13 +88,17 @@ fn cli_empty() {
 hal-simplicity 0.1.0
 
 USAGE:
-    hal [FLAGS] <SUBCOMMAND>
+    hal [FLAGS] [OPTIONS] <SUBCOMMAND>
 
 FLAGS:
     -h, --help       Prints help information
     -V, --version    Prints version information
     -v, --verbose    print verbose logging output to stderr
 
+OPTIONS:
+        --log-format <log-format>    the format of the logging output [possible values: text, json]
+        --out <out>                  write the output to the given file instead of stdout, gzipped if it ends in .gz
+
 SUBCOMMANDS:
     help          Prints this message or the help of the given subcommand(s)
     simplicity    hal-simplicity -- a Simplicity extension of hal
@@ -108,13 +112,17 @@ fn cli_help() {
 hal-simplicity 0.1.0
 
 USAGE:
-    hal [FLAGS] <SUBCOMMAND>
+    hal [FLAGS] [OPTIONS] <SUBCOMMAND>
 
 FLAGS:
     -h, --help       Prints help information
     -V, --version    Prints version information
     -v, --verbose    print verbose logging output to stderr
 
+OPTIONS:
+        --log-format <log-format>    the format of the logging output [possible values: text, json]
+        --out <out>                  write the output to the given file instead of stdout, gzipped if it ends in .gz
+
 SUBCOMMANDS:
     help          Prints this message or the help of the given subcommand(s)
     simplicity    hal-simplicity -- a Simplicity extension of hal
@@ -133,13 +141,52 @@ fn cli_bad_flag() {
 error: Found argument '-?' which wasn't expected, or isn't valid in this context
 
 USAGE:
-    hal [FLAGS] <SUBCOMMAND>
+    hal [FLAGS] [OPTIONS] <SUBCOMMAND>
 
 For more information try --help
 ",
 	);
 }
 
+#[test]
+fn cli_out() {
+	use std::io::Read;
+
+	let program = "ztM33xqq2wHIdc/BfZZ0/NBxcrvfyLEt2sDrdyBroNkyycFDijYwEA==";
+	let output =
+		self_command().args(["simplicity", "simplicity", "roots", program]).output().unwrap();
+	let expected = String::from_utf8(output.stdout).unwrap();
+
+	// The output goes to the file instead of stdout, and the option can come anywhere.
+	let path = std::env::temp_dir().join(format!("hal-simplicity-out-{}.json", std::process::id()));
+	let path_str = path.to_str().unwrap();
+	assert_cmd(&["simplicity", "--out", path_str, "simplicity", "roots", program], "", "");
+	assert_eq!(std::fs::read_to_string(&path).unwrap(), expected);
+	assert_cmd(
+		&["simplicity", "simplicity", "roots", program, "--out", path_str, "-v"],
+		"",
+		format!(
+			"Assuming base64 input for 'program'.\nThe program has witness nodes, pass the witness to compute its AMR and IHR.\nWrote 247 bytes of output to {}.\n",
+			path_str
+		),
+	);
+	std::fs::remove_file(&path).unwrap();
+
+	// A .gz file is compressed.
+	let path =
+		std::env::temp_dir().join(format!("hal-simplicity-out-{}.json.gz", std::process::id()));
+	assert_cmd(
+		&["simplicity", "simplicity", "roots", program, "--out", path.to_str().unwrap()],
+		"",
+		"",
+	);
+	let file = std::fs::File::open(&path).unwrap();
+	let mut decompressed = String::new();
+	flate2::read::GzDecoder::new(file).read_to_string(&mut decompressed).unwrap();
+	assert_eq!(decompressed, expected);
+	std::fs::remove_file(&path).unwrap();
+}
+
 #[test]
 fn cli_simplicity() {
 	// FIXME where does the initial hal-simplicity come from? Also there is a trailing
@@ -149,19 +196,34 @@ hal-simplicity
 hal-simplicity -- a Simplicity extension of hal
 
 USAGE:
-    hal simplicity [FLAGS] <SUBCOMMAND>
+    hal simplicity [FLAGS] [OPTIONS] <SUBCOMMAND>
 
 FLAGS:
     -h, --help       Prints help information
     -V, --version    Prints version information
     -v, --verbose    print verbose logging output to stderr
 
+OPTIONS:
+        --log-format <log-format>    the format of the logging output [possible values: text, json]
+        --out <out>                  write the output to the given file instead of stdout, gzipped if it ends in .gz
+
 SUBCOMMANDS:
     address       work with addresses
+    asset         issued asset tools
+    audit         inspect audit logs written with --audit-log
     block         manipulate blocks
+    doctor        run self-checks of the cryptography, known vectors and connectivity
+    fee           fee estimation
+    info          report the version and capabilities of hal-simplicity
     keypair       manipulate private and public keys
+    pegin         peg Bitcoin into an Elements chain
+    schema        print the JSON schemas of the outputs of the commands
+    session       collaborative signing of Simplicity spends
     simplicity    manipulate Simplicity programs
     tx            manipulate transactions
+    vectors       generate deterministic test vectors for cross-checking other implementations
+    wallet        watch-only wallet tools
+    watch         print the transactions and blocks of an Elements node as NDJSON as they arrive
 ";
 	assert_cmd(&["simplicity"], "", expected_help);
 	assert_cmd(&["simplicity", "-h"], expected_help, "");
@@ -177,12 +239,16 @@ hal-simplicity-address
 work with addresses
 
 USAGE:
-    hal simplicity address [FLAGS] <SUBCOMMAND>
+    hal simplicity address [FLAGS] [OPTIONS] <SUBCOMMAND>
 
 FLAGS:
     -h, --help       Prints help information
     -v, --verbose    print verbose logging output to stderr
 
+OPTIONS:
+        --log-format <log-format>    the format of the logging output [possible values: text, json]
+        --out <out>                  write the output to the given file instead of stdout, gzipped if it ends in .gz
+
 SUBCOMMANDS:
     create     create addresses
     inspect    inspect addresses
@@ -205,14 +271,23 @@ USAGE:
 FLAGS:
     -r, --elementsregtest    run in elementsregtest mode
     -h, --help               Prints help information
+        --keep-going         with --ndjson, print an error record for items that fail and continue with the rest
         --liquid             run in liquid mode
+        --liquidtestnet      run in liquidtestnet mode
+        --ndjson             print one compact JSON object per line for each item instead of an array
+        --no-newline         don't print a trailing newline after a raw value
     -v, --verbose            print verbose logging output to stderr
     -y, --yaml               print output in YAML instead of JSON
 
 OPTIONS:
-        --blinder <blinder>    a blinding pubkey in hex
-        --pubkey <pubkey>      a public key in hex
-        --script <script>      a script in hex
+        --blinder <blinder>          a blinding pubkey in hex
+        --log-format <log-format>    the format of the logging output [possible values: text, json]
+        --out <out>                  write the output to the given file instead of stdout, gzipped if it ends in .gz
+    -o, --output <output>            the output format (see `schema --proto` for the protobuf messages) [possible
+                                     values: json, yaml, protobuf]
+        --pubkey <pubkey>...         a public key in hex; may be given several times
+        --raw-value <raw-value>      print only the given output field (e.g. txid) as a raw value
+        --script <script>...         a script in hex; may be given several times
 ";
 	// newline not escaped v
 	// FIXME yes, you can, with a script rather than pubkey. Also the script is not
@@ -449,22 +524,11 @@ For more information try --help
 }"#,
 		"",
 	);
-	// Check that all three things are allowed only once
+	// Check that pubkeys and scripts may be given several times, but not blinders
 	assert_cmd(
-		&[
-			"simplicity", "address", "create",
-			"--pubkey", "40",
-			"--pubkey", "20"
-		],
+		&["simplicity", "address", "create", "--pubkey", "40", "--pubkey", "20"],
+		"Execution failed: invalid pubkey: InvalidHexLength(2)\n",
 		"",
-		"\
-error: The argument '--pubkey <pubkey>' was provided more than once, but cannot be used multiple times
-
-USAGE:
-    hal simplicity address create --pubkey <pubkey>
-
-For more information try --help
-",
 	);
 	assert_cmd(
 		&[
@@ -483,20 +547,20 @@ For more information try --help
 ",
 	);
 	assert_cmd(
-		&[
-			"simplicity", "address", "create",
-			"--script", "40",
-			"--script", "20"
-		],
+		&["simplicity", "address", "create", "--script", "40", "--script", "20"],
+		r#"[
+  {
+    "p2sh": "XKLW7rD7tEnddSzwsHfg8rZa3a8wLTuEts",
+    "p2wsh": "ert1qcdjplp2y6lqz7dvqkp7qlxy87rr2yll44vw5503fetce0n7znxhqtj2wee",
+    "p2shwsh": "XVvMsyprXnwTCUxQZ4RQpkQYg5bBqYSofS"
+  },
+  {
+    "p2sh": "XHpNKwgaMugHU1CqL6Ftjhq3uZpYdTqBZL",
+    "p2wsh": "ert1qx6570uwftwp0lwvhg0svt3xwjhvrexjrp2k9n7zw709l4ds52p5qzq895x",
+    "p2shwsh": "XQkw78ePHfKSKuGn1iwXFntvYwCLwrVXzH"
+  }
+]"#,
 		"",
-		"\
-error: The argument '--script <script>' was provided more than once, but cannot be used multiple times
-
-USAGE:
-    hal simplicity address create --script <script>
-
-For more information try --help
-",
 	);
 
 	// Test --yaml flag changes output format
@@ -563,6 +627,22 @@ For more information try --help
 		"",
 	);
 
+	// Test --liquidtestnet flag
+	assert_cmd(
+		&[
+			"simplicity",
+			"address",
+			"create",
+			"--pubkey",
+			"0200000000000000000000003b78ce563f89a0ed9414f5aa28ad0d96d6795f9c63",
+			"--liquidtestnet",
+			"--raw-value",
+			"p2wpkh",
+		],
+		"tex1qr7z8s0phhs4v4v968cmhu2jcemkyllt0hve2eu\n",
+		"",
+	);
+
 	// Test -r flag (short form of --elementsregtest)
 	assert_cmd(
 		&[
@@ -645,6 +725,23 @@ For more information try --help
 }"#,
 		"",
 	);
+
+	assert_cmd(
+		&[
+			"simplicity",
+			"address",
+			"create",
+			"--ndjson",
+			"--pubkey",
+			"0279be667ef9dcbbac55a06295ce870b07029bfcdb2dce28d959f2815b16f81798",
+			"--pubkey",
+			"02c6047f9441ed7d6d3045406e95c07cd85c778e4b8cef3ca7abac09b95c709ee5",
+		],
+		r#"{"p2pkh":"2dk71wfwKDTkfLAxLyNPDnKNs2ezE7bDLRp","p2wpkh":"ert1qw508d6qejxtdg4y5r3zarvary0c5xw7kuu73e0","p2shwpkh":"XUaZ29e3pyvqxY8LuV7pQsqQhbQL4rWrHb"}
+{"p2pkh":"2da375Y4q8r6d8HtvdAreEb81rYBgLiwiQm","p2wpkh":"ert1qq6hag67dl53wl99vzg42z8eyzfz2xlkvkz0hqm","p2shwpkh":"XRAWCpubCKRFLGJq5Dw7NQD69DwiBpaHSY"}
+"#,
+		"",
+	);
 }
 
 // TODO address inspect
@@ -656,15 +753,25 @@ hal-simplicity-address-inspect
 inspect addresses
 
 USAGE:
-    hal simplicity address inspect [FLAGS] <address>
+    hal simplicity address inspect [FLAGS] [OPTIONS] <address>...
 
 FLAGS:
-    -h, --help       Prints help information
-    -v, --verbose    print verbose logging output to stderr
-    -y, --yaml       print output in YAML instead of JSON
+    -h, --help          Prints help information
+        --keep-going    with --ndjson, print an error record for items that fail and continue with the rest
+        --ndjson        print one compact JSON object per line for each item instead of an array
+        --no-newline    don't print a trailing newline after a raw value
+    -v, --verbose       print verbose logging output to stderr
+    -y, --yaml          print output in YAML instead of JSON
+
+OPTIONS:
+        --log-format <log-format>    the format of the logging output [possible values: text, json]
+        --out <out>                  write the output to the given file instead of stdout, gzipped if it ends in .gz
+    -o, --output <output>            the output format (see `schema --proto` for the protobuf messages) [possible
+                                     values: json, yaml, protobuf]
+        --raw-value <raw-value>      print only the given output field (e.g. txid) as a raw value
 
 ARGS:
-    <address>    the address
+    <address>...    the addresses
 ";
 	// newline not escaped v
 	// FIXME yes, you can, with a script rather than pubkey. Also the script is not
@@ -673,10 +780,10 @@ ARGS:
 		&["simplicity", "address", "inspect"],
 		"",
 		"error: The following required arguments were not provided:
-    <address>
+    <address>...
 
 USAGE:
-    hal simplicity address inspect [FLAGS] <address>
+    hal simplicity address inspect [FLAGS] [OPTIONS] <address>...
 
 For more information try --help
 ",
@@ -703,6 +810,19 @@ For more information try --help
 		"Execution failed: invalid address format: InvalidAddress(\"1Au8w4fejHaJBbrZCMrfg6v2hwJNr3go1N\")\n",
 		"",
 	);
+	// liquid testnet addresses ok, and translate to the other networks
+	assert_cmd(
+		&[
+			"simplicity",
+			"address",
+			"inspect",
+			"--raw-value",
+			"other_networks",
+			"tex1q7z3dshje7e4tftag5c3w7e85pr00r6cqp6exxv",
+		],
+		"[{\"address\":\"ex1q7z3dshje7e4tftag5c3w7e85pr00r6cqmut068\",\"network\":\"liquid\"},{\"address\":\"ert1q7z3dshje7e4tftag5c3w7e85pr00r6cqpwph9a\",\"network\":\"elementsregtest\"}]\n",
+		"",
+	);
 	// liquid addresses ok
 	assert_cmd(
 		&["simplicity", "address", "inspect", "ex1q7z3dshje7e4tftag5c3w7e85pr00r6cqmut068"],
@@ -714,7 +834,17 @@ For more information try --help
     "asm": "OP_0 OP_PUSHBYTES_20 f0a2d85e59f66ab4afa8a622ef64f408def1eb00"
   },
   "witness_program_version": 0,
-  "witness_pubkey_hash": "f0a2d85e59f66ab4afa8a622ef64f408def1eb00"
+  "witness_pubkey_hash": "f0a2d85e59f66ab4afa8a622ef64f408def1eb00",
+  "other_networks": [
+    {
+      "network": "liquidtestnet",
+      "address": "tex1q7z3dshje7e4tftag5c3w7e85pr00r6cqp6exxv"
+    },
+    {
+      "network": "elementsregtest",
+      "address": "ert1q7z3dshje7e4tftag5c3w7e85pr00r6cqpwph9a"
+    }
+  ]
 }"#,
 		"",
 	);
@@ -728,7 +858,17 @@ For more information try --help
     "asm": "OP_0 OP_PUSHBYTES_20 f0a2d85e59f66ab4afa8a622ef64f408def1eb00"
   },
   "witness_program_version": 0,
-  "witness_pubkey_hash": "f0a2d85e59f66ab4afa8a622ef64f408def1eb00"
+  "witness_pubkey_hash": "f0a2d85e59f66ab4afa8a622ef64f408def1eb00",
+  "other_networks": [
+    {
+      "network": "liquid",
+      "address": "ex1q7z3dshje7e4tftag5c3w7e85pr00r6cqmut068"
+    },
+    {
+      "network": "liquidtestnet",
+      "address": "tex1q7z3dshje7e4tftag5c3w7e85pr00r6cqp6exxv"
+    }
+  ]
 }"#,
 		"",
 	);
@@ -741,7 +881,17 @@ For more information try --help
     "hex": "76a9146c95622b280be97792ec1b3505700f9e674cf50988ac",
     "asm": "OP_DUP OP_HASH160 OP_PUSHBYTES_20 6c95622b280be97792ec1b3505700f9e674cf509 OP_EQUALVERIFY OP_CHECKSIG"
   },
-  "pubkey_hash": "6c95622b280be97792ec1b3505700f9e674cf509"
+  "pubkey_hash": "6c95622b280be97792ec1b3505700f9e674cf509",
+  "other_networks": [
+    {
+      "network": "liquidtestnet",
+      "address": "Ff4rNyQ2HnFqdCsg5Tr98ciMN6aLRevkaN"
+    },
+    {
+      "network": "elementsregtest",
+      "address": "2djKtKaiMagUCNTcuwx8ZdZsucUr3tt4WQu"
+    }
+  ]
 }"#,
 		"",
 	);
@@ -754,7 +904,17 @@ For more information try --help
     "hex": "76a9146c95622b280be97792ec1b3505700f9e674cf50988ac",
     "asm": "OP_DUP OP_HASH160 OP_PUSHBYTES_20 6c95622b280be97792ec1b3505700f9e674cf509 OP_EQUALVERIFY OP_CHECKSIG"
   },
-  "pubkey_hash": "6c95622b280be97792ec1b3505700f9e674cf509"
+  "pubkey_hash": "6c95622b280be97792ec1b3505700f9e674cf509",
+  "other_networks": [
+    {
+      "network": "liquid",
+      "address": "Q7AX4Ff5CZzEoJoVbGqqKFRsagz9Q3bS1v"
+    },
+    {
+      "network": "liquidtestnet",
+      "address": "Ff4rNyQ2HnFqdCsg5Tr98ciMN6aLRevkaN"
+    }
+  ]
 }"#,
 		"",
 	);
@@ -768,7 +928,17 @@ For more information try --help
     "hex": "76a9146c95622b280be97792ec1b3505700f9e674cf50988ac",
     "asm": "OP_DUP OP_HASH160 OP_PUSHBYTES_20 6c95622b280be97792ec1b3505700f9e674cf509 OP_EQUALVERIFY OP_CHECKSIG"
   },
-  "pubkey_hash": "6c95622b280be97792ec1b3505700f9e674cf509"
+  "pubkey_hash": "6c95622b280be97792ec1b3505700f9e674cf509",
+  "other_networks": [
+    {
+      "network": "liquid",
+      "address": "Q7AX4Ff5CZzEoJoVbGqqKFRsagz9Q3bS1v"
+    },
+    {
+      "network": "liquidtestnet",
+      "address": "Ff4rNyQ2HnFqdCsg5Tr98ciMN6aLRevkaN"
+    }
+  ]
 }"#,
 		"",
 	);
@@ -781,21 +951,325 @@ type: p2pkh
 script_pub_key:
   hex: 76a9146c95622b280be97792ec1b3505700f9e674cf50988ac
   asm: OP_DUP OP_HASH160 OP_PUSHBYTES_20 6c95622b280be97792ec1b3505700f9e674cf509 OP_EQUALVERIFY OP_CHECKSIG
-pubkey_hash: 6c95622b280be97792ec1b3505700f9e674cf509"#,
+pubkey_hash: 6c95622b280be97792ec1b3505700f9e674cf509
+other_networks:
+  - network: liquid
+    address: Q7AX4Ff5CZzEoJoVbGqqKFRsagz9Q3bS1v
+  - network: liquidtestnet
+    address: Ff4rNyQ2HnFqdCsg5Tr98ciMN6aLRevkaN"#,
 		"",
 	);
 	assert_cmd(
-		&["simplicity", "address", "inspect", "2djKtKaiMagUCNTcuwx8ZdZsucUr3tt4WQu", ""],
+		&["simplicity", "address", "inspect", "", "2djKtKaiMagUCNTcuwx8ZdZsucUr3tt4WQu"],
+		"Execution failed: invalid address format: Base58(TooShort(TooShortError { length: 0 }))\n",
 		"",
-		"\
-error: Found argument '' which wasn't expected, or isn't valid in this context
+	);
+	assert_cmd(
+		&[
+			"simplicity",
+			"address",
+			"inspect",
+			"--ndjson",
+			"--raw-value",
+			"type",
+			"2djKtKaiMagUCNTcuwx8ZdZsucUr3tt4WQu",
+			"ex1q7z3dshje7e4tftag5c3w7e85pr00r6cqmut068",
+		],
+		"p2pkh\np2wpkh\n",
+		"",
+	);
+
+	// Several outputs are encoded as the repeated items of a list message.
+	let protobuf = |addresses: &[&str]| {
+		let output = self_command()
+			.args(["simplicity", "address", "inspect", "-o", "protobuf"])
+			.args(addresses)
+			.output()
+			.unwrap();
+		assert_eq!(output.stderr, Vec::<u8>::new());
+		output.stdout
+	};
+	let addresses =
+		["2djKtKaiMagUCNTcuwx8ZdZsucUr3tt4WQu", "ex1q7z3dshje7e4tftag5c3w7e85pr00r6cqmut068"];
+	let mut expected = Vec::new();
+	for address in addresses {
+		let item = protobuf(&[address]);
+		expected.push(0x0a);
+		let mut len = item.len();
+		while len >= 0x80 {
+			expected.push(len as u8 | 0x80);
+			len >>= 7;
+		}
+		expected.push(len as u8);
+		expected.extend(item);
+	}
+	assert_eq!(protobuf(&addresses), expected);
+}
+
+#[test]
+fn cli_simplicity_asset() {
+	let expected_help = "\
+hal-simplicity-asset 
+issued asset tools
 
 USAGE:
-    hal simplicity address inspect [FLAGS] <address>
+    hal simplicity asset [FLAGS] [OPTIONS] <SUBCOMMAND>
 
-For more information try --help
-",
+FLAGS:
+    -h, --help       Prints help information
+    -v, --verbose    print verbose logging output to stderr
+
+OPTIONS:
+        --log-format <log-format>    the format of the logging output [possible values: text, json]
+        --out <out>                  write the output to the given file instead of stdout, gzipped if it ends in .gz
+
+SUBCOMMANDS:
+    issue    build the unsigned transaction issuing a new asset
+";
+	assert_cmd(&["simplicity", "asset", "-h"], expected_help, "");
+	assert_cmd(&["simplicity", "asset", "--help"], expected_help, "");
+}
+
+#[test]
+fn cli_simplicity_asset_issue() {
+	let expected_help = "\
+hal-simplicity-asset-issue 
+build the unsigned transaction issuing a new asset
+
+USAGE:
+    hal simplicity asset issue [FLAGS] [OPTIONS] --address <address> --fee <fee> --supply <supply> --utxo <utxo>
+
+FLAGS:
+    -h, --help          Prints help information
+        --no-newline    don't print a trailing newline after a raw value
+    -v, --verbose       print verbose logging output to stderr
+    -y, --yaml          print output in YAML instead of JSON
+
+OPTIONS:
+    -a, --address <address>                  the address receiving the issued asset
+        --change-address <change-address>    the address receiving the change (defaults to --address)
+    -c, --contract <contract>                the asset's contract in JSON, committed to by the asset ID
+    -f, --fee <fee>                          the fee of the transaction in satoshis, paid in the UTXO's asset
+        --log-format <log-format>            the format of the logging output [possible values: text, json]
+        --out <out>                          write the output to the given file instead of stdout, gzipped if it ends in
+                                             .gz
+    -o, --output <output>                    the output format (see `schema --proto` for the protobuf messages)
+                                             [possible values: json, yaml, protobuf]
+        --raw-value <raw-value>              print only the given output field (e.g. txid) as a raw value
+    -s, --supply <supply>                    the amount of the asset to issue, in satoshis
+        --token-address <token-address>      the address receiving the tokens (defaults to --address)
+        --tokens <tokens>                    the amount of reissuance tokens to issue, in satoshis [default: 0]
+    -u, --utxo <utxo>                        the output funding the issuance and its fee as TXID:VOUT:ASSET:VALUE
+";
+	assert_cmd(&["simplicity", "asset", "issue", "-h"], expected_help, "");
+	assert_cmd(&["simplicity", "asset", "issue", "--help"], expected_help, "");
+
+	let utxo = "2222222222222222222222222222222222222222222222222222222222222222:0:b2e15d0d7a0c94e4e2ce0fe6e8691b9e451377f6e46e8045a86f7c4b5d4f0f23:100000";
+	let address = "ert1qw508d6qejxtdg4y5r3zarvary0c5xw7kuu73e0";
+	let contract = r#"{"entity":{"domain":"example.com"},"issuer_pubkey":"0279be667ef9dcbbac55a06295ce870b07029bfcdb2dce28d959f2815b16f81798","name":"Test","precision":8,"ticker":"TST","version":0}"#;
+	let expected_issue = r#"{
+  "tx": "02000000000122222222222222222222222222222222222222222222222222222222222222220000008000ffffffff0000000000000000000000000000000000000000000000000000000000000000617519be2e623dde0456734b9b987768d58cb3e04f8f8b7f3aacae6653d6933c01000775f05a0740000100000000000000010401f3049795e1eeebed5b45bb71254fac6aba231924ceabcad412ee4c6eb8fa56c001000775f05a07400000160014751e76e8199196d454941c45d1b3a323f1433bd6016c98ac5f4d66de836ad56c020ba34fc7227a68a7f1ff29efb89e7f3ad66d4e8e01000000000000000100160014751e76e8199196d454941c45d1b3a323f1433bd601230f4f5d4b7c6fa845806ee4f67713459e1b69e8e60fcee2e4940c7a0d5de1b20100000000000184ac00160014751e76e8199196d454941c45d1b3a323f1433bd601230f4f5d4b7c6fa845806ee4f67713459e1b69e8e60fcee2e4940c7a0d5de1b20100000000000001f4000000000000",
+  "txid": "359cc5e158fe6a7223e2b367b8c07cac4705ead624da7973c178dee82a3029ab",
+  "asset_id": "c056fab86e4cee12d4caabce241923ba6aac4f2571bb455bedebeee1959704f3",
+  "token_id": "8e4e6dd63a7f9eb8ef29fff1a7687a22c74fa30b026cd56a83de664d5fac986c",
+  "entropy": "8ef1f815638de7a34d52eade2a7980032b75fec367a611219502f90d064da222",
+  "contract_hash": "3c93d65366aeac3a7f8b8f4fe0b38cd56877989b4b735604de3d622ebe197561",
+  "registry": {
+    "asset_id": "c056fab86e4cee12d4caabce241923ba6aac4f2571bb455bedebeee1959704f3",
+    "contract": {
+      "entity": {
+        "domain": "example.com"
+      },
+      "issuer_pubkey": "0279be667ef9dcbbac55a06295ce870b07029bfcdb2dce28d959f2815b16f81798",
+      "name": "Test",
+      "precision": 8,
+      "ticker": "TST",
+      "version": 0
+    }
+  }
+}"#;
+	assert_cmd(
+		&[
+			"simplicity",
+			"asset",
+			"issue",
+			"-u",
+			utxo,
+			"-s",
+			"2100000000000000",
+			"--tokens",
+			"1",
+			"-a",
+			address,
+			"-f",
+			"500",
+			"-c",
+			contract,
+		],
+		expected_issue,
+		"",
+	);
+	// Without a contract, the contract hash is zero and there is nothing to register.
+	assert_cmd(
+		&[
+			"simplicity",
+			"asset",
+			"issue",
+			"-u",
+			utxo,
+			"-s",
+			"1000",
+			"-a",
+			address,
+			"-f",
+			"500",
+			"--raw-value",
+			"contract_hash",
+		],
+		"0000000000000000000000000000000000000000000000000000000000000000\n",
+		"",
+	);
+	assert_cmd(
+		&[
+			"simplicity",
+			"asset",
+			"issue",
+			"-u",
+			utxo,
+			"-s",
+			"1000",
+			"-a",
+			address,
+			"-f",
+			"500",
+			"-c",
+			r#"{"name":"Test","ticker":"TST","precision":0,"entity":{"domain":"example.com"},"issuer_pubkey":"0279be667ef9dcbbac55a06295ce870b07029bfcdb2dce28d959f2815b16f81798"}"#,
+			"--raw-value",
+			"registry.contract.name",
+		],
+		"Test\n",
+		"Contract has no \"version\" field, required by the asset registry.\n",
+	);
+	assert_cmd(
+		&["simplicity", "asset", "issue", "-u", utxo, "-s", "0", "-a", address, "-f", "500"],
+		"Execution failed: nothing to issue, --supply and --tokens are both zero\n",
+		"",
+	);
+	assert_cmd(
+		&["simplicity", "asset", "issue", "-u", "22:0", "-s", "1", "-a", address, "-f", "500"],
+		"Execution failed: invalid UTXO '22:0', expected TXID:VOUT:ASSET:VALUE\n",
+		"",
+	);
+}
+
+#[test]
+fn cli_simplicity_audit() {
+	let expected_help = "\
+hal-simplicity-audit 
+inspect audit logs written with --audit-log
+
+USAGE:
+    hal simplicity audit [FLAGS] [OPTIONS] <SUBCOMMAND>
+
+FLAGS:
+    -h, --help       Prints help information
+    -v, --verbose    print verbose logging output to stderr
+
+OPTIONS:
+        --log-format <log-format>    the format of the logging output [possible values: text, json]
+        --out <out>                  write the output to the given file instead of stdout, gzipped if it ends in .gz
+
+SUBCOMMANDS:
+    verify    check the hash chain of an audit log
+";
+	assert_cmd(&["simplicity", "audit"], "", expected_help);
+	assert_cmd(&["simplicity", "audit", "--help"], expected_help, "");
+}
+
+#[test]
+fn cli_simplicity_audit_verify() {
+	let expected_help = "\
+hal-simplicity-audit-verify 
+check the hash chain of an audit log
+
+USAGE:
+    hal simplicity audit verify [FLAGS] [OPTIONS] <audit-log>
+
+FLAGS:
+    -h, --help          Prints help information
+        --no-newline    don't print a trailing newline after a raw value
+    -v, --verbose       print verbose logging output to stderr
+    -y, --yaml          print output in YAML instead of JSON
+
+OPTIONS:
+        --log-format <log-format>    the format of the logging output [possible values: text, json]
+        --out <out>                  write the output to the given file instead of stdout, gzipped if it ends in .gz
+    -o, --output <output>            the output format (see `schema --proto` for the protobuf messages) [possible
+                                     values: json, yaml, protobuf]
+        --raw-value <raw-value>      print only the given output field (e.g. txid) as a raw value
+
+ARGS:
+    <audit-log>    the path of the audit log
+";
+	assert_cmd(&["simplicity", "audit", "verify", "-h"], expected_help, "");
+	assert_cmd(&["simplicity", "audit", "verify", "--help"], expected_help, "");
+
+	let log =
+		std::env::temp_dir().join(format!("hal-simplicity-verify-{}.log", std::process::id()));
+	let log = log.to_str().unwrap();
+	let genesis = "0".repeat(64);
+	std::fs::write(log, "").unwrap();
+	assert_cmd(
+		&["simplicity", "audit", "verify", log],
+		&format!("{{\n  \"records\": 0,\n  \"last_hash\": \"{}\"\n}}\n", genesis),
+		"",
+	);
+
+	// Two records chained by hash, as written by `--audit-log`.
+	let record = |seq: u64, prev: &str| {
+		use elements::hashes::{sha256, Hash};
+
+		let mut record = serde_json::json!({
+			"seq": seq,
+			"timestamp": "2025-01-01T00:00:00.000Z",
+			"command": "simplicity sighash",
+			"inputs": {},
+			"output": {},
+			"prev": prev,
+		});
+		let hash = sha256::Hash::hash(record.to_string().as_bytes()).to_string();
+		record["hash"] = serde_json::Value::String(hash.clone());
+		(record.to_string(), hash)
+	};
+	let (first, first_hash) = record(0, &genesis);
+	let (second, second_hash) = record(1, &first_hash);
+	std::fs::write(log, format!("{}\n{}\n", first, second)).unwrap();
+	assert_cmd(
+		&["simplicity", "audit", "verify", log, "--raw-value", "last_hash"],
+		&format!("{}\n", second_hash),
+		"",
+	);
+
+	std::fs::write(log, format!("{}\n", second)).unwrap();
+	assert_cmd(
+		&["simplicity", "audit", "verify", log],
+		"Execution failed: record on line 1 has seq 1, expected 0\n",
+		"",
 	);
+	let (other, _) = record(1, &genesis);
+	std::fs::write(log, format!("{}\n{}\n", first, other)).unwrap();
+	assert_cmd(
+		&["simplicity", "audit", "verify", log],
+		"Execution failed: record on line 2 does not chain from the previous record\n",
+		"",
+	);
+	std::fs::write(log, format!("{}\n{}\n", first, second.replace("sighash", "sighash2"))).unwrap();
+	assert_cmd(
+		&["simplicity", "audit", "verify", log],
+		"Execution failed: record on line 2 does not match its hash\n",
+		"",
+	);
+	std::fs::remove_file(log).unwrap();
 }
 
 #[test]
@@ -805,12 +1279,16 @@ hal-simplicity-block
 manipulate blocks
 
 USAGE:
-    hal simplicity block [FLAGS] <SUBCOMMAND>
+    hal simplicity block [FLAGS] [OPTIONS] <SUBCOMMAND>
 
 FLAGS:
     -h, --help       Prints help information
     -v, --verbose    print verbose logging output to stderr
 
+OPTIONS:
+        --log-format <log-format>    the format of the logging output [possible values: text, json]
+        --out <out>                  write the output to the given file instead of stdout, gzipped if it ends in .gz
+
 SUBCOMMANDS:
     create    create a raw block from JSON
     decode    decode a raw block to JSON
@@ -828,15 +1306,21 @@ hal-simplicity-block-create
 create a raw block from JSON
 
 USAGE:
-    hal simplicity block create [FLAGS] [block-info]
+    hal simplicity block create [FLAGS] [OPTIONS] [block-info]
 
 FLAGS:
     -h, --help          Prints help information
     -r, --raw-stdout    output the raw bytes of the result to stdout
     -v, --verbose       print verbose logging output to stderr
 
+OPTIONS:
+        --input-format <input-format>    the format of the input (by default JSON if it starts with '{', else YAML)
+                                         [possible values: json, yaml]
+        --log-format <log-format>        the format of the logging output [possible values: text, json]
+        --out <out>                      write the output to the given file instead of stdout, gzipped if it ends in .gz
+
 ARGS:
-    <block-info>    the block info in JSON
+    <block-info>    the block info in JSON or YAML
 ";
 	// FIXME stdout not stderr
 	assert_cmd(
@@ -849,8 +1333,8 @@ ARGS:
 	assert_cmd(&["simplicity", "block", "create", "--help", "xyz"], expected_help, "");
 
 	// TODO this was as far as I got trying to find a valid input
-	assert_cmd(&["simplicity", "block", "create", ""], "Execution failed: invaid json JSON input: Error(\"EOF while parsing a value\", line: 1, column: 0)\n", "");
-	assert_cmd(&["simplicity", "block", "create", "{}"], "Execution failed: invaid json JSON input: Error(\"missing field `header`\", line: 1, column: 2)\n", "");
+	assert_cmd(&["simplicity", "block", "create", ""], "Execution failed: invalid JSON provided: Error(\"EOF while parsing a value\", line: 1, column: 0)\n", "");
+	assert_cmd(&["simplicity", "block", "create", "{}"], "Execution failed: invalid JSON provided: Error(\"missing field `header`\", line: 1, column: 2)\n", "");
 	assert_cmd(
 		&[
 			"simplicity",
@@ -870,7 +1354,7 @@ ARGS:
 		"Execution failed: missing challenge\n",
 		"",
 	);
-	assert_cmd(&["simplicity", "block", "create", "{}"], "Execution failed: invaid json JSON input: Error(\"missing field `header`\", line: 1, column: 2)\n", "");
+	assert_cmd(&["simplicity", "block", "create", "{}"], "Execution failed: invalid JSON provided: Error(\"missing field `header`\", line: 1, column: 2)\n", "");
 	// FIXME this error is awful; the actual field it wants is called `dynafed_current`
 	assert_cmd(
 		&[
@@ -982,18 +1466,32 @@ hal-simplicity-block-decode
 decode a raw block to JSON
 
 USAGE:
-    hal simplicity block decode [FLAGS] [raw-block]
+    hal simplicity block decode [FLAGS] [OPTIONS] [raw-block]...
 
 FLAGS:
+        --abbrev             abbreviate long hex fields such as rangeproofs and witnesses
+        --coinbase           provide only the coinbase transaction, with its height, fees and commitments
     -r, --elementsregtest    run in elementsregtest mode
     -h, --help               Prints help information
+        --keep-going         with --ndjson, print an error record for items that fail and continue with the rest
         --liquid             run in liquid mode
+        --liquidtestnet      run in liquidtestnet mode
+        --ndjson             print one compact JSON object per line for each item instead of an array
+        --no-newline         don't print a trailing newline after a raw value
         --txids              provide transactions IDs instead of full transactions
     -v, --verbose            print verbose logging output to stderr
     -y, --yaml               print output in YAML instead of JSON
 
+OPTIONS:
+        --address-book <address-book>    label the inputs and outputs whose scripts are in the given JSON or YAML file
+        --log-format <log-format>        the format of the logging output [possible values: text, json]
+        --out <out>                      write the output to the given file instead of stdout, gzipped if it ends in .gz
+    -o, --output <output>                the output format (see `schema --proto` for the protobuf messages) [possible
+                                         values: json, yaml, protobuf]
+        --raw-value <raw-value>          print only the given output field (e.g. txid) as a raw value
+
 ARGS:
-    <raw-block>    the raw block in hex
+    <raw-block>...    the raw blocks or headers in hex, base64 or as file paths
 ";
 	// FIXME stdout not stderr
 	assert_cmd(
@@ -1056,134 +1554,2645 @@ ARGS:
 		),
 		"",
 	);
-}
-
-#[test]
-fn cli_simplicity_keypair() {
-	let expected_help = "\
-hal-simplicity-keypair 
-manipulate private and public keys
-
-USAGE:
-    hal simplicity keypair [FLAGS] <SUBCOMMAND>
-
-FLAGS:
-    -h, --help       Prints help information
-    -v, --verbose    print verbose logging output to stderr
-
-SUBCOMMANDS:
-    generate    generate a random private/public keypair
-";
-	assert_cmd(&["simplicity", "keypair"], "", expected_help);
-	// -h does NOT mean --help. It is just ignored entirely.
-	//assert_cmd(&["simplicity", "keypair", "-h"], expected_help, "");
-	assert_cmd(&["simplicity", "keypair", "--help"], expected_help, "");
-	assert_cmd(&["simplicity", "keypair", "--help", "xyz"], expected_help, "");
-}
-
-#[test]
-fn cli_simplicity_keypair_generate() {
-	let expected_help = "\
-hal-simplicity-keypair-generate 
+	// --abbrev shortens the dynafed witness items but not other long hex fields
+	assert_cmd(
+		&["simplicity", "block", "decode", "--abbrev", FULL_BLOCK_1585319],
+		r#"{
+  "block_hash": "5f37039a5ae15d9239bb2e137643a51d3a525d6e850b5e8974b4323c9e13a39b",
+  "version": 536870912,
+  "previous_block_hash": "3365afcddd47de8f1e53f7407115f47ae20c91a818a64016de5b4ea3e0096417",
+  "merkle_root": "242f440712c6f758f584b28179b65c1b3e33d015db0b81ca32cfc8865ac9e08c",
+  "time": 1637622420,
+  "height": 1585319,
+  "dynafed": true,
+  "dynafed_current": {
+    "params_type": "compact",
+    "signblockscript": "0020e51211e91d9cf4aec3bdc370a0303acde5d24baedb12235fdd2786885069d91c",
+    "signblock_witness_limit": 1416,
+    "elided_root": "ff0f60e85234ad045ac9a8f174b41ac9e3461ad2f6b05d0fccbd964eed5d757e"
+  },
+  "dynafed_proposed": {
+    "params_type": "null",
+    "signblockscript": null,
+    "signblock_witness_limit": null
+  },
+  "dynafed_witness": [
+    "",
+    "30450221…105a5901 (72 bytes)",
+    "30450221…e11dda01 (72 bytes)",
+    "30440220…bc7fc301 (71 bytes)",
+    "30450221…d04fda01 (72 bytes)",
+    "30450221…da37e701 (72 bytes)",
+    "30450221…a789b201 (72 bytes)",
+    "30450221…39534901 (72 bytes)",
+    "30440220…6d56c201 (71 bytes)",
+    "30450221…ecfd1101 (72 bytes)",
+    "30440220…f9bf1a01 (71 bytes)",
+    "30450221…c46b9c01 (72 bytes)",
+    "5b21026a…68375fae (513 bytes)"
+  ]
+}"#,
+		"",
+	);
+	assert_cmd(
+		&["simplicity", "block", "decode", "--raw-value", "block_hash", FULL_BLOCK_1585319],
+		"5f37039a5ae15d9239bb2e137643a51d3a525d6e850b5e8974b4323c9e13a39b\n",
+		"",
+	);
+
+	// With an address book the transactions are decoded too, with their outputs labelled.
+	let book =
+		std::env::temp_dir().join(format!("hal-simplicity-block-book-{}.yaml", std::process::id()));
+	std::fs::write(
+		&book,
+		"2dxQzjvrkmRGSa5gwgaQn1oLtRo5pXS94oJ: miner\n\
+		 a914d96d23a467b3245554b4290d4a4b12d008f3ba8287: exchange\n",
+	)
+	.unwrap();
+	let book = book.to_str().unwrap();
+	let output = self_command()
+		.args(["simplicity", "block", "decode", "--address-book", book, FULL_BLOCK_1585319])
+		.output()
+		.unwrap();
+	let info: serde_json::Value = serde_json::from_slice(&output.stdout).unwrap();
+	assert_eq!(
+		info["header"]["block_hash"],
+		"5f37039a5ae15d9239bb2e137643a51d3a525d6e850b5e8974b4323c9e13a39b"
+	);
+	assert_eq!(info["transactions"][0]["outputs"][1]["label"], "miner");
+	assert_eq!(info["transactions"][1]["outputs"][0]["label"], "exchange");
+	assert!(info["transactions"][1]["outputs"][1].get("label").is_none());
+	// The header alone has no transactions to label.
+	assert_cmd(
+		&["simplicity", "block", "decode", "--address-book", book, &FULL_BLOCK_1585319[..2 * 1467]],
+		"Execution failed: invalid block format, --address-book needs full blocks: Io(Error { kind: UnexpectedEof, message: \"failed to fill whole buffer\" })\n",
+		"",
+	);
+
+	// --coinbase decodes only the coinbase transaction, labelled with the address book too.
+	let output = self_command()
+		.args([
+			"simplicity",
+			"block",
+			"decode",
+			"--coinbase",
+			"--address-book",
+			book,
+			FULL_BLOCK_1585319,
+		])
+		.output()
+		.unwrap();
+	let info: serde_json::Value = serde_json::from_slice(&output.stdout).unwrap();
+	assert_eq!(info["txid"], "9523d75b48b3411a3f4ebd31b6005898deebbe748875aa6ee084b94aa8422ba6");
+	assert_eq!(info["height"], 1585319);
+	assert_eq!(
+		info["fees_claimed"],
+		serde_json::json!([{
+			"asset": "6f0279e9ed041c3d710a9f57d0c02928416460c4b722ae3457a11eec381c526d",
+			"value": 262,
+		}])
+	);
+	assert_eq!(
+		info["commitments"],
+		serde_json::json!([
+			{
+				"vout": 0,
+				"type": "unknown",
+				"data": "0a8ce26fdbb51a2d03d4e62fdafd4a06dd7faa0d1c083aa7e27905000000000000000000",
+			},
+			{
+				"vout": 2,
+				"type": "witness",
+				"data": "e8497768bc893ee587244bf5303ac3cf482bab8e4b3fd22e8b114c2a52525ab3",
+			},
+		])
+	);
+	assert_eq!(info["outputs"][1]["label"], "miner");
+	std::fs::remove_file(book).unwrap();
+	assert_cmd(
+		&[
+			"simplicity",
+			"block",
+			"decode",
+			"--coinbase",
+			"--raw-value",
+			"height",
+			FULL_BLOCK_1585319,
+		],
+		"1585319\n",
+		"",
+	);
+	assert_cmd(
+		&["simplicity", "block", "decode", "--coinbase", &FULL_BLOCK_1585319[..2 * 1467]],
+		"Execution failed: invalid block format, --coinbase needs full blocks: Io(Error { kind: UnexpectedEof, message: \"failed to fill whole buffer\" })\n",
+		"",
+	);
+	// Claimed amounts which overflow are an error rather than wrapping around.
+	use elements::hashes::hex::FromHex;
+
+	let raw_block = Vec::<u8>::from_hex(FULL_BLOCK_1585319).unwrap();
+	let mut block: elements::Block = elements::encode::deserialize(&raw_block).unwrap();
+	block.txdata[0].output[0].value = elements::confidential::Value::Explicit(1);
+	block.txdata[0].output[1].value = elements::confidential::Value::Explicit(u64::MAX);
+	let overflowing = elements::encode::serialize(&block).to_lower_hex_string();
+	assert_cmd(
+		&["simplicity", "block", "decode", "--coinbase", &overflowing],
+		"Execution failed: the coinbase claims more than 18446744073709551615 sat of asset 6f0279e9ed041c3d710a9f57d0c02928416460c4b722ae3457a11eec381c526d\n",
+		"",
+	);
+}
+
+#[test]
+fn cli_simplicity_doctor() {
+	let expected_help = "\
+hal-simplicity-doctor 
+run self-checks of the cryptography, known vectors and connectivity
+
+USAGE:
+    hal simplicity doctor [FLAGS] [OPTIONS]
+
+FLAGS:
+    -h, --help          Prints help information
+        --no-newline    don't print a trailing newline after a raw value
+    -v, --verbose       print verbose logging output to stderr
+    -y, --yaml          print output in YAML instead of JSON
+
+OPTIONS:
+        --esplora <esplora>          also check the Esplora API at the given URL
+        --log-format <log-format>    the format of the logging output [possible values: text, json]
+        --out <out>                  write the output to the given file instead of stdout, gzipped if it ends in .gz
+    -o, --output <output>            the output format (see `schema --proto` for the protobuf messages) [possible
+                                     values: json, yaml, protobuf]
+        --raw-value <raw-value>      print only the given output field (e.g. txid) as a raw value
+        --rpc <rpc>                  also check the JSON-RPC interface of the node at the given URL
+        --rpc-cookie <rpc-cookie>    the path of the JSON-RPC cookie file
+        --rpc-pass <rpc-pass>        the JSON-RPC password
+        --rpc-user <rpc-user>        the JSON-RPC user name
+";
+	assert_cmd(&["simplicity", "doctor", "-h"], expected_help, "");
+	assert_cmd(&["simplicity", "doctor", "--help"], expected_help, "");
+
+	assert_cmd(
+		&["simplicity", "doctor"],
+		r#"{
+  "checks": [
+    {
+      "name": "secp256k1",
+      "passed": true,
+      "detail": "BIP-340 and ECDSA signatures verified"
+    },
+    {
+      "name": "cmr",
+      "passed": true,
+      "detail": "CMR a3d5258f85377454e6929bdbee3cc808c2e659863ee8b1c674dc6203e0492fe7 recomputed"
+    },
+    {
+      "name": "sighash",
+      "passed": true,
+      "detail": "sighash 34783b35f568dbe7b2eb818f46a1db268902d4224163412e21b94c726060f46b recomputed"
+    },
+    {
+      "name": "genesis-hashes",
+      "passed": true,
+      "detail": "bitcoin and regtest genesis hashes recomputed"
+    }
+  ],
+  "passed": true
+}"#,
+		"",
+	);
+	// Connectivity failures are reported as failed checks, with a failing exit status.
+	let output = self_command()
+		.args(["simplicity", "doctor", "--rpc", "http://127.0.0.1:1"])
+		.output()
+		.unwrap();
+	assert!(!output.status.success());
+	let report: serde_json::Value = serde_json::from_slice(&output.stdout).unwrap();
+	assert_eq!(report["passed"], false);
+	assert_eq!(report["checks"][4]["name"], "rpc");
+	assert_eq!(report["checks"][4]["passed"], false);
+}
+
+#[test]
+fn cli_simplicity_fee() {
+	let expected_help = "\
+hal-simplicity-fee 
+fee estimation
+
+USAGE:
+    hal simplicity fee [FLAGS] [OPTIONS] <SUBCOMMAND>
+
+FLAGS:
+    -h, --help       Prints help information
+    -v, --verbose    print verbose logging output to stderr
+
+OPTIONS:
+        --log-format <log-format>    the format of the logging output [possible values: text, json]
+        --out <out>                  write the output to the given file instead of stdout, gzipped if it ends in .gz
+
+SUBCOMMANDS:
+    estimate    suggest a feerate from the feerates paid in recent blocks, using discounted vsizes
+";
+	assert_cmd(&["simplicity", "fee", "-h"], expected_help, "");
+	assert_cmd(&["simplicity", "fee", "--help"], expected_help, "");
+}
+
+#[test]
+fn cli_simplicity_fee_estimate() {
+	let expected_help = "\
+hal-simplicity-fee-estimate 
+suggest a feerate from the feerates paid in recent blocks, using discounted vsizes
+
+USAGE:
+    hal simplicity fee estimate [FLAGS] [OPTIONS] [raw-block]...
+
+FLAGS:
+    -r, --elementsregtest    run in elementsregtest mode
+    -h, --help               Prints help information
+        --liquid             run in liquid mode
+        --liquidtestnet      run in liquidtestnet mode
+        --no-newline         don't print a trailing newline after a raw value
+    -v, --verbose            print verbose logging output to stderr
+    -y, --yaml               print output in YAML instead of JSON
+
+OPTIONS:
+    -n, --count <count>              the number of recent blocks to fetch with --rpc or --esplora [default: 6]
+        --esplora <esplora>          fetch the recent blocks from the Esplora API at the given URL
+        --log-format <log-format>    the format of the logging output [possible values: text, json]
+        --out <out>                  write the output to the given file instead of stdout, gzipped if it ends in .gz
+    -o, --output <output>            the output format (see `schema --proto` for the protobuf messages) [possible
+                                     values: json, yaml, protobuf]
+        --percentile <percentile>    the feerate percentile to suggest [default: 50]
+        --raw-value <raw-value>      print only the given output field (e.g. txid) as a raw value
+        --rpc <rpc>                  fetch the recent blocks from the JSON-RPC interface at the given URL
+        --rpc-cookie <rpc-cookie>    the path of the JSON-RPC cookie file
+        --rpc-pass <rpc-pass>        the JSON-RPC password
+        --rpc-user <rpc-user>        the JSON-RPC user name
+
+ARGS:
+    <raw-block>...    the blocks to examine in hex, base64 or as file paths (read from stdin if omitted)
+";
+	assert_cmd(&["simplicity", "fee", "estimate", "-h"], expected_help, "");
+	assert_cmd(&["simplicity", "fee", "estimate", "--help"], expected_help, "");
+
+	let expected_estimate = r#"{
+  "blocks": 1,
+  "transactions": 1,
+  "min_feerate": 0.701,
+  "max_feerate": 0.701,
+  "percentiles": {
+    "p10": 0.701,
+    "p25": 0.701,
+    "p50": 0.701,
+    "p75": 0.701,
+    "p90": 0.701
+  },
+  "feerate": 0.701
+}"#;
+	assert_cmd(
+		&["simplicity", "fee", "estimate", "--liquid", FULL_BLOCK_1585319],
+		expected_estimate,
+		"",
+	);
+	assert_cmd(
+		&[
+			"simplicity",
+			"fee",
+			"estimate",
+			"--liquid",
+			"--raw-value",
+			"feerate",
+			FULL_BLOCK_1585319,
+		],
+		"0.701\n",
+		"",
+	);
+	// The L-BTC fees of a Liquid block are not fees on elementsregtest.
+	assert_cmd(
+		&["simplicity", "fee", "estimate", FULL_BLOCK_1585319],
+		"{\n  \"blocks\": 1,\n  \"transactions\": 0,\n  \"feerate\": 0.1\n}",
+		"",
+	);
+	assert_cmd(
+		&["simplicity", "fee", "estimate", "--percentile", "101", FULL_BLOCK_1585319],
+		"Execution failed: percentile must be between 0 and 100\n",
+		"",
+	);
+}
+
+#[test]
+fn cli_simplicity_info() {
+	let expected_help = "\
+hal-simplicity-info 
+report the version and capabilities of hal-simplicity
+
+USAGE:
+    hal simplicity info [FLAGS] [OPTIONS]
+
+FLAGS:
+    -h, --help          Prints help information
+        --no-newline    don't print a trailing newline after a raw value
+    -v, --verbose       print verbose logging output to stderr
+    -y, --yaml          print output in YAML instead of JSON
+
+OPTIONS:
+        --log-format <log-format>    the format of the logging output [possible values: text, json]
+        --out <out>                  write the output to the given file instead of stdout, gzipped if it ends in .gz
+    -o, --output <output>            the output format (see `schema --proto` for the protobuf messages) [possible
+                                     values: json, yaml, protobuf]
+        --raw-value <raw-value>      print only the given output field (e.g. txid) as a raw value
+";
+	assert_cmd(&["simplicity", "info", "-h"], expected_help, "");
+	assert_cmd(&["simplicity", "info", "--help"], expected_help, "");
+
+	assert_cmd(&["simplicity", "info", "--raw-value", "consensus.max_cost"], "4000050000\n", "");
+
+	assert_cmd(&["simplicity", "info", "--raw-value", "jet_families.0.jets"], "471\n", "");
+
+	assert_cmd(&["simplicity", "info", "--raw-value", "consensus.tapleaf_version"], "190\n", "");
+}
+
+#[test]
+fn cli_simplicity_keypair() {
+	let expected_help = "\
+hal-simplicity-keypair 
+manipulate private and public keys
+
+USAGE:
+    hal simplicity keypair [FLAGS] [OPTIONS] <SUBCOMMAND>
+
+FLAGS:
+    -h, --help       Prints help information
+    -v, --verbose    print verbose logging output to stderr
+
+OPTIONS:
+        --log-format <log-format>    the format of the logging output [possible values: text, json]
+        --out <out>                  write the output to the given file instead of stdout, gzipped if it ends in .gz
+
+SUBCOMMANDS:
+    generate    generate a random private/public keypair
+    grind       generate random keypairs until one has an even public key, optionally with a given prefix
+    recover     recover the public keys which made a compact ECDSA signature
+";
+	assert_cmd(&["simplicity", "keypair"], "", expected_help);
+	// -h does NOT mean --help. It is just ignored entirely.
+	//assert_cmd(&["simplicity", "keypair", "-h"], expected_help, "");
+	assert_cmd(&["simplicity", "keypair", "--help"], expected_help, "");
+	assert_cmd(&["simplicity", "keypair", "--help", "xyz"], expected_help, "");
+}
+
+#[test]
+fn cli_simplicity_keypair_generate() {
+	let expected_help = "\
+hal-simplicity-keypair-generate 
 generate a random private/public keypair
 
 USAGE:
-    hal simplicity keypair generate [FLAGS]
+    hal simplicity keypair generate [FLAGS] [OPTIONS]
+
+FLAGS:
+    -r, --elementsregtest    run in elementsregtest mode
+        --even-parity        regenerate the keypair until its public key has even parity
+    -h, --help               Prints help information
+        --liquid             run in liquid mode
+        --liquidtestnet      run in liquidtestnet mode
+        --no-newline         don't print a trailing newline after a raw value
+    -v, --verbose            print verbose logging output to stderr
+    -y, --yaml               print output in YAML instead of JSON
+
+OPTIONS:
+        --log-format <log-format>    the format of the logging output [possible values: text, json]
+        --out <out>                  write the output to the given file instead of stdout, gzipped if it ends in .gz
+    -o, --output <output>            the output format (see `schema --proto` for the protobuf messages) [possible
+                                     values: json, yaml, protobuf]
+        --raw-value <raw-value>      print only the given output field (e.g. txid) as a raw value
+";
+	assert_cmd(&["simplicity", "keypair", "generate", "-h"], expected_help, "");
+	assert_cmd(&["simplicity", "keypair", "generate", "--help"], expected_help, "");
+	assert_cmd(&["simplicity", "keypair", "generate", "--help", "xyz"], expected_help, "");
+
+	// New block to avoid warnings about `struct`s being defined not at the beginning of block
+	{
+		use elements::bitcoin::secp256k1;
+
+		#[allow(dead_code)]
+		#[derive(serde::Deserialize)]
+		struct Object {
+			secret: secp256k1::SecretKey,
+			x_only: secp256k1::XOnlyPublicKey,
+			parity: usize, // secp256k1::Parity does not seem to round-trip through serde_json
+		}
+
+		// Closure needed for borrowck reasons
+		assert_deserialize_cmd(&["simplicity", "keypair", "generate"], |s| {
+			serde_json::from_slice::<Object>(s)
+		});
+		assert_deserialize_cmd(&["simplicity", "keypair", "generate"], |s| {
+			serde_yaml::from_slice::<Object>(s)
+		});
+
+		// The key is regenerated until its parity is even.
+		for _ in 0..8 {
+			let obj = assert_deserialize_cmd(
+				&["simplicity", "keypair", "generate", "--even-parity"],
+				|s| serde_json::from_slice::<Object>(s),
+			);
+			let keypair = secp256k1::Keypair::from_secret_key(secp256k1::SECP256K1, &obj.secret);
+			assert_eq!(keypair.x_only_public_key(), (obj.x_only, secp256k1::Parity::Even));
+			assert_eq!(obj.parity, 0);
+		}
+	}
+
+	// The keys are also given as descriptor key expressions, with the WIF network of the chain.
+	{
+		use elements::bitcoin::secp256k1;
+		use elements::hashes::{hash160, Hash};
+		use elements_miniscript::descriptor::{DescriptorSecretKey, SinglePriv};
+		use elements_miniscript::{Descriptor, DescriptorPublicKey};
+		use std::str::FromStr;
+
+		#[derive(serde::Deserialize)]
+		struct Object {
+			secret: secp256k1::SecretKey,
+			x_only: secp256k1::XOnlyPublicKey,
+			descriptor_public_key: String,
+			descriptor_secret_key: String,
+		}
+
+		for (flag, network) in [
+			("-r", elements::bitcoin::NetworkKind::Test),
+			("--liquid", elements::bitcoin::NetworkKind::Main),
+		] {
+			let obj = assert_deserialize_cmd(&["simplicity", "keypair", "generate", flag], |s| {
+				serde_json::from_slice::<Object>(s)
+			});
+			DescriptorPublicKey::from_str(&obj.descriptor_public_key).unwrap();
+			assert!(obj.descriptor_public_key.ends_with(&obj.x_only.to_string()));
+			let descriptor = format!("eltr({})", obj.descriptor_public_key);
+			Descriptor::<DescriptorPublicKey>::from_str(&descriptor).unwrap();
+			// The origin of a single secret key is dropped when parsing it.
+			let public_key = obj.secret.public_key(secp256k1::SECP256K1).serialize();
+			let fingerprint = &hash160::Hash::hash(&public_key)[..4];
+			let origin = format!("[{}]", fingerprint.to_lower_hex_string());
+			assert!(obj.descriptor_public_key.starts_with(&origin));
+			assert!(obj.descriptor_secret_key.starts_with(&origin));
+			match DescriptorSecretKey::from_str(&obj.descriptor_secret_key).unwrap() {
+				DescriptorSecretKey::Single(SinglePriv {
+					key,
+					..
+				}) => {
+					assert_eq!(key.inner, obj.secret);
+					assert_eq!(key.network, network);
+				}
+				key => panic!("unexpected descriptor secret key {}", key),
+			}
+		}
+	}
+}
+
+#[test]
+fn cli_simplicity_keypair_grind() {
+	let expected_help = "\
+hal-simplicity-keypair-grind 
+generate random keypairs until one has an even public key, optionally with a given prefix
+
+USAGE:
+    hal simplicity keypair grind [FLAGS] [OPTIONS]
+
+FLAGS:
+    -r, --elementsregtest    run in elementsregtest mode
+    -h, --help               Prints help information
+        --liquid             run in liquid mode
+        --liquidtestnet      run in liquidtestnet mode
+        --no-newline         don't print a trailing newline after a raw value
+    -v, --verbose            print verbose logging output to stderr
+    -y, --yaml               print output in YAML instead of JSON
+
+OPTIONS:
+        --log-format <log-format>    the format of the logging output [possible values: text, json]
+        --out <out>                  write the output to the given file instead of stdout, gzipped if it ends in .gz
+    -o, --output <output>            the output format (see `schema --proto` for the protobuf messages) [possible
+                                     values: json, yaml, protobuf]
+        --prefix <prefix>            the hex digits the x-only public key must start with
+        --raw-value <raw-value>      print only the given output field (e.g. txid) as a raw value
+";
+	assert_cmd(&["simplicity", "keypair", "grind", "-h"], expected_help, "");
+	assert_cmd(&["simplicity", "keypair", "grind", "--help"], expected_help, "");
+
+	{
+		use elements::bitcoin::secp256k1;
+
+		#[derive(serde::Deserialize)]
+		struct Object {
+			secret: secp256k1::SecretKey,
+			x_only: secp256k1::XOnlyPublicKey,
+			parity: usize,
+			attempts: u64,
+		}
+
+		// Keys with odd parity are negated, so the secret key must give the even
+		// x-only key.
+		for _ in 0..8 {
+			let obj =
+				assert_deserialize_cmd(&["simplicity", "keypair", "grind", "--prefix", "A"], |s| {
+					serde_json::from_slice::<Object>(s)
+				});
+			let keypair = secp256k1::Keypair::from_secret_key(secp256k1::SECP256K1, &obj.secret);
+			assert_eq!(keypair.x_only_public_key(), (obj.x_only, secp256k1::Parity::Even));
+			assert_eq!(obj.parity, 0);
+			assert!(obj.x_only.to_string().starts_with('a'));
+			assert!(obj.attempts > 0);
+		}
+	}
+
+	assert_cmd(
+		&["simplicity", "keypair", "grind", "--prefix", "xyz"],
+		"Execution failed: invalid prefix 'xyz', expected hex digits\n",
+		"",
+	);
+	assert_cmd(
+		&["simplicity", "keypair", "grind", "--prefix", "abcde"],
+		"Execution failed: the prefix 'abcde' is too long to grind, use at most 4 hex digits\n",
+		"",
+	);
+}
+
+#[test]
+fn cli_simplicity_keypair_recover() {
+	let expected_help = "\
+hal-simplicity-keypair-recover 
+recover the public keys which made a compact ECDSA signature
+
+USAGE:
+    hal simplicity keypair recover [FLAGS] [OPTIONS] <signature> <digest>
+
+FLAGS:
+    -h, --help          Prints help information
+        --no-newline    don't print a trailing newline after a raw value
+    -v, --verbose       print verbose logging output to stderr
+    -y, --yaml          print output in YAML instead of JSON
+
+OPTIONS:
+        --log-format <log-format>      the format of the logging output [possible values: text, json]
+        --out <out>                    write the output to the given file instead of stdout, gzipped if it ends in .gz
+    -o, --output <output>              the output format (see `schema --proto` for the protobuf messages) [possible
+                                       values: json, yaml, protobuf]
+        --raw-value <raw-value>        print only the given output field (e.g. txid) as a raw value
+        --recovery-id <recovery-id>    the recovery ID (0 to 3) of a 64-byte signature (default: try all of them)
+
+ARGS:
+    <signature>    the signature in hex or base64, either 64 bytes or 65 bytes starting with a recovery header as
+                   made by signmessage
+    <digest>       the signed 32-byte message digest in hex
+";
+	assert_cmd(&["simplicity", "keypair", "recover", "-h"], expected_help, "");
+	assert_cmd(&["simplicity", "keypair", "recover", "--help"], expected_help, "");
+
+	{
+		use elements::bitcoin::base64::engine::general_purpose::STANDARD as BASE64;
+		use elements::bitcoin::base64::Engine;
+		use elements::bitcoin::secp256k1;
+
+		#[derive(serde::Deserialize)]
+		struct Object {
+			recovery_id: u8,
+			public_key: secp256k1::PublicKey,
+			compressed: Option<bool>,
+		}
+
+		let secret: secp256k1::SecretKey =
+			"0000000000000000000000000000000000000000000000000000000000000003".parse().unwrap();
+		let public_key = secret.public_key(secp256k1::SECP256K1);
+		let digest = "01".repeat(32);
+		let msg = secp256k1::Message::from_digest([1; 32]);
+		let sig = secp256k1::SECP256K1.sign_ecdsa_recoverable(&msg, &secret);
+		let (recid, compact) = sig.serialize_compact();
+		let recid = recid.to_i32() as u8;
+		let compact_hex = compact.to_lower_hex_string();
+
+		// Without the recovery ID, every key which can be recovered is listed.
+		let keys = assert_deserialize_cmd(
+			&["simplicity", "keypair", "recover", &compact_hex, &digest],
+			|s| serde_json::from_slice::<Vec<Object>>(s),
+		);
+		assert!(keys.len() >= 2);
+		assert!(keys.iter().any(|key| key.recovery_id == recid && key.public_key == public_key));
+		assert!(keys.iter().all(|key| key.compressed.is_none()));
+
+		assert_cmd(
+			&[
+				"simplicity",
+				"keypair",
+				"recover",
+				"--recovery-id",
+				&recid.to_string(),
+				"--raw-value",
+				"0.public_key",
+				&compact_hex,
+				&digest,
+			],
+			format!("{}\n", public_key),
+			"",
+		);
+
+		// The header of a signmessage signature gives the recovery ID and compression.
+		let mut signed = vec![31 + recid];
+		signed.extend_from_slice(&compact);
+		let keys = assert_deserialize_cmd(
+			&["simplicity", "keypair", "recover", &BASE64.encode(&signed), &digest],
+			|s| serde_json::from_slice::<Vec<Object>>(s),
+		);
+		assert_eq!(keys.len(), 1);
+		assert_eq!((keys[0].recovery_id, keys[0].public_key), (recid, public_key));
+		assert_eq!(keys[0].compressed, Some(true));
+
+		signed[0] = 26;
+		assert_cmd(
+			&["simplicity", "keypair", "recover", &signed.to_lower_hex_string(), &digest],
+			"Execution failed: invalid signature header 26, expected 27 to 34\n",
+			"",
+		);
+		assert_cmd(
+			&["simplicity", "keypair", "recover", "--recovery-id", "4", &compact_hex, &digest],
+			"Execution failed: invalid recovery ID, expected 0 to 3: InvalidRecoveryId\n",
+			"",
+		);
+	}
+	assert_cmd(
+		&["simplicity", "keypair", "recover", "0011", &"01".repeat(32)],
+		"Execution failed: invalid signature of 2 bytes, expected 64 or 65\n",
+		"",
+	);
+}
+
+#[test]
+fn cli_simplicity_pegin() {
+	let expected_help = "\
+hal-simplicity-pegin 
+peg Bitcoin into an Elements chain
+
+USAGE:
+    hal simplicity pegin [FLAGS] [OPTIONS] <SUBCOMMAND>
+
+FLAGS:
+    -h, --help       Prints help information
+    -v, --verbose    print verbose logging output to stderr
+
+OPTIONS:
+        --log-format <log-format>    the format of the logging output [possible values: text, json]
+        --out <out>                  write the output to the given file instead of stdout, gzipped if it ends in .gz
+
+SUBCOMMANDS:
+    address    derive the mainchain address to send Bitcoin to peg in
+    claim      build the unsigned transaction claiming a peg-in from its mainchain transaction
+";
+	assert_cmd(&["simplicity", "pegin", "-h"], expected_help, "");
+	assert_cmd(&["simplicity", "pegin", "--help"], expected_help, "");
+}
+
+#[test]
+fn cli_simplicity_pegin_address() {
+	let expected_help = "\
+hal-simplicity-pegin-address 
+derive the mainchain address to send Bitcoin to peg in
+
+USAGE:
+    hal simplicity pegin address [FLAGS] [OPTIONS] --claim-script <claim-script>
+
+FLAGS:
+    -r, --elementsregtest    run in elementsregtest mode
+    -h, --help               Prints help information
+        --liquid             run in liquid mode
+        --liquidtestnet      run in liquidtestnet mode
+        --no-newline         don't print a trailing newline after a raw value
+        --p2wsh              use a native P2WSH address instead of P2SH-wrapped P2WSH
+    -v, --verbose            print verbose logging output to stderr
+    -y, --yaml               print output in YAML instead of JSON
+
+OPTIONS:
+    -c, --claim-script <claim-script>      the script claiming the peg-in on the Elements chain, in hex or as an address
+        --fedpeg-script <fedpeg-script>    the federation's fedpegscript in hex (defaults to OP_TRUE on elementsregtest)
+        --log-format <log-format>          the format of the logging output [possible values: text, json]
+        --out <out>                        write the output to the given file instead of stdout, gzipped if it ends in
+                                           .gz
+    -o, --output <output>                  the output format (see `schema --proto` for the protobuf messages) [possible
+                                           values: json, yaml, protobuf]
+        --raw-value <raw-value>            print only the given output field (e.g. txid) as a raw value
+";
+	assert_cmd(&["simplicity", "pegin", "address", "-h"], expected_help, "");
+	assert_cmd(&["simplicity", "pegin", "address", "--help"], expected_help, "");
+
+	let claim_script = "00141111111111111111111111111111111111111111";
+	// The default elementsregtest fedpegscript OP_TRUE has no key to tweak.
+	assert_cmd(
+		&["simplicity", "pegin", "address", "-c", claim_script],
+		r#"{
+  "mainchain_address": "2N3i4C56DiqfpdcAJsAdZd2xYpCQMRAroye",
+  "witness_script": "51",
+  "claim_script": "00141111111111111111111111111111111111111111"
+}"#,
+		"",
+	);
+	// A 1-of-1 multisig of the generator, whose key is tweaked with the claim script.
+	let fedpeg_script =
+		"51210279be667ef9dcbbac55a06295ce870b07029bfcdb2dce28d959f2815b16f8179851ae";
+	assert_cmd(
+		&[
+			"simplicity",
+			"pegin",
+			"address",
+			"--liquid",
+			"-c",
+			claim_script,
+			"--fedpeg-script",
+			fedpeg_script,
+		],
+		r#"{
+  "mainchain_address": "344QXSiVdHsMy7g4SsFjkTemwACYyxSXvx",
+  "witness_script": "512103861bbd98db6ac3063bd53bbd4617f304d161b4f81b0cab5766a05f995ccbea5f51ae",
+  "claim_script": "00141111111111111111111111111111111111111111"
+}"#,
+		"",
+	);
+	assert_cmd(
+		&[
+			"simplicity",
+			"pegin",
+			"address",
+			"--liquid",
+			"-c",
+			claim_script,
+			"--fedpeg-script",
+			fedpeg_script,
+			"--p2wsh",
+			"--raw-value",
+			"mainchain_address",
+		],
+		"bc1q0z07wftkxalfphmljeg9jllc6ua0hxeu6z3hnxjdkxtaj2gl99fsa6emn7\n",
+		"",
+	);
+	assert_cmd(
+		&["simplicity", "pegin", "address", "--liquid", "-c", claim_script],
+		"Execution failed: --fedpeg-script is required on Liquid\n",
+		"",
+	);
+}
+
+#[test]
+fn cli_simplicity_pegin_claim() {
+	let expected_help = "\
+hal-simplicity-pegin-claim 
+build the unsigned transaction claiming a peg-in from its mainchain transaction
+
+USAGE:
+    hal simplicity pegin claim [FLAGS] [OPTIONS] <mainchain-tx> <txoutproof> --claim-script <claim-script> --fee <fee>
+
+FLAGS:
+    -r, --elementsregtest    run in elementsregtest mode
+    -h, --help               Prints help information
+        --liquid             run in liquid mode
+        --liquidtestnet      run in liquidtestnet mode
+        --no-newline         don't print a trailing newline after a raw value
+        --p2wsh              use a native P2WSH address instead of P2SH-wrapped P2WSH
+    -v, --verbose            print verbose logging output to stderr
+    -y, --yaml               print output in YAML instead of JSON
+
+OPTIONS:
+    -a, --address <address>
+            the Elements address receiving the peg-in (defaults to the claim script)
+
+        --asset <asset>                                      the pegged asset (defaults to the network's)
+    -c, --claim-script <claim-script>
+            the script claiming the peg-in on the Elements chain, in hex or as an address
+
+        --fedpeg-script <fedpeg-script>
+            the federation's fedpegscript in hex (defaults to OP_TRUE on elementsregtest)
+
+    -f, --fee <fee>                                          the fee of the claim transaction in satoshis
+        --log-format <log-format>
+            the format of the logging output [possible values: text, json]
+
+        --mainchain-genesis-hash <mainchain-genesis-hash>
+            the genesis block hash of the mainchain (defaults to the network's)
+
+        --out <out>
+            write the output to the given file instead of stdout, gzipped if it ends in .gz
+
+    -o, --output <output>
+            the output format (see `schema --proto` for the protobuf messages) [possible values: json, yaml, protobuf]
+
+        --raw-value <raw-value>
+            print only the given output field (e.g. txid) as a raw value
+
+
+ARGS:
+    <mainchain-tx>    the mainchain transaction in hex, base64 or as a file path
+    <txoutproof>      the proof of the mainchain transaction's inclusion in a block, as returned by gettxoutproof
+";
+	assert_cmd(&["simplicity", "pegin", "claim", "-h"], expected_help, "");
+	assert_cmd(&["simplicity", "pegin", "claim", "--help"], expected_help, "");
+
+	// A regtest transaction paying 1 BTC to the peg-in address of the claim script
+	// with the default fedpegscript, and a proof of its inclusion in a block of its own.
+	let claim_script = "00141111111111111111111111111111111111111111";
+	let mainchain_tx = "020000000122222222222222222222222222222222222222222222222222222222222222220000000000ffffffff0200e1f5050000000017a91472c44f957fc011d97e3406667dca5b1c930c4026878813000000000000160014333333333333333333333333333333333333333300000000";
+	let txoutproof = "0000002000000000000000000000000000000000000000000000000000000000000000004549de14faefef580366ca985f9b403f221b3c2c3487d4ca7d285fe86fef5409dae5494dffff7f200000000001000000014549de14faefef580366ca985f9b403f221b3c2c3487d4ca7d285fe86fef54090101";
+	let expected_claim = r#"{
+  "tx": "0200000001014549de14faefef580366ca985f9b403f221b3c2c3487d4ca7d285fe86fef54090000004000ffffffff0201230f4f5d4b7c6fa845806ee4f67713459e1b69e8e60fcee2e4940c7a0d5de1b2010000000005f5dd1800160014111111111111111111111111111111111111111101230f4f5d4b7c6fa845806ee4f67713459e1b69e8e60fcee2e4940c7a0d5de1b20100000000000003e8000000000000000000060800e1f5050000000020230f4f5d4b7c6fa845806ee4f67713459e1b69e8e60fcee2e4940c7a0d5de1b22006226e46111a0b59caaf126043eb5bbf28c34f3a5e332a1fc7b2b73cf188910f160014111111111111111111111111111111111111111172020000000122222222222222222222222222222222222222222222222222222222222222220000000000ffffffff0200e1f5050000000017a91472c44f957fc011d97e3406667dca5b1c930c4026878813000000000000160014333333333333333333333333333333333333333300000000770000002000000000000000000000000000000000000000000000000000000000000000004549de14faefef580366ca985f9b403f221b3c2c3487d4ca7d285fe86fef5409dae5494dffff7f200000000001000000014549de14faefef580366ca985f9b403f221b3c2c3487d4ca7d285fe86fef5409010100000000",
+  "txid": "fc2b4f25d01a83f8f1d942b0a788d3dca61e66240b6979bd39fd671223a7de02",
+  "mainchain_txid": "0954ef6fe85f287dcad487342c3c1b223f409b5f98ca660358efeffa14de4945",
+  "mainchain_vout": 0,
+  "value": 100000000,
+  "fee": 1000,
+  "claim_script": "00141111111111111111111111111111111111111111"
+}"#;
+	assert_cmd(
+		&[
+			"simplicity",
+			"pegin",
+			"claim",
+			"-c",
+			claim_script,
+			"-f",
+			"1000",
+			mainchain_tx,
+			txoutproof,
+		],
+		expected_claim,
+		"",
+	);
+	// The claim input carries the peg-in data.
+	let claim_tx = "0200000001014549de14faefef580366ca985f9b403f221b3c2c3487d4ca7d285fe86fef54090000004000ffffffff0201230f4f5d4b7c6fa845806ee4f67713459e1b69e8e60fcee2e4940c7a0d5de1b2010000000005f5dd1800160014111111111111111111111111111111111111111101230f4f5d4b7c6fa845806ee4f67713459e1b69e8e60fcee2e4940c7a0d5de1b20100000000000003e8000000000000000000060800e1f5050000000020230f4f5d4b7c6fa845806ee4f67713459e1b69e8e60fcee2e4940c7a0d5de1b22006226e46111a0b59caaf126043eb5bbf28c34f3a5e332a1fc7b2b73cf188910f160014111111111111111111111111111111111111111172020000000122222222222222222222222222222222222222222222222222222222222222220000000000ffffffff0200e1f5050000000017a91472c44f957fc011d97e3406667dca5b1c930c4026878813000000000000160014333333333333333333333333333333333333333300000000770000002000000000000000000000000000000000000000000000000000000000000000004549de14faefef580366ca985f9b403f221b3c2c3487d4ca7d285fe86fef5409dae5494dffff7f200000000001000000014549de14faefef580366ca985f9b403f221b3c2c3487d4ca7d285fe86fef5409010100000000";
+	assert_cmd(
+		&["simplicity", "tx", "decode", "--raw-value", "inputs.0.pegin_data.value", claim_tx],
+		"100000000\n",
+		"",
+	);
+	assert_cmd(
+		&[
+			"simplicity",
+			"pegin",
+			"claim",
+			"-c",
+			claim_script,
+			"-f",
+			"100000000",
+			mainchain_tx,
+			txoutproof,
+		],
+		"Execution failed: fee of 100000000 sat is not less than the peg-in value of 100000000 sat\n",
+		"",
+	);
+	assert_cmd(
+		&[
+			"simplicity",
+			"pegin",
+			"claim",
+			"-c",
+			claim_script,
+			"-f",
+			"1000",
+			"--p2wsh",
+			mainchain_tx,
+			txoutproof,
+		],
+		"Execution failed: mainchain tx has no output paying to bcrt1qft5p2uhsdcdc3l2ua4ap5qqfg4pjaqlp250x7us7a8qqhrxrxfsqseac85\n",
+		"",
+	);
+}
+
+#[test]
+fn cli_simplicity_schema() {
+	let expected_help = "\
+hal-simplicity-schema 
+print the JSON schemas of the outputs of the commands
+
+USAGE:
+    hal simplicity schema [FLAGS] [OPTIONS] [command]...
+
+FLAGS:
+    -h, --help       Prints help information
+        --proto      print the protobuf messages used by `--output protobuf` instead
+    -v, --verbose    print verbose logging output to stderr
+    -y, --yaml       print output in YAML instead of JSON
+
+OPTIONS:
+        --log-format <log-format>    the format of the logging output [possible values: text, json]
+        --out <out>                  write the output to the given file instead of stdout, gzipped if it ends in .gz
+
+ARGS:
+    <command>...    the command to print the schema of, e.g. 'tx decode' (default: all)
+";
+	assert_cmd(&["simplicity", "schema", "-h"], expected_help, "");
+	assert_cmd(&["simplicity", "schema", "--help"], expected_help, "");
+
+	assert_cmd(
+		&["simplicity", "schema", "keypair", "generate"],
+		r#"{
+  "$schema": "http://json-schema.org/draft-07/schema#",
+  "title": "KeypairInfo",
+  "type": "object",
+  "required": [
+    "descriptor_public_key",
+    "descriptor_secret_key",
+    "parity",
+    "secret",
+    "x_only"
+  ],
+  "properties": {
+    "descriptor_public_key": {
+      "description": "The x-only public key as a descriptor key expression with its fingerprint as origin, e.g. for `eltr(...)`.",
+      "type": "string"
+    },
+    "descriptor_secret_key": {
+      "description": "The secret key as a descriptor key expression, in WIF with its fingerprint as origin.",
+      "type": "string"
+    },
+    "parity": {
+      "type": "integer",
+      "format": "uint8",
+      "minimum": 0.0
+    },
+    "secret": {
+      "type": "string"
+    },
+    "x_only": {
+      "type": "string"
+    }
+  }
+}"#,
+		"",
+	);
+
+	assert_cmd(
+		&["simplicity", "schema", "foo"],
+		"Execution failed: no schema for command 'foo'\n",
+		"",
+	);
+
+	// The published messages are up to date. After changing an output, regenerate
+	// them with `hal-simplicity simplicity schema --proto > proto/hal_simplicity.proto`.
+	assert_cmd(
+		&["simplicity", "schema", "--proto"],
+		include_str!("../proto/hal_simplicity.proto"),
+		"",
+	);
+
+	assert_cmd(
+		&["simplicity", "schema", "--proto", "simplicity", "witness-types"],
+		r#"syntax = "proto3";
+
+package hal_simplicity;
+
+// The output of `simplicity witness-types`.
+message WitnessTypesInfo {
+  uint64 disconnect_count = 1;
+  uint64 max_witness_bits = 2;
+  repeated WitnessNode nodes = 3;
+  uint64 witness_count = 4;
+}
+
+message WitnessNode {
+  uint64 bit_width = 1;
+  uint64 index = 2;
+  string kind = 3;
+  string type = 4;
+}
+
+"#,
+		"",
+	);
+}
+
+#[test]
+fn cli_simplicity_session() {
+	let expected_help = "\
+hal-simplicity-session 
+collaborative signing of Simplicity spends
+
+USAGE:
+    hal simplicity session [FLAGS] [OPTIONS] <SUBCOMMAND>
+
+FLAGS:
+    -h, --help       Prints help information
+    -v, --verbose    print verbose logging output to stderr
+
+OPTIONS:
+        --log-format <log-format>    the format of the logging output [possible values: text, json]
+        --out <out>                  write the output to the given file instead of stdout, gzipped if it ends in .gz
+
+SUBCOMMANDS:
+    add-sig     add signatures or preimages to a session file
+    create      start a signing session for a transaction in a new session file
+    finalize    build the signed transaction from a complete session file
+    status      show the signatures collected and missing in a session file
+";
+	assert_cmd(&["simplicity", "session", "-h"], expected_help, "");
+	assert_cmd(&["simplicity", "session", "--help"], expected_help, "");
+}
+
+/// Start a session in a fresh temporary file spending the 2-of-2 multisig output
+/// of `SESSION_UTXO`, returning the path of the file.
+fn create_session(name: &str) -> String {
+	let path = std::env::temp_dir().join(format!(
+		"hal-simplicity-session-{}-{}.json",
+		name,
+		std::process::id()
+	));
+	let path = path.to_str().unwrap().to_owned();
+	let _ = std::fs::remove_file(&path);
+	let output = self_command()
+		.args(["simplicity", "session", "create", &path, SESSION_TX, "-i", SESSION_UTXO])
+		.args(["-p", &format!("0:{}", SESSION_POLICY), "--raw-value", "complete"])
+		.output()
+		.unwrap();
+	assert_eq!(String::from_utf8(output.stdout).unwrap(), "false\n");
+	path
+}
+
+#[test]
+fn cli_simplicity_session_add_sig() {
+	let expected_help = "\
+hal-simplicity-session-add-sig 
+add signatures or preimages to a session file
+
+USAGE:
+    hal simplicity session add-sig [FLAGS] [OPTIONS] <session>
+
+FLAGS:
+    -h, --help          Prints help information
+        --no-newline    don't print a trailing newline after a raw value
+    -v, --verbose       print verbose logging output to stderr
+    -y, --yaml          print output in YAML instead of JSON
+
+OPTIONS:
+        --audit-log <audit-log>         append a hash-chained record of the operation to the given file
+        --log-format <log-format>       the format of the logging output [possible values: text, json]
+        --out <out>                     write the output to the given file instead of stdout, gzipped if it ends in .gz
+    -o, --output <output>               the output format (see `schema --proto` for the protobuf messages) [possible
+                                        values: json, yaml, protobuf]
+        --preimage <preimage>...        a 32-byte preimage of a sha256 fragment in hex; may be given several times
+        --raw-value <raw-value>         print only the given output field (e.g. txid) as a raw value
+    -s, --secret-key <secret-key>...    a secret key to sign all inputs whose policy has its public key; may be given
+                                        several times
+        --signature <signature>...      a signature made elsewhere as INDEX:PUBKEY:SIGNATURE; may be given several times
+
+ARGS:
+    <session>    the path of the session file
+";
+	assert_cmd(&["simplicity", "session", "add-sig", "-h"], expected_help, "");
+	assert_cmd(&["simplicity", "session", "add-sig", "--help"], expected_help, "");
+
+	let path = create_session("add-sig");
+	assert_cmd(
+		&["simplicity", "session", "add-sig", &path, "-s", "0000000000000000000000000000000000000000000000000000000000000003"],
+		"Execution failed: key f9308a019258c31049344f85f89d5229b531c845836f99b08601f113bce036f9 is not in the policy of any input\n",
+		"",
+	);
+	// A signature of the wrong message is rejected.
+	assert_cmd(
+		&["simplicity", "session", "add-sig", &path, "--signature", "0:c6047f9441ed7d6d3045406e95c07cd85c778e4b8cef3ca7abac09b95c709ee5:107e8152388cecff3e0dc8af1bea5f0897a6b54fb5a1ecf31d4b2b682684b648e8909c7f5ecd23d30656c6a36bb1a87e1a98e760a115abbadd4e6e3245f751d9"],
+		"Execution failed: invalid signature by c6047f9441ed7d6d3045406e95c07cd85c778e4b8cef3ca7abac09b95c709ee5 for input 0\n",
+		"",
+	);
+	assert_cmd(
+		&[
+			"simplicity",
+			"session",
+			"add-sig",
+			&path,
+			"--signature",
+			"1:c6047f9441ed7d6d3045406e95c07cd85c778e4b8cef3ca7abac09b95c709ee5:00",
+		],
+		"Execution failed: signature index 1 out of range for 1 inputs\n",
+		"",
+	);
+	assert_cmd(
+		&[
+			"simplicity",
+			"session",
+			"add-sig",
+			&path,
+			"-s",
+			"0000000000000000000000000000000000000000000000000000000000000001",
+		],
+		r#"{
+  "txid": "f60a7be9a32458f9c7aa177a90804e0acec9a7e1d6b476a6a3859eeb4aa1a802",
+  "inputs": [
+    {
+      "index": 0,
+      "cmr": "4f759c5d73e401998c9264ff96922d7d9073c56f9574208955bbe2df10cc3d4d",
+      "signed": [
+        "79be667ef9dcbbac55a06295ce870b07029bfcdb2dce28d959f2815b16f81798"
+      ],
+      "unsigned": [
+        "c6047f9441ed7d6d3045406e95c07cd85c778e4b8cef3ca7abac09b95c709ee5"
+      ],
+      "ready": false
+    }
+  ],
+  "complete": false
+}"#,
+		"",
+	);
+	// The signature of the second signer, as made by `simplicity sighash`.
+	assert_cmd(
+		&["simplicity", "session", "add-sig", &path, "--raw-value", "complete", "--signature", "0:c6047f9441ed7d6d3045406e95c07cd85c778e4b8cef3ca7abac09b95c709ee5:643e9ac7d77fd26994637ec5e395f556b1d0d8740a87a0e080a54026bedd7e4e2cac034d97e5c33c0da6b8e35618439db08de43e93cb09fc3fc40f58d8ad56e1"],
+		"true\n",
+		"",
+	);
+	assert_cmd(
+		&["simplicity", "session", "add-sig", &path, "--preimage", "0000000000000000000000000000000000000000000000000000000000000000", "--raw-value", "complete"],
+		"true\n",
+		"No policy has a sha256(66687aadf862bd776c8fc18b8e9f8e20089714856ee233b3902a591d0d5f2925) fragment.\n",
+	);
+	std::fs::remove_file(&path).unwrap();
+
+	// A session file whose sighash was swapped for another message isn't signed.
+	let path = create_session("add-sig-tampered");
+	let contents = std::fs::read_to_string(&path).unwrap();
+	let sighash = "1abd262e8b252cd85dfc77458cbb6f5363295480b475dbd2f135278a2e24317b";
+	std::fs::write(&path, contents.replace(sighash, &"11".repeat(32))).unwrap();
+	assert_cmd(
+		&[
+			"simplicity",
+			"session",
+			"add-sig",
+			&path,
+			"-s",
+			"0000000000000000000000000000000000000000000000000000000000000001",
+		],
+		"Execution failed: the sighash of input 0 doesn't match the transaction of the session\n",
+		"",
+	);
+	let cmr = "4f759c5d73e401998c9264ff96922d7d9073c56f9574208955bbe2df10cc3d4d";
+	std::fs::write(&path, contents.replace(cmr, &"11".repeat(32))).unwrap();
+	assert_cmd(
+		&["simplicity", "session", "status", &path],
+		"Execution failed: the CMR of input 0 doesn't match its policy\n",
+		"",
+	);
+	let control_block = "bef5919fa64ce45f8306849072b26c1bfdd2937e6b81774796ff372bd1eb5362d2";
+	std::fs::write(
+		&path,
+		contents.replace(
+			control_block,
+			"bff5919fa64ce45f8306849072b26c1bfdd2937e6b81774796ff372bd1eb5362d2",
+		),
+	)
+	.unwrap();
+	assert_cmd(
+		&["simplicity", "session", "status", &path],
+		"Execution failed: the control block of input 0 doesn't match its policy\n",
+		"",
+	);
+	std::fs::remove_file(&path).unwrap();
+}
+
+#[test]
+fn cli_simplicity_session_create() {
+	let expected_help = "\
+hal-simplicity-session-create 
+start a signing session for a transaction in a new session file
+
+USAGE:
+    hal simplicity session create [FLAGS] [OPTIONS] <session> <tx> --input-utxo <input-utxo>... --policy <policy>...
+
+FLAGS:
+    -h, --help          Prints help information
+        --no-newline    don't print a trailing newline after a raw value
+    -v, --verbose       print verbose logging output to stderr
+    -y, --yaml          print output in YAML instead of JSON
+
+OPTIONS:
+    -g, --genesis-hash <genesis-hash>    the genesis block hash of the network (defaults to Liquid)
+    -i, --input-utxo <input-utxo>...     an output spent by the transaction as SCRIPTPUBKEY:ASSET:VALUE, given once per
+                                         input
+        --log-format <log-format>        the format of the logging output [possible values: text, json]
+        --out <out>                      write the output to the given file instead of stdout, gzipped if it ends in .gz
+    -o, --output <output>                the output format (see `schema --proto` for the protobuf messages) [possible
+                                         values: json, yaml, protobuf]
+    -p, --policy <policy>...             the policy of the Simplicity program spent by an input as INDEX:POLICY; may be
+                                         given several times
+        --raw-value <raw-value>          print only the given output field (e.g. txid) as a raw value
+
+ARGS:
+    <session>    the path of the session file
+    <tx>         the unsigned transaction in hex, base64 or as a file path
+";
+	assert_cmd(&["simplicity", "session", "create", "-h"], expected_help, "");
+	assert_cmd(&["simplicity", "session", "create", "--help"], expected_help, "");
+
+	let path = create_session("create");
+	let session: serde_json::Value =
+		serde_json::from_str(&std::fs::read_to_string(&path).unwrap()).unwrap();
+	assert_eq!(session["version"], 1);
+	assert_eq!(session["tx"], SESSION_TX);
+	assert_eq!(session["inputs"][0]["utxo"], SESSION_UTXO);
+	assert_eq!(
+		session["inputs"][0]["simplicity"]["sighash"],
+		"1abd262e8b252cd85dfc77458cbb6f5363295480b475dbd2f135278a2e24317b"
+	);
+	assert_eq!(
+		session["inputs"][0]["simplicity"]["control_block"],
+		"bef5919fa64ce45f8306849072b26c1bfdd2937e6b81774796ff372bd1eb5362d2"
+	);
+	assert_cmd(
+		&[
+			"simplicity",
+			"session",
+			"create",
+			&path,
+			SESSION_TX,
+			"-i",
+			SESSION_UTXO,
+			"-p",
+			"0:pk(79be667ef9dcbbac55a06295ce870b07029bfcdb2dce28d959f2815b16f81798)",
+		],
+		format!("Execution failed: session file {} already exists\n", path),
+		"",
+	);
+	std::fs::remove_file(&path).unwrap();
+	assert_cmd(
+		&["simplicity", "session", "create", &path, SESSION_TX, "-i", SESSION_UTXO, "-p", "0:pk(79be667ef9dcbbac55a06295ce870b07029bfcdb2dce28d959f2815b16f81798)"],
+		"Execution failed: the UTXO of input 0 is not an output of the policy pk(79be667ef9dcbbac55a06295ce870b07029bfcdb2dce28d959f2815b16f81798)\n",
+		"",
+	);
+	assert_cmd(
+		&[
+			"simplicity",
+			"session",
+			"create",
+			&path,
+			SESSION_TX,
+			"-i",
+			SESSION_UTXO,
+			"-i",
+			SESSION_UTXO,
+			"-p",
+			"0:pk(79be667ef9dcbbac55a06295ce870b07029bfcdb2dce28d959f2815b16f81798)",
+		],
+		"Execution failed: 2 input UTXOs given for 1 inputs\n",
+		"",
+	);
+	assert!(!std::path::Path::new(&path).exists());
+}
+
+#[test]
+fn cli_simplicity_session_finalize() {
+	let expected_help = "\
+hal-simplicity-session-finalize 
+build the signed transaction from a complete session file
+
+USAGE:
+    hal simplicity session finalize [FLAGS] [OPTIONS] <session>
+
+FLAGS:
+    -h, --help          Prints help information
+        --no-newline    don't print a trailing newline after a raw value
+    -v, --verbose       print verbose logging output to stderr
+    -y, --yaml          print output in YAML instead of JSON
+
+OPTIONS:
+        --log-format <log-format>    the format of the logging output [possible values: text, json]
+        --out <out>                  write the output to the given file instead of stdout, gzipped if it ends in .gz
+    -o, --output <output>            the output format (see `schema --proto` for the protobuf messages) [possible
+                                     values: json, yaml, protobuf]
+        --raw-value <raw-value>      print only the given output field (e.g. txid) as a raw value
+
+ARGS:
+    <session>    the path of the session file
+";
+	assert_cmd(&["simplicity", "session", "finalize", "-h"], expected_help, "");
+	assert_cmd(&["simplicity", "session", "finalize", "--help"], expected_help, "");
+
+	let path = create_session("finalize");
+	assert_cmd(
+		&["simplicity", "session", "finalize", &path],
+		"Execution failed: input 0 can't be satisfied with the signatures and preimages collected\n",
+		"",
+	);
+	for sk in [
+		"0000000000000000000000000000000000000000000000000000000000000001",
+		"0000000000000000000000000000000000000000000000000000000000000002",
+	] {
+		let status = self_command()
+			.args(["simplicity", "session", "add-sig", &path, "-s", sk])
+			.status()
+			.unwrap();
+		assert!(status.success());
+	}
+	// The signatures use fresh randomness, so only the witness data is different
+	// between runs.
+	let info: serde_json::Value =
+		assert_deserialize_cmd(&["simplicity", "session", "finalize", &path], |s| {
+			serde_json::from_slice(s)
+		});
+	assert_eq!(info["txid"], "f60a7be9a32458f9c7aa177a90804e0acec9a7e1d6b476a6a3859eeb4aa1a802");
+	let tx: elements::Transaction =
+		elements::encode::deserialize(&hex::decode(info["tx"].as_str().unwrap()).unwrap()).unwrap();
+	let expected: elements::Transaction = elements::encode::deserialize(&hex::decode("020000000101222222222222222222222222222222222222222222222222222222222222222200000000000000000002016f0279e9ed041c3d710a9f57d0c02928416460c4b722ae3457a11eec381c526d0100000000000182b80022512000c68b5f264f0c8db4cf7a90af36c9376c25d7184b3a129de99d56f40bb6e8a6016f0279e9ed041c3d710a9f57d0c02928416460c4b722ae3457a11eec381c526d0100000000000003e800000000000000000481883f40a91c46767f9f06e4578df52f844bd35aa7dad0f6798ea595b413425b2474484e3faf6691e9832b6351b5d8d43f0d4c73b0508ad5dd6ea7371922fba8ecb21f4d63ebbfe934ca31bf62f1cafaab58e86c3a0543d0704052a0135f6ebf27165601a6cbf2e19e06d35c71ab0c21ced846f21f49e584fe1fe207ac6c56ab708096e25b200000002748a1aa5f8d193c429951b328e32cf80e65d4228be0eb9e25368c05e846ec44cdd6176d2f37cccfdf3b97758ab40c52b9d0e160e0537f9b65b9c51b2b3e502b62df02f31c1438a363011640000000208383ac0dab4e3023fca20f6beb69822a0374ae03e6c2e3bc725c6779e53d5d604dcae384f7289b1c5006b0351c1e0807060b534b8821070285c40cdb88230080204f759c5d73e401998c9264ff96922d7d9073c56f9574208955bbe2df10cc3d4d21bef5919fa64ce45f8306849072b26c1bfdd2937e6b81774796ff372bd1eb5362d20000000000").unwrap()).unwrap();
+	assert_eq!(tx.txid().to_string(), info["txid"]);
+	let witness = &tx.input[0].witness.script_witness;
+	let expected = &expected.input[0].witness.script_witness;
+	assert_eq!(witness.len(), expected.len());
+	assert_eq!(witness[0].len(), expected[0].len());
+	assert_eq!(witness[1..], expected[1..]);
+	std::fs::remove_file(&path).unwrap();
+}
+
+#[test]
+fn cli_simplicity_session_status() {
+	let expected_help = "\
+hal-simplicity-session-status 
+show the signatures collected and missing in a session file
+
+USAGE:
+    hal simplicity session status [FLAGS] [OPTIONS] <session>
+
+FLAGS:
+    -h, --help          Prints help information
+        --no-newline    don't print a trailing newline after a raw value
+    -v, --verbose       print verbose logging output to stderr
+    -y, --yaml          print output in YAML instead of JSON
+
+OPTIONS:
+        --log-format <log-format>    the format of the logging output [possible values: text, json]
+        --out <out>                  write the output to the given file instead of stdout, gzipped if it ends in .gz
+    -o, --output <output>            the output format (see `schema --proto` for the protobuf messages) [possible
+                                     values: json, yaml, protobuf]
+        --raw-value <raw-value>      print only the given output field (e.g. txid) as a raw value
+
+ARGS:
+    <session>    the path of the session file
+";
+	assert_cmd(&["simplicity", "session", "status", "-h"], expected_help, "");
+	assert_cmd(&["simplicity", "session", "status", "--help"], expected_help, "");
+
+	let path = create_session("status");
+	assert_cmd(
+		&["simplicity", "session", "status", &path],
+		r#"{
+  "txid": "f60a7be9a32458f9c7aa177a90804e0acec9a7e1d6b476a6a3859eeb4aa1a802",
+  "inputs": [
+    {
+      "index": 0,
+      "cmr": "4f759c5d73e401998c9264ff96922d7d9073c56f9574208955bbe2df10cc3d4d",
+      "unsigned": [
+        "79be667ef9dcbbac55a06295ce870b07029bfcdb2dce28d959f2815b16f81798",
+        "c6047f9441ed7d6d3045406e95c07cd85c778e4b8cef3ca7abac09b95c709ee5"
+      ],
+      "ready": false
+    }
+  ],
+  "complete": false
+}"#,
+		"",
+	);
+	std::fs::remove_file(&path).unwrap();
+	assert_cmd(
+		&["simplicity", "session", "status", &path],
+		format!("Execution failed: could not read session file {}: No such file or directory (os error 2)\n", path),
+		"",
+	);
+}
+
+#[test]
+fn cli_simplicity_simplicity() {
+	let expected_help = "\
+hal-simplicity-simplicity 
+manipulate Simplicity programs
+
+USAGE:
+    hal simplicity simplicity [FLAGS] [OPTIONS] <SUBCOMMAND>
+
+FLAGS:
+    -h, --help       Prints help information
+    -v, --verbose    print verbose logging output to stderr
+
+OPTIONS:
+        --log-format <log-format>    the format of the logging output [possible values: text, json]
+        --out <out>                  write the output to the given file instead of stdout, gzipped if it ends in .gz
+
+SUBCOMMANDS:
+    cmr-at           Show the CMR and type of a sub-expression of a Simplicity program
+    htlc             Generate a hash-timeout contract as a Simplicity program
+    info             Parse a base64-encoded Simplicity program and decode it
+    match            Check whether an address commits to a Simplicity program
+    multisig         Generate a k-of-n multisignature Simplicity program
+    policy           Compile a policy into a Simplicity program
+    prune            Prune the branches of a program not executed by a spending transaction
+    roots            List the CMR, AMR, IHR and type Merkle roots of a Simplicity program
+    sighash          Compute the SIGHASH_ALL of a Simplicity transaction input
+    template         Generate a common Simplicity program from parameters
+    vault            Generate a covenant paying to a fixed destination with a recovery path
+    witness-types    List the witness and disconnect nodes of a Simplicity program with their types
+";
+	assert_cmd(&["simplicity", "simplicity"], "", expected_help);
+	assert_cmd(&["simplicity", "simplicity", "-h"], expected_help, "");
+	assert_cmd(&["simplicity", "simplicity", "--help"], expected_help, "");
+	assert_cmd(&["simplicity", "simplicity", "--help", "xyz"], expected_help, "");
+}
+
+#[test]
+fn cli_simplicity_simplicity_cmr_at() {
+	let expected_help = "\
+hal-simplicity-simplicity-cmr-at 
+Show the CMR and type of a sub-expression of a Simplicity program
+
+USAGE:
+    hal simplicity simplicity cmr-at [FLAGS] [OPTIONS] <program> <node>
+
+FLAGS:
+    -h, --help          Prints help information
+        --no-newline    don't print a trailing newline after a raw value
+    -v, --verbose       print verbose logging output to stderr
+    -y, --yaml          print output in YAML instead of JSON
+
+OPTIONS:
+        --log-format <log-format>    the format of the logging output [possible values: text, json]
+        --out <out>                  write the output to the given file instead of stdout, gzipped if it ends in .gz
+    -o, --output <output>            the output format (see `schema --proto` for the protobuf messages) [possible
+                                     values: json, yaml, protobuf]
+        --raw-value <raw-value>      print only the given output field (e.g. txid) as a raw value
+
+ARGS:
+    <program>    a Simplicity program in base64
+    <node>       the index of the node (as listed by info --format nodes), or a path from the root made of 'l' and
+                 'r' for the left and right children
+";
+	assert_cmd(&["simplicity", "simplicity", "cmr-at", "-h"], expected_help, "");
+	assert_cmd(&["simplicity", "simplicity", "cmr-at", "--help"], expected_help, "");
+
+	assert_cmd(
+		&[
+			"simplicity",
+			"simplicity",
+			"cmr-at",
+			"ztM33xqq2wHIdc/BfZZ0/NBxcrvfyLEt2sDrdyBroNkyycFDijYwEA==",
+			"ll",
+		],
+		r#"{
+  "index": 2,
+  "node": "pair",
+  "cmr": "b920d9457129fad2af959f1699d581528469437fca52e48908501aa505fb239c",
+  "type_arrow": "1 → 2^512",
+  "decode": "const 0x9bef8d556d80e43ae7e0becb3a7e6838b95defe45896ed6075bb9035d06c9964  & jet_sig_all_hash "
+}"#,
+		"",
+	);
+
+	assert_cmd(
+		&[
+			"simplicity",
+			"simplicity",
+			"cmr-at",
+			"ztM33xqq2wHIdc/BfZZ0/NBxcrvfyLEt2sDrdyBroNkyycFDijYwEA==",
+			"1",
+			"--raw-value",
+			"cmr",
+		],
+		"6ac53d3f93b8caf3ea8534ae612abd32325d2b0fec17de36af0f71aa978f7cde\n",
+		"",
+	);
+
+	assert_cmd(
+		&[
+			"simplicity",
+			"simplicity",
+			"cmr-at",
+			"ztM33xqq2wHIdc/BfZZ0/NBxcrvfyLEt2sDrdyBroNkyycFDijYwEA==",
+			"lrl",
+		],
+		"Execution failed: no child 'l' at depth 2 of path 'lrl'\n",
+		"",
+	);
+
+	assert_cmd(
+		&[
+			"simplicity",
+			"simplicity",
+			"cmr-at",
+			"ztM33xqq2wHIdc/BfZZ0/NBxcrvfyLEt2sDrdyBroNkyycFDijYwEA==",
+			"7",
+		],
+		"Execution failed: no node at index 7\n",
+		"",
+	);
+
+	// Paths to identical sub-expressions lead to the same, shared node.
+	let program = "4lsgAAAAF0iiyAAAAADrTN98aqtsByHXPwX2WdPzQcXK738ixLdrA63cga6DZMsnBQ4o2MBAG04OoDacTgDi2ANQHBjg8DAzC1NLiCEHAoXEDNuIIwCA";
+	for path in ["lll", "llrllrrrlr", "llrlllrrlr"] {
+		assert_cmd(
+			&["simplicity", "simplicity", "cmr-at", program, path, "--raw-value", "index"],
+			"0\n",
+			"",
+		);
+	}
+	assert_cmd(
+		&["simplicity", "simplicity", "cmr-at", program, "l", "--raw-value", "index"],
+		"34\n",
+		"",
+	);
+}
+
+#[test]
+fn cli_simplicity_simplicity_htlc() {
+	let expected_help = "\
+hal-simplicity-simplicity-htlc 
+Generate a hash-timeout contract as a Simplicity program
+
+USAGE:
+    hal simplicity simplicity htlc [FLAGS] [OPTIONS] --hash <hash> --recipient <recipient> --refund <refund> --timeout <timeout>
+
+FLAGS:
+    -h, --help          Prints help information
+        --no-newline    don't print a trailing newline after a raw value
+    -v, --verbose       print verbose logging output to stderr
+    -y, --yaml          print output in YAML instead of JSON
+
+OPTIONS:
+        --hash <hash>                the SHA256 hash whose preimage lets the recipient claim
+        --log-format <log-format>    the format of the logging output [possible values: text, json]
+        --out <out>                  write the output to the given file instead of stdout, gzipped if it ends in .gz
+    -o, --output <output>            the output format (see `schema --proto` for the protobuf messages) [possible
+                                     values: json, yaml, protobuf]
+        --raw-value <raw-value>      print only the given output field (e.g. txid) as a raw value
+        --recipient <recipient>      the x-only public key of the recipient
+        --refund <refund>            the x-only public key which can reclaim the coins after the timeout
+        --timeout <timeout>          the block height after which the refund path is enabled
+";
+	assert_cmd(&["simplicity", "simplicity", "htlc", "-h"], expected_help, "");
+	assert_cmd(&["simplicity", "simplicity", "htlc", "--help"], expected_help, "");
+
+	assert_cmd(
+		&[
+			"simplicity",
+			"simplicity",
+			"htlc",
+			"--hash",
+			"2bb80d537b1da3e38bd30361aa855686bde0eacd7162fef6a25fe97bf527a25b",
+			"--recipient",
+			"9bef8d556d80e43ae7e0becb3a7e6838b95defe45896ed6075bb9035d06c9964",
+			"--refund",
+			"f22ce68b963eab1522c3b19e3a53f20f19ea6ed61577c565956111bafe8e7cef",
+			"--timeout",
+			"3000000",
+		],
+		r#"{
+  "policy": "or(and(pk(9bef8d556d80e43ae7e0becb3a7e6838b95defe45896ed6075bb9035d06c9964),sha256(2bb80d537b1da3e38bd30361aa855686bde0eacd7162fef6a25fe97bf527a25b)),and(pk(f22ce68b963eab1522c3b19e3a53f20f19ea6ed61577c565956111bafe8e7cef),after(3000000)))",
+  "commit_base64": "4ldIotM33xqq2wHIdc/BfZZ0/NBxcrvfyLEt2sDrdyBroNkyycFDijYwEWkruA1Tex2j44vTA2GqhVaGveDqzXFi/vaiX+l79SeiW9WTijVcgRqxBAtDNugEYBANw60+RZzRcsfVYqRYdjPHSn5B4z1N2sKu+KyyrCI3X9HPneJwZxQBwWyAC3GwPAIBgODrA4iA",
+  "commit_decode": "(witness  & unit); case (drop ((((const 0x9bef8d556d80e43ae7e0becb3a7e6838b95defe45896ed6075bb9035d06c9964  & jet_sig_all_hash ) & witness ); jet_bip_0340_verify ); (((const 0x2bb80d537b1da3e38bd30361aa855686bde0eacd7162fef6a25fe97bf527a25b  & (((jet_sha_256_ctx_8_init  & witness ); jet_sha_256_ctx_8_add_32 ); jet_sha_256_ctx_8_finalize )); jet_eq_256 ); jet_verify ))) (drop ((((const 0xf22ce68b963eab1522c3b19e3a53f20f19ea6ed61577c565956111bafe8e7cef  & jet_sig_all_hash ) & witness ); jet_bip_0340_verify ); (const 0x002dc6c0 ; jet_check_lock_height )))",
+  "type_arrow": "1 → 1",
+  "cmr": "ceb063de6c7f75ef4df82bb9bbe0d01b8f8747366127da2046d18b36feea9694",
+  "liquid_address_unconf": "ex1pqrrgkhexfuxgmdx002g27dkfxakzt4ccfvap980fn4t0gzakaznqkarzrw",
+  "liquid_testnet_address_unconf": "tex1pqrrgkhexfuxgmdx002g27dkfxakzt4ccfvap980fn4t0gzakaznqhy39jp",
+  "control_block": "bff5919fa64ce45f8306849072b26c1bfdd2937e6b81774796ff372bd1eb5362d2",
+  "witness": [
+    {
+      "type": "2",
+      "bit_width": 1,
+      "description": "0 to claim, 1 to refund"
+    },
+    {
+      "type": "2^512",
+      "bit_width": 512,
+      "description": "BIP-340 signature by 9bef8d556d80e43ae7e0becb3a7e6838b95defe45896ed6075bb9035d06c9964 of the SIGHASH_ALL of the spending transaction (claim path only)"
+    },
+    {
+      "type": "2^256",
+      "bit_width": 256,
+      "description": "32-byte preimage of the SHA256 hash 2bb80d537b1da3e38bd30361aa855686bde0eacd7162fef6a25fe97bf527a25b (claim path only)"
+    },
+    {
+      "type": "2^512",
+      "bit_width": 512,
+      "description": "BIP-340 signature by f22ce68b963eab1522c3b19e3a53f20f19ea6ed61577c565956111bafe8e7cef of the SIGHASH_ALL of the spending transaction (refund path only)"
+    }
+  ],
+  "claim": {
+    "selector": 0,
+    "condition": "the recipient knows the preimage of 2bb80d537b1da3e38bd30361aa855686bde0eacd7162fef6a25fe97bf527a25b",
+    "witness": [
+      "BIP-340 signature by 9bef8d556d80e43ae7e0becb3a7e6838b95defe45896ed6075bb9035d06c9964 of the SIGHASH_ALL of the spending transaction",
+      "32-byte preimage of the SHA256 hash 2bb80d537b1da3e38bd30361aa855686bde0eacd7162fef6a25fe97bf527a25b"
+    ]
+  },
+  "refund": {
+    "selector": 1,
+    "condition": "the spending transaction has a locktime of at least 3000000",
+    "witness": [
+      "BIP-340 signature by f22ce68b963eab1522c3b19e3a53f20f19ea6ed61577c565956111bafe8e7cef of the SIGHASH_ALL of the spending transaction"
+    ]
+  }
+}"#,
+		"",
+	);
+
+	assert_cmd(
+		&[
+			"simplicity",
+			"simplicity",
+			"htlc",
+			"--hash",
+			"2bb80d537b1da3e38bd30361aa855686bde0eacd7162fef6a25fe97bf527a25b",
+			"--recipient",
+			"9bef8d556d80e43ae7e0becb3a7e6838b95defe45896ed6075bb9035d06c9964",
+			"--refund",
+			"f22ce68b963eab1522c3b19e3a53f20f19ea6ed61577c565956111bafe8e7cef",
+			"--timeout",
+			"3000000",
+			"--raw-value",
+			"refund.condition",
+		],
+		"the spending transaction has a locktime of at least 3000000\n",
+		"",
+	);
+	for timeout in ["0", "500000000"] {
+		assert_cmd(
+			&[
+				"simplicity",
+				"simplicity",
+				"htlc",
+				"--hash",
+				"2bb80d537b1da3e38bd30361aa855686bde0eacd7162fef6a25fe97bf527a25b",
+				"--recipient",
+				"9bef8d556d80e43ae7e0becb3a7e6838b95defe45896ed6075bb9035d06c9964",
+				"--refund",
+				"f22ce68b963eab1522c3b19e3a53f20f19ea6ed61577c565956111bafe8e7cef",
+				"--timeout",
+				timeout,
+			],
+			format!(
+				"Execution failed: invalid timeout {}, expected a block height from 1 to 499999999\n",
+				timeout
+			),
+			"",
+		);
+	}
+}
+
+#[test]
+fn cli_simplicity_simplicity_info() {
+	let expected_help = "\
+hal-simplicity-simplicity-info 
+Parse a base64-encoded Simplicity program and decode it
+
+USAGE:
+    hal simplicity simplicity info [FLAGS] [OPTIONS] <program> [witness]
+
+FLAGS:
+        --abbrev             abbreviate long hex fields such as rangeproofs and witnesses
+    -r, --elementsregtest    run in elementsregtest mode
+    -h, --help               Prints help information
+        --liquid             run in liquid mode
+        --liquidtestnet      run in liquidtestnet mode
+        --no-newline         don't print a trailing newline after a raw value
+    -v, --verbose            print verbose logging output to stderr
+    -y, --yaml               print output in YAML instead of JSON
+
+OPTIONS:
+        --format <format>            print the program as an expression, or as a table of its nodes with the sharing of
+                                     its encoding [possible values: expr, nodes]
+        --log-format <log-format>    the format of the logging output [possible values: text, json]
+        --out <out>                  write the output to the given file instead of stdout, gzipped if it ends in .gz
+    -o, --output <output>            the output format (see `schema --proto` for the protobuf messages) [possible
+                                     values: json, yaml, protobuf]
+        --raw-value <raw-value>      print only the given output field (e.g. txid) as a raw value
+
+ARGS:
+    <program>    a Simplicity program in base64, hex or as a file path
+    <witness>    all the witness data for the program in hex, base64 or as a file path
+";
+	// For the transaction/block create / decode functions we can take input by
+	// stdin as an undocumented JSON blob. FIXME we probably want to do this
+	// here (and in the other simplicity commands) to allow for very large
+	// programs and witnesses. But I'd rather do it properly (i.e. with some
+	// docs and help) so not gonna do it now.
+	assert_cmd(
+		&["simplicity", "simplicity", "info"],
+		"",
+		"\
+error: The following required arguments were not provided:
+    <program>
+
+USAGE:
+    hal simplicity simplicity info [FLAGS] [OPTIONS] <program> [witness]
+
+For more information try --help
+",
+	);
+	assert_cmd(&["simplicity", "simplicity", "info", "-h"], expected_help, "");
+	assert_cmd(&["simplicity", "simplicity", "info", "--help"], expected_help, "");
+	assert_cmd(&["simplicity", "simplicity", "info", "--help", "xyz"], expected_help, "");
+
+	// The node table follows the encoding, children referring to earlier nodes.
+	assert_cmd(
+		&[
+			"simplicity",
+			"simplicity",
+			"info",
+			"--format",
+			"nodes",
+			"-y",
+			"ztM33xqq2wHIdc/BfZZ0/NBxcrvfyLEt2sDrdyBroNkyycFDijYwEA==",
+		],
+		"---
+- index: 0
+  combinator: word(0x9bef8d556d80e43ae7e0becb3a7e6838b95defe45896ed6075bb9035d06c9964)
+  children: []
+  cmr: d66ab7774a03fe4a936b52726efa7989e52dd8e45a88df62aabf8c6c25094375
+  type_arrow: 1 → 2^256
+- index: 1
+  combinator: jet(sig_all_hash)
+  children: []
+  cmr: 6ac53d3f93b8caf3ea8534ae612abd32325d2b0fec17de36af0f71aa978f7cde
+  type_arrow: 1 → 2^256
+- index: 2
+  combinator: pair
+  children:
+    - 0
+    - 1
+  cmr: b920d9457129fad2af959f1699d581528469437fca52e48908501aa505fb239c
+  type_arrow: 1 → 2^512
+- index: 3
+  combinator: witness
+  children: []
+  cmr: a0fc8debd6796917c86b77aded82e6c61649889ae8f2ed65b57b41aa9d90e375
+  type_arrow: 1 → 2^512
+- index: 4
+  combinator: pair
+  children:
+    - 2
+    - 3
+  cmr: 53ed4e82840ec2b45222f3bca120bcc9d87f0be5075d463f82f1cb5a74b003a1
+  type_arrow: 1 → 2^1024
+- index: 5
+  combinator: jet(bip_0340_verify)
+  children: []
+  cmr: c9c45a8aec8659143bfe2af6ead48d4e0542453acae84b9bbb97656b670bdfdd
+  type_arrow: 2^1024 → 1
+- index: 6
+  combinator: comp
+  children:
+    - 4
+    - 5
+  cmr: 34fd0c6accd5689186fc10bde76af7870f210e7b59f55f7e88e70ce78cb9c7ba
+  type_arrow: 1 → 1",
+		"",
+	);
+}
+
+#[test]
+fn cli_simplicity_simplicity_roots() {
+	let expected_help = "\
+hal-simplicity-simplicity-roots 
+List the CMR, AMR, IHR and type Merkle roots of a Simplicity program
+
+USAGE:
+    hal simplicity simplicity roots [FLAGS] [OPTIONS] <program> [witness]
+
+FLAGS:
+    -h, --help          Prints help information
+        --no-newline    don't print a trailing newline after a raw value
+        --nodes         also list the roots of each node of the program
+    -v, --verbose       print verbose logging output to stderr
+    -y, --yaml          print output in YAML instead of JSON
+
+OPTIONS:
+        --log-format <log-format>    the format of the logging output [possible values: text, json]
+        --out <out>                  write the output to the given file instead of stdout, gzipped if it ends in .gz
+    -o, --output <output>            the output format (see `schema --proto` for the protobuf messages) [possible
+                                     values: json, yaml, protobuf]
+        --raw-value <raw-value>      print only the given output field (e.g. txid) as a raw value
+
+ARGS:
+    <program>    a Simplicity program in base64, hex or as a file path
+    <witness>    all the witness data for the program in hex, base64 or as a file path
+";
+	assert_cmd(&["simplicity", "simplicity", "roots", "-h"], expected_help, "");
+	assert_cmd(&["simplicity", "simplicity", "roots", "--help"], expected_help, "");
+
+	// Without the witness, a program with witness nodes has no AMR or IHR.
+	assert_cmd(
+		&[
+			"simplicity",
+			"simplicity",
+			"roots",
+			"ztM33xqq2wHIdc/BfZZ0/NBxcrvfyLEt2sDrdyBroNkyycFDijYwEA==",
+		],
+		"{
+  \"cmr\": \"34fd0c6accd5689186fc10bde76af7870f210e7b59f55f7e88e70ce78cb9c7ba\",
+  \"source_tmr\": \"50b38cd76475ff8929288bfcd0d9df0e4a241c0a5708572ad264192a4fe67bee\",
+  \"target_tmr\": \"50b38cd76475ff8929288bfcd0d9df0e4a241c0a5708572ad264192a4fe67bee\"
+}",
+		"",
+	);
+
+	// The witness gives the AMR and IHR of the program and its nodes, except for
+	// the hidden ones.
+	let pruned = "4ndIosgAAAAL4YijNuIIwCLTArFs1v32PDFCsiTr0+4z/bNPc5Z4vhld8+86AN4cip9kAAAAAfDUCBNrOiGc5Czz/7fGmfzX05uacOSENCf3bkYmhTCJad+bCYugwNAxC1M26AQBvBvWQAAAAD8NgIE4WgwPAAMAHCQahzF419uZZ4aiKDyXDtjsEPdCAa+I0JuklYXa1UCokZLPgGAcSA==";
+	assert_cmd(
+		&["simplicity", "simplicity", "roots", pruned, "00", "--raw-value", "amr"],
+		"c886158d07ee8f7c9bace652aa06bbcb416a65388a967b5493036d3e3dd86174\n",
+		"",
+	);
+	assert_cmd(
+		&[
+			"simplicity",
+			"simplicity",
+			"roots",
+			"--nodes",
+			pruned,
+			"00",
+			"--raw-value",
+			"nodes.38.ihr",
+		],
+		"7f89e60330bbe4776b2512626240ffae701620994c2df8ea542313b4044ac2c4\n",
+		"",
+	);
+	assert_cmd(
+		&["simplicity", "simplicity", "roots", "--nodes", pruned, "00", "--raw-value", "nodes.15"],
+		"{\"cmr\":\"744339c859e7ff6f8d33f9afa73734e1c908684feedc8c4d0a6112d3bf361317\",\"combinator\":\"hidden\",\"index\":15}\n",
+		"",
+	);
+
+	// A program which is valid hex too is read as base64, as by `simplicity info`.
+	let output =
+		self_command().args(["simplicity", "simplicity", "roots", "deadbeef"]).output().unwrap();
+	let stderr = String::from_utf8(output.stderr).unwrap();
+	assert!(
+		stderr.starts_with("Assuming base64 input for 'program', although it is valid hex too.\n")
+	);
+	let info =
+		self_command().args(["simplicity", "simplicity", "info", "deadbeef"]).output().unwrap();
+	assert_eq!(stderr, String::from_utf8(info.stderr).unwrap());
+}
+
+#[test]
+fn cli_simplicity_simplicity_match() {
+	let expected_help = "\
+hal-simplicity-simplicity-match 
+Check whether an address commits to a Simplicity program
+
+USAGE:
+    hal simplicity simplicity match [FLAGS] [OPTIONS] <program> <address>
+
+FLAGS:
+    -h, --help          Prints help information
+        --no-newline    don't print a trailing newline after a raw value
+    -v, --verbose       print verbose logging output to stderr
+    -y, --yaml          print output in YAML instead of JSON
+
+OPTIONS:
+        --internal-key <internal-key>    the taproot internal key (defaults to the unspendable key used by hal-
+                                         simplicity)
+        --log-format <log-format>        the format of the logging output [possible values: text, json]
+        --out <out>                      write the output to the given file instead of stdout, gzipped if it ends in .gz
+    -o, --output <output>                the output format (see `schema --proto` for the protobuf messages) [possible
+                                         values: json, yaml, protobuf]
+        --raw-value <raw-value>          print only the given output field (e.g. txid) as a raw value
+
+ARGS:
+    <program>    a Simplicity program in base64, or its CMR in hex
+    <address>    a Liquid or regtest address, or a scriptPubKey in hex
+";
+	assert_cmd(&["simplicity", "simplicity", "match", "-h"], expected_help, "");
+	assert_cmd(&["simplicity", "simplicity", "match", "--help"], expected_help, "");
+
+	assert_cmd(
+		&[
+			"simplicity",
+			"simplicity",
+			"match",
+			"34fd0c6accd5689186fc10bde76af7870f210e7b59f55f7e88e70ce78cb9c7ba",
+			"tex1ps870gqnjk0l33gnrawg7etvkxa8cw7u99lvkpfyk95pev628pg5s4vkh6h",
+		],
+		r#"{
+  "cmr": "34fd0c6accd5689186fc10bde76af7870f210e7b59f55f7e88e70ce78cb9c7ba",
+  "internal_key": "f5919fa64ce45f8306849072b26c1bfdd2937e6b81774796ff372bd1eb5362d2",
+  "expected_address": "tex1ps870gqnjk0l33gnrawg7etvkxa8cw7u99lvkpfyk95pev628pg5s4vkh6h",
+  "expected_script_pubkey": "512081fcf40272b3ff18a263eb91ecad96374f877b852fd960a4962d039669470a29",
+  "script_pubkey": "512081fcf40272b3ff18a263eb91ecad96374f877b852fd960a4962d039669470a29",
+  "matches": true
+}"#,
+		"",
+	);
+
+	assert_cmd(
+		&[
+			"simplicity",
+			"simplicity",
+			"match",
+			"ztM33xqq2wHIdc/BfZZ0/NBxcrvfyLEt2sDrdyBroNkyycFDijYwEA==",
+			"512081fcf40272b3ff18a263eb91ecad96374f877b852fd960a4962d039669470a29",
+			"--internal-key",
+			"9bef8d556d80e43ae7e0becb3a7e6838b95defe45896ed6075bb9035d06c9964",
+			"--raw-value",
+			"matches",
+		],
+		"false\n",
+		"",
+	);
+}
+
+#[test]
+fn cli_simplicity_simplicity_multisig() {
+	let expected_help = "\
+hal-simplicity-simplicity-multisig 
+Generate a k-of-n multisignature Simplicity program
+
+USAGE:
+    hal simplicity simplicity multisig [FLAGS] [OPTIONS] --keys <keys>... --threshold <threshold>
+
+FLAGS:
+    -h, --help          Prints help information
+        --no-newline    don't print a trailing newline after a raw value
+    -v, --verbose       print verbose logging output to stderr
+    -y, --yaml          print output in YAML instead of JSON
+
+OPTIONS:
+        --keys <keys>...             the x-only public key of a signer; given once per signer
+        --log-format <log-format>    the format of the logging output [possible values: text, json]
+        --out <out>                  write the output to the given file instead of stdout, gzipped if it ends in .gz
+    -o, --output <output>            the output format (see `schema --proto` for the protobuf messages) [possible
+                                     values: json, yaml, protobuf]
+        --raw-value <raw-value>      print only the given output field (e.g. txid) as a raw value
+    -k, --threshold <threshold>      the number of signatures required
+";
+	assert_cmd(&["simplicity", "simplicity", "multisig", "-h"], expected_help, "");
+	assert_cmd(&["simplicity", "simplicity", "multisig", "--help"], expected_help, "");
+
+	assert_cmd(
+		&[
+			"simplicity",
+			"simplicity",
+			"multisig",
+			"-k",
+			"1",
+			"--keys",
+			"9bef8d556d80e43ae7e0becb3a7e6838b95defe45896ed6075bb9035d06c9964",
+			"--keys",
+			"f22ce68b963eab1522c3b19e3a53f20f19ea6ed61577c565956111bafe8e7cef",
+		],
+		r#"{
+  "threshold": 1,
+  "keys": [
+    "9bef8d556d80e43ae7e0becb3a7e6838b95defe45896ed6075bb9035d06c9964",
+    "f22ce68b963eab1522c3b19e3a53f20f19ea6ed61577c565956111bafe8e7cef"
+  ],
+  "policy": "thresh(1,pk(9bef8d556d80e43ae7e0becb3a7e6838b95defe45896ed6075bb9035d06c9964),pk(f22ce68b963eab1522c3b19e3a53f20f19ea6ed61577c565956111bafe8e7cef))",
+  "commit_base64": "4nsgAAAAF0iiyAAAAADrTN98aqtsByHXPwX2WdPzQcXK738ixLdrA63cga6DZMsnBQ4o2MBAG04OoDacTgFp8izmi5Y+qxUiw7GeOlPyDxnqbtYVd8VllWERuv6OfO8Ta4oA2AcIODwUDSFsaXEEIOBQuImbcQRgEA==",
+  "commit_decode": "((const 0x00000001  & (((((witness  & unit); case (drop const 0x00000000 ) (drop ((((const 0x9bef8d556d80e43ae7e0becb3a7e6838b95defe45896ed6075bb9035d06c9964  & jet_sig_all_hash ) & witness ); jet_bip_0340_verify ); const 0x00000001 ))) & ((witness  & unit); case (drop const 0x00000000 ) (drop ((((const 0xf22ce68b963eab1522c3b19e3a53f20f19ea6ed61577c565956111bafe8e7cef  & jet_sig_all_hash ) & witness ); jet_bip_0340_verify ); const 0x00000001 )))); jet_add_32 ); IH)); jet_eq_32 ); jet_verify ",
+  "type_arrow": "1 → 1",
+  "cmr": "a3d5258f85377454e6929bdbee3cc808c2e659863ee8b1c674dc6203e0492fe7",
+  "liquid_address_unconf": "ex1pjukxxz5p4d96ku50xy5r926tguym5mf6tjjvuetpz4negzvhr3uqt9w2cg",
+  "liquid_testnet_address_unconf": "tex1pjukxxz5p4d96ku50xy5r926tguym5mf6tjjvuetpz4negzvhr3uq2uudf8",
+  "control_block": "bff5919fa64ce45f8306849072b26c1bfdd2937e6b81774796ff372bd1eb5362d2",
+  "witness": [
+    {
+      "type": "2",
+      "bit_width": 1,
+      "description": "1 if signer 0 provides a signature, 0 otherwise"
+    },
+    {
+      "type": "2^512",
+      "bit_width": 512,
+      "description": "BIP-340 signature by 9bef8d556d80e43ae7e0becb3a7e6838b95defe45896ed6075bb9035d06c9964 of the SIGHASH_ALL of the spending transaction (omitted if the selector bit is 0)"
+    },
+    {
+      "type": "2",
+      "bit_width": 1,
+      "description": "1 if signer 1 provides a signature, 0 otherwise"
+    },
+    {
+      "type": "2^512",
+      "bit_width": 512,
+      "description": "BIP-340 signature by f22ce68b963eab1522c3b19e3a53f20f19ea6ed61577c565956111bafe8e7cef of the SIGHASH_ALL of the spending transaction (omitted if the selector bit is 0)"
+    }
+  ]
+}"#,
+		"",
+	);
+	assert_cmd(
+		&[
+			"simplicity",
+			"simplicity",
+			"multisig",
+			"-k",
+			"3",
+			"--keys",
+			"9bef8d556d80e43ae7e0becb3a7e6838b95defe45896ed6075bb9035d06c9964",
+			"--keys",
+			"f22ce68b963eab1522c3b19e3a53f20f19ea6ed61577c565956111bafe8e7cef",
+		],
+		"Execution failed: invalid threshold 3 of 2 keys\n",
+		"",
+	);
+	assert_cmd(
+		&[
+			"simplicity",
+			"simplicity",
+			"multisig",
+			"-k",
+			"2",
+			"--keys",
+			"9bef8d556d80e43ae7e0becb3a7e6838b95defe45896ed6075bb9035d06c9964",
+			"--keys",
+			"9bef8d556d80e43ae7e0becb3a7e6838b95defe45896ed6075bb9035d06c9964",
+			"--keys",
+			"f22ce68b963eab1522c3b19e3a53f20f19ea6ed61577c565956111bafe8e7cef",
+		],
+		"Execution failed: public key 9bef8d556d80e43ae7e0becb3a7e6838b95defe45896ed6075bb9035d06c9964 given more than once\n",
+		"",
+	);
+}
+
+#[test]
+fn cli_simplicity_simplicity_policy() {
+	let expected_help = "\
+hal-simplicity-simplicity-policy 
+Compile a policy into a Simplicity program
+
+USAGE:
+    hal simplicity simplicity policy [FLAGS] [OPTIONS] <policy>
+
+FLAGS:
+    -h, --help          Prints help information
+        --no-newline    don't print a trailing newline after a raw value
+    -v, --verbose       print verbose logging output to stderr
+    -y, --yaml          print output in YAML instead of JSON
+
+OPTIONS:
+        --log-format <log-format>    the format of the logging output [possible values: text, json]
+        --out <out>                  write the output to the given file instead of stdout, gzipped if it ends in .gz
+    -o, --output <output>            the output format (see `schema --proto` for the protobuf messages) [possible
+                                     values: json, yaml, protobuf]
+        --raw-value <raw-value>      print only the given output field (e.g. txid) as a raw value
+
+ARGS:
+    <policy>    a policy such as or(pk(KEY),and(older(N),sha256(HASH))); also supports after(N) and thresh(K,...)
+";
+	assert_cmd(&["simplicity", "simplicity", "policy", "-h"], expected_help, "");
+	assert_cmd(&["simplicity", "simplicity", "policy", "--help"], expected_help, "");
+
+	assert_cmd(
+		&[
+			"simplicity",
+			"simplicity",
+			"policy",
+			"pk(9bef8d556d80e43ae7e0becb3a7e6838b95defe45896ed6075bb9035d06c9964)",
+		],
+		r#"{
+  "policy": "pk(9bef8d556d80e43ae7e0becb3a7e6838b95defe45896ed6075bb9035d06c9964)",
+  "commit_base64": "ztM33xqq2wHIdc/BfZZ0/NBxcrvfyLEt2sDrdyBroNkyycFDijYwEA==",
+  "commit_decode": "((const 0x9bef8d556d80e43ae7e0becb3a7e6838b95defe45896ed6075bb9035d06c9964  & jet_sig_all_hash ) & witness ); jet_bip_0340_verify ",
+  "type_arrow": "1 → 1",
+  "cmr": "34fd0c6accd5689186fc10bde76af7870f210e7b59f55f7e88e70ce78cb9c7ba",
+  "liquid_address_unconf": "ex1ps870gqnjk0l33gnrawg7etvkxa8cw7u99lvkpfyk95pev628pg5s54ystc",
+  "liquid_testnet_address_unconf": "tex1ps870gqnjk0l33gnrawg7etvkxa8cw7u99lvkpfyk95pev628pg5s4vkh6h"
+}"#,
+		"",
+	);
+	// The CMR only depends on the policy, so can be extracted for scripting
+	assert_cmd(
+		&[
+			"simplicity",
+			"simplicity",
+			"policy",
+			"--raw-value",
+			"cmr",
+			"or(pk(9bef8d556d80e43ae7e0becb3a7e6838b95defe45896ed6075bb9035d06c9964),and(older(144),sha256(9bef8d556d80e43ae7e0becb3a7e6838b95defe45896ed6075bb9035d06c9964)))",
+		],
+		"9dc4843c8bfe979c962c2238d3f1b128cf7729d32e66abaaeb9d6f634411159c\n",
+		"",
+	);
+	assert_cmd(
+		&["simplicity", "simplicity", "policy", "thresh(3,older(1),older(2))"],
+		"Execution failed: invalid policy: InvalidThreshold(3, 2)\n",
+		"",
+	);
+	assert_cmd(
+		&["simplicity", "simplicity", "policy", "pk(02)"],
+		"Execution failed: invalid policy: InvalidArgument { fragment: \"pk\", arg: \"02\", error: \"malformed public key\" }\n",
+		"",
+	);
+}
+
+#[test]
+fn cli_simplicity_simplicity_prune() {
+	let expected_help = "\
+hal-simplicity-simplicity-prune 
+Prune the branches of a program not executed by a spending transaction
+
+USAGE:
+    hal simplicity simplicity prune [FLAGS] [OPTIONS] <program> <witness> --against <against> --input-utxo <input-utxo>...
+
+FLAGS:
+    -h, --help          Prints help information
+        --no-newline    don't print a trailing newline after a raw value
+    -v, --verbose       print verbose logging output to stderr
+    -y, --yaml          print output in YAML instead of JSON
+
+OPTIONS:
+        --against <against>                the spending transaction in hex
+        --control-block <control-block>    the taproot control block in hex (defaults to the one of hal-simplicity's
+                                           address)
+    -g, --genesis-hash <genesis-hash>      the genesis block hash of the network (defaults to Liquid)
+        --input-index <input-index>        the index of the input spending the program [default: 0]
+    -i, --input-utxo <input-utxo>...       an output spent by the transaction as SCRIPTPUBKEY:ASSET:VALUE, given once
+                                           per input
+        --log-format <log-format>          the format of the logging output [possible values: text, json]
+        --out <out>                        write the output to the given file instead of stdout, gzipped if it ends in
+                                           .gz
+    -o, --output <output>                  the output format (see `schema --proto` for the protobuf messages) [possible
+                                           values: json, yaml, protobuf]
+        --raw-value <raw-value>            print only the given output field (e.g. txid) as a raw value
+
+ARGS:
+    <program>    a Simplicity program in base64
+    <witness>    a hex encoding of all the witness data for the program
+";
+	assert_cmd(&["simplicity", "simplicity", "prune", "-h"], expected_help, "");
+	assert_cmd(&["simplicity", "simplicity", "prune", "--help"], expected_help, "");
+
+	assert_cmd(
+		&["simplicity", "simplicity", "prune", "4zdIosgAAAAL4YijNuIIwCLTArFs1v32PDFCsiTr0+4z/bNPc5Z4vhld8+86AN4cip9kAAAAAfDUCBNqgAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAABBgaBiFqZt0AgDeDesgAAAAH4bAQJwtQAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAgwNAxAcKBrHWIH4eUEWmb741VbYDkOufgvss6fmg4uV3v5FiW7WB1u5A10GyZZOChxRsYCAaBwdoHGAA==", "0000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000", "--against", "02000000000111111111111111111111111111111111111111111111111111111111111111110000000000feffffff02016d521c38ec1ea15734ae22b7c46064412829c0d0579f0a713d1c04ede979026f0100000000000182b800225120972c630a81ab4bab728f312832ab4b4709ba6d3a5ca4ce656115679409971c78016d521c38ec1ea15734ae22b7c46064412829c0d0579f0a713d1c04ede979026f0100000000000003e8000000000000", "-i", "5120cfaad1e47e9cef7ed63a3db1d11c90ff6dfddd252527648273932c8917918aa1:6f0279e9ed041c3d710a9f57d0c02928416460c4b722ae3457a11eec381c526d:100000"],
+		r#"{
+  "cmr": "4e55da1f7550222b9e7c372fe5d9a18d6ce2c4b435330706d1c927dd36c6ee53",
+  "redeem_base64": "4ndIosgAAAAL4YijNuIIwCLTArFs1v32PDFCsiTr0+4z/bNPc5Z4vhld8+86AN4cip9kAAAAAfDUCBNrOiGc5Czz/7fGmfzX05uacOSENCf3bkYmhTCJad+bCYugwNAxC1M26AQBvBvWQAAAAD8NgIE4WgwPAAMAHCQahzF419uZZ4aiKDyXDtjsEPdCAa+I0JuklYXa1UCokZLPgGAcSA==",
+  "witness_hex": "00",
+  "amr": "c886158d07ee8f7c9bace652aa06bbcb416a65388a967b5493036d3e3dd86174",
+  "ihr": "7f89e60330bbe4776b2512626240ffae701620994c2df8ea542313b4044ac2c4",
+  "unpruned_size": 336,
+  "pruned_size": 161
+}"#,
+		"",
+	);
+
+	assert_cmd(
+		&["simplicity", "simplicity", "prune", "4zdIosgAAAAL4YijNuIIwCLTArFs1v32PDFCsiTr0+4z/bNPc5Z4vhld8+86AN4cip9kAAAAAfDUCBNqgAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAABBgaBiFqZt0AgDeDesgAAAAH4bAQJwtQAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAgwNAxAcKBrHWIH4eUEWmb741VbYDkOufgvss6fmg4uV3v5FiW7WB1u5A10GyZZOChxRsYCAaBwdoHGAA==", "0000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000", "--against", "0200000001010000000000000000000000000000000000000000000000000000000000000000ffffffff0603a730180101ffffffff03016d521c38ec1ea15734ae22b7c46064412829c0d0579f0a713d1c04ede979026f01000000000000000000266a240a8ce26fdbb51a2d03d4e62fdafd4a06dd7faa0d1c083aa7e27905000000000000000000016d521c38ec1ea15734ae22b7c46064412829c0d0579f0a713d1c04ede979026f010000000000000106001976a914fc26751a5025129a2fd006c6fbfa598ddd67f7e188ac016d521c38ec1ea15734ae22b7c46064412829c0d0579f0a713d1c04ede979026f01000000000000000000266a24aa21a9ede8497768bc893ee587244bf5303ac3cf482bab8e4b3fd22e8b114c2a52525ab30000000000000120000000000000000000000000000000000000000000000000000000000000000000000000000000", "-i", "5120cfaad1e47e9cef7ed63a3db1d11c90ff6dfddd252527648273932c8917918aa1:6f0279e9ed041c3d710a9f57d0c02928416460c4b722ae3457a11eec381c526d:100000"],
+		"Execution failed: program failed to execute: JetFailed(JetFailed)\n",
+		"",
+	);
+}
+
+#[test]
+fn cli_simplicity_simplicity_sighash() {
+	let expected_help = "\
+hal-simplicity-simplicity-sighash 
+Compute the SIGHASH_ALL of a Simplicity transaction input
+
+USAGE:
+    hal simplicity simplicity sighash [FLAGS] [OPTIONS] <tx> <input-index> <cmr> <control-block> --input-utxo <input-utxo>...
+
+FLAGS:
+    -h, --help          Prints help information
+        --no-newline    don't print a trailing newline after a raw value
+    -v, --verbose       print verbose logging output to stderr
+    -y, --yaml          print output in YAML instead of JSON
+
+OPTIONS:
+        --audit-log <audit-log>          append a hash-chained record of the operation to the given file
+    -g, --genesis-hash <genesis-hash>    the genesis block hash of the network (defaults to Liquid)
+    -i, --input-utxo <input-utxo>...     an output spent by the transaction as SCRIPTPUBKEY:ASSET:VALUE, given once per
+                                         input
+        --log-format <log-format>        the format of the logging output [possible values: text, json]
+        --out <out>                      write the output to the given file instead of stdout, gzipped if it ends in .gz
+    -o, --output <output>                the output format (see `schema --proto` for the protobuf messages) [possible
+                                         values: json, yaml, protobuf]
+        --raw-value <raw-value>          print only the given output field (e.g. txid) as a raw value
+    -s, --secret-key <secret-key>...     a secret key to sign the sighash with; may be given several times
+
+ARGS:
+    <tx>               the spending transaction in hex
+    <input-index>      the index of the input being signed
+    <cmr>              the CMR of the Simplicity program being spent
+    <control-block>    the taproot control block of the program in hex
+";
+	assert_cmd(&["simplicity", "simplicity", "sighash", "-h"], expected_help, "");
+	assert_cmd(&["simplicity", "simplicity", "sighash", "--help"], expected_help, "");
+
+	assert_cmd(
+		&["simplicity", "simplicity", "sighash", "0200000001010000000000000000000000000000000000000000000000000000000000000000ffffffff0603a730180101ffffffff03016d521c38ec1ea15734ae22b7c46064412829c0d0579f0a713d1c04ede979026f01000000000000000000266a240a8ce26fdbb51a2d03d4e62fdafd4a06dd7faa0d1c083aa7e27905000000000000000000016d521c38ec1ea15734ae22b7c46064412829c0d0579f0a713d1c04ede979026f010000000000000106001976a914fc26751a5025129a2fd006c6fbfa598ddd67f7e188ac016d521c38ec1ea15734ae22b7c46064412829c0d0579f0a713d1c04ede979026f01000000000000000000266a24aa21a9ede8497768bc893ee587244bf5303ac3cf482bab8e4b3fd22e8b114c2a52525ab30000000000000120000000000000000000000000000000000000000000000000000000000000000000000000000000", "0", "a3d5258f85377454e6929bdbee3cc808c2e659863ee8b1c674dc6203e0492fe7", "bff5919fa64ce45f8306849072b26c1bfdd2937e6b81774796ff372bd1eb5362d2", "-i", "512096ec63185c1aebdd3f1c3fe8d87d9f27be14b7fc1eccc38d06059ba0c1c71e3c:6f0279e9ed041c3d710a9f57d0c02928416460c4b722ae3457a11eec381c526d:100000"],
+		r#"{
+  "sighash": "34783b35f568dbe7b2eb818f46a1db268902d4224163412e21b94c726060f46b"
+}"#,
+		"",
+	);
+
+	// Signatures use fresh randomness, so only check that they verify.
+	{
+		use elements::bitcoin::secp256k1::{self, schnorr, Message, XOnlyPublicKey};
+		use elements::hashes::{sha256, Hash};
+
+		let info: serde_json::Value = assert_deserialize_cmd(
+			&["simplicity", "simplicity", "sighash", "0200000001010000000000000000000000000000000000000000000000000000000000000000ffffffff0603a730180101ffffffff03016d521c38ec1ea15734ae22b7c46064412829c0d0579f0a713d1c04ede979026f01000000000000000000266a240a8ce26fdbb51a2d03d4e62fdafd4a06dd7faa0d1c083aa7e27905000000000000000000016d521c38ec1ea15734ae22b7c46064412829c0d0579f0a713d1c04ede979026f010000000000000106001976a914fc26751a5025129a2fd006c6fbfa598ddd67f7e188ac016d521c38ec1ea15734ae22b7c46064412829c0d0579f0a713d1c04ede979026f01000000000000000000266a24aa21a9ede8497768bc893ee587244bf5303ac3cf482bab8e4b3fd22e8b114c2a52525ab30000000000000120000000000000000000000000000000000000000000000000000000000000000000000000000000", "0", "a3d5258f85377454e6929bdbee3cc808c2e659863ee8b1c674dc6203e0492fe7", "bff5919fa64ce45f8306849072b26c1bfdd2937e6b81774796ff372bd1eb5362d2", "-i", "512096ec63185c1aebdd3f1c3fe8d87d9f27be14b7fc1eccc38d06059ba0c1c71e3c:6f0279e9ed041c3d710a9f57d0c02928416460c4b722ae3457a11eec381c526d:100000", "-s", "0000000000000000000000000000000000000000000000000000000000000001", "-s", "0000000000000000000000000000000000000000000000000000000000000002"],
+			|s| serde_json::from_slice(s),
+		);
+		let sighash: sha256::Hash = info["sighash"].as_str().unwrap().parse().unwrap();
+		let msg = Message::from_digest(sighash.to_byte_array());
+		let signatures = info["signatures"].as_object().unwrap();
+		assert_eq!(
+			signatures.keys().collect::<Vec<_>>(),
+			[
+				"79be667ef9dcbbac55a06295ce870b07029bfcdb2dce28d959f2815b16f81798",
+				"c6047f9441ed7d6d3045406e95c07cd85c778e4b8cef3ca7abac09b95c709ee5",
+			],
+		);
+		for (key, sig) in signatures {
+			let key: XOnlyPublicKey = key.parse().unwrap();
+			let sig: schnorr::Signature = sig.as_str().unwrap().parse().unwrap();
+			secp256k1::SECP256K1.verify_schnorr(&sig, &msg, &key).unwrap();
+		}
+	}
+	assert_cmd(
+		&["simplicity", "simplicity", "sighash", "0200000001010000000000000000000000000000000000000000000000000000000000000000ffffffff0603a730180101ffffffff03016d521c38ec1ea15734ae22b7c46064412829c0d0579f0a713d1c04ede979026f01000000000000000000266a240a8ce26fdbb51a2d03d4e62fdafd4a06dd7faa0d1c083aa7e27905000000000000000000016d521c38ec1ea15734ae22b7c46064412829c0d0579f0a713d1c04ede979026f010000000000000106001976a914fc26751a5025129a2fd006c6fbfa598ddd67f7e188ac016d521c38ec1ea15734ae22b7c46064412829c0d0579f0a713d1c04ede979026f01000000000000000000266a24aa21a9ede8497768bc893ee587244bf5303ac3cf482bab8e4b3fd22e8b114c2a52525ab30000000000000120000000000000000000000000000000000000000000000000000000000000000000000000000000", "0", "a3d5258f85377454e6929bdbee3cc808c2e659863ee8b1c674dc6203e0492fe7", "bff5919fa64ce45f8306849072b26c1bfdd2937e6b81774796ff372bd1eb5362d2", "-i", "512096ec63185c1aebdd3f1c3fe8d87d9f27be14b7fc1eccc38d06059ba0c1c71e3c:6f0279e9ed041c3d710a9f57d0c02928416460c4b722ae3457a11eec381c526d:100000", "-s", "0000000000000000000000000000000000000000000000000000000000000001", "-s", "0000000000000000000000000000000000000000000000000000000000000001"],
+		"Execution failed: secret key for public key 79be667ef9dcbbac55a06295ce870b07029bfcdb2dce28d959f2815b16f81798 given more than once\n",
+		"",
+	);
+
+	assert_cmd(
+		&["simplicity", "simplicity", "sighash", "0200000001010000000000000000000000000000000000000000000000000000000000000000ffffffff0603a730180101ffffffff03016d521c38ec1ea15734ae22b7c46064412829c0d0579f0a713d1c04ede979026f01000000000000000000266a240a8ce26fdbb51a2d03d4e62fdafd4a06dd7faa0d1c083aa7e27905000000000000000000016d521c38ec1ea15734ae22b7c46064412829c0d0579f0a713d1c04ede979026f010000000000000106001976a914fc26751a5025129a2fd006c6fbfa598ddd67f7e188ac016d521c38ec1ea15734ae22b7c46064412829c0d0579f0a713d1c04ede979026f01000000000000000000266a24aa21a9ede8497768bc893ee587244bf5303ac3cf482bab8e4b3fd22e8b114c2a52525ab30000000000000120000000000000000000000000000000000000000000000000000000000000000000000000000000", "0", "a3d5258f85377454e6929bdbee3cc808c2e659863ee8b1c674dc6203e0492fe7", "bff5919fa64ce45f8306849072b26c1bfdd2937e6b81774796ff372bd1eb5362d2", "-i", "512096ec63185c1aebdd3f1c3fe8d87d9f27be14b7fc1eccc38d06059ba0c1c71e3c:6f0279e9ed041c3d710a9f57d0c02928416460c4b722ae3457a11eec381c526d:100000", "-i", "512096ec63185c1aebdd3f1c3fe8d87d9f27be14b7fc1eccc38d06059ba0c1c71e3c:6f0279e9ed041c3d710a9f57d0c02928416460c4b722ae3457a11eec381c526d:100000"],
+		"Execution failed: 2 input UTXOs given for 1 inputs\n",
+		"",
+	);
+
+	// Each signing operation is appended to the audit log, chained by hash.
+	let log = std::env::temp_dir().join(format!("hal-simplicity-audit-{}.log", std::process::id()));
+	let log = log.to_str().unwrap();
+	let _ = std::fs::remove_file(log);
+	let args = [
+		"simplicity",
+		"simplicity",
+		"sighash",
+		"0200000001010000000000000000000000000000000000000000000000000000000000000000ffffffff0603a730180101ffffffff03016d521c38ec1ea15734ae22b7c46064412829c0d0579f0a713d1c04ede979026f01000000000000000000266a240a8ce26fdbb51a2d03d4e62fdafd4a06dd7faa0d1c083aa7e27905000000000000000000016d521c38ec1ea15734ae22b7c46064412829c0d0579f0a713d1c04ede979026f010000000000000106001976a914fc26751a5025129a2fd006c6fbfa598ddd67f7e188ac016d521c38ec1ea15734ae22b7c46064412829c0d0579f0a713d1c04ede979026f01000000000000000000266a24aa21a9ede8497768bc893ee587244bf5303ac3cf482bab8e4b3fd22e8b114c2a52525ab30000000000000120000000000000000000000000000000000000000000000000000000000000000000000000000000",
+		"0",
+		"a3d5258f85377454e6929bdbee3cc808c2e659863ee8b1c674dc6203e0492fe7",
+		"bff5919fa64ce45f8306849072b26c1bfdd2937e6b81774796ff372bd1eb5362d2",
+		"-i",
+		"512096ec63185c1aebdd3f1c3fe8d87d9f27be14b7fc1eccc38d06059ba0c1c71e3c:6f0279e9ed041c3d710a9f57d0c02928416460c4b722ae3457a11eec381c526d:100000",
+		"-s",
+		"0000000000000000000000000000000000000000000000000000000000000001",
+		"--audit-log",
+		log,
+		"--raw-value",
+		"sighash",
+	];
+	for _ in 0..2 {
+		assert_cmd(&args, "34783b35f568dbe7b2eb818f46a1db268902d4224163412e21b94c726060f46b\n", "");
+	}
+	assert_cmd(&["simplicity", "audit", "verify", log, "--raw-value", "records"], "2\n", "");
+	let contents = std::fs::read_to_string(log).unwrap();
+	std::fs::remove_file(log).unwrap();
+	assert!(!contents.contains("0000000000000000000000000000000000000000000000000000000000000001"));
+	let mut prev = "0".repeat(64);
+	for (seq, line) in contents.lines().enumerate() {
+		use elements::hashes::{sha256, Hash};
+
+		let mut record: serde_json::Value = serde_json::from_str(line).unwrap();
+		let hash = record.as_object_mut().unwrap().remove("hash").unwrap();
+		assert_eq!(record["seq"], seq);
+		assert_eq!(record["prev"], prev);
+		assert_eq!(record["command"], "simplicity sighash");
+		assert_eq!(
+			record["output"]["sighash"],
+			"34783b35f568dbe7b2eb818f46a1db268902d4224163412e21b94c726060f46b"
+		);
+		assert_eq!(hash, sha256::Hash::hash(record.to_string().as_bytes()).to_string());
+		prev = hash.as_str().unwrap().to_owned();
+	}
+	assert_eq!(contents.lines().count(), 2);
+}
+
+#[test]
+fn cli_simplicity_simplicity_template() {
+	let expected_help = "\
+hal-simplicity-simplicity-template 
+Generate a common Simplicity program from parameters
+
+USAGE:
+    hal simplicity simplicity template [FLAGS] [OPTIONS] <template>
 
 FLAGS:
-    -h, --help       Prints help information
-    -v, --verbose    print verbose logging output to stderr
-    -y, --yaml       print output in YAML instead of JSON
-";
-	assert_cmd(&["simplicity", "keypair", "generate", "-h"], expected_help, "");
-	assert_cmd(&["simplicity", "keypair", "generate", "--help"], expected_help, "");
-	assert_cmd(&["simplicity", "keypair", "generate", "--help", "xyz"], expected_help, "");
+    -h, --help          Prints help information
+        --no-newline    don't print a trailing newline after a raw value
+    -v, --verbose       print verbose logging output to stderr
+    -y, --yaml          print output in YAML instead of JSON
 
-	// New block to avoid warnings about `struct`s being defined not at the beginning of block
-	{
-		use elements::bitcoin::secp256k1;
+OPTIONS:
+        --log-format <log-format>    the format of the logging output [possible values: text, json]
+        --out <out>                  write the output to the given file instead of stdout, gzipped if it ends in .gz
+    -o, --output <output>            the output format (see `schema --proto` for the protobuf messages) [possible
+                                     values: json, yaml, protobuf]
+    -p, --param <param>...           a template parameter as NAME=VALUE
+        --raw-value <raw-value>      print only the given output field (e.g. txid) as a raw value
 
-		#[allow(dead_code)]
-		#[derive(serde::Deserialize)]
-		struct Object {
-			secret: secp256k1::SecretKey,
-			x_only: secp256k1::XOnlyPublicKey,
-			parity: usize, // secp256k1::Parity does not seem to round-trip through serde_json
-		}
+ARGS:
+    <template>    the name of the template [possible values: pk, hashlock, csv, cltv, pk-csv, pk-cltv]
 
-		// Closure needed for borrowck reasons
-		assert_deserialize_cmd(&["simplicity", "keypair", "generate"], |s| {
-			serde_json::from_slice::<Object>(s)
-		});
-		assert_deserialize_cmd(&["simplicity", "keypair", "generate"], |s| {
-			serde_yaml::from_slice::<Object>(s)
-		});
+TEMPLATES:
+    pk         a single BIP-340 signature check (key=<x-only pubkey>)
+    hashlock   a SHA256 preimage check (hash=<sha256 hash>)
+    csv        a relative timelock (blocks=<n>)
+    cltv       an absolute timelock (height=<n>, from 1 to 499999999)
+    pk-csv     a signature check after a relative timelock (key=<x-only pubkey>, blocks=<n>)
+    pk-cltv    a signature check after an absolute timelock (key=<x-only pubkey>, height=<n>)
+";
+	assert_cmd(&["simplicity", "simplicity", "template", "-h"], expected_help, "");
+	assert_cmd(&["simplicity", "simplicity", "template", "--help"], expected_help, "");
+
+	assert_cmd(
+		&[
+			"simplicity",
+			"simplicity",
+			"template",
+			"pk-csv",
+			"-p",
+			"key=9bef8d556d80e43ae7e0becb3a7e6838b95defe45896ed6075bb9035d06c9964",
+			"-p",
+			"blocks=144",
+		],
+		r#"{
+  "template": "pk-csv",
+  "policy": "and(pk(9bef8d556d80e43ae7e0becb3a7e6838b95defe45896ed6075bb9035d06c9964),older(144))",
+  "commit_base64": "12mb741VbYDkOufgvss6fmg4uV3v5FiW7WB1u5A10GyZZOChxRsYCLEAkPKCAYA=",
+  "commit_decode": "(((const 0x9bef8d556d80e43ae7e0becb3a7e6838b95defe45896ed6075bb9035d06c9964  & jet_sig_all_hash ) & witness ); jet_bip_0340_verify ); (const 0x0090 ; jet_check_lock_distance )",
+  "type_arrow": "1 → 1",
+  "cmr": "5218b08973267c7184c7ff8cc36df43dd159a148dad856e11023f3247e6eda89",
+  "liquid_address_unconf": "ex1pnmh0v7f46j50e3dj2gcmjc07mx35mq2gzwuvmf2tc4g90s283nlqxd6evj",
+  "liquid_testnet_address_unconf": "tex1pnmh0v7f46j50e3dj2gcmjc07mx35mq2gzwuvmf2tc4g90s283nlq85g7aa",
+  "witness": [
+    {
+      "type": "2^512",
+      "bit_width": 512,
+      "description": "BIP-340 signature by 9bef8d556d80e43ae7e0becb3a7e6838b95defe45896ed6075bb9035d06c9964 of the SIGHASH_ALL of the spending transaction"
+    }
+  ]
+}"#,
+		"",
+	);
+	assert_cmd(
+		&["simplicity", "simplicity", "template", "csv", "-p", "blocks=144", "--raw-value", "cmr"],
+		"2903e87021cca4ab0e514ea3fc3f99d8ad38e6239b882397fe2be11a975d6eb3
+",
+		"",
+	);
+	assert_cmd(
+		&["simplicity", "simplicity", "template", "pk"],
+		"Execution failed: missing parameter 'key'
+",
+		"",
+	);
+	assert_cmd(
+		&["simplicity", "simplicity", "template", "csv", "-p", "blocks=144", "-p", "height=10"],
+		"Execution failed: unknown parameter 'height' for template 'csv'
+",
+		"",
+	);
+	assert_cmd(
+		&["simplicity", "simplicity", "template", "hashlock", "-p", "hash"],
+		"Execution failed: invalid parameter 'hash', expected NAME=VALUE
+",
+		"",
+	);
+	assert_cmd(
+		&["simplicity", "simplicity", "template", "csv", "-p", "blocks=144", "-p", "blocks=10"],
+		"Execution failed: parameter 'blocks' given more than once
+",
+		"",
+	);
+	let key = "key=9bef8d556d80e43ae7e0becb3a7e6838b95defe45896ed6075bb9035d06c9964";
+	for height in ["height=0", "height=500000000"] {
+		for args in [&["cltv", "-p", height][..], &["pk-cltv", "-p", key, "-p", height]] {
+			let mut cmd = vec!["simplicity", "simplicity", "template"];
+			cmd.extend_from_slice(args);
+			assert_cmd(
+				&cmd,
+				"Execution failed: invalid parameter 'height': expected a block height from 1 to 499999999\n",
+				"",
+			);
+		}
 	}
 }
 
 #[test]
-fn cli_simplicity_simplicity() {
+fn cli_simplicity_simplicity_vault() {
 	let expected_help = "\
-hal-simplicity-simplicity 
-manipulate Simplicity programs
+hal-simplicity-simplicity-vault 
+Generate a covenant paying to a fixed destination with a recovery path
 
 USAGE:
-    hal simplicity simplicity [FLAGS] <SUBCOMMAND>
+    hal simplicity simplicity vault [FLAGS] [OPTIONS] --delay <delay> --destination <destination> --max-fee <max-fee> --recovery-key <recovery-key>
 
 FLAGS:
-    -h, --help       Prints help information
-    -v, --verbose    print verbose logging output to stderr
+    -h, --help          Prints help information
+        --no-newline    don't print a trailing newline after a raw value
+    -v, --verbose       print verbose logging output to stderr
+    -y, --yaml          print output in YAML instead of JSON
 
-SUBCOMMANDS:
-    info    Parse a base64-encoded Simplicity program and decode it
+OPTIONS:
+        --delay <delay>                  the number of blocks before the recovery path is enabled
+        --destination <destination>      the address which the covenant path must pay to
+        --log-format <log-format>        the format of the logging output [possible values: text, json]
+        --max-fee <max-fee>              the maximum amount in satoshis by which the payment to the destination may fall
+                                         short of the coins
+        --out <out>                      write the output to the given file instead of stdout, gzipped if it ends in .gz
+    -o, --output <output>                the output format (see `schema --proto` for the protobuf messages) [possible
+                                         values: json, yaml, protobuf]
+        --raw-value <raw-value>          print only the given output field (e.g. txid) as a raw value
+        --recovery-key <recovery-key>    the x-only public key which can recover the coins
 ";
-	assert_cmd(&["simplicity", "simplicity"], "", expected_help);
-	assert_cmd(&["simplicity", "simplicity", "-h"], expected_help, "");
-	assert_cmd(&["simplicity", "simplicity", "--help"], expected_help, "");
-	assert_cmd(&["simplicity", "simplicity", "--help", "xyz"], expected_help, "");
+	assert_cmd(&["simplicity", "simplicity", "vault", "-h"], expected_help, "");
+	assert_cmd(&["simplicity", "simplicity", "vault", "--help"], expected_help, "");
+
+	assert_cmd(
+		&[
+			"simplicity",
+			"simplicity",
+			"vault",
+			"--destination",
+			"ex1pjukxxz5p4d96ku50xy5r926tguym5mf6tjjvuetpz4negzvhr3uqt9w2cg",
+			"--recovery-key",
+			"9bef8d556d80e43ae7e0becb3a7e6838b95defe45896ed6075bb9035d06c9964",
+			"--delay",
+			"1008",
+			"--max-fee",
+			"1000",
+		],
+		r#"{
+  "destination": "ex1pjukxxz5p4d96ku50xy5r926tguym5mf6tjjvuetpz4negzvhr3uqt9w2cg",
+  "destination_script": "5120972c630a81ab4bab728f312832ab4b4709ba6d3a5ca4ce656115679409971c78",
+  "max_fee": 1000,
+  "recovery_key": "9bef8d556d80e43ae7e0becb3a7e6838b95defe45896ed6075bb9035d06c9964",
+  "delay": 1008,
+  "commit_base64": "5XukUWQAAAAD8MBRm3EEYBFkAAAABfDEUAZgZAyWmBWLZrfvseGKFZEnXp9xn+2ae5yzxfDK75950Abw5FT7IAAAAA+GoECcDUAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAIMDQMQtTNugEAcFBvfDgQnD1AAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAWsFfDMHBAnEqgAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAABBgaBiE4rA1i1gOCAcUfDQDiEJxkoAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAQYGgYkHAoTjtQAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAgwNAxfDgYGkTkGBiLFpwDIEAcgQcKCy1aaMgQfgAIswAAAAAAAAPoFAGsDkWDUBxu+GwHJQJyXUAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAIMDQMQHJYGodYgfh5QRaZvvjVVtgOQ65+C+yzp+aDi5Xe/kWJbtYHW7kDXQbJlk4KHFGxgIBoHB2gcrAA=",
+  "commit_decode": "(witness  & unit); case (drop (((((((const 0x00000001  & jet_num_inputs ); jet_eq_32 ); jet_verify ); (((const 0x00000002  & jet_num_outputs ); jet_eq_32 ); jet_verify )); (((const 0x8158b66b7efb1e18a1591275e9f719fed9a7b9cb3c5f0caef9f79d006f0e454f  & (((const 0x00000000 ; jet_output_script_hash ) & unit); case (fail) (OH))); jet_eq_256 ); jet_verify )); (((((((jet_current_asset  & unit); case (fail) (OH)) & (((((const 0x00000000 ; jet_output_asset ) & unit); case (fail) (OH)) & unit); case (fail) (OH))); jet_eq_256 ); jet_verify ); (((((((((const 0x00000000 ; jet_output_amount ) & unit); case (fail) (OH)); IH) & unit); case (fail) (OH)) & (((jet_current_amount ; IH) & unit); case (fail) (OH))); jet_le_64 ); jet_verify )); (((((((((jet_current_amount ; IH) & unit); case (fail) (OH)) & ((((((const 0x00000000 ; jet_output_amount ) & unit); case (fail) (OH)); IH) & unit); case (fail) (OH))); jet_subtract_64 ); IH) & const 0x00000000000003e8 ); jet_le_64 ); jet_verify ))); ((((const 0x00000001 ; jet_output_is_fee ) & unit); case (fail) (OH)); jet_verify ))) (drop ((const 0x03f0 ; jet_check_lock_distance ); (((const 0x9bef8d556d80e43ae7e0becb3a7e6838b95defe45896ed6075bb9035d06c9964  & jet_sig_all_hash ) & witness ); jet_bip_0340_verify )))",
+  "type_arrow": "1 → 1",
+  "cmr": "3f6327bca4cdf925e835ffbe32d10909dea441c148f84772dfaf37f49121d483",
+  "liquid_address_unconf": "ex1pu5dpmmdx3hpvfxm93wkmestszylflvamnk2n6s4d3gugce67xfmqxxffxl",
+  "liquid_testnet_address_unconf": "tex1pu5dpmmdx3hpvfxm93wkmestszylflvamnk2n6s4d3gugce67xfmq8lmwhs",
+  "control_block": "bef5919fa64ce45f8306849072b26c1bfdd2937e6b81774796ff372bd1eb5362d2",
+  "witness": [
+    {
+      "type": "2",
+      "bit_width": 1,
+      "description": "0 for the covenant path, 1 for the recovery path"
+    },
+    {
+      "type": "2^512",
+      "bit_width": 512,
+      "description": "BIP-340 signature by 9bef8d556d80e43ae7e0becb3a7e6838b95defe45896ed6075bb9035d06c9964 of the SIGHASH_ALL of the spending transaction (recovery path only)"
+    }
+  ],
+  "covenant": {
+    "selector": 0,
+    "condition": "the spending transaction has exactly one input and exactly two outputs: output 0 pays the asset of the coins to ex1pjukxxz5p4d96ku50xy5r926tguym5mf6tjjvuetpz4negzvhr3uqt9w2cg, at most 1000 less than their amount, and output 1 is the fee; the asset and amount of the coins and of output 0 must be explicit",
+    "witness": []
+  },
+  "recovery": {
+    "selector": 1,
+    "condition": "the spending input has a relative timelock of at least 1008 blocks",
+    "witness": [
+      "BIP-340 signature by 9bef8d556d80e43ae7e0becb3a7e6838b95defe45896ed6075bb9035d06c9964 of the SIGHASH_ALL of the spending transaction"
+    ]
+  }
+}"#,
+		"",
+	);
+
+	assert_cmd(
+		&[
+			"simplicity",
+			"simplicity",
+			"vault",
+			"--destination",
+			"ex1pjukxxz5p4d96ku50xy5r926tguym5mf6tjjvuetpz4negzvhr3uqt9w2cg",
+			"--recovery-key",
+			"9bef8d556d80e43ae7e0becb3a7e6838b95defe45896ed6075bb9035d06c9964",
+			"--delay",
+			"70000",
+			"--max-fee",
+			"1000",
+		],
+		"Execution failed: invalid delay: ParseIntError { kind: PosOverflow }\n",
+		"",
+	);
 }
 
 #[test]
-fn cli_simplicity_simplicity_info() {
+fn cli_simplicity_simplicity_witness_types() {
 	let expected_help = "\
-hal-simplicity-simplicity-info 
-Parse a base64-encoded Simplicity program and decode it
+hal-simplicity-simplicity-witness-types 
+List the witness and disconnect nodes of a Simplicity program with their types
 
 USAGE:
-    hal simplicity simplicity info [FLAGS] <program> [witness]
+    hal simplicity simplicity witness-types [FLAGS] [OPTIONS] <program>
 
 FLAGS:
-    -r, --elementsregtest    run in elementsregtest mode
-    -h, --help               Prints help information
-        --liquid             run in liquid mode
-    -v, --verbose            print verbose logging output to stderr
-    -y, --yaml               print output in YAML instead of JSON
+    -h, --help          Prints help information
+        --no-newline    don't print a trailing newline after a raw value
+    -v, --verbose       print verbose logging output to stderr
+    -y, --yaml          print output in YAML instead of JSON
+
+OPTIONS:
+        --log-format <log-format>    the format of the logging output [possible values: text, json]
+        --out <out>                  write the output to the given file instead of stdout, gzipped if it ends in .gz
+    -o, --output <output>            the output format (see `schema --proto` for the protobuf messages) [possible
+                                     values: json, yaml, protobuf]
+        --raw-value <raw-value>      print only the given output field (e.g. txid) as a raw value
 
 ARGS:
     <program>    a Simplicity program in base64
-    <witness>    a hex encoding of all the witness data for the program
 ";
-	// For the transaction/block create / decode functions we can take input by
-	// stdin as an undocumented JSON blob. FIXME we probably want to do this
-	// here (and in the other simplicity commands) to allow for very large
-	// programs and witnesses. But I'd rather do it properly (i.e. with some
-	// docs and help) so not gonna do it now.
+	assert_cmd(&["simplicity", "simplicity", "witness-types", "-h"], expected_help, "");
+	assert_cmd(&["simplicity", "simplicity", "witness-types", "--help"], expected_help, "");
+
 	assert_cmd(
-		&["simplicity", "simplicity", "info"],
+		&["simplicity", "simplicity", "witness-types", "4lsgAAAAF0iiyAAAAADrTN98aqtsByHXPwX2WdPzQcXK738ixLdrA63cga6DZMsnBQ4o2MBAG04OoDacTgDi2ANQHBjg8DAzC1NLiCEHAoXEDNuIIwCA"],
+		r#"{
+  "witness_count": 4,
+  "disconnect_count": 0,
+  "max_witness_bits": 1026,
+  "nodes": [
+    {
+      "index": 1,
+      "kind": "witness",
+      "type": "2",
+      "bit_width": 1
+    },
+    {
+      "index": 9,
+      "kind": "witness",
+      "type": "2^512",
+      "bit_width": 512
+    },
+    {
+      "index": 17,
+      "kind": "witness",
+      "type": "2",
+      "bit_width": 1
+    },
+    {
+      "index": 19,
+      "kind": "witness",
+      "type": "2^512",
+      "bit_width": 512
+    }
+  ]
+}"#,
 		"",
-		"\
-error: The following required arguments were not provided:
-    <program>
+	);
 
-USAGE:
-    hal simplicity simplicity info [FLAGS] <program> [witness]
+	// The indexes are those of the node listing of `info`, with the sharing of
+	// the encoding.
+	let program = "4lsgAAAAF0iiyAAAAADrTN98aqtsByHXPwX2WdPzQcXK738ixLdrA63cga6DZMsnBQ4o2MBAG04OoDacTgDi2ANQHBjg8DAzC1NLiCEHAoXEDNuIIwCA";
+	let witness: serde_json::Value =
+		assert_deserialize_cmd(&["simplicity", "simplicity", "witness-types", program], |s| {
+			serde_json::from_slice(s)
+		});
+	let listing: serde_json::Value = assert_deserialize_cmd(
+		&["simplicity", "simplicity", "info", "--format", "nodes", program],
+		|s| serde_json::from_slice(s),
+	);
+	let listed = listing
+		.as_array()
+		.unwrap()
+		.iter()
+		.filter(|node| node["combinator"] == "witness")
+		.map(|node| node["index"].clone())
+		.collect::<Vec<_>>();
+	let indexes = witness["nodes"].as_array().unwrap().iter().map(|node| node["index"].clone());
+	assert_eq!(indexes.collect::<Vec<_>>(), listed);
 
-For more information try --help
-",
+	assert_cmd(
+		&[
+			"simplicity",
+			"simplicity",
+			"witness-types",
+			"ztM33xqq2wHIdc/BfZZ0/NBxcrvfyLEt2sDrdyBroNkyycFDijYwEA==",
+			"--raw-value",
+			"max_witness_bits",
+		],
+		"512\n",
+		"",
 	);
-	assert_cmd(&["simplicity", "simplicity", "info", "-h"], expected_help, "");
-	assert_cmd(&["simplicity", "simplicity", "info", "--help"], expected_help, "");
-	assert_cmd(&["simplicity", "simplicity", "info", "--help", "xyz"], expected_help, "");
+
+	// Protobuf output isn't valid utf-8, so we can't use `assert_cmd`.
+	let args = &[
+		"simplicity",
+		"simplicity",
+		"witness-types",
+		"ztM33xqq2wHIdc/BfZZ0/NBxcrvfyLEt2sDrdyBroNkyycFDijYwEA==",
+		"-o",
+		"protobuf",
+	];
+	let output = self_command().args(args.iter()).output().unwrap();
+	assert_eq!(
+		output.stdout.as_hex().to_string(),
+		"08001080041a1508800410031a077769746e6573732205325e3531322001",
+	);
+	assert_eq!(output.stderr, Vec::<u8>::new());
 }
 
 #[test]
@@ -1193,12 +4202,16 @@ hal-simplicity-tx
 manipulate transactions
 
 USAGE:
-    hal simplicity tx [FLAGS] <SUBCOMMAND>
+    hal simplicity tx [FLAGS] [OPTIONS] <SUBCOMMAND>
 
 FLAGS:
     -h, --help       Prints help information
     -v, --verbose    print verbose logging output to stderr
 
+OPTIONS:
+        --log-format <log-format>    the format of the logging output [possible values: text, json]
+        --out <out>                  write the output to the given file instead of stdout, gzipped if it ends in .gz
+
 SUBCOMMANDS:
     create    create a raw transaction from JSON
     decode    decode a raw transaction to JSON
@@ -1216,15 +4229,25 @@ hal-simplicity-tx-create
 create a raw transaction from JSON
 
 USAGE:
-    hal simplicity tx create [FLAGS] [tx-info]
+    hal simplicity tx create [FLAGS] [OPTIONS] [--] [tx-info]
 
 FLAGS:
+        --check         check the transaction for duplicate inputs, unbalanced values, non-standard scripts and a
+                        missing fee output
     -h, --help          Prints help information
     -r, --raw-stdout    output the raw bytes of the result to stdout
     -v, --verbose       print verbose logging output to stderr
 
+OPTIONS:
+        --input-format <input-format>    the format of the input (by default JSON if it starts with '{', else YAML)
+                                         [possible values: json, yaml]
+    -i, --input-utxo <input-utxo>...     an output spent by the transaction as SCRIPTPUBKEY:ASSET:VALUE, given once per
+                                         input to check the value balance
+        --log-format <log-format>        the format of the logging output [possible values: text, json]
+        --out <out>                      write the output to the given file instead of stdout, gzipped if it ends in .gz
+
 ARGS:
-    <tx-info>    the transaction info in JSON
+    <tx-info>    the transaction info in JSON or YAML
 ";
 	assert_cmd(
 		&["simplicity", "tx", "create"],
@@ -1237,28 +4260,158 @@ ARGS:
 
 	assert_cmd(&["simplicity", "tx", "create", ""], "Execution failed: invalid JSON provided: Error(\"EOF while parsing a value\", line: 1, column: 0)\n", "");
 	assert_cmd(
-		&["simplicity", "tx", "create", "{ }"],
-		"Execution failed: Field \"version\" is required.\n",
-		"",
+		&["simplicity", "tx", "create", "{ }"],
+		"Execution failed: Field \"version\" is required.\n",
+		"",
+	);
+	// FIXME I have no idea what is wrong here. But putting a test in to track fixing
+	//  whatever is causing this nonsense error.
+	assert_cmd(
+		&["simplicity", "tx", "create", "{ \"version\": 10, \"locktime\": 10 }"],
+		"Execution failed: invalid JSON provided: Error(\"expected value\", line: 1, column: 30)\n",
+		"",
+	);
+	// FIXME: lol, replace this locktime format with something sane
+	assert_cmd(
+		&["simplicity", "tx", "create", "{ \"version\": 10, \"locktime\": { \"Blocks\": 10 }, \"inputs\": [], \"outputs\": [] }"],
+		"0a0000000000000a000000",
+		"",
+	);
+	assert_cmd(
+		&[
+			"simplicity",
+			"tx",
+			"create",
+			"version: 10\nlocktime:\n  Blocks: 10\ninputs: []\noutputs: []\n",
+		],
+		"0a0000000000000a000000",
+		"",
+	);
+	assert_cmd(
+		&[
+			"simplicity",
+			"tx",
+			"create",
+			"{ version: 10, locktime: { Blocks: 10 }, inputs: [], outputs: [] }",
+			"--input-format",
+			"yaml",
+		],
+		"0a0000000000000a000000",
+		"",
+	);
+	assert_cmd(
+		&["simplicity", "tx", "create", "version: 10", "--input-format", "json"],
+		"Execution failed: invalid JSON provided: Error(\"expected value\", line: 1, column: 1)\n",
+		"",
+	);
+	assert_cmd(
+		&["simplicity", "tx", "create", "version: [10"],
+		"Execution failed: invalid YAML provided: while parsing a flow sequence, expectd ',' or ']' at line 2 column 1\n",
+		"",
+	);
+	// -v does nothing
+	assert_cmd(
+		&["simplicity", "tx", "create", "-v", "{ \"version\": 10, \"locktime\": { \"Blocks\": 10 }, \"inputs\": [], \"outputs\": [] }"],
+		"0a0000000000000a000000",
+		"",
+	);
+
+	// OP_RETURN data outputs
+	let data_output = |data: &str| {
+		format!(
+			"{{ version: 2, locktime: {{ Blocks: 0 }}, inputs: [], outputs: [{{ asset: {{ type: explicit, asset: 6f0279e9ed041c3d710a9f57d0c02928416460c4b722ae3457a11eec381c526d }}, data: \"{}\" }}] }}",
+			data,
+		)
+	};
+	assert_cmd(
+		&["simplicity", "tx", "create", &data_output("utf8:hello"), "--input-format", "yaml"],
+		"02000000000001016d521c38ec1ea15734ae22b7c46064412829c0d0579f0a713d1c04ede979026f01000000000000000000076a0568656c6c6f00000000",
+		"",
+	);
+	assert_cmd(
+		&["simplicity", "tx", "create", &data_output("68656c6c6f"), "--input-format", "yaml"],
+		"02000000000001016d521c38ec1ea15734ae22b7c46064412829c0d0579f0a713d1c04ede979026f01000000000000000000076a0568656c6c6f00000000",
+		"",
+	);
+	assert_cmd(
+		&["simplicity", "tx", "create", &data_output("hello"), "--input-format", "yaml"],
+		"Execution failed: invalid hex in \"data\" field of output (use \"utf8:\" for text): OddLength\n",
+		"",
+	);
+	assert_cmd(
+		&["simplicity", "tx", "create", &data_output(&"00".repeat(81)), "--input-format", "yaml"],
+		"Execution failed: Field \"data\" of output is 81 bytes, more than the 80 bytes relayed by nodes.\n",
+		"",
+	);
+
+	// --check catches transactions which a node would reject.
+	let lbtc = "6f0279e9ed041c3d710a9f57d0c02928416460c4b722ae3457a11eec381c526d";
+	let output = |script: &str, value: u64| {
+		format!(
+			"{{ script_pub_key: {{ hex: \"{}\" }}, asset: {{ type: explicit, asset: {} }}, value: {{ type: explicit, value: {} }}, nonce: {{ type: \"null\" }} }}",
+			script, lbtc, value,
+		)
+	};
+	let spend = |vouts: &[u32], outputs: &[String]| {
+		let inputs = vouts
+			.iter()
+			.map(|vout| format!("{{ txid: \"{}\", vout: {} }}", "22".repeat(32), vout))
+			.collect::<Vec<_>>();
+		format!(
+			"{{ version: 2, locktime: {{ Blocks: 0 }}, inputs: [{}], outputs: [{}] }}",
+			inputs.join(", "),
+			outputs.join(", "),
+		)
+	};
+	let utxo = format!("51:{}:100000", lbtc);
+	let balanced = spend(&[0], &[output("51", 99000), output("", 1000)]);
+	let unbalanced = spend(&[0], &[output("51", 99000), output("", 999)]);
+	assert_cmd(
+		&["simplicity", "tx", "create", "--input-format", "yaml", "--check", "-i", &utxo, &balanced],
+		"020000000001222222222222222222222222222222222222222222222222222222222222222200000000000000000002016d521c38ec1ea15734ae22b7c46064412829c0d0579f0a713d1c04ede979026f0100000000000182b8000151016d521c38ec1ea15734ae22b7c46064412829c0d0579f0a713d1c04ede979026f0100000000000003e8000000000000",
+		"",
+	);
+	assert_cmd(
+		&["simplicity", "tx", "create", "--input-format", "yaml", "--check", "-i", &utxo, &unbalanced],
+		"Execution failed: the transaction failed 1 of the checks\n",
+		"Check failed: asset 6f0279e9ed041c3d710a9f57d0c02928416460c4b722ae3457a11eec381c526d is unbalanced: 100000 sat spent and 99999 sat in outputs.\n",
+	);
+	let overflowing = spend(&[0], &[output("51", u64::MAX), output("", 1000)]);
+	assert_cmd(
+		&["simplicity", "tx", "create", "--input-format", "yaml", "--check", "-i", &utxo, &overflowing],
+		"Execution failed: the transaction failed 1 of the checks\n",
+		"Check failed: the amounts of asset 6f0279e9ed041c3d710a9f57d0c02928416460c4b722ae3457a11eec381c526d add up to more than 18446744073709551615 sat.\n",
 	);
-	// FIXME I have no idea what is wrong here. But putting a test in to track fixing
-	//  whatever is causing this nonsense error.
 	assert_cmd(
-		&["simplicity", "tx", "create", "{ \"version\": 10, \"locktime\": 10 }"],
-		"Execution failed: invalid JSON provided: Error(\"expected value\", line: 1, column: 30)\n",
-		"",
+		&["simplicity", "tx", "create", "--input-format", "yaml", "--check", "-i", &utxo, "-i", &utxo, &spend(&[0, 0], &[output("51", 99000)])],
+		"Execution failed: the transaction failed 3 of the checks\n",
+		"Check failed: input 1 spends [elements]2222222222222222222222222222222222222222222222222222222222222222:0, already spent by input 0.\n\
+		 Check failed: the transaction has no fee output.\n\
+		 Check failed: asset 6f0279e9ed041c3d710a9f57d0c02928416460c4b722ae3457a11eec381c526d is unbalanced: 200000 sat spent and 99000 sat in outputs.\n",
 	);
-	// FIXME: lol, replace this locktime format with something sane
 	assert_cmd(
-		&["simplicity", "tx", "create", "{ \"version\": 10, \"locktime\": { \"Blocks\": 10 }, \"inputs\": [], \"outputs\": [] }"],
-		"0a0000000000000a000000",
-		"",
+		&[
+			"simplicity",
+			"tx",
+			"create",
+			"--input-format",
+			"yaml",
+			"--check",
+			&spend(&[0], &[output(&format!("6a4c51{}", "00".repeat(81)), 0), output("", 1000)]),
+		],
+		"Execution failed: the transaction failed 1 of the checks\n",
+		"Check failed: output 0 has a 84 byte OP_RETURN script, more than the standard 83 bytes.\n",
 	);
-	// -v does nothing
+	// Without the spent outputs, or with confidential ones, the balance isn't checked.
 	assert_cmd(
-		&["simplicity", "tx", "create", "-v", "{ \"version\": 10, \"locktime\": { \"Blocks\": 10 }, \"inputs\": [], \"outputs\": [] }"],
-		"0a0000000000000a000000",
-		"",
+		&["simplicity", "tx", "create", "--input-format", "yaml", "--check", "-v", &unbalanced],
+		"020000000001222222222222222222222222222222222222222222222222222222222222222200000000000000000002016d521c38ec1ea15734ae22b7c46064412829c0d0579f0a713d1c04ede979026f0100000000000182b8000151016d521c38ec1ea15734ae22b7c46064412829c0d0579f0a713d1c04ede979026f0100000000000003e7000000000000",
+		"Skipping the value balance check, pass the spent outputs with --input-utxo.\n",
+	);
+	assert_cmd(
+		&["simplicity", "tx", "create", "--input-format", "yaml", "--check", "-v", "-i", &format!("51:{}:0879be667ef9dcbbac55a06295ce870b07029bfcdb2dce28d959f2815b16f81798", lbtc), &unbalanced],
+		"020000000001222222222222222222222222222222222222222222222222222222222222222200000000000000000002016d521c38ec1ea15734ae22b7c46064412829c0d0579f0a713d1c04ede979026f0100000000000182b8000151016d521c38ec1ea15734ae22b7c46064412829c0d0579f0a713d1c04ede979026f0100000000000003e7000000000000",
+		"Skipping the value balance check, some amounts are confidential.\n",
 	);
 
 	// To test -r we can't use `assert_cmd` since it assumes that stdout
@@ -1282,17 +4435,34 @@ hal-simplicity-tx-decode
 decode a raw transaction to JSON
 
 USAGE:
-    hal simplicity tx decode [FLAGS] [raw-tx]
+    hal simplicity tx decode [FLAGS] [OPTIONS] [--] [raw-tx]...
 
 FLAGS:
+        --abbrev             abbreviate long hex fields such as rangeproofs and witnesses
     -r, --elementsregtest    run in elementsregtest mode
     -h, --help               Prints help information
+        --keep-going         with --ndjson, print an error record for items that fail and continue with the rest
         --liquid             run in liquid mode
+        --liquidtestnet      run in liquidtestnet mode
+        --ndjson             print one compact JSON object per line for each item instead of an array
+        --no-newline         don't print a trailing newline after a raw value
     -v, --verbose            print verbose logging output to stderr
+        --verify-sigs        check the signatures of P2PKH, P2WPKH and taproot key path inputs
     -y, --yaml               print output in YAML instead of JSON
 
+OPTIONS:
+        --address-book <address-book>    label the inputs and outputs whose scripts are in the given JSON or YAML file
+    -g, --genesis-hash <genesis-hash>    the genesis block hash of the network (defaults to Liquid)
+    -i, --input-utxo <input-utxo>...     an output spent by the transaction as SCRIPTPUBKEY:ASSET:VALUE, given once per
+                                         input
+        --log-format <log-format>        the format of the logging output [possible values: text, json]
+        --out <out>                      write the output to the given file instead of stdout, gzipped if it ends in .gz
+    -o, --output <output>                the output format (see `schema --proto` for the protobuf messages) [possible
+                                         values: json, yaml, protobuf]
+        --raw-value <raw-value>          print only the given output field (e.g. txid) as a raw value
+
 ARGS:
-    <raw-tx>    the raw transaction in hex
+    <raw-tx>...    the raw transactions in hex, base64 or as file paths
 ";
 	assert_cmd(
 		&["simplicity", "tx", "decode"],
@@ -1421,10 +4591,10 @@ ARGS:
 	assert_cmd(&["simplicity", "tx", "decode", "-r", "0200000001010000000000000000000000000000000000000000000000000000000000000000ffffffff0603a730180101ffffffff03016d521c38ec1ea15734ae22b7c46064412829c0d0579f0a713d1c04ede979026f01000000000000000000266a240a8ce26fdbb51a2d03d4e62fdafd4a06dd7faa0d1c083aa7e27905000000000000000000016d521c38ec1ea15734ae22b7c46064412829c0d0579f0a713d1c04ede979026f010000000000000106001976a914fc26751a5025129a2fd006c6fbfa598ddd67f7e188ac016d521c38ec1ea15734ae22b7c46064412829c0d0579f0a713d1c04ede979026f01000000000000000000266a24aa21a9ede8497768bc893ee587244bf5303ac3cf482bab8e4b3fd22e8b114c2a52525ab30000000000000120000000000000000000000000000000000000000000000000000000000000000000000000000000"],
 		tx_decode,
 		"");
-	// -v works but seems to do nothing
+	// -v reports the assumed input format
 	assert_cmd(&["simplicity", "tx", "decode", "-v", "0200000001010000000000000000000000000000000000000000000000000000000000000000ffffffff0603a730180101ffffffff03016d521c38ec1ea15734ae22b7c46064412829c0d0579f0a713d1c04ede979026f01000000000000000000266a240a8ce26fdbb51a2d03d4e62fdafd4a06dd7faa0d1c083aa7e27905000000000000000000016d521c38ec1ea15734ae22b7c46064412829c0d0579f0a713d1c04ede979026f010000000000000106001976a914fc26751a5025129a2fd006c6fbfa598ddd67f7e188ac016d521c38ec1ea15734ae22b7c46064412829c0d0579f0a713d1c04ede979026f01000000000000000000266a24aa21a9ede8497768bc893ee587244bf5303ac3cf482bab8e4b3fd22e8b114c2a52525ab30000000000000120000000000000000000000000000000000000000000000000000000000000000000000000000000"],
 		tx_decode,
-		"");
+		"Assuming hex input for 'raw-tx'.\n");
 	assert_cmd(&["simplicity", "tx", "decode", "--liquid", "0200000001010000000000000000000000000000000000000000000000000000000000000000ffffffff0603a730180101ffffffff03016d521c38ec1ea15734ae22b7c46064412829c0d0579f0a713d1c04ede979026f01000000000000000000266a240a8ce26fdbb51a2d03d4e62fdafd4a06dd7faa0d1c083aa7e27905000000000000000000016d521c38ec1ea15734ae22b7c46064412829c0d0579f0a713d1c04ede979026f010000000000000106001976a914fc26751a5025129a2fd006c6fbfa598ddd67f7e188ac016d521c38ec1ea15734ae22b7c46064412829c0d0579f0a713d1c04ede979026f01000000000000000000266a24aa21a9ede8497768bc893ee587244bf5303ac3cf482bab8e4b3fd22e8b114c2a52525ab30000000000000120000000000000000000000000000000000000000000000000000000000000000000000000000000"],
 		tx_decode.replace("2dxQzjvrkmRGSa5gwgaQn1oLtRo5pXS94oJ", "QLFdUboUPJnUzvsXKu83hUtrQ1DuxyggRg"),
 		"");
@@ -1513,9 +4683,750 @@ outputs:
       rangeproof: ~
     is_fee: false"#,
 		"");
+
+	// --raw-value prints a single field, unquoted, with a trailing newline unless told otherwise
+	let raw_tx = "0200000001010000000000000000000000000000000000000000000000000000000000000000ffffffff0603a730180101ffffffff03016d521c38ec1ea15734ae22b7c46064412829c0d0579f0a713d1c04ede979026f01000000000000000000266a240a8ce26fdbb51a2d03d4e62fdafd4a06dd7faa0d1c083aa7e27905000000000000000000016d521c38ec1ea15734ae22b7c46064412829c0d0579f0a713d1c04ede979026f010000000000000106001976a914fc26751a5025129a2fd006c6fbfa598ddd67f7e188ac016d521c38ec1ea15734ae22b7c46064412829c0d0579f0a713d1c04ede979026f01000000000000000000266a24aa21a9ede8497768bc893ee587244bf5303ac3cf482bab8e4b3fd22e8b114c2a52525ab30000000000000120000000000000000000000000000000000000000000000000000000000000000000000000000000";
+	assert_cmd(
+		&["simplicity", "tx", "decode", "--raw-value", "txid", raw_tx],
+		"9523d75b48b3411a3f4ebd31b6005898deebbe748875aa6ee084b94aa8422ba6\n",
+		"",
+	);
+	assert_cmd(
+		&["simplicity", "tx", "decode", "--raw-value", "txid", "--no-newline", raw_tx],
+		"9523d75b48b3411a3f4ebd31b6005898deebbe748875aa6ee084b94aa8422ba6",
+		"",
+	);
+	// Nested fields and array elements are separated by dots; non-strings are printed as JSON
+	assert_cmd(
+		&["simplicity", "tx", "decode", "--raw-value", "outputs.1.value.value", raw_tx],
+		"262\n",
+		"",
+	);
+	assert_cmd(
+		&["simplicity", "tx", "decode", "--raw-value", "outputs.0.script_pub_key.address", raw_tx],
+		"Execution failed: no field 'outputs.0.script_pub_key.address' in output\n",
+		"",
+	);
+	assert_cmd(
+		&["simplicity", "tx", "decode", "--no-newline", raw_tx],
+		"",
+		"\
+error: The following required arguments were not provided:
+    --raw-value <raw-value>
+
+USAGE:
+    hal simplicity tx decode --no-newline --raw-value <raw-value>
+
+For more information try --help
+",
+	);
+
+	// Several transactions are printed as an array, or one per line with --ndjson.
+	let liquid_tx = "0200000001010000000000000000000000000000000000000000000000000000000000000000ffffffff0603a730180101ffffffff03016d521c38ec1ea15734ae22b7c46064412829c0d0579f0a713d1c04ede979026f01000000000000000000266a240a8ce26fdbb51a2d03d4e62fdafd4a06dd7faa0d1c083aa7e27905000000000000000000016d521c38ec1ea15734ae22b7c46064412829c0d0579f0a713d1c04ede979026f010000000000000106001976a914fc26751a5025129a2fd006c6fbfa598ddd67f7e188ac016d521c38ec1ea15734ae22b7c46064412829c0d0579f0a713d1c04ede979026f01000000000000000000266a24aa21a9ede8497768bc893ee587244bf5303ac3cf482bab8e4b3fd22e8b114c2a52525ab30000000000000120000000000000000000000000000000000000000000000000000000000000000000000000000000";
+	assert_cmd(
+		&["simplicity", "tx", "decode", "--raw-value", "txid", liquid_tx, liquid_tx],
+		"9523d75b48b3411a3f4ebd31b6005898deebbe748875aa6ee084b94aa8422ba6\n\
+		 9523d75b48b3411a3f4ebd31b6005898deebbe748875aa6ee084b94aa8422ba6\n",
+		"",
+	);
+	let single: serde_json::Value =
+		assert_deserialize_cmd(&["simplicity", "tx", "decode", liquid_tx], |s| {
+			serde_json::from_slice(s)
+		});
+	let several: serde_json::Value =
+		assert_deserialize_cmd(&["simplicity", "tx", "decode", liquid_tx, liquid_tx], |s| {
+			serde_json::from_slice(s)
+		});
+	assert_eq!(several, serde_json::json!([single, single]));
+	let several: serde_json::Value =
+		assert_deserialize_cmd(&["simplicity", "tx", "decode", "-y", liquid_tx, liquid_tx], |s| {
+			serde_yaml::from_slice(s)
+		});
+	assert_eq!(several, serde_json::json!([single, single]));
+	// A single transaction on stdin is printed as a single object, several as an array.
+	let output = {
+		use std::io::Write;
+
+		let mut child = self_command()
+			.args(["simplicity", "tx", "decode"])
+			.stdin(std::process::Stdio::piped())
+			.stdout(std::process::Stdio::piped())
+			.spawn()
+			.unwrap();
+		child.stdin.take().unwrap().write_all(liquid_tx.as_bytes()).unwrap();
+		child.wait_with_output().unwrap()
+	};
+	let stdin: serde_json::Value = serde_json::from_slice(&output.stdout).unwrap();
+	assert_eq!(stdin, single);
+	let output = {
+		use std::io::Write;
+
+		let mut child = self_command()
+			.args(["simplicity", "tx", "decode"])
+			.stdin(std::process::Stdio::piped())
+			.stdout(std::process::Stdio::piped())
+			.spawn()
+			.unwrap();
+		writeln!(child.stdin.take().unwrap(), "{}\n{}", liquid_tx, liquid_tx).unwrap();
+		child.wait_with_output().unwrap()
+	};
+	let stdin: serde_json::Value = serde_json::from_slice(&output.stdout).unwrap();
+	assert_eq!(stdin, serde_json::json!([single, single]));
+	// With --ndjson, transactions on stdin are printed as they are read.
+	{
+		use std::io::{BufRead, BufReader, Write};
+
+		let mut child = self_command()
+			.args(["simplicity", "tx", "decode", "--ndjson", "--raw-value", "txid"])
+			.stdin(std::process::Stdio::piped())
+			.stdout(std::process::Stdio::piped())
+			.spawn()
+			.unwrap();
+		let mut stdin = child.stdin.take().unwrap();
+		let mut stdout = BufReader::new(child.stdout.take().unwrap());
+		for _ in 0..2 {
+			writeln!(stdin, "{}", liquid_tx).unwrap();
+			let mut line = String::new();
+			stdout.read_line(&mut line).unwrap();
+			assert_eq!(line, "9523d75b48b3411a3f4ebd31b6005898deebbe748875aa6ee084b94aa8422ba6\n");
+		}
+		drop(stdin);
+		assert!(child.wait().unwrap().success());
+	}
+	// Base64 input is detected too.
+	assert_cmd(
+		&["simplicity", "tx", "decode", "-v", "--raw-value", "txid", "AgAAAAEBAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAD/////BgOnMBgBAf////8DAW1SHDjsHqFXNK4it8RgZEEoKcDQV58KcT0cBO3peQJvAQAAAAAAAAAAACZqJAqM4m/btRotA9TmL9r9Sgbdf6oNHAg6p+J5BQAAAAAAAAAAAAFtUhw47B6hVzSuIrfEYGRBKCnA0FefCnE9HATt6XkCbwEAAAAAAAABBgAZdqkU/CZ1GlAlEpov0AbG+/pZjd1n9+GIrAFtUhw47B6hVzSuIrfEYGRBKCnA0FefCnE9HATt6XkCbwEAAAAAAAAAAAAmaiSqIant6El3aLyJPuWHJEv1MDrDz0grq45LP9IuixFMKlJSWrMAAAAAAAABIAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAA=="],
+		"9523d75b48b3411a3f4ebd31b6005898deebbe748875aa6ee084b94aa8422ba6\n",
+		"Assuming base64 input for 'raw-tx'.\n",
+	);
+	// A file whose path is valid base64 too is read, with a warning even without -v.
+	let dir = std::env::temp_dir().join(format!("hal-simplicity-input-{}", std::process::id()));
+	std::fs::create_dir_all(&dir).unwrap();
+	std::fs::write(dir.join("txfile00"), hex::decode(liquid_tx).unwrap()).unwrap();
+	let output = self_command()
+		.current_dir(&dir)
+		.args(["simplicity", "tx", "decode", "--raw-value", "txid", "txfile00"])
+		.output()
+		.unwrap();
+	assert_eq!(
+		String::from_utf8(output.stdout).unwrap(),
+		"9523d75b48b3411a3f4ebd31b6005898deebbe748875aa6ee084b94aa8422ba6\n"
+	);
+	assert_eq!(
+		String::from_utf8(output.stderr).unwrap(),
+		"Assuming file input for 'raw-tx', although it is valid base64 too.\n"
+	);
+	std::fs::remove_dir_all(&dir).unwrap();
+	// With --log-format json each log record is a JSON object on its own line.
+	let output = self_command()
+		.args(["simplicity", "tx", "decode", "-v", "--log-format", "json", liquid_tx])
+		.output()
+		.unwrap();
+	let stderr = String::from_utf8(output.stderr).unwrap();
+	let record: serde_json::Value = serde_json::from_str(stderr.trim_end()).unwrap();
+	assert_eq!(record["level"], "INFO");
+	assert_eq!(record["module"], "hal_simplicity::cmd");
+	assert_eq!(record["message"], "Assuming hex input for 'raw-tx'.");
+	assert!(record["timestamp"].as_str().unwrap().ends_with('Z'));
+	assert_cmd(
+		&["simplicity", "tx", "decode", "zz"],
+		"Execution failed: invalid 'raw-tx': expected hex, base64 or the path of a file\n",
+		"",
+	);
+	assert_cmd(
+		&["simplicity", "tx", "decode", "--ndjson", "--raw-value", "txid", liquid_tx, liquid_tx],
+		"9523d75b48b3411a3f4ebd31b6005898deebbe748875aa6ee084b94aa8422ba6\n\
+		 9523d75b48b3411a3f4ebd31b6005898deebbe748875aa6ee084b94aa8422ba6\n",
+		"",
+	); // With --keep-going failures are reported per item, followed by a summary.
+	assert_cmd(
+		&[
+			"simplicity",
+			"tx",
+			"decode",
+			"--ndjson",
+			"--keep-going",
+			"--raw-value",
+			"txid",
+			liquid_tx,
+			"zz",
+			liquid_tx,
+		],
+		"9523d75b48b3411a3f4ebd31b6005898deebbe748875aa6ee084b94aa8422ba6\n\
+		 {\"error\":\"invalid 'raw-tx': expected hex, base64 or the path of a file\",\"index\":1}\n\
+		 9523d75b48b3411a3f4ebd31b6005898deebbe748875aa6ee084b94aa8422ba6\n\
+		 {\"summary\":{\"failed\":1,\"succeeded\":2,\"total\":3}}\n",
+		"",
+	);
+	// A line of stdin which is not UTF-8 is an error record too.
+	let output = {
+		use std::io::Write;
+
+		let mut child = self_command()
+			.args(["simplicity", "tx", "decode", "--ndjson", "--keep-going", "--raw-value", "txid"])
+			.stdin(std::process::Stdio::piped())
+			.stdout(std::process::Stdio::piped())
+			.stderr(std::process::Stdio::piped())
+			.spawn()
+			.unwrap();
+		let mut stdin = child.stdin.take().unwrap();
+		writeln!(stdin, "{}", liquid_tx).unwrap();
+		stdin.write_all(b"\xff\n").unwrap();
+		writeln!(stdin, "{}", liquid_tx).unwrap();
+		drop(stdin);
+		child.wait_with_output().unwrap()
+	};
+	assert_eq!(output.status.code(), Some(1));
+	assert_eq!(
+		String::from_utf8(output.stdout).unwrap(),
+		"9523d75b48b3411a3f4ebd31b6005898deebbe748875aa6ee084b94aa8422ba6\n\
+		 {\"error\":\"invalid utf8 on stdin for 'raw-tx': stream did not contain valid UTF-8\",\"index\":1}\n\
+		 9523d75b48b3411a3f4ebd31b6005898deebbe748875aa6ee084b94aa8422ba6\n\
+		 {\"summary\":{\"failed\":1,\"succeeded\":2,\"total\":3}}\n"
+	);
+	assert!(output.stderr.is_empty());
+
+	// Inputs are labelled by the script they spend, here a Simplicity taproot spend and
+	// a P2WPKH spend, and outputs by their scriptPubKey.
+	let book =
+		std::env::temp_dir().join(format!("hal-simplicity-book-{}.yaml", std::process::id()));
+	std::fs::write(
+		&book,
+		"ex1pqrrgkhexfuxgmdx002g27dkfxakzt4ccfvap980fn4t0gzakaznqkarzrw: htlc\n\
+		 ex1qw508d6qejxtdg4y5r3zarvary0c5xw7kxw5fx4: hot wallet\n",
+	)
+	.unwrap();
+	let labelled_tx = "0200000001022222222222222222222222222222222222222222222222222222222222222222000000000000000000333333333333333333333333333333333333333333333333333333333333333301000000000000000001016f0279e9ed041c3d710a9f57d0c02928416460c4b722ae3457a11eec381c526d0100000000000003e80022512000c68b5f264f0c8db4cf7a90af36c9376c25d7184b3a129de99d56f40bb6e8a6000000000000040100010020ceb063de6c7f75ef4df82bb9bbe0d01b8f8747366127da2046d18b36feea969421bff5919fa64ce45f8306849072b26c1bfdd2937e6b81774796ff372bd1eb5362d200000002023044210279be667ef9dcbbac55a06295ce870b07029bfcdb2dce28d959f2815b16f81798000000";
+	let output = self_command()
+		.args(["simplicity", "tx", "decode", "--address-book", book.to_str().unwrap(), labelled_tx])
+		.output()
+		.unwrap();
+	std::fs::remove_file(&book).unwrap();
+	let info: serde_json::Value = serde_json::from_slice(&output.stdout).unwrap();
+	assert_eq!(info["inputs"][0]["label"], "htlc");
+	assert_eq!(info["inputs"][1]["label"], "hot wallet");
+	assert_eq!(info["outputs"][0]["label"], "htlc");
+	// --abbrev shortens the witness items but not the scriptPubKeys, however long.
+	let output = self_command()
+		.args(["simplicity", "tx", "decode", "--abbrev", labelled_tx])
+		.output()
+		.unwrap();
+	let info: serde_json::Value = serde_json::from_slice(&output.stdout).unwrap();
+	assert_eq!(info["inputs"][1]["witness"]["script_witness"][1], "0279be66…16f81798 (33 bytes)");
+	assert_eq!(
+		info["outputs"][0]["script_pub_key"]["hex"],
+		"512000c68b5f264f0c8db4cf7a90af36c9376c25d7184b3a129de99d56f40bb6e8a6"
+	);
+	assert_cmd(
+		&["simplicity", "tx", "decode", "--address-book", "/nonexistent/book.yaml", labelled_tx],
+		"Execution failed: could not read address book /nonexistent/book.yaml: No such file or directory (os error 2)\n",
+		"",
+	);
+
+	// --verify-sigs checks a P2WPKH and a taproot key path signature made here.
+	{
+		use elements::bitcoin::secp256k1;
+		use elements::encode::serialize_hex;
+		use elements::hashes::Hash;
+		use elements::sighash::{Prevouts, SighashCache};
+		use elements::{confidential, AssetId, EcdsaSighashType, SchnorrSighashType};
+
+		let secp = secp256k1::SECP256K1;
+		let secret: secp256k1::SecretKey =
+			"0000000000000000000000000000000000000000000000000000000000000003".parse().unwrap();
+		let public_key = elements::bitcoin::PublicKey::new(secret.public_key(secp));
+		let keypair = secp256k1::Keypair::from_secret_key(secp, &secret);
+		let asset: AssetId =
+			"6f0279e9ed041c3d710a9f57d0c02928416460c4b722ae3457a11eec381c526d".parse().unwrap();
+		let pubkey_hash = elements::PubkeyHash::hash(&public_key.to_bytes());
+		let wpkh =
+			elements::Script::new_v0_wpkh(&elements::WPubkeyHash::hash(&public_key.to_bytes()));
+		let tr = elements::script::Builder::new()
+			.push_int(1)
+			.push_slice(&keypair.x_only_public_key().0.serialize())
+			.into_script();
+		let prevouts = [&wpkh, &tr]
+			.iter()
+			.map(|&spk| elements::TxOut {
+				asset: confidential::Asset::Explicit(asset),
+				value: confidential::Value::Explicit(50_000),
+				nonce: confidential::Nonce::Null,
+				script_pubkey: spk.clone(),
+				witness: Default::default(),
+			})
+			.collect::<Vec<_>>();
+		let input = |vout| elements::TxIn {
+			previous_output: elements::OutPoint::new(elements::Txid::all_zeros(), vout),
+			..Default::default()
+		};
+		let mut tx = elements::Transaction {
+			version: 2,
+			lock_time: elements::LockTime::ZERO,
+			input: vec![input(0), input(1)],
+			output: vec![elements::TxOut::new_fee(100_000, asset)],
+		};
+
+		let genesis_hash = "1466275836220db2944ca059a3a10ef6fd2ea684b0688d2c379296888a206003";
+		let script_code = elements::Script::new_p2pkh(&pubkey_hash);
+		let mut cache = SighashCache::new(&tx);
+		let sighash = cache.segwitv0_sighash(
+			0,
+			&script_code,
+			confidential::Value::Explicit(50_000),
+			EcdsaSighashType::All,
+		);
+		let msg = secp256k1::Message::from_digest(sighash.to_byte_array());
+		let mut ecdsa_sig = secp.sign_ecdsa(&msg, &secret).serialize_der().to_vec();
+		ecdsa_sig.push(EcdsaSighashType::All as u8);
+		let sighash = cache
+			.taproot_key_spend_signature_hash(
+				1,
+				&Prevouts::All(&prevouts),
+				SchnorrSighashType::Default,
+				genesis_hash.parse().unwrap(),
+			)
+			.unwrap();
+		let msg = secp256k1::Message::from_digest(sighash.to_byte_array());
+		let schnorr_sig = secp.sign_schnorr_no_aux_rand(&msg, &keypair);
+		tx.input[0].witness.script_witness = vec![ecdsa_sig, public_key.to_bytes()];
+		tx.input[1].witness.script_witness = vec![schnorr_sig.as_ref().to_vec()];
+
+		let utxos = prevouts
+			.iter()
+			.map(|o| {
+				format!("{}:{}:50000", o.script_pubkey.as_bytes().to_lower_hex_string(), asset)
+			})
+			.collect::<Vec<_>>();
+		let decode = |tx: &elements::Transaction, utxos: &[String]| {
+			let raw_tx = serialize_hex(tx);
+			let mut args = vec!["simplicity", "tx", "decode", "--verify-sigs", &raw_tx];
+			for utxo in utxos {
+				args.extend(["-i", utxo.as_str()]);
+			}
+			let output = self_command().args(args).output().unwrap();
+			String::from_utf8(output.stdout).unwrap()
+		};
+		let info: serde_json::Value = serde_json::from_str(&decode(&tx, &utxos)).unwrap();
+		assert_eq!(
+			info["inputs"][0]["signature_check"],
+			serde_json::json!({ "type": "p2wpkh", "valid": true })
+		);
+		assert_eq!(
+			info["inputs"][1]["signature_check"],
+			serde_json::json!({ "type": "p2tr", "valid": true })
+		);
+
+		// Changing the outputs invalidates both signatures.
+		tx.output[0] = elements::TxOut::new_fee(99_000, asset);
+		let info: serde_json::Value = serde_json::from_str(&decode(&tx, &utxos)).unwrap();
+		assert_eq!(info["inputs"][0]["signature_check"]["valid"], false);
+		assert_eq!(info["inputs"][1]["signature_check"]["valid"], false);
+		assert!(info["inputs"][1]["signature_check"]["error"]
+			.as_str()
+			.unwrap()
+			.starts_with("invalid signature of sighash "));
+
+		assert_eq!(
+			decode(&tx, &utxos[..1]),
+			"Execution failed: the transaction has 2 inputs but 1 input UTXOs were given\n"
+		);
+
+		// The UTXOs can't be those of several transactions.
+		let raw_tx = serialize_hex(&tx);
+		let mut args = vec!["simplicity", "tx", "decode", "--verify-sigs", &raw_tx, &raw_tx];
+		for utxo in &utxos {
+			args.extend(["-i", utxo.as_str()]);
+		}
+		assert_cmd(
+			&args,
+			"Execution failed: --verify-sigs checks a single transaction, but several were given\n",
+			"",
+		);
+
+		// A 65-byte signature can't have the default sighash type 0 (BIP-341).
+		tx.output[0] = elements::TxOut::new_fee(100_000, asset);
+		let mut sig = schnorr_sig.as_ref().to_vec();
+		sig.push(0);
+		tx.input[1].witness.script_witness = vec![sig];
+		let info: serde_json::Value = serde_json::from_str(&decode(&tx, &utxos)).unwrap();
+		assert_eq!(info["inputs"][0]["signature_check"]["valid"], true);
+		assert_eq!(
+			info["inputs"][1]["signature_check"],
+			serde_json::json!({
+				"type": "p2tr",
+				"valid": false,
+				"error": "invalid sighash type 0 in a 65-byte signature",
+			})
+		);
+	}
+}
+
+#[test]
+fn cli_simplicity_vectors() {
+	let expected_help = "\
+hal-simplicity-vectors 
+generate deterministic test vectors for cross-checking other implementations
+
+USAGE:
+    hal simplicity vectors [FLAGS] [OPTIONS] <SUBCOMMAND>
+
+FLAGS:
+    -h, --help       Prints help information
+    -v, --verbose    print verbose logging output to stderr
+
+OPTIONS:
+        --log-format <log-format>    the format of the logging output [possible values: text, json]
+        --out <out>                  write the output to the given file instead of stdout, gzipped if it ends in .gz
+
+SUBCOMMANDS:
+    address       addresses of Simplicity programs
+    cmr           CMRs of Simplicity programs compiled from policies
+    commitment    Pedersen commitments to assets and values
+    sighash       sighashes and signatures of Simplicity inputs
+";
+	assert_cmd(&["simplicity", "vectors"], "", expected_help);
+	assert_cmd(&["simplicity", "vectors", "-h"], expected_help, "");
+	assert_cmd(&["simplicity", "vectors", "--help"], expected_help, "");
+}
+
+#[test]
+fn cli_simplicity_vectors_sighash() {
+	use std::str::FromStr;
+
+	use elements::bitcoin::secp256k1::{self, schnorr, Message, XOnlyPublicKey};
+	use elements::hashes::{sha256, Hash};
+
+	let expected_help = "\
+hal-simplicity-vectors-sighash 
+sighashes and signatures of Simplicity inputs
+
+USAGE:
+    hal simplicity vectors sighash [FLAGS] [OPTIONS]
+
+FLAGS:
+    -h, --help          Prints help information
+        --no-newline    don't print a trailing newline after a raw value
+    -v, --verbose       print verbose logging output to stderr
+    -y, --yaml          print output in YAML instead of JSON
+
+OPTIONS:
+    -n, --count <count>              the number of vectors to generate [default: 4]
+        --log-format <log-format>    the format of the logging output [possible values: text, json]
+        --out <out>                  write the output to the given file instead of stdout, gzipped if it ends in .gz
+    -o, --output <output>            the output format (see `schema --proto` for the protobuf messages) [possible
+                                     values: json, yaml, protobuf]
+        --raw-value <raw-value>      print only the given output field (e.g. txid) as a raw value
+";
+	assert_cmd(&["simplicity", "vectors", "sighash", "-h"], expected_help, "");
+	assert_cmd(&["simplicity", "vectors", "sighash", "--help"], expected_help, "");
+
+	// The vectors must never change, as other implementations rely on them.
+	assert_cmd(
+		&["simplicity", "vectors", "sighash", "--raw-value", "vectors.1.sighash"],
+		"b2f92739f5b516870bc81b5058a291746b0cf0eb40071270b15f4e3a628cb011\n",
+		"",
+	);
+	assert_cmd(
+		&["simplicity", "vectors", "address", "--raw-value", "vectors.0.liquid_address"],
+		"ex1pm2uptdz2vy6tpe29tagp2f2jn3ej8peqh0zmvr7rvt6tl9ruawlq5caajq\n",
+		"",
+	);
+	assert_cmd(
+		&["simplicity", "vectors", "cmr", "--raw-value", "vectors.1.cmr"],
+		"c5339a7e58d83a05238aa946ab70f0c93caeac986475e79da40d678dbce15fd3\n",
+		"",
+	);
+	assert_cmd(
+		&["simplicity", "vectors", "commitment", "--raw-value", "vectors.0.value_commitment"],
+		"09e1a240aace6332f013b40ed4787e1c79fec33412598cd2f633121366ca833115\n",
+		"",
+	);
+	assert_cmd(
+		&["simplicity", "vectors", "cmr", "-n", "6", "--raw-value", "vectors.5.seed"],
+		format!("{}\n", sha256::Hash::hash(b"hal-simplicity vectors cmr 5")),
+		"",
+	);
+
+	// The sighash vectors agree with `simplicity sighash`.
+	let vector: serde_json::Value = assert_deserialize_cmd(
+		&["simplicity", "vectors", "sighash", "-n", "1", "--raw-value", "vectors.0"],
+		|s| serde_json::from_slice(s),
+	);
+	let field = |name: &str| vector[name].as_str().unwrap().to_owned();
+	let sighash: serde_json::Value = assert_deserialize_cmd(
+		&[
+			"simplicity",
+			"simplicity",
+			"sighash",
+			&field("tx"),
+			"0",
+			&field("cmr"),
+			&field("control_block"),
+			"-i",
+			&field("input_utxo"),
+			"-s",
+			&field("secret_key"),
+		],
+		|s| serde_json::from_slice(s),
+	);
+	assert_eq!(sighash["sighash"].as_str().unwrap(), field("sighash"));
+	// The vector signature is deterministic, the one of `simplicity sighash` is not.
+	let msg =
+		Message::from_digest(sha256::Hash::from_str(&field("sighash")).unwrap().to_byte_array());
+	let key = XOnlyPublicKey::from_str(&field("x_only")).unwrap();
+	for sig in
+		[field("signature"), sighash["signatures"][field("x_only")].as_str().unwrap().to_owned()]
+	{
+		let sig = schnorr::Signature::from_str(&sig).unwrap();
+		secp256k1::SECP256K1.verify_schnorr(&sig, &msg, &key).unwrap();
+	}
+}
+
+#[test]
+fn cli_simplicity_wallet() {
+	let expected_help = "\
+hal-simplicity-wallet 
+watch-only wallet tools
+
+USAGE:
+    hal simplicity wallet [FLAGS] [OPTIONS] <SUBCOMMAND>
+
+FLAGS:
+    -h, --help       Prints help information
+    -v, --verbose    print verbose logging output to stderr
+
+OPTIONS:
+        --log-format <log-format>    the format of the logging output [possible values: text, json]
+        --out <out>                  write the output to the given file instead of stdout, gzipped if it ends in .gz
+
+SUBCOMMANDS:
+    scan    derive the first addresses of an xpub or descriptor and report their unspent outputs
+";
+	assert_cmd(&["simplicity", "wallet"], "", expected_help);
+	assert_cmd(&["simplicity", "wallet", "--help"], expected_help, "");
+}
+
+#[test]
+fn cli_simplicity_wallet_scan() {
+	let expected_help = "\
+hal-simplicity-wallet-scan 
+derive the first addresses of an xpub or descriptor and report their unspent outputs
+
+USAGE:
+    hal simplicity wallet scan [FLAGS] [OPTIONS] <descriptor> [raw-tx]...
+
+FLAGS:
+        --all                list the addresses without unspent outputs too
+    -r, --elementsregtest    run in elementsregtest mode
+    -h, --help               Prints help information
+        --liquid             run in liquid mode
+        --liquidtestnet      run in liquidtestnet mode
+        --no-newline         don't print a trailing newline after a raw value
+    -v, --verbose            print verbose logging output to stderr
+    -y, --yaml               print output in YAML instead of JSON
+
+OPTIONS:
+    -n, --count <count>              the number of addresses to derive [default: 20]
+        --esplora <esplora>          query the unspent outputs from the Esplora API at the given URL
+        --log-format <log-format>    the format of the logging output [possible values: text, json]
+        --out <out>                  write the output to the given file instead of stdout, gzipped if it ends in .gz
+    -o, --output <output>            the output format (see `schema --proto` for the protobuf messages) [possible
+                                     values: json, yaml, protobuf]
+        --raw-value <raw-value>      print only the given output field (e.g. txid) as a raw value
+
+ARGS:
+    <descriptor>    an output descriptor (e.g. elwpkh(xpub.../0/*)), or an xpub for elwpkh(<xpub>/0/*)
+    <raw-tx>...     the transactions to scan in hex, base64 or as file paths (read from stdin if omitted)
+";
+	assert_cmd(&["simplicity", "wallet", "scan", "-h"], expected_help, "");
+	assert_cmd(&["simplicity", "wallet", "scan", "--help"], expected_help, "");
+
+	let xpub = "tpubD6NzVbkrYhZ4XgiXtGrdW5XDAPFCL9h7we1vwNCpn8tGbBcgfVYjXyhWo4E1xkh56hjod1RhGjxbaTLV3X4FyWuejifB9jusQ46QzG87VKp";
+	// Pays to the addresses at index 0 and 1.
+	let tx1 = "0200000000011f1e1d1c1b1a191817161514131211100f0e0d0c0b0a090807060504030201000000000000ffffffff02016d521c38ec1ea15734ae22b7c46064412829c0d0579f0a713d1c04ede979026f01000000000000c350001600140d1c9c02a7be9ba8b8842804feb961481ce6561b016d521c38ec1ea15734ae22b7c46064412829c0d0579f0a713d1c04ede979026f010000000000007530001600141a4c3d16409dddc499160230dc84a1182b2ab38e00000000";
+	// Spends the output of tx1 to index 0 and pays to index 1 again.
+	let tx2 = "02000000000156d169d92d730c7528ba74916208d9f242b0f6dd74904cc8794c381df2d4849a0000000000ffffffff01016d521c38ec1ea15734ae22b7c46064412829c0d0579f0a713d1c04ede979026f01000000000000afc8001600141a4c3d16409dddc499160230dc84a1182b2ab38e00000000";
+	assert_cmd(
+		&["simplicity", "wallet", "scan", xpub, "--raw-value", "addresses.0.address", tx1, tx2],
+		"ert1qrfxr69jqnhwufxgkqgcdep9prq4j4vuw64ngak\n",
+		"",
+	);
+	let descriptor = format!("elwpkh({}/0/*)", xpub);
+	assert_cmd(
+		&[
+			"simplicity",
+			"wallet",
+			"scan",
+			&descriptor,
+			"--raw-value",
+			"balances.6f0279e9ed041c3d710a9f57d0c02928416460c4b722ae3457a11eec381c526d",
+			tx1,
+			tx2,
+		],
+		"75000\n",
+		"",
+	);
+	// With only the first transaction both outputs are unspent.
+	assert_cmd(
+		&["simplicity", "wallet", "scan", xpub, "--raw-value", "addresses.0.utxos.0.value", tx1],
+		"50000\n",
+		"",
+	);
+	assert_cmd(
+		&[
+			"simplicity",
+			"wallet",
+			"scan",
+			xpub,
+			"-n",
+			"1",
+			"--all",
+			"--raw-value",
+			"addresses.0.balances",
+			tx2,
+		],
+		"{}\n",
+		"",
+	);
+	// Balances which don't fit in 64 bits are an error.
+	let tx_max = tx1.replace("01000000000000c350", "01ffffffffffffffff");
+	assert_cmd(
+		&["simplicity", "wallet", "scan", xpub, &tx_max],
+		"Execution failed: the balances of asset 6f0279e9ed041c3d710a9f57d0c02928416460c4b722ae3457a11eec381c526d add up to more than 18446744073709551615 sat\n",
+		"",
+	);
+	assert_cmd(
+		&["simplicity", "wallet", "scan", "elwpkh(foo)", tx1],
+		"Execution failed: invalid descriptor 'elwpkh(foo)': unexpected «Key too short (<66 char), doesn't match any format»\n",
+		"",
+	);
+}
+
+#[test]
+fn cli_simplicity_watch() {
+	let expected_help = "\
+hal-simplicity-watch 
+print the transactions and blocks of an Elements node as NDJSON as they arrive
+
+USAGE:
+    hal simplicity watch [FLAGS] [OPTIONS] --zmq <zmq>...
+
+FLAGS:
+    -r, --elementsregtest    run in elementsregtest mode
+    -h, --help               Prints help information
+        --liquid             run in liquid mode
+        --liquidtestnet      run in liquidtestnet mode
+        --no-newline         don't print a trailing newline after a raw value
+        --txids              print the IDs of the transactions of blocks instead of full transactions
+    -v, --verbose            print verbose logging output to stderr
+
+OPTIONS:
+    -n, --count <count>              stop after printing the given number of items
+        --interval <interval>        the number of seconds between two RPC polls [default: 5]
+        --log-format <log-format>    the format of the logging output [possible values: text, json]
+        --out <out>                  write the output to the given file instead of stdout, gzipped if it ends in .gz
+        --raw-value <raw-value>      print only the given output field (e.g. txid) as a raw value
+        --rpc <rpc>                  poll the JSON-RPC interface at the given URL (e.g. http://127.0.0.1:7041)
+        --rpc-cookie <rpc-cookie>    the path of the JSON-RPC cookie file
+        --rpc-pass <rpc-pass>        the JSON-RPC password
+        --rpc-user <rpc-user>        the JSON-RPC user name
+        --zmq <zmq>...               a ZMQ endpoint publishing rawtx and/or rawblock (e.g. tcp://127.0.0.1:28332)
+";
+	assert_cmd(&["simplicity", "watch", "-h"], expected_help, "");
+	assert_cmd(&["simplicity", "watch", "--help"], expected_help, "");
+
+	assert_cmd(
+		&["simplicity", "watch", "--zmq", "127.0.0.1:28332"],
+		"Execution failed: unsupported ZMQ endpoint '127.0.0.1:28332', expected tcp://HOST:PORT\n",
+		"",
+	);
+
+	// A fake ZMQ publisher sending a single rawtx message.
+	use elements::hashes::hex::FromHex;
+	use std::io::{Read, Write};
+
+	let tx = Vec::<u8>::from_hex("0200000001010000000000000000000000000000000000000000000000000000000000000000ffffffff0603a730180101ffffffff03016d521c38ec1ea15734ae22b7c46064412829c0d0579f0a713d1c04ede979026f01000000000000000000266a240a8ce26fdbb51a2d03d4e62fdafd4a06dd7faa0d1c083aa7e27905000000000000000000016d521c38ec1ea15734ae22b7c46064412829c0d0579f0a713d1c04ede979026f010000000000000106001976a914fc26751a5025129a2fd006c6fbfa598ddd67f7e188ac016d521c38ec1ea15734ae22b7c46064412829c0d0579f0a713d1c04ede979026f01000000000000000000266a24aa21a9ede8497768bc893ee587244bf5303ac3cf482bab8e4b3fd22e8b114c2a52525ab30000000000000120000000000000000000000000000000000000000000000000000000000000000000000000000000").unwrap();
+	let listener = std::net::TcpListener::bind("127.0.0.1:0").unwrap();
+	let endpoint = format!("tcp://{}", listener.local_addr().unwrap());
+	let publisher = std::thread::spawn(move || {
+		let (mut stream, _) = listener.accept().unwrap();
+		let mut greeting = [0; 64];
+		greeting[0] = 0xff;
+		greeting[9] = 0x7f;
+		greeting[10] = 3;
+		greeting[12..16].copy_from_slice(b"NULL");
+		stream.write_all(&greeting).unwrap();
+		let mut peer_greeting = [0; 64];
+		stream.read_exact(&mut peer_greeting).unwrap();
+		let ready = b"\x05READY\x0bSocket-Type\x00\x00\x00\x03PUB";
+		stream.write_all(&[0x04, ready.len() as u8]).unwrap();
+		stream.write_all(ready).unwrap();
+
+		stream.write_all(b"\x01\x05rawtx").unwrap();
+		stream.write_all(&[0x03]).unwrap();
+		stream.write_all(&(tx.len() as u64).to_be_bytes()).unwrap();
+		stream.write_all(&tx).unwrap();
+		stream.write_all(b"\x00\x04\x00\x00\x00\x00").unwrap();
+		// Keep the connection open until the subscriber is done.
+		let _ = stream.read(&mut [0; 1024]);
+		let _ = stream.read_to_end(&mut Vec::new());
+	});
+	assert_cmd(
+		&[
+			"simplicity",
+			"watch",
+			"--liquid",
+			"--zmq",
+			&endpoint,
+			"-n",
+			"1",
+			"--raw-value",
+			"tx.txid",
+		],
+		"9523d75b48b3411a3f4ebd31b6005898deebbe748875aa6ee084b94aa8422ba6\n",
+		"",
+	);
+	publisher.join().unwrap();
+
+	// A frame announcing a huge body is refused instead of being allocated.
+	let listener = std::net::TcpListener::bind("127.0.0.1:0").unwrap();
+	let endpoint = format!("tcp://{}", listener.local_addr().unwrap());
+	let publisher = std::thread::spawn(move || {
+		let (mut stream, _) = listener.accept().unwrap();
+		let mut greeting = [0; 64];
+		greeting[0] = 0xff;
+		greeting[9] = 0x7f;
+		greeting[10] = 3;
+		greeting[12..16].copy_from_slice(b"NULL");
+		stream.write_all(&greeting).unwrap();
+		let mut peer_greeting = [0; 64];
+		stream.read_exact(&mut peer_greeting).unwrap();
+		let ready = b"\x05READY\x0bSocket-Type\x00\x00\x00\x03PUB";
+		stream.write_all(&[0x04, ready.len() as u8]).unwrap();
+		stream.write_all(ready).unwrap();
+
+		stream.write_all(b"\x01\x05rawtx").unwrap();
+		stream.write_all(&[0x02]).unwrap();
+		stream.write_all(&(1u64 << 40).to_be_bytes()).unwrap();
+		let _ = stream.read_to_end(&mut Vec::new());
+	});
+	assert_cmd(
+		&["simplicity", "watch", "--liquid", "--zmq", &endpoint],
+		"",
+		&format!(
+			"Connection to {} lost: ZMTP frame of 1099511627776 bytes exceeds the limit of 33554432 bytes\n",
+			endpoint
+		),
+	);
+	publisher.join().unwrap();
 }
 
 // Stick some big constants down here
+
+/// An unsigned transaction spending the output `SESSION_UTXO` of the `SESSION_POLICY` multisig.
+const SESSION_TX: &str = "020000000001222222222222222222222222222222222222222222222222222222222222222200000000000000000002016f0279e9ed041c3d710a9f57d0c02928416460c4b722ae3457a11eec381c526d0100000000000182b80022512000c68b5f264f0c8db4cf7a90af36c9376c25d7184b3a129de99d56f40bb6e8a6016f0279e9ed041c3d710a9f57d0c02928416460c4b722ae3457a11eec381c526d0100000000000003e8000000000000";
+const SESSION_UTXO: &str = "5120233869f1ba695acb7f38260edf7cc1ea3ae1f80cf05dcb0e776867af85559450:6f0279e9ed041c3d710a9f57d0c02928416460c4b722ae3457a11eec381c526d:100000";
+const SESSION_POLICY: &str = "thresh(2,pk(79be667ef9dcbbac55a06295ce870b07029bfcdb2dce28d959f2815b16f81798),pk(c6047f9441ed7d6d3045406e95c07cd85c778e4b8cef3ca7abac09b95c709ee5))";
 static BLOCK_HEADER_1585319: &str = concat!(
 	"000000a0176409e0a34e5bde1640a618a8910ce27af4157140f7531e8fde47ddcdaf65338ce0c95a",
 	"86c8cf32ca810bdb15d0333e1b5cb67981b284f558f7c61207442f2494229c61a730180001220020",